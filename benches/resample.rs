@@ -0,0 +1,27 @@
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use ffcodex_lib::resample;
+
+fn test_signal(len: usize) -> Vec<f32> {
+    (0..len).map(|i| (i as f32 * 0.01).sin() * 0.5).collect()
+}
+
+fn bench_resample(c: &mut Criterion) {
+    let input = test_signal(48_000 * 5);
+    let mut group = c.benchmark_group("resample_44100_to_48000");
+    group.sample_size(10);
+
+    group.bench_function(BenchmarkId::new("windowed_sinc", input.len()), |b| {
+        b.iter(|| resample::resample_windowed_sinc(black_box(&input), 44100, 48000))
+    });
+    group.bench_function(BenchmarkId::new("sinc_optimized", input.len()), |b| {
+        b.iter(|| resample::resample_windowed_sinc_optimized(black_box(&input), 44100, 48000))
+    });
+    group.bench_function(BenchmarkId::new("parallel_simd", input.len()), |b| {
+        b.iter(|| resample::resample_parallel_simd(black_box(&input), 44100, 48000))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_resample);
+criterion_main!(benches);
@@ -0,0 +1,257 @@
+//! Typed view of the ASWG (Audio Standards Working Group) metadata set, stored
+//! as `ASWG_<element>` fields and written to the iXML `<ASWG>` block.
+//!
+//! Fields that Soundminer also keeps under `USER_*` (category, fxName, library,
+//! micType, ...) are aliases in `get_metadata_keys`, so reading falls back to
+//! the `USER_*` value and writing sets both.
+
+use crate::prelude::*;
+
+/// `contentType` values
+const CONTENT_TYPES: [&str; 4] = ["sfx", "music", "dialogue", "mixed"];
+/// `micDistance` values, the mic perspective (`USER_MICPERSPECTIVE`)
+const PERSPECTIVES: [&str; 4] = ["close", "medium", "distant", "mixed"];
+const BOOLEANS: [&str; 2] = ["true", "false"];
+
+macro_rules! aswg_fields {
+    ($($field:ident => $element:literal),* $(,)?) => {
+        /// Every ASWG field, `None` where the metadata doesn't set it
+        #[derive(Debug, Clone, Default, PartialEq, Eq)]
+        pub struct AswgMetadata {
+            $(pub $field: Option<String>,)*
+        }
+
+        impl AswgMetadata {
+            /// Each field paired with its ASWG element name
+            fn entries(&self) -> Vec<(&'static str, &Option<String>)> {
+                vec![$(($element, &self.$field)),*]
+            }
+
+            fn entries_mut(&mut self) -> Vec<(&'static str, &mut Option<String>)> {
+                vec![$(($element, &mut self.$field)),*]
+            }
+        }
+    };
+}
+
+aswg_fields! {
+    // Project and production
+    content_type => "contentType",
+    project => "project",
+    originator => "originator",
+    originator_studio => "originatorStudio",
+    notes => "notes",
+    session => "session",
+    state => "state",
+    editor => "editor",
+    mixer => "mixer",
+    fx_chain_name => "fxChainName",
+    channel_config => "channelConfig",
+    ambisonic_format => "ambisonicFormat",
+    ambisonic_chn_order => "ambisonicChnOrder",
+    ambisonic_norm => "ambisonicNorm",
+    is_designed => "isDesigned",
+    rec_engineer => "recEngineer",
+    rec_studio => "recStudio",
+    impulse_location => "impulseLocation",
+    // Dialogue
+    text => "text",
+    efforts => "efforts",
+    effort_type => "effortType",
+    projection => "projection",
+    language => "language",
+    timing_restriction => "timingRestriction",
+    character_name => "characterName",
+    character_gender => "characterGender",
+    character_age => "characterAge",
+    character_role => "characterRole",
+    actor_name => "actorName",
+    actor_gender => "actorGender",
+    direction => "direction",
+    director_notes => "directorNotes",
+    fx_used => "fxUsed",
+    usage_rights => "usageRights",
+    is_union => "isUnion",
+    accent => "accent",
+    emotion => "emotion",
+    // Music
+    composer => "composer",
+    artist => "artist",
+    song_title => "songTitle",
+    genre => "genre",
+    sub_genre => "subGenre",
+    producer => "producer",
+    music_sup => "musicSup",
+    instrument => "instrument",
+    music_publisher => "musicPublisher",
+    rights_owner => "rightsOwner",
+    is_source => "isSource",
+    is_loop => "isLoop",
+    intensity => "intensity",
+    is_final => "isFinal",
+    order_ref => "orderRef",
+    is_ost => "isOst",
+    is_cinematic => "isCinematic",
+    is_licensed => "isLicensed",
+    is_diegetic => "isDiegetic",
+    music_version => "musicVersion",
+    isrc_id => "isrcId",
+    tempo => "tempo",
+    time_sig => "timeSig",
+    in_key => "inKey",
+    billing_code => "billingCode",
+    // Library and recording
+    library => "library",
+    cat_id => "catId",
+    category => "category",
+    sub_category => "subCategory",
+    user_category => "userCategory",
+    vendor_category => "vendorCategory",
+    fx_name => "fxName",
+    user_data => "userData",
+    mic_type => "micType",
+    mic_config => "micConfig",
+    mic_distance => "micDistance",
+    recording_loc => "recordingLoc",
+    release_date => "releaseDate",
+}
+
+impl AswgMetadata {
+    /// Check every set field against its ASWG type. The error lists all
+    /// problems, not just the first.
+    pub fn validate(&self) -> R<()> {
+        let problems: Vec<String> = self
+            .entries()
+            .into_iter()
+            .filter_map(|(element, value)| canonical(element, value.as_deref()?).err())
+            .map(|e| e.to_string())
+            .collect();
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Invalid ASWG metadata: {}", problems.join("; ")))
+        }
+    }
+}
+
+/// The value as it should be written, or why `element` can't hold it.
+/// Enumerated values match case-insensitively and come back in the spec's case.
+fn canonical(element: &str, value: &str) -> R<String> {
+    let value = value.trim();
+    let one_of = |allowed: &[&str]| {
+        allowed
+            .iter()
+            .find(|a| a.eq_ignore_ascii_case(value))
+            .map(|a| a.to_string())
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} '{}' is not one of {}",
+                    element,
+                    value,
+                    allowed.join(", ")
+                )
+            })
+    };
+    match element {
+        "contentType" => one_of(&CONTENT_TYPES),
+        "micDistance" => one_of(&PERSPECTIVES),
+        "isDesigned" | "isUnion" | "isSource" | "isLoop" | "isFinal" | "isOst" | "isCinematic"
+        | "isLicensed" | "isDiegetic" => one_of(&BOOLEANS),
+        "tempo" => match value.parse::<f32>() {
+            Ok(bpm) if bpm > 0.0 => Ok(value.to_string()),
+            _ => Err(anyhow!("tempo '{}' is not a positive BPM", value)),
+        },
+        "releaseDate" => {
+            let digits = value.chars().filter(char::is_ascii_digit).count();
+            let dashes: Vec<usize> = value.match_indices('-').map(|(i, _)| i).collect();
+            if value.len() == 10 && digits == 8 && dashes == [4, 7] {
+                Ok(value.to_string())
+            } else {
+                Err(anyhow!("releaseDate '{}' is not YYYY-MM-DD", value))
+            }
+        }
+        _ => Ok(value.to_string()),
+    }
+}
+
+impl Metadata {
+    /// Read the ASWG fields, falling back to their `USER_*` aliases
+    pub fn as_aswg(&self) -> AswgMetadata {
+        let mut aswg = AswgMetadata::default();
+        for (element, value) in aswg.entries_mut() {
+            *value = self
+                .get_field(&format!("ASWG_{element}"))
+                .filter(|v| !v.is_empty());
+        }
+        aswg
+    }
+
+    /// Validate `aswg` and write every field it sets, along with the `USER_*`
+    /// aliases. Nothing is written if any field is invalid; fields left `None`
+    /// keep their current value.
+    pub fn set_aswg(&mut self, aswg: &AswgMetadata) -> R<()> {
+        aswg.validate()?;
+        for (element, value) in aswg.entries() {
+            if let Some(value) = value {
+                self.set_field(&format!("ASWG_{element}"), &canonical(element, value)?)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aswg_maps_user_fields_both_ways() {
+        let mut metadata = Metadata::new();
+        metadata.set_field("USER_FXNAME", "Door Slam").unwrap();
+        metadata.set_field("USER_MICPERSPECTIVE", "close").unwrap();
+        metadata.set_field("CATEGORY", "DOORS").unwrap();
+
+        let mut aswg = metadata.as_aswg();
+        assert_eq!(aswg.fx_name.as_deref(), Some("Door Slam"));
+        assert_eq!(aswg.mic_distance.as_deref(), Some("close"));
+        assert_eq!(aswg.category.as_deref(), Some("DOORS"));
+        assert_eq!(aswg.project, None);
+
+        aswg.content_type = Some("SFX".into());
+        aswg.vendor_category = Some("Wood".into());
+        aswg.is_loop = Some("False".into());
+        metadata.set_aswg(&aswg).unwrap();
+        assert_eq!(
+            metadata.get_field("ASWG_contentType").as_deref(),
+            Some("sfx")
+        );
+        assert_eq!(metadata.get_field("ASWG_isLoop").as_deref(), Some("false"));
+        assert_eq!(
+            metadata.get_field("USER_VENDORCATEGORY").as_deref(),
+            Some("Wood")
+        );
+        assert_eq!(
+            metadata.get_field("ASWG_fxName").as_deref(),
+            Some("Door Slam")
+        );
+    }
+
+    #[test]
+    fn test_invalid_aswg_values_are_rejected() {
+        let aswg = AswgMetadata {
+            mic_distance: Some("overhead".into()),
+            is_final: Some("yes".into()),
+            release_date: Some("2024-1-5".into()),
+            fx_name: Some("Door Slam".into()),
+            ..Default::default()
+        };
+        let error = aswg.validate().unwrap_err().to_string();
+        assert!(error.contains("micDistance 'overhead' is not one of close"));
+        assert!(error.contains("isFinal 'yes'"));
+        assert!(error.contains("releaseDate '2024-1-5'"));
+
+        let mut metadata = Metadata::new();
+        assert!(metadata.set_aswg(&aswg).is_err());
+        assert_eq!(metadata.get_field("ASWG_fxName"), None);
+    }
+}
@@ -17,7 +17,7 @@ impl Codex {
                 "{} bit depth is not supported. Converting to 24bit",
                 self.get_filename()
             );
-            self.change_bit_depth(24)?;
+            self.change_bit_depth(24, DitherMode::Triangular)?;
         }
 
         // Determine target sample rate and resample if needed
@@ -49,18 +49,13 @@ impl Codex {
             return self.generate_pcm_hash();
         }
 
-        let num_channels = if buffer.channels > 1 { 2 } else { 1 };
-
-        let samples = if buffer.channels > 1 {
-            interleave_stereo(&buffer.data)
-        } else {
-            single_channel(&buffer.data)
-        };
+        // Always fingerprint a mono sum, so channel count doesn't change the result
+        let samples = downmix_to_mono(&buffer.data);
 
         // Try Chromaprint fingerprinting
         let c = Chromaprint::new(CHROMAPRINT_ALGORITHM_DEFAULT)
             .map_err(|e| anyhow::anyhow!(e))?;
-        if c.start(target_sample_rate as i32, num_channels) {
+        if c.start(target_sample_rate as i32, 1) {
             c.feed(&samples);
             c.finish();
 
@@ -100,11 +95,7 @@ impl Codex {
             ));
         };
 
-        let samples = if buffer.channels > 1 {
-            interleave_stereo(&buffer.data)
-        } else {
-            single_channel(&buffer.data)
-        };
+        let samples = downmix_to_mono(&buffer.data);
 
         if samples.is_empty() {
             return Err(anyhow::anyhow!(
@@ -131,40 +122,55 @@ impl Codex {
     }
 }
 
-// Helper functions to convert audio data remain the same
-fn interleave_stereo(channels: &[Vec<f32>]) -> Vec<i16> {
-    let left = &channels[0];
-    let right = &channels[1];
-
-    // Pre-allocate the exact capacity needed
-    let len = left.len().min(right.len());
-    let mut interleaved = Vec::with_capacity(len * 2);
-
-    // Use iterators with zip() instead of indexed for loop
-    left.iter()
-        .zip(right.iter())
-        .take(len)
-        .for_each(|(&l, &r)| {
-            let l_sample = (l.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
-            let r_sample = (r.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
-            interleaved.push(l_sample);
-            interleaved.push(r_sample);
-        });
-
-    interleaved
-}
-
-fn single_channel(channels: &[Vec<f32>]) -> Vec<i16> {
-    if channels.is_empty() {
+/// Sum every channel to mono with equal weights of `1 / channels`, so a stereo
+/// file whose channels are identical yields the same samples as its mono bounce.
+/// Channels of different lengths are summed up to the shortest one.
+fn downmix_to_mono(channels: &[Vec<f32>]) -> Vec<i16> {
+    let Some(len) = channels.iter().map(Vec::len).min() else {
         return Vec::new();
-    }
-
-    let channel = &channels[0];
+    };
+    let weight = 1.0 / channels.len() as f32;
     let scale = i16::MAX as f32;
 
-    // Use map+collect instead of push in a loop
-    channel
-        .iter()
-        .map(|&sample| (sample.clamp(-1.0, 1.0) * scale) as i16)
+    (0..len)
+        .map(|i| {
+            let sum: f32 = channels.iter().map(|channel| channel[i] * weight).sum();
+            (sum.clamp(-1.0, 1.0) * scale) as i16
+        })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stereo_and_mono_bounce_fingerprint_identically() {
+        // Long enough for Chromaprint rather than the PCM hash fallback
+        let samples: Vec<f32> = (0..48000 * 4)
+            .map(|i| {
+                let t = i as f32 / 48000.0;
+                0.3 * (2.0 * std::f32::consts::PI * 440.0 * t).sin()
+                    + 0.2 * (2.0 * std::f32::consts::PI * (660.0 + 100.0 * t) * t).sin()
+            })
+            .collect();
+        let codex = |channels: u16| Codex {
+            buffer: Some(AudioBuffer {
+                sample_rate: 48000,
+                channels,
+                format: SampleFormat::I16,
+                data: vec![samples.clone(); channels as usize],
+            }),
+            ..Default::default()
+        };
+
+        let mono = codex(1).get_chromaprint_fingerprint().unwrap();
+        let stereo = codex(2).get_chromaprint_fingerprint().unwrap();
+        assert!(!mono.starts_with("PCM:"));
+        assert_eq!(mono, stereo);
+        assert_eq!(
+            downmix_to_mono(&[samples.clone(), samples.clone()]),
+            downmix_to_mono(&[samples])
+        );
+    }
+}
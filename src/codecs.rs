@@ -1,27 +1,38 @@
 use crate::{prelude::*, soundminer::get_metadata_keys};
 mod aif;
+mod caf;
+mod chunk;
 mod flac;
 // mod mp3;
+mod opus;
+mod w64;
 mod wav;
 mod wavpack;
 pub use aif::AifCodec;
-pub use flac::FlacCodec;
+pub use caf::CafCodec;
+pub use flac::{FlacCodec, FlacEncodeOptions, FlacStreamInfo};
 // pub use mp3::Mp3Codec;
+pub use opus::OpusCodec;
+pub use w64::W64Codec;
 pub use wav::WavCodec;
-pub use wavpack::WvCodec;
+pub use wavpack::{WavpackHybrid, WavpackQuality, WavpackTags, WvCodec};
 
 pub fn get_codec(file_path: &str) -> R<Box<dyn Codec>> {
     let extension = std::path::Path::new(file_path)
         .extension()
         .and_then(|ext| ext.to_str())
-        .ok_or_else(|| anyhow::anyhow!("Invalid file extension"))?;
+        .ok_or_else(|| anyhow::anyhow!("No file extension to choose a codec from"))?;
 
     match extension.to_lowercase().as_str() {
         "wav" => Ok(Box::new(WavCodec)),
         "flac" => Ok(Box::new(FlacCodec)),
         "aif" => Ok(Box::new(AifCodec)),
         "aiff" => Ok(Box::new(AifCodec)),
+        "aifc" => Ok(Box::new(AifCodec)),
         "wv" => Ok(Box::new(WvCodec)),
+        "caf" => Ok(Box::new(CafCodec)),
+        "opus" => Ok(Box::new(OpusCodec::default())),
+        "w64" => Ok(Box::new(W64Codec)),
         // "mp3" => Ok(Box::new(Mp3Codec)),
         _ => Err(anyhow::anyhow!(
             "No codec found for extension: {}",
@@ -30,6 +41,21 @@ pub fn get_codec(file_path: &str) -> R<Box<dyn Codec>> {
     }
 }
 
+/// Frame step of the mono sum hashed by `AudioBuffer::content_key`
+const CONTENT_KEY_DECIMATION: usize = 16;
+
+/// Integer PCM value for a ±1.0 sample at `bits_per_sample`: rounded and
+/// clamped so it is the exact inverse of the decoders' n / 2^(bits-1) scaling.
+/// Every integer encoder goes through this, so the same audio writes the same
+/// samples in any format.
+pub(crate) fn quantize_sample(sample: f32, bits_per_sample: u16) -> i32 {
+    let scale = (1i64 << (bits_per_sample - 1)) as f64;
+    (sample as f64 * scale).round().clamp(-scale, scale - 1.0) as i32
+}
+
+/// Decoded audio. Samples are always held as de-interleaved `f32` in ±1.0;
+/// `format` records the resolution of the source (and of what gets encoded),
+/// not how the samples are stored in memory.
 #[derive(Debug, Default, Clone)]
 pub struct AudioBuffer {
     pub sample_rate: u32,
@@ -39,23 +65,148 @@ pub struct AudioBuffer {
 }
 
 impl AudioBuffer {
-    pub fn resample(&mut self, new_rate: u32) {
-        if self.sample_rate != new_rate {
-            // Resample each channel individually using optimized functions
-            for i in 0..self.data.len() {
-                // Try fast common ratios first, fall back to optimized general algorithm
-                self.data[i] = resample::resample_fast_common_ratios(
-                    &self.data[i],
-                    self.sample_rate,
-                    new_rate,
-                )
-                .unwrap_or_else(|| {
-                    resample::resample_optimized(&self.data[i], self.sample_rate, new_rate)
-                });
+    /// Native sample format of the source file; the same as `format`
+    pub fn source_format(&self) -> SampleFormat {
+        self.format
+    }
+
+    /// Format the samples are actually held in, which is `F32` for every codec
+    pub fn working_precision(&self) -> SampleFormat {
+        SampleFormat::F32
+    }
+
+    /// SHA-256 of the decoded audio (rate, format, channels and every sample),
+    /// independent of container and metadata
+    pub fn content_hash(&self) -> String {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        hasher.update(self.sample_rate.to_le_bytes());
+        hasher.update(self.channels.to_le_bytes());
+        hasher.update(format!("{:?}", self.format).as_bytes());
+        for channel in &self.data {
+            hasher.update((channel.len() as u64).to_le_bytes());
+            for sample in channel {
+                hasher.update(sample.to_bits().to_le_bytes());
+            }
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Cheap key for spotting exact duplicates: a hash of every
+    /// `CONTENT_KEY_DECIMATION`th frame of the mono sum, quantized to 16 bits.
+    /// Metadata, container and channel layout of identical audio don't change it.
+    pub fn content_key(&self) -> u64 {
+        use sha2::{Digest, Sha256};
+
+        let mono = self.averaged_mono();
+        let mut hasher = Sha256::new();
+        hasher.update(self.sample_rate.to_le_bytes());
+        hasher.update((mono.data[0].len() as u64).to_le_bytes());
+        for sample in mono.data[0].iter().step_by(CONTENT_KEY_DECIMATION) {
+            let quantized = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+            hasher.update(quantized.to_le_bytes());
+        }
+        let digest = hasher.finalize();
+        u64::from_le_bytes(digest[..8].try_into().unwrap_or_default())
+    }
+
+    /// MD5 of the interleaved PCM as it sits in a WAV data chunk at `format`'s
+    /// bit depth (little-endian, unsigned 8-bit, float bits for `F32`). This is
+    /// the checksum WavPack stores, and FLAC's STREAMINFO one for 16 bits and up,
+    /// so identical audio hashes the same whatever container it came from.
+    pub fn pcm_md5(&self) -> [u8; 16] {
+        use md5::{Digest, Md5};
+
+        let bits = self.format.bits_per_sample();
+        let width = bits as usize / 8;
+        let frames = self.data.first().map_or(0, Vec::len);
+        let mut hasher = Md5::new();
+        for i in 0..frames {
+            for channel in &self.data {
+                let sample = channel[i];
+                match self.format {
+                    SampleFormat::F32 => hasher.update(sample.to_le_bytes()),
+                    SampleFormat::U8 => hasher.update([(quantize_sample(sample, 8) + 128) as u8]),
+                    _ => hasher.update(&quantize_sample(sample, bits).to_le_bytes()[..width]),
+                }
             }
+        }
+        let mut md5 = [0u8; 16];
+        md5.copy_from_slice(&hasher.finalize());
+        md5
+    }
 
-            self.sample_rate = new_rate;
+    /// Interleave the buffer into little-endian PCM bytes laid out as `format`,
+    /// the same sample layout used inside a WAV data chunk
+    pub fn to_interleaved_bytes(&self, format: SampleFormat) -> R<Vec<u8>> {
+        if self.data.is_empty() {
+            return Ok(Vec::new());
         }
+        // 32-bit output picks float vs integer from the buffer's own format
+        let source = if (format == SampleFormat::F32) == (self.format == SampleFormat::F32) {
+            std::borrow::Cow::Borrowed(self)
+        } else {
+            std::borrow::Cow::Owned(AudioBuffer {
+                format,
+                ..self.clone()
+            })
+        };
+        let mut bytes = Vec::with_capacity(
+            self.data[0].len() * self.data.len() * format.bits_per_sample() as usize / 8,
+        );
+        wav::encode_samples(&mut bytes, &source, format.bits_per_sample())?;
+        Ok(bytes)
+    }
+
+    /// Build a buffer from interleaved little-endian PCM bytes laid out as `format`
+    pub fn from_interleaved_bytes(
+        bytes: &[u8],
+        sample_rate: u32,
+        channels: u16,
+        format: SampleFormat,
+    ) -> R<Self> {
+        if channels == 0 {
+            return Err(anyhow!("Channel count must be at least 1"));
+        }
+        let data = wav::decode_samples(
+            bytes,
+            channels,
+            format.bits_per_sample(),
+            format == SampleFormat::F32,
+        )?;
+        Ok(AudioBuffer {
+            sample_rate,
+            channels,
+            format,
+            data,
+        })
+    }
+
+    pub fn resample(&mut self, new_rate: u32) {
+        self.resample_with(new_rate, resample::ResampleQuality::default());
+    }
+
+    /// Resample every channel with the given interpolation. Downsampling
+    /// low-passes below the new Nyquist frequency before decimating.
+    pub fn resample_with(&mut self, new_rate: u32, quality: resample::ResampleQuality) {
+        self.resample_with_engine(&quality, new_rate);
+    }
+
+    /// Resample every channel with a caller-supplied engine. Resampling to the
+    /// current rate returns immediately, leaving the samples untouched.
+    pub fn resample_with_engine(&mut self, engine: &dyn resample::Resample, new_rate: u32) {
+        if self.sample_rate == new_rate {
+            return;
+        }
+        for channel in self.data.iter_mut() {
+            *channel = engine.process(channel, self.sample_rate, new_rate);
+        }
+        self.sample_rate = new_rate;
     }
     pub fn resample_channel(&mut self, i: usize, new_rate: u32) -> Vec<f32> {
         if self.sample_rate != new_rate {
@@ -64,15 +215,24 @@ impl AudioBuffer {
             std::mem::take(&mut self.data[i])
         }
     }
-    pub fn change_bit_depth(&mut self, new_bit_depth: u16) {
+    /// Requantize to `new_bit_depth`, adding `dither` noise only when the depth goes down
+    pub fn change_bit_depth(&mut self, new_bit_depth: u16, dither: DitherMode) {
+        self.change_bit_depth_with_rng(new_bit_depth, dither, &mut rand::rng());
+    }
+
+    /// `change_bit_depth` drawing dither noise from `rng`, so a seeded generator
+    /// gives reproducible output
+    pub fn change_bit_depth_with_rng<G: rand::Rng + ?Sized>(
+        &mut self,
+        new_bit_depth: u16,
+        dither: DitherMode,
+        rng: &mut G,
+    ) {
         if self.format.bits_per_sample() != new_bit_depth {
-            for i in 0..self.data.len() {
-                self.data[i] = resample::change_bit_depth(
-                    &self.data[i],
-                    self.sample_rate,
-                    new_bit_depth as u32,
-                    true,
-                );
+            let src_bits = self.format.bits_per_sample() as u32;
+            for channel in self.data.iter_mut() {
+                *channel =
+                    resample::requantize(channel, src_bits, new_bit_depth as u32, dither, rng);
             }
             self.format = match new_bit_depth {
                 8 => SampleFormat::U8,
@@ -84,8 +244,132 @@ impl AudioBuffer {
         }
     }
 
+    /// Convert to `format`, requantizing integer targets with the given dither.
+    /// Samples stay normalized floats; only their resolution changes.
+    pub fn set_format(&mut self, format: SampleFormat, dither: DitherMode) {
+        if format == self.format {
+            return;
+        }
+        if format != SampleFormat::F32 {
+            let src_bits = self.format.bits_per_sample() as u32;
+            let dst_bits = format.bits_per_sample() as u32;
+            let mut rng = rand::rng();
+            for channel in self.data.iter_mut() {
+                *channel = resample::requantize(channel, src_bits, dst_bits, dither, &mut rng);
+            }
+        }
+        self.format = format;
+    }
+
+    /// Scale each channel by its gain in dB, in channel order. With `clamp` the
+    /// result is limited to ±1.0; otherwise float buffers keep any overshoot and
+    /// integer formats are clamped when encoded.
+    pub fn apply_gain(&mut self, gains_db: &[f32], clamp: bool) -> R<()> {
+        if gains_db.len() != self.data.len() {
+            return Err(anyhow::anyhow!(
+                "Expected {} channel gains, got {}",
+                self.data.len(),
+                gains_db.len()
+            ));
+        }
+        for (channel, &gain_db) in self.data.iter_mut().zip(gains_db) {
+            apply_channel_gain(channel, gain_db, clamp);
+        }
+        Ok(())
+    }
+
+    /// Scale every channel by `gain_db`; `clamp` as for `apply_gain`
+    pub fn apply_gain_all(&mut self, gain_db: f32, clamp: bool) {
+        for channel in self.data.iter_mut() {
+            apply_channel_gain(channel, gain_db, clamp);
+        }
+    }
+
+    /// Limit samples to full scale as `mode` describes; `ClipMode::None` leaves
+    /// the buffer as it is
+    pub fn clip(&mut self, mode: ClipMode) {
+        let clip: fn(f32) -> f32 = match mode {
+            ClipMode::Hard => |sample| sample.clamp(-1.0, 1.0),
+            ClipMode::Soft => soft_clip,
+            ClipMode::None => return,
+        };
+        for sample in self.data.iter_mut().flatten() {
+            *sample = clip(*sample);
+        }
+    }
+
+    /// Ramp the first `fade_in_frames` up from silence and the last `fade_out_frames`
+    /// down to it, with the same gain on every channel. Fades longer than the
+    /// buffer are shortened in proportion so they meet without overlapping.
+    pub fn apply_fade(&mut self, fade_in_frames: usize, fade_out_frames: usize, curve: FadeCurve) {
+        let frames = self.data.iter().map(Vec::len).max().unwrap_or(0);
+        let (fade_in, fade_out) = if fade_in_frames + fade_out_frames > frames {
+            let fade_in = (frames as u128 * fade_in_frames as u128
+                / (fade_in_frames as u128 + fade_out_frames as u128))
+                as usize;
+            (fade_in, frames - fade_in)
+        } else {
+            (fade_in_frames, fade_out_frames)
+        };
+
+        let ramp_in = (0..fade_in).map(|i| (i, curve.gain(i as f32 / fade_in as f32)));
+        let ramp_out =
+            (0..fade_out).map(|i| (frames - 1 - i, curve.gain(i as f32 / fade_out as f32)));
+        let gains: Vec<(usize, f32)> = ramp_in.chain(ramp_out).collect();
+        for channel in self.data.iter_mut() {
+            for &(i, gain) in &gains {
+                if let Some(sample) = channel.get_mut(i) {
+                    let faded = *sample * gain;
+                    // Flush denormals from the quiet end of the ramp to zero
+                    *sample = if faded.abs() < f32::MIN_POSITIVE {
+                        0.0
+                    } else {
+                        faded
+                    };
+                }
+            }
+        }
+    }
+
+    /// Insert `head_frames` of silence before and `tail_frames` after the audio.
+    /// Channels that ended early are extended so every channel ends together.
+    pub fn pad(&mut self, head_frames: usize, tail_frames: usize) {
+        let frames = self.data.iter().map(Vec::len).max().unwrap_or(0);
+        for channel in self.data.iter_mut() {
+            channel.splice(0..0, std::iter::repeat_n(0.0, head_frames));
+            channel.resize(head_frames + frames + tail_frames, 0.0);
+        }
+    }
+
+    /// Copy frames `[start_frame, end_frame)` of every channel into a new buffer
+    /// with the same rate and format. `start_frame == end_frame` gives an empty one.
+    pub fn slice(&self, start_frame: usize, end_frame: usize) -> R<AudioBuffer> {
+        let frames = self.data.iter().map(Vec::len).max().unwrap_or(0);
+        if start_frame > end_frame || end_frame > frames {
+            return Err(anyhow::anyhow!(
+                "Frame range {}..{} out of bounds ({} frames)",
+                start_frame,
+                end_frame,
+                frames
+            ));
+        }
+
+        Ok(AudioBuffer {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            format: self.format,
+            data: self
+                .data
+                .iter()
+                .map(|channel| {
+                    channel[start_frame.min(channel.len())..end_frame.min(channel.len())].to_vec()
+                })
+                .collect(),
+        })
+    }
+
     pub fn strip_multi_mono(&mut self) -> R<()> {
-        if self.data.is_empty() || self.channels < 2 {
+        if self.data.len() < 2 {
             return Err(anyhow::anyhow!(
                 "Cannot strip multi-mono: no data or less than 2 channels"
             ));
@@ -97,6 +381,367 @@ impl AudioBuffer {
 
         Ok(())
     }
+
+    /// Reduce to a single channel in place. Unlike `strip_multi_mono`, which
+    /// keeps the first of several identical channels, `MonoMode::Average` sums
+    /// distinct channels.
+    pub fn to_mono(&mut self, mode: MonoMode) -> R<()> {
+        match mode {
+            MonoMode::Average => *self = self.averaged_mono(),
+            MonoMode::SelectChannel(index) => {
+                if index >= self.data.len() {
+                    return Err(anyhow!(
+                        "Channel {} out of range for a {}-channel buffer",
+                        index,
+                        self.data.len()
+                    ));
+                }
+                self.data = vec![self.data.swap_remove(index)];
+                self.channels = 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Average all channels into a single channel
+    fn averaged_mono(&self) -> AudioBuffer {
+        let frames = self.data.iter().map(|c| c.len()).max().unwrap_or(0);
+        let scale = 1.0 / self.data.len().max(1) as f32;
+        let mut mono = vec![0.0f32; frames];
+        for channel in &self.data {
+            for (sum, sample) in mono.iter_mut().zip(channel.iter()) {
+                *sum += sample * scale;
+            }
+        }
+
+        AudioBuffer {
+            sample_rate: self.sample_rate,
+            channels: 1,
+            format: self.format,
+            data: vec![mono],
+        }
+    }
+
+    /// Fold a surround buffer down to stereo, identifying channels by their
+    /// position in the WAV order of 5.1 and 7.1; see `downmix_to_stereo_with_mask`
+    pub fn downmix_to_stereo(&mut self, coefficients: DownmixCoefficients) -> R<()> {
+        self.downmix_to_stereo_with_mask(coefficients, 0)
+    }
+
+    /// Fold a surround buffer down to stereo. `channel_mask` is the WAV/WavPack
+    /// speaker mask, or 0 when the file has none. Stereo input is left as is.
+    /// The result is not normalized, so loud programme can exceed full scale.
+    pub fn downmix_to_stereo_with_mask(
+        &mut self,
+        coefficients: DownmixCoefficients,
+        channel_mask: u32,
+    ) -> R<()> {
+        if self.data.len() == 2 {
+            return Ok(());
+        }
+        let layout = speaker_layout(self.data.len(), channel_mask)?;
+
+        let gain = |db: f32| 10f32.powf(db / 20.0);
+        let center = gain(coefficients.center_db);
+        let surround = gain(coefficients.surround_db);
+        let lfe = coefficients.lfe_db.map_or(0.0, gain);
+        let back_center = surround * std::f32::consts::FRAC_1_SQRT_2;
+
+        let frames = self.data.iter().map(|c| c.len()).max().unwrap_or(0);
+        let mut left = vec![0.0f32; frames];
+        let mut right = vec![0.0f32; frames];
+        for (channel, speaker) in self.data.iter().zip(layout) {
+            let (to_left, to_right) = match (speaker, coefficients.matrix) {
+                (Speaker::Left, _) => (1.0, 0.0),
+                (Speaker::Right, _) => (0.0, 1.0),
+                (Speaker::Center, _) => (center, center),
+                (Speaker::Lfe, _) => (lfe, lfe),
+                (Speaker::LeftSurround, DownmixMatrix::LoRo) => (surround, 0.0),
+                (Speaker::RightSurround, DownmixMatrix::LoRo) => (0.0, surround),
+                (Speaker::BackCenter, DownmixMatrix::LoRo) => (back_center, back_center),
+                // Lt/Rt carries the mono surround sum as the L-R difference
+                (Speaker::LeftSurround | Speaker::RightSurround, DownmixMatrix::LtRt) => {
+                    (-surround, surround)
+                }
+                (Speaker::BackCenter, DownmixMatrix::LtRt) => (-back_center, back_center),
+            };
+            for ((l, r), &sample) in left.iter_mut().zip(right.iter_mut()).zip(channel) {
+                *l += sample * to_left;
+                *r += sample * to_right;
+            }
+        }
+
+        self.data = vec![left, right];
+        self.channels = 2;
+        Ok(())
+    }
+
+    /// Indices of channels whose peak exceeds `threshold_dbfs`; silent/unused tracks are omitted
+    pub fn active_channels(&self, threshold_dbfs: f32) -> Vec<usize> {
+        let threshold = 10f32.powf(threshold_dbfs / 20.0);
+        self.data
+            .iter()
+            .enumerate()
+            .filter(|(_, channel)| channel.iter().any(|sample| sample.abs() > threshold))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Copy a single channel out as a mono buffer
+    pub fn extract_channel(&self, index: usize) -> R<AudioBuffer> {
+        let channel = self.data.get(index).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Channel {} out of range ({} channels)",
+                index,
+                self.data.len()
+            )
+        })?;
+
+        Ok(AudioBuffer {
+            sample_rate: self.sample_rate,
+            channels: 1,
+            format: self.format,
+            data: vec![channel.clone()],
+        })
+    }
+
+    /// Split into one mono buffer per channel, in channel order, e.g. for
+    /// multi-mono export
+    pub fn split_channels(self) -> Vec<AudioBuffer> {
+        let (sample_rate, format) = (self.sample_rate, self.format);
+        self.data
+            .into_iter()
+            .map(|channel| AudioBuffer {
+                sample_rate,
+                channels: 1,
+                format,
+                data: vec![channel],
+            })
+            .collect()
+    }
+
+    /// Set `channels` from the channel data, which is authoritative when the two
+    /// disagree. Returns whether the count changed.
+    pub fn sync_channels(&mut self) -> bool {
+        let channels = self.data.len() as u16;
+        let changed = self.channels != channels;
+        self.channels = channels;
+        changed
+    }
+
+    /// Rebuild the channel list from `order`; channels not listed are dropped
+    pub fn reorder_channels(&mut self, order: &[usize]) -> R<()> {
+        if order.is_empty() {
+            return Err(anyhow::anyhow!("Channel order cannot be empty"));
+        }
+        if let Some(bad) = order.iter().find(|&&i| i >= self.data.len()) {
+            return Err(anyhow::anyhow!(
+                "Channel {} out of range ({} channels)",
+                bad,
+                self.data.len()
+            ));
+        }
+
+        self.data = order.iter().map(|&i| self.data[i].clone()).collect();
+        self.channels = self.data.len() as u16;
+        Ok(())
+    }
+
+    /// Negate every sample, or only the listed channel indices
+    pub fn invert_polarity(&mut self, channels: Option<&[usize]>) {
+        for (i, channel) in self.data.iter_mut().enumerate() {
+            if channels.is_none_or(|selected| selected.contains(&i)) {
+                channel.iter_mut().for_each(|sample| *sample = -*sample);
+            }
+        }
+    }
+
+    /// Sum another buffer into this one, sample by sample
+    pub fn mix(&mut self, other: &AudioBuffer) -> R<()> {
+        if self.sample_rate != other.sample_rate || self.data.len() != other.data.len() {
+            return Err(anyhow::anyhow!(
+                "Cannot mix buffers with different sample rates or channel counts"
+            ));
+        }
+
+        for (channel, other_channel) in self.data.iter_mut().zip(other.data.iter()) {
+            if other_channel.len() > channel.len() {
+                channel.resize(other_channel.len(), 0.0);
+            }
+            for (sample, other_sample) in channel.iter_mut().zip(other_channel.iter()) {
+                *sample += other_sample;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Peak, RMS, clip count and DC offset over all channels in a single pass
+    pub fn stats(&self) -> AudioStats {
+        let mut stats = AudioStats::default();
+        let mut sum = 0.0f64;
+        let mut sum_squares = 0.0f64;
+
+        for &sample in self.data.iter().flatten() {
+            let magnitude = sample.abs();
+            stats.peak = stats.peak.max(magnitude);
+            if magnitude >= CLIP_THRESHOLD {
+                stats.clip_count += 1;
+            }
+            sum += sample as f64;
+            sum_squares += sample as f64 * sample as f64;
+            stats.sample_count += 1;
+        }
+
+        if stats.sample_count > 0 {
+            stats.rms = (sum_squares / stats.sample_count as f64).sqrt() as f32;
+            stats.dc_offset = (sum / stats.sample_count as f64) as f32;
+        }
+        stats
+    }
+
+    /// Per-channel and overall sample peak, 4x oversampled true peak and RMS,
+    /// plus BS.1770 integrated loudness
+    pub fn analyze(&self) -> LoudnessStats {
+        crate::loudness::analyze(self)
+    }
+
+    /// Apply one gain so integrated loudness reaches `target_lufs`, reduced as
+    /// needed to keep the true peak at or below `true_peak_ceiling_db`.
+    /// Returns the integrated loudness actually achieved.
+    pub fn normalize_lufs(&mut self, target_lufs: f32, true_peak_ceiling_db: f32) -> R<f32> {
+        let stats = self.analyze();
+        if !stats.integrated_lufs.is_finite() {
+            return Err(anyhow!("Cannot normalize silent or too-short audio"));
+        }
+
+        let loudness_gain = (target_lufs - stats.integrated_lufs) as f64;
+        let peak_gain = (true_peak_ceiling_db - stats.true_peak_dbfs) as f64;
+        let gain_db = loudness_gain.min(peak_gain);
+        let achieved = stats.integrated_lufs + gain_db as f32;
+        if peak_gain < loudness_gain {
+            dprintln!(
+                "Loudness normalize: true peak ceiling {} dB limits loudness to {:.2} LUFS (target {})",
+                true_peak_ceiling_db,
+                achieved,
+                target_lufs
+            );
+        }
+
+        let gain = 10f64.powf(gain_db / 20.0) as f32;
+        for sample in self.data.iter_mut().flatten() {
+            *sample *= gain;
+        }
+        Ok(achieved)
+    }
+
+    /// Scale every channel by one gain so the sample peak reaches `target_dbfs`.
+    /// Returns the gain applied in dB. Silent audio is an error.
+    pub fn normalize_peak(&mut self, target_dbfs: f32) -> R<f32> {
+        let peak = self.peak();
+        if peak == 0.0 {
+            return Err(anyhow!("Cannot normalize silent audio"));
+        }
+        let gain_db = target_dbfs - 20.0 * peak.log10();
+        self.apply_gain_all(gain_db, false);
+        Ok(gain_db)
+    }
+
+    /// Largest absolute sample value over all channels
+    pub fn peak(&self) -> f32 {
+        self.data
+            .iter()
+            .flatten()
+            .fold(0.0, |peak, sample| peak.max(sample.abs()))
+    }
+
+    /// Root mean square level over all channels
+    pub fn rms(&self) -> f32 {
+        let count = self.data.iter().map(Vec::len).sum::<usize>();
+        if count == 0 {
+            return 0.0;
+        }
+        let sum_squares: f64 = self
+            .data
+            .iter()
+            .flatten()
+            .map(|&sample| sample as f64 * sample as f64)
+            .sum();
+        (sum_squares / count as f64).sqrt() as f32
+    }
+
+    /// Number of samples at or beyond full scale
+    pub fn count_clipped(&self) -> usize {
+        self.data
+            .iter()
+            .flatten()
+            .filter(|sample| sample.abs() >= CLIP_THRESHOLD)
+            .count()
+    }
+
+    /// Mean sample value over all channels
+    pub fn dc_offset(&self) -> f32 {
+        let count = self.data.iter().map(Vec::len).sum::<usize>();
+        if count == 0 {
+            return 0.0;
+        }
+        let sum: f64 = self
+            .data
+            .iter()
+            .flatten()
+            .map(|&sample| sample as f64)
+            .sum();
+        (sum / count as f64) as f32
+    }
+}
+
+/// Level above which `ClipMode::Soft` starts bending the signal
+const SOFT_CLIP_KNEE: f32 = 0.9;
+
+fn apply_channel_gain(channel: &mut [f32], gain_db: f32, clamp: bool) {
+    let gain = 10f32.powf(gain_db / 20.0);
+    for sample in channel.iter_mut() {
+        *sample *= gain;
+        if clamp {
+            *sample = sample.clamp(-1.0, 1.0);
+        }
+    }
+}
+
+/// Unchanged up to the knee, then a tanh curve that meets it with the same
+/// slope and approaches full scale. In f32 the curve rounds to exactly ±1.0
+/// for peaks far enough over, from about 1.6 up.
+fn soft_clip(sample: f32) -> f32 {
+    let magnitude = sample.abs();
+    if magnitude <= SOFT_CLIP_KNEE {
+        return sample;
+    }
+    let headroom = 1.0 - SOFT_CLIP_KNEE;
+    let bent = SOFT_CLIP_KNEE + headroom * ((magnitude - SOFT_CLIP_KNEE) / headroom).tanh();
+    bent.copysign(sample)
+}
+
+/// Samples at or above the largest positive 16-bit value count as clipped
+const CLIP_THRESHOLD: f32 = 32767.0 / 32768.0;
+
+/// Level statistics gathered by `AudioBuffer::stats`, all linear full scale = 1.0
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AudioStats {
+    pub peak: f32,
+    pub rms: f32,
+    pub clip_count: usize,
+    pub dc_offset: f32,
+    pub sample_count: usize,
+}
+
+impl AudioStats {
+    pub fn peak_dbfs(&self) -> f32 {
+        20.0 * self.peak.log10()
+    }
+
+    pub fn rms_dbfs(&self) -> f32 {
+        20.0 * self.rms.log10()
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -121,29 +766,293 @@ impl SampleFormat {
     }
 }
 
+/// Gain curve of `AudioBuffer::apply_fade`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FadeCurve {
+    /// Gain rises in a straight line
+    #[default]
+    Linear,
+    /// Quarter sine, so two overlapping fades keep constant power
+    EqualPower,
+    /// Gain rises evenly in dB from `FADE_FLOOR_DB`, then jumps from silence
+    Logarithmic,
+}
+
+/// Starting level of a `FadeCurve::Logarithmic` ramp
+const FADE_FLOOR_DB: f32 = -60.0;
+
+impl FadeCurve {
+    /// Gain at `position` through the ramp, from 0.0 (silent) to 1.0 (unity)
+    fn gain(self, position: f32) -> f32 {
+        match self {
+            FadeCurve::Linear => position,
+            FadeCurve::EqualPower => (position * std::f32::consts::FRAC_PI_2).sin(),
+            FadeCurve::Logarithmic if position <= 0.0 => 0.0,
+            FadeCurve::Logarithmic => 10f32.powf(FADE_FLOOR_DB * (1.0 - position) / 20.0),
+        }
+    }
+}
+
+/// Noise added when `set_format` or `change_bit_depth` reduces bit depth
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DitherMode {
+    /// Plain rounding to the target resolution
+    None,
+    /// Uniform (RPDF) dither of 1 LSB peak-to-peak at the target resolution
+    Rectangular,
+    /// Triangular (TPDF) dither of ±1 LSB at the target resolution
+    #[default]
+    Triangular,
+    /// Triangular dither with 2nd-order error feedback moving the noise up in frequency
+    NoiseShaped,
+}
+
+/// How samples beyond full scale are limited before integer quantization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClipMode {
+    /// Clamp to ±1.0
+    #[default]
+    Hard,
+    /// Round off peaks above `SOFT_CLIP_KNEE` with a tanh curve
+    Soft,
+    /// No clip stage. Integer encoders still clamp at full scale when they
+    /// quantize, so for integer output this gives the same samples as `Hard`;
+    /// only `AudioBuffer::clip` and float output leave overs untouched.
+    None,
+}
+
+/// How `AudioBuffer::to_mono` reduces a buffer to one channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MonoMode {
+    /// Sum of all channels divided by the channel count, so it can't clip.
+    /// Correlated channels keep their level; there is no energy compensation
+    /// for uncorrelated ones.
+    #[default]
+    Average,
+    /// Keep one channel (0-based) and drop the rest
+    SelectChannel(usize),
+}
+
+/// How surrounds fold into the two outputs of `AudioBuffer::downmix_to_stereo`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DownmixMatrix {
+    /// Lo/Ro: each surround folds into its own side
+    #[default]
+    LoRo,
+    /// Lt/Rt: surrounds are summed and matrixed out of phase for Pro Logic decoding
+    LtRt,
+}
+
+/// Gains for `AudioBuffer::downmix_to_stereo`. The default is the ITU-R BS.775
+/// fold-down: centre and surrounds at -3 dB, LFE left out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DownmixCoefficients {
+    pub center_db: f32,
+    pub surround_db: f32,
+    /// LFE gain; None drops the LFE channel
+    pub lfe_db: Option<f32>,
+    pub matrix: DownmixMatrix,
+}
+
+impl Default for DownmixCoefficients {
+    fn default() -> Self {
+        DownmixCoefficients {
+            center_db: -3.0,
+            surround_db: -3.0,
+            lfe_db: None,
+            matrix: DownmixMatrix::LoRo,
+        }
+    }
+}
+
+/// Part a channel plays in a stereo fold-down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Speaker {
+    Left,
+    Right,
+    Center,
+    Lfe,
+    LeftSurround,
+    RightSurround,
+    BackCenter,
+}
+
+/// WAVE_FORMAT_EXTENSIBLE speaker bits, in channel order. Front-of-centre and
+/// height channels fold in with the nearest ear-level speaker.
+const SPEAKER_POSITIONS: [(u32, Speaker); 18] = [
+    (0x1, Speaker::Left),              // front left
+    (0x2, Speaker::Right),             // front right
+    (0x4, Speaker::Center),            // front center
+    (0x8, Speaker::Lfe),               // low frequency
+    (0x10, Speaker::LeftSurround),     // back left
+    (0x20, Speaker::RightSurround),    // back right
+    (0x40, Speaker::Left),             // front left of center
+    (0x80, Speaker::Right),            // front right of center
+    (0x100, Speaker::BackCenter),      // back center
+    (0x200, Speaker::LeftSurround),    // side left
+    (0x400, Speaker::RightSurround),   // side right
+    (0x800, Speaker::Center),          // top center
+    (0x1000, Speaker::Left),           // top front left
+    (0x2000, Speaker::Center),         // top front center
+    (0x4000, Speaker::Right),          // top front right
+    (0x8000, Speaker::LeftSurround),   // top back left
+    (0x10000, Speaker::BackCenter),    // top back center
+    (0x20000, Speaker::RightSurround), // top back right
+];
+
+/// Speaker of each channel, from the channel mask when it covers every
+/// channel, otherwise the WAV order of 5.1 and 7.1. Other channel counts
+/// without a mask (e.g. 4-channel multi-mono) have no known layout.
+fn speaker_layout(channels: usize, channel_mask: u32) -> R<Vec<Speaker>> {
+    if channel_mask.count_ones() as usize == channels {
+        return Ok(SPEAKER_POSITIONS
+            .iter()
+            .filter(|(bit, _)| channel_mask & bit != 0)
+            .map(|&(_, speaker)| speaker)
+            .collect());
+    }
+    use Speaker::*;
+    match channels {
+        6 => Ok(vec![Left, Right, Center, Lfe, LeftSurround, RightSurround]),
+        8 => Ok(vec![
+            Left,
+            Right,
+            Center,
+            Lfe,
+            LeftSurround,
+            RightSurround,
+            LeftSurround,
+            RightSurround,
+        ]),
+        n => Err(anyhow!(
+            "No surround layout known for {} channels without a channel mask",
+            n
+        )),
+    }
+}
+
+/// Options controlling how `Codex::export_with_options` writes a file
+#[derive(Debug, Clone, Default)]
+pub struct EncodeOptions {
+    /// Synthesize minimal broadcast metadata when the Codex has none loaded
+    pub synthesize_missing_metadata: bool,
+    /// Originator written into synthesized metadata
+    pub originator: Option<String>,
+    /// Average all channels down to one before encoding
+    pub force_mono: bool,
+    /// Clipping applied to integer formats; float output is written unclipped
+    pub clip_mode: ClipMode,
+    /// Stamp loaded metadata lacking a bext OriginationDate/Time with the current
+    /// UTC date and time, as synthesized metadata already is
+    pub fill_origination_date_time: bool,
+    /// WavPack and FLAC: compute an MD5 of the audio while encoding and store it in
+    /// the file (FLAC's goes in STREAMINFO)
+    pub store_md5: bool,
+    /// FLAC: compression level and block size; None keeps the default encoder settings
+    pub flac: Option<FlacEncodeOptions>,
+    /// WavPack: whether text metadata goes into native tag items, an iXML item, or both
+    pub wavpack_tags: WavpackTags,
+    /// WavPack: encode in lossy hybrid mode; None encodes losslessly. Exports write
+    /// the correction stream next to the output with a `.wvc` extension.
+    pub wavpack_hybrid: Option<WavpackHybrid>,
+    /// WavPack: compression effort, from fast to very high
+    pub wavpack_quality: WavpackQuality,
+    /// WavPack: encoder threads, with 0 meaning every available core; None
+    /// encodes on one thread. The output is the same for any count.
+    pub wavpack_threads: Option<usize>,
+}
+
+impl EncodeOptions {
+    /// Buffer to encode after applying these options, or None if the source can be used as-is
+    pub fn prepare_buffer(&self, buffer: &AudioBuffer) -> Option<AudioBuffer> {
+        let mut prepared =
+            (self.force_mono && buffer.data.len() > 1).then(|| buffer.averaged_mono());
+        // Hard clipping is what the integer encoders do anyway
+        if self.clip_mode == ClipMode::Soft && buffer.format != SampleFormat::F32 {
+            prepared
+                .get_or_insert_with(|| buffer.clone())
+                .clip(ClipMode::Soft);
+        }
+        // Encoders disagree on a stale `channels` count; the channel data wins
+        if buffer.channels as usize != buffer.data.len() {
+            prepared
+                .get_or_insert_with(|| buffer.clone())
+                .sync_channels();
+        }
+        prepared
+    }
+}
+
+/// How `Metadata::merge` settles a field or image both sides carry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Keep the existing value; the overlay only adds what is missing
+    PreferSelf,
+    /// The overlay's value replaces the existing one
+    PreferOther,
+    /// As `PreferSelf`, but an existing empty value is filled too
+    FillEmptyOnly,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Metadata {
     map: std::collections::HashMap<String, String>, // Key-value pairs for metadata fields
+    multi_values: std::collections::HashMap<String, Vec<String>>, // All values for repeated keys
     images: Vec<ImageChunk>,                        // Associated images (album art, etc.)
+    markers: Vec<CueMarker>,                        // Cue points with adtl labels
+    raw_chunks: Vec<([u8; 4], Vec<u8>)>,            // Caller-supplied chunks written verbatim
+    application_data: Vec<([u8; 4], Vec<u8>)>,      // AIFF APPL chunks: OSType signature and data
     // Audio format information needed for fmt chunk reconstruction
     pub sample_rate: u32,
     pub channels: u16,
     pub bit_depth: u16,
     pub format_tag: u16,
+    /// WAVE_FORMAT_EXTENSIBLE / WavPack speaker mask; 0 when the file has none
+    pub channel_mask: u32,
 }
 
 impl Metadata {
     pub fn new() -> Self {
         Metadata {
             map: std::collections::HashMap::new(),
+            multi_values: std::collections::HashMap::new(),
             images: Vec::new(),
+            markers: Vec::new(),
+            raw_chunks: Vec::new(),
+            application_data: Vec::new(),
             sample_rate: 0,
             channels: 0,
             bit_depth: 0,
             format_tag: 1, // Default to PCM
+            channel_mask: 0,
         }
     }
 
+    /// Minimal broadcast metadata stamped with the current UTC date and time
+    pub fn synthesized(originator: Option<&str>) -> R<Self> {
+        let mut metadata = Metadata::new();
+        metadata.fill_origination_date_time()?;
+        if let Some(originator) = originator {
+            metadata.set_field("Originator", originator)?;
+            metadata.set_field("USER_DESIGNER", originator)?;
+        }
+        Ok(metadata)
+    }
+
+    /// Set whichever of OriginationDate and OriginationTime is missing or empty to
+    /// the current UTC date/time. Returns whether anything was filled in.
+    pub fn fill_origination_date_time(&mut self) -> R<bool> {
+        let (date, time) = utc_date_time_now();
+        let mut filled = false;
+        for (key, now) in [("OriginationDate", date), ("OriginationTime", time)] {
+            if self.get_field(key).is_none_or(|value| value.is_empty()) {
+                self.set_field(key, &now)?;
+                filled = true;
+            }
+        }
+        Ok(filled)
+    }
+
     pub fn set_field(&mut self, key: &str, value: &str) -> R<()> {
         let trimmed_value = value.trim().replace("\n", "").replace("\r", "");
 
@@ -151,11 +1060,13 @@ impl Metadata {
         match keys.is_empty() {
             true => {
                 dprintln!("Set field: {} = {}", key, &trimmed_value);
+                self.clear_extra_values(key, &trimmed_value);
                 self.map.insert(key.to_string(), trimmed_value);
             }
             false => {
                 for key in keys {
                     dprintln!("Set field: {} = {}", key, &trimmed_value);
+                    self.clear_extra_values(key, &trimmed_value);
                     self.map.insert(key.to_string(), trimmed_value.clone());
                 }
             }
@@ -164,6 +1075,39 @@ impl Metadata {
         Ok(())
     }
 
+    /// Add another value to a field that may repeat (e.g. multiple Vorbis ARTIST comments).
+    /// The first value stays visible through `get_field`; all values through `get_field_all`.
+    /// A value the field already holds is not added again, since aliases written as
+    /// separate tags (ARTIST and DESIGNER) bring the same value back more than once.
+    pub fn add_field_value(&mut self, key: &str, value: &str) -> R<()> {
+        if self.get_field(key).is_none() {
+            return self.set_field(key, value);
+        }
+
+        let trimmed_value = value.trim().replace("\n", "").replace("\r", "");
+        if self.get_field_all(key).contains(&trimmed_value) {
+            return Ok(());
+        }
+        let keys = get_metadata_keys(key);
+        let keys: Vec<&str> = if keys.is_empty() {
+            vec![key]
+        } else {
+            keys.to_vec()
+        };
+
+        for key in keys {
+            let Some(first) = self.map.get(key).cloned() else {
+                continue;
+            };
+            self.multi_values
+                .entry(key.to_string())
+                .or_insert_with(|| vec![first])
+                .push(trimmed_value.clone());
+        }
+
+        Ok(())
+    }
+
     pub fn get_field(&self, key: &str) -> Option<String> {
         let keys = get_metadata_keys(key);
         for k in keys {
@@ -174,6 +1118,312 @@ impl Metadata {
         self.map.get(key).cloned()
     }
 
+    // Re-setting a field to its current primary value (e.g. the same artist coming back
+    // through iXML) keeps the repeated values; any other value replaces them.
+    fn clear_extra_values(&mut self, key: &str, value: &str) {
+        if self.map.get(key).map(String::as_str) != Some(value) {
+            self.multi_values.remove(key);
+        }
+    }
+
+    /// Get every value stored for a field, in the order they were added
+    pub fn get_field_all(&self, key: &str) -> Vec<String> {
+        let keys = get_metadata_keys(key);
+        for k in keys.iter().copied().chain(std::iter::once(key)) {
+            if let Some(values) = self.multi_values.get(k) {
+                return values.clone();
+            }
+            if let Some(v) = self.map.get(k) {
+                return vec![v.clone()];
+            }
+        }
+        Vec::new()
+    }
+
+    /// Remove a field under every alias, along with its repeated values and any
+    /// fields derived from it (e.g. `USER_CATEGORYFULL` from `USER_CATEGORY`).
+    /// Returns the value `get_field` gave before removal.
+    pub fn remove_field(&mut self, key: &str) -> Option<String> {
+        let previous = self.get_field(key);
+        let keys = get_metadata_keys(key);
+        for k in keys.iter().copied().chain(std::iter::once(key)) {
+            self.map.remove(k);
+            self.multi_values.remove(k);
+        }
+        if !keys.is_empty() {
+            for (source, derived) in DERIVED_FIELDS {
+                if get_metadata_keys(source) == keys {
+                    self.remove_field(derived);
+                }
+            }
+        }
+        previous
+    }
+
+    /// Remove every content field and image. Technical stream fields, markers,
+    /// raw chunks, application data and the audio format stay.
+    pub fn clear_fields(&mut self) {
+        self.map.retain(|key, _| is_technical_field(key));
+        self.multi_values.retain(|key, _| is_technical_field(key));
+        self.images.clear();
+    }
+
+    /// Number of stored fields (aliases count separately), not counting technical
+    /// stream fields such as `WAVPACK_MODE`
+    pub fn len(&self) -> usize {
+        self.field_count(false)
+    }
+
+    /// Number of fields, optionally including technical stream fields
+    pub fn field_count(&self, include_technical: bool) -> usize {
+        self.map
+            .keys()
+            .filter(|key| include_technical || !is_technical_field(key))
+            .count()
+    }
+
+    /// True when there is nothing worth writing: no content fields, images,
+    /// markers, raw chunks or application data
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+            && self.images.is_empty()
+            && self.markers.is_empty()
+            && self.raw_chunks.is_empty()
+            && self.application_data.is_empty()
+    }
+
+    /// Overlay `other`'s fields and images, settling conflicts by `strategy`.
+    /// Fields are compared through their aliases and images by description.
+    /// Empty overlay values never apply. The audio format, technical stream
+    /// fields, markers and chunks describe `other`'s own file and are left alone.
+    pub fn merge(&mut self, other: &Metadata, strategy: MergeStrategy) -> R<()> {
+        for (key, value) in &other.map {
+            if value.is_empty() || is_technical_field(key) {
+                continue;
+            }
+            let take = match (strategy, self.get_field(key)) {
+                (_, None) | (MergeStrategy::PreferOther, _) => true,
+                (MergeStrategy::FillEmptyOnly, Some(existing)) => existing.is_empty(),
+                (MergeStrategy::PreferSelf, Some(_)) => false,
+            };
+            if take {
+                self.set_field(key, value)?;
+                if let Some(values) = other.multi_values.get(key) {
+                    self.multi_values.insert(key.clone(), values.clone());
+                }
+            }
+        }
+
+        for image in &other.images {
+            match self
+                .images
+                .iter_mut()
+                .find(|existing| existing.description() == image.description())
+            {
+                None => self.images.push(image.clone()),
+                Some(existing) if strategy == MergeStrategy::PreferOther => {
+                    *existing = image.clone();
+                }
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// True when both carry the same fields, values, images and markers.
+    /// Technical stream fields (`PCM_MD5`, `WAVPACK_MODE`, ...) describe the
+    /// audio rather than the tags, are never written back, and are ignored.
+    pub fn content_eq(&self, other: &Metadata) -> bool {
+        fn same_content<V: PartialEq>(
+            a: &std::collections::HashMap<String, V>,
+            b: &std::collections::HashMap<String, V>,
+        ) -> bool {
+            let content = |map: &std::collections::HashMap<String, V>| {
+                map.keys().filter(|key| !is_technical_field(key)).count()
+            };
+            content(a) == content(b)
+                && a.iter()
+                    .filter(|(key, _)| !is_technical_field(key))
+                    .all(|(key, value)| b.get(key) == Some(value))
+        }
+
+        same_content(&self.map, &other.map)
+            && same_content(&self.multi_values, &other.multi_values)
+            && self.images == other.images
+            && self.markers == other.markers
+            && self.raw_chunks == other.raw_chunks
+            && self.application_data == other.application_data
+    }
+
+    /// Serialize everything `content_eq` compares, plus the audio format, as
+    /// pretty-printed JSON with sorted keys so it diffs cleanly. Binary data
+    /// (images, chunks) is base64; chunk ids are four Latin-1 characters.
+    /// `from_json` restores it exactly.
+    pub fn to_json(&self) -> R<String> {
+        use base64::{Engine as _, engine::general_purpose::STANDARD};
+        use serde_json::json;
+        use std::collections::BTreeMap;
+
+        let chunks = |chunks: &[([u8; 4], Vec<u8>)]| -> Vec<serde_json::Value> {
+            chunks
+                .iter()
+                .map(|(id, data)| {
+                    json!({
+                        "id": id.iter().map(|&b| b as char).collect::<String>(),
+                        "data": STANDARD.encode(data),
+                    })
+                })
+                .collect()
+        };
+        let images: Vec<_> = self
+            .images
+            .iter()
+            .map(|image| {
+                json!({
+                    "mime_type": image.mime_type,
+                    "description": image.description,
+                    "picture_type": image.picture_type.code(),
+                    "data": STANDARD.encode(&image.data),
+                })
+            })
+            .collect();
+        let markers: Vec<_> = self
+            .markers
+            .iter()
+            .map(|marker| {
+                json!({
+                    "id": marker.id,
+                    "position": marker.position,
+                    "length": marker.length,
+                    "purpose": marker.purpose,
+                    "label": marker.label,
+                    "note": marker.note,
+                    "text": marker.text,
+                })
+            })
+            .collect();
+        let document = json!({
+            "version": METADATA_JSON_VERSION,
+            "format": {
+                "sample_rate": self.sample_rate,
+                "channels": self.channels,
+                "bit_depth": self.bit_depth,
+                "format_tag": self.format_tag,
+                "channel_mask": self.channel_mask,
+            },
+            "fields": self.map.iter().collect::<BTreeMap<_, _>>(),
+            "multi_values": self.multi_values.iter().collect::<BTreeMap<_, _>>(),
+            "images": images,
+            "markers": markers,
+            "raw_chunks": chunks(&self.raw_chunks),
+            "application_data": chunks(&self.application_data),
+        });
+        Ok(serde_json::to_string_pretty(&document)?)
+    }
+
+    /// Parse metadata written by `to_json`. Fields are restored under their
+    /// exact keys, without the alias expansion `set_field` does.
+    pub fn from_json(s: &str) -> R<Metadata> {
+        use base64::{Engine as _, engine::general_purpose::STANDARD};
+        use serde_json::Value;
+
+        let document: Value =
+            serde_json::from_str(s).map_err(|e| anyhow!("Invalid metadata JSON: {}", e))?;
+        let version = document["version"].as_u64();
+        if version != Some(METADATA_JSON_VERSION) {
+            return Err(anyhow!("Unsupported metadata JSON version: {:?}", version));
+        }
+
+        let string = |value: &Value, what: &str| -> R<String> {
+            value
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| anyhow!("Metadata JSON: {} must be a string", what))
+        };
+        let number = |value: &Value, what: &str| -> R<u64> {
+            value
+                .as_u64()
+                .ok_or_else(|| anyhow!("Metadata JSON: {} must be a number", what))
+        };
+        let bytes = |value: &Value, what: &str| -> R<Vec<u8>> {
+            STANDARD
+                .decode(string(value, what)?)
+                .map_err(|e| anyhow!("Metadata JSON: {} is not valid base64: {}", what, e))
+        };
+        let optional = |value: &Value, what: &str| -> R<Option<String>> {
+            match value {
+                Value::Null => Ok(None),
+                value => string(value, what).map(Some),
+            }
+        };
+        let list = |value: &Value| value.as_array().cloned().unwrap_or_default();
+        let chunks = |value: &Value| -> R<Vec<([u8; 4], Vec<u8>)>> {
+            list(value)
+                .iter()
+                .map(|chunk| {
+                    let id: Vec<u8> = string(&chunk["id"], "chunk id")?
+                        .chars()
+                        .map(u8::try_from)
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| anyhow!("Metadata JSON: chunk ids are Latin-1"))?;
+                    let id = <[u8; 4]>::try_from(id)
+                        .map_err(|_| anyhow!("Metadata JSON: chunk ids are four characters"))?;
+                    Ok((id, bytes(&chunk["data"], "chunk data")?))
+                })
+                .collect()
+        };
+
+        let mut metadata = Metadata::new();
+        let format = &document["format"];
+        metadata.sample_rate = number(&format["sample_rate"], "sample_rate")? as u32;
+        metadata.channels = number(&format["channels"], "channels")? as u16;
+        metadata.bit_depth = number(&format["bit_depth"], "bit_depth")? as u16;
+        metadata.format_tag = number(&format["format_tag"], "format_tag")? as u16;
+        metadata.channel_mask = number(&format["channel_mask"], "channel_mask")? as u32;
+
+        if let Some(fields) = document["fields"].as_object() {
+            for (key, value) in fields {
+                metadata
+                    .map
+                    .insert(key.clone(), string(value, key.as_str())?);
+            }
+        }
+        if let Some(multi_values) = document["multi_values"].as_object() {
+            for (key, values) in multi_values {
+                let values = list(values)
+                    .iter()
+                    .map(|value| string(value, key.as_str()))
+                    .collect::<R<_>>()?;
+                metadata.multi_values.insert(key.clone(), values);
+            }
+        }
+        for image in list(&document["images"]) {
+            let picture_type = number(&image["picture_type"], "picture_type")? as u8;
+            metadata.images.push(
+                ImageChunk::new(
+                    string(&image["mime_type"], "image mime_type")?,
+                    string(&image["description"], "image description")?,
+                    bytes(&image["data"], "image data")?,
+                )
+                .with_picture_type(PictureType::from_code(picture_type)),
+            );
+        }
+        for marker in list(&document["markers"]) {
+            metadata.markers.push(CueMarker {
+                id: number(&marker["id"], "marker id")? as u32,
+                position: number(&marker["position"], "marker position")? as u32,
+                length: number(&marker["length"], "marker length")? as u32,
+                purpose: string(&marker["purpose"], "marker purpose")?,
+                label: optional(&marker["label"], "marker label")?,
+                note: optional(&marker["note"], "marker note")?,
+                text: optional(&marker["text"], "marker text")?,
+            });
+        }
+        metadata.raw_chunks = chunks(&document["raw_chunks"])?;
+        metadata.application_data = chunks(&document["application_data"])?;
+        Ok(metadata)
+    }
+
     pub fn add_image(&mut self, image: ImageChunk) {
         self.images.push(image);
     }
@@ -182,6 +1432,67 @@ impl Metadata {
         &self.images
     }
 
+    pub fn add_marker(&mut self, marker: CueMarker) {
+        match self.markers.iter_mut().find(|m| m.id == marker.id) {
+            Some(existing) => *existing = marker,
+            None => self.markers.push(marker),
+        }
+    }
+
+    pub fn get_markers(&self) -> &[CueMarker] {
+        &self.markers
+    }
+
+    pub(crate) fn markers_mut(&mut self) -> &mut Vec<CueMarker> {
+        &mut self.markers
+    }
+
+    /// Queue a chunk to be written verbatim among the metadata chunks on the next
+    /// embed. Ids that carry audio or format data are rejected.
+    pub fn add_raw_chunk(&mut self, id: [u8; 4], data: Vec<u8>) -> R<()> {
+        const RESERVED: [&[u8; 4]; 6] = [b"RIFF", b"fmt ", b"data", b"FORM", b"COMM", b"SSND"];
+        if RESERVED.contains(&&id) {
+            return Err(anyhow!(
+                "Chunk '{}' is managed by the codec and cannot be added raw",
+                String::from_utf8_lossy(&id)
+            ));
+        }
+        self.raw_chunks.push((id, data));
+        Ok(())
+    }
+
+    pub fn get_raw_chunks(&self) -> &[([u8; 4], Vec<u8>)] {
+        &self.raw_chunks
+    }
+
+    /// Keep application-specific data (an AIFF `APPL` chunk) under its OSType
+    /// signature; AIFF embeds write it back. Identical entries are stored once.
+    pub fn add_application_data(&mut self, signature: [u8; 4], data: Vec<u8>) {
+        let entry = (signature, data);
+        if !self.application_data.contains(&entry) {
+            self.application_data.push(entry);
+        }
+    }
+
+    pub fn get_application_data(&self) -> &[([u8; 4], Vec<u8>)] {
+        &self.application_data
+    }
+
+    /// Marker with the given cue ID, created if it doesn't exist yet
+    pub fn marker_mut(&mut self, id: u32) -> &mut CueMarker {
+        let index = match self.markers.iter().position(|m| m.id == id) {
+            Some(index) => index,
+            None => {
+                self.markers.push(CueMarker {
+                    id,
+                    ..Default::default()
+                });
+                self.markers.len() - 1
+            }
+        };
+        &mut self.markers[index]
+    }
+
     pub fn get_all_fields(&self) -> &std::collections::HashMap<String, String> {
         &self.map
     }
@@ -274,7 +1585,7 @@ impl Metadata {
     //     Ok(())
     // }
 
-    /// Parse basic ID3 metadata (ID3v1 and simple ID3v2)
+    /// Parse ID3 metadata: a full ID3v2 tag, or an ID3v1 tag at the end of `data`
     pub fn parse_id3(&mut self, data: &[u8]) -> R<()> {
         if data.is_empty() {
             return Ok(());
@@ -282,80 +1593,57 @@ impl Metadata {
 
         // Check for ID3v2 tag (starts with "ID3")
         if data.len() >= 10 && &data[0..3] == b"ID3" {
-            let version_major = data[3];
-            let version_minor = data[4];
-
-            // Parse syncsafe integer (7 bits per byte, MSB is always 0)
-            let size = ((data[6] as u32) << 21)
-                | ((data[7] as u32) << 14)
-                | ((data[8] as u32) << 7)
-                | (data[9] as u32);
-
-            self.set_field(
-                "ID3Version",
-                &format!("2.{}.{}", version_major, version_minor),
-            )?;
-
-            if size > 0 && data.len() >= (10 + size as usize) {
-                let tag_data = &data[10..(10 + size as usize)];
-
-                // Simple ID3 parsing - just extract common text frames
-                if version_major >= 3 {
-                    self.parse_id3v2_frames(tag_data)?;
-                }
-            }
+            crate::id3::parse_tag(data, self)?;
         }
         // Check for ID3v1 tag (last 128 bytes, starts with "TAG")
-        else if data.len() >= 128 && &data[data.len() - 128..data.len() - 125] == b"TAG" {
-            let tag_start = data.len() - 128;
-
-            // Parse ID3v1 fields
-            if let Some(title) = clean_text_field(&data[tag_start + 3..tag_start + 33]) {
-                self.set_field("Title", &title)?;
-            }
-            if let Some(artist) = clean_text_field(&data[tag_start + 33..tag_start + 63]) {
-                self.set_field("Artist", &artist)?;
-            }
-            if let Some(album) = clean_text_field(&data[tag_start + 63..tag_start + 93]) {
-                self.set_field("Album", &album)?;
-            }
-            if let Some(year) = clean_text_field(&data[tag_start + 93..tag_start + 97]) {
-                self.set_field("Year", &year)?;
-            }
-
-            self.set_field("ID3Version", "1.0")?;
+        else if data.len() >= ID3V1_TAG_SIZE {
+            self.parse_id3v1(&data[data.len() - ID3V1_TAG_SIZE..])?;
         }
 
         Ok(())
     }
 
-    /// Parse ID3v2 frames (basic implementation)
-    fn parse_id3v2_frames(&mut self, data: &[u8]) -> R<()> {
-        let mut offset = 0;
-        while offset + 10 <= data.len() {
-            // ID3v2.3/2.4 frame header: 4-byte ID + 4-byte size + 2-byte flags
-            let frame_id = String::from_utf8_lossy(&data[offset..offset + 4]).to_string();
-
-            let frame_size = ((data[offset + 4] as usize) << 24)
-                | ((data[offset + 5] as usize) << 16)
-                | ((data[offset + 6] as usize) << 8)
-                | (data[offset + 7] as usize);
-
-            if frame_size == 0 || offset + 10 + frame_size > data.len() {
-                break;
-            }
-
-            let frame_data = &data[offset + 10..offset + 10 + frame_size];
+    /// Parse a 128-byte ID3v1/ID3v1.1 tag into `TAG_` fields, as ID3v2 frames
+    /// are, and into the bare `Title`/`Album`/`Track`/`Genre`/... keys earlier
+    /// versions used. Only fills fields that are still empty, since any other
+    /// metadata source is richer.
+    pub fn parse_id3v1(&mut self, tag: &[u8]) -> R<()> {
+        if tag.len() != ID3V1_TAG_SIZE || &tag[0..3] != b"TAG" {
+            return Ok(());
+        }
 
-            // Parse common text frames
-            if let Some(key) = get_id3_frame_name(&frame_id)
-                && let Some(text) = parse_id3_text_frame(frame_data)
+        let mut fields = vec![
+            ("TAG_Title", clean_text_field(&tag[3..33])),
+            ("TAG_Artist", clean_text_field(&tag[33..63])),
+            ("TAG_Album", clean_text_field(&tag[63..93])),
+            ("TAG_Year", clean_text_field(&tag[93..97])),
+        ];
+        // ID3v1.1 gives up the last two comment bytes for a zero and a track number
+        let is_v1_1 = tag[125] == 0 && tag[126] != 0;
+        if is_v1_1 {
+            fields.push(("TAG_Track", Some(tag[126].to_string())));
+        }
+        let comment_end = if is_v1_1 { 125 } else { 127 };
+        fields.push(("TAG_Comment", clean_text_field(&tag[97..comment_end])));
+        fields.push((
+            "TAG_Genre",
+            ID3V1_GENRES.get(tag[127] as usize).map(|g| g.to_string()),
+        ));
+
+        for (key, value) in fields {
+            if let Some(value) = value
+                && self.get_field(key).is_none()
             {
-                let prefixed_key = format!("TAG_{}", key);
-                self.set_field(&prefixed_key, &text)?;
+                self.set_field(key, &value)?;
+                // Keep the bare keys (Title, Album, ...) ID3v1 values were read into before
+                let legacy_key = &key["TAG_".len()..];
+                if self.get_field(legacy_key).is_none() {
+                    self.set_field(legacy_key, &value)?;
+                }
             }
-
-            offset += 10 + frame_size;
+        }
+        if self.get_field("ID3Version").is_none() {
+            self.set_field("ID3Version", if is_v1_1 { "1.1" } else { "1.0" })?;
         }
         Ok(())
     }
@@ -422,6 +1710,111 @@ impl Metadata {
 }
 
 // Helper functions for common text processing
+/// Fields composed from another one, dropped when their source is removed
+const DERIVED_FIELDS: [(&str, &str); 2] = [
+    ("USER_CATEGORY", "USER_CATEGORYFULL"),
+    ("USER_SUBCATEGORY", "USER_CATEGORYFULL"),
+];
+
+/// Prefixes of fields codecs record about the stream itself rather than its content
+const TECHNICAL_FIELD_PREFIXES: [&str; 3] = ["WAVPACK_", "OPUS_", "PCM_"];
+
+pub(crate) fn is_technical_field(key: &str) -> bool {
+    TECHNICAL_FIELD_PREFIXES
+        .iter()
+        .any(|prefix| key.starts_with(prefix))
+}
+
+/// Schema version written by `Metadata::to_json`; bumped on incompatible changes
+const METADATA_JSON_VERSION: u64 = 1;
+
+/// Size of a legacy ID3v1 tag, which always sits at the very end of a file
+pub(crate) const ID3V1_TAG_SIZE: usize = 128;
+
+/// Standard ID3v1 genre names, indexed by the genre byte
+const ID3V1_GENRES: [&str; 80] = [
+    "Blues",
+    "Classic Rock",
+    "Country",
+    "Dance",
+    "Disco",
+    "Funk",
+    "Grunge",
+    "Hip-Hop",
+    "Jazz",
+    "Metal",
+    "New Age",
+    "Oldies",
+    "Other",
+    "Pop",
+    "R&B",
+    "Rap",
+    "Reggae",
+    "Rock",
+    "Techno",
+    "Industrial",
+    "Alternative",
+    "Ska",
+    "Death Metal",
+    "Pranks",
+    "Soundtrack",
+    "Euro-Techno",
+    "Ambient",
+    "Trip-Hop",
+    "Vocal",
+    "Jazz+Funk",
+    "Fusion",
+    "Trance",
+    "Classical",
+    "Instrumental",
+    "Acid",
+    "House",
+    "Game",
+    "Sound Clip",
+    "Gospel",
+    "Noise",
+    "AlternRock",
+    "Bass",
+    "Soul",
+    "Punk",
+    "Space",
+    "Meditative",
+    "Instrumental Pop",
+    "Instrumental Rock",
+    "Ethnic",
+    "Gothic",
+    "Darkwave",
+    "Techno-Industrial",
+    "Electronic",
+    "Pop-Folk",
+    "Eurodance",
+    "Dream",
+    "Southern Rock",
+    "Comedy",
+    "Cult",
+    "Gangsta",
+    "Top 40",
+    "Christian Rap",
+    "Pop/Funk",
+    "Jungle",
+    "Native American",
+    "Cabaret",
+    "New Wave",
+    "Psychadelic",
+    "Rave",
+    "Showtunes",
+    "Trailer",
+    "Lo-Fi",
+    "Tribal",
+    "Acid Punk",
+    "Acid Jazz",
+    "Polka",
+    "Retro",
+    "Musical",
+    "Rock & Roll",
+    "Hard Rock",
+];
+
 fn clean_text_field(data: &[u8]) -> Option<String> {
     let text = String::from_utf8_lossy(data)
         .trim_end_matches('\0')
@@ -430,43 +1823,43 @@ fn clean_text_field(data: &[u8]) -> Option<String> {
     if text.is_empty() { None } else { Some(text) }
 }
 
-fn get_id3_frame_name(frame_id: &str) -> Option<String> {
-    match frame_id {
-        "TIT2" => Some("Title".to_string()),
-        "TPE1" => Some("Artist".to_string()),
-        "TALB" => Some("Album".to_string()),
-        "TYER" | "TDRC" => Some("Year".to_string()),
-        "TCON" => Some("Genre".to_string()),
-        "TRCK" => Some("Track".to_string()),
-        "COMM" => Some("Comment".to_string()),
-        "TPE2" => Some("AlbumArtist".to_string()),
-        "TCOM" => Some("Composer".to_string()),
-        _ => None,
-    }
+/// What an attached picture shows, using the ID3v2/FLAC picture type codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PictureType {
+    #[default]
+    Other,
+    FrontCover,
+    BackCover,
+    Artist,
 }
 
-fn parse_id3_text_frame(data: &[u8]) -> Option<String> {
-    if data.is_empty() {
-        return None;
+impl PictureType {
+    pub fn code(self) -> u8 {
+        match self {
+            PictureType::Other => 0,
+            PictureType::FrontCover => 3,
+            PictureType::BackCover => 4,
+            PictureType::Artist => 8,
+        }
     }
 
-    // First byte is text encoding, skip it for simplicity
-    let text_data = &data[1..];
-    let text = String::from_utf8_lossy(text_data).to_string();
-    let trimmed = text.trim_end_matches('\0').trim();
-
-    if trimmed.is_empty() {
-        None
-    } else {
-        Some(trimmed.to_string())
+    /// Codes without a variant of their own map to `Other`
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            3 => PictureType::FrontCover,
+            4 => PictureType::BackCover,
+            8 => PictureType::Artist,
+            _ => PictureType::Other,
+        }
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ImageChunk {
     mime_type: String,
     description: String,
     data: Vec<u8>,
+    picture_type: PictureType,
 }
 
 impl ImageChunk {
@@ -475,9 +1868,19 @@ impl ImageChunk {
             mime_type,
             description,
             data,
+            picture_type: PictureType::Other,
         }
     }
 
+    pub fn with_picture_type(mut self, picture_type: PictureType) -> Self {
+        self.picture_type = picture_type;
+        self
+    }
+
+    pub fn picture_type(&self) -> PictureType {
+        self.picture_type
+    }
+
     pub fn mime_type(&self) -> &str {
         &self.mime_type
     }
@@ -491,6 +1894,49 @@ impl ImageChunk {
     }
 }
 
+/// A cue point with its `LIST adtl` annotations (`labl`, `note`, `ltxt`), linked by cue ID
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CueMarker {
+    pub id: u32,
+    pub position: u32,         // Sample offset into the data chunk
+    pub length: u32,           // Region length in samples from ltxt (0 for plain markers)
+    pub purpose: String,       // ltxt purpose code, e.g. "rgn "
+    pub label: Option<String>, // labl
+    pub note: Option<String>,  // note
+    pub text: Option<String>,  // ltxt text
+}
+
+/// Current UTC date and time formatted for bext (`YYYY-MM-DD`, `HH:MM:SS`)
+pub(crate) fn utc_date_time_now() -> (String, String) {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let days = (secs / 86400) as i64;
+    let remainder = secs % 86400;
+
+    // Civil-from-days conversion (proleptic Gregorian calendar)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (
+        format!("{:04}-{:02}-{:02}", year, month, day),
+        format!(
+            "{:02}:{:02}:{:02}",
+            remainder / 3600,
+            (remainder % 3600) / 60,
+            remainder % 60
+        ),
+    )
+}
+
 pub fn detect_image_mime_type(data: &[u8]) -> String {
     if data.len() < 8 {
         return "application/octet-stream".to_string();
@@ -510,3 +1956,633 @@ pub fn detect_image_mime_type(data: &[u8]) -> String {
         "application/octet-stream".to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_buffer(channels: usize, frames: usize) -> AudioBuffer {
+        AudioBuffer {
+            sample_rate: 48000,
+            channels: channels as u16,
+            format: SampleFormat::F32,
+            data: (0..channels)
+                .map(|ch| {
+                    (0..frames)
+                        .map(|i| ((i as f32 * 0.03) + ch as f32).sin() * 0.5)
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_metadata_len_and_is_empty() {
+        let mut metadata = Metadata::new();
+        assert!(metadata.is_empty());
+        assert_eq!(metadata.len(), 0);
+
+        metadata.set_field("WAVPACK_MODE", "0x2").unwrap();
+        metadata.set_field("WAVPACK_VERSION", "5").unwrap();
+        assert!(metadata.is_empty());
+        assert_eq!(metadata.len(), 0);
+        assert_eq!(metadata.field_count(true), 2);
+
+        // Description is stored under each of its chunk aliases
+        metadata.set_field("Description", "Harbor bell").unwrap();
+        assert!(!metadata.is_empty());
+        assert!(metadata.len() > 0);
+        assert_eq!(metadata.field_count(true), metadata.len() + 2);
+    }
+
+    #[test]
+    fn test_interleaved_bytes_roundtrip() {
+        let formats = [
+            SampleFormat::U8,
+            SampleFormat::I16,
+            SampleFormat::I24,
+            SampleFormat::I32,
+            SampleFormat::F32,
+        ];
+        for format in formats {
+            let bytes = test_buffer(3, 256).to_interleaved_bytes(format).unwrap();
+            assert_eq!(bytes.len(), 3 * 256 * format.bits_per_sample() as usize / 8);
+
+            let buffer = AudioBuffer::from_interleaved_bytes(&bytes, 48000, 3, format).unwrap();
+            assert_eq!(buffer.format, format);
+            assert_eq!(buffer.data[2].len(), 256);
+            assert_eq!(
+                buffer.to_interleaved_bytes(format).unwrap(),
+                bytes,
+                "{:?}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn test_quantize_sample_rounds_and_clamps() {
+        assert_eq!(quantize_sample(0.5 / 32768.0, 16), 1);
+        assert_eq!(quantize_sample(-0.4 / 32768.0, 16), 0);
+        assert_eq!(quantize_sample(1.0, 16), 32767);
+        assert_eq!(quantize_sample(-1.5, 16), -32768);
+        assert_eq!(quantize_sample(1.0, 8), 127);
+        assert_eq!(quantize_sample(-1.0, 32), i32::MIN);
+        assert_eq!(quantize_sample(1.0, 32), i32::MAX);
+    }
+
+    /// Encode samples between grid steps, at full scale and past it with `codec`
+    /// at each depth in `formats`, and check they come back rounded to the
+    /// nearest step and clamped to the format's range
+    pub(crate) fn assert_encoder_quantizes(codec: &dyn Codec, formats: &[SampleFormat]) {
+        for &format in formats {
+            let scale = (1i64 << (format.bits_per_sample() - 1)) as f32;
+            let step = 1.0 / scale;
+            let (max, min) = (scale as i64 - 1, -(scale as i64));
+            // Between steps, then at and past full scale
+            let between = [0.4, 0.6, -0.6, -1.4].map(|n| n * step);
+            let samples = [&between[..], &[1.0, 1.5, -1.0, -1.5][..]].concat();
+            let steps = [0, 1, -1, -1, max, max, min, min];
+            let expected: Vec<f32> = steps.map(|n| n as f32 / scale).repeat(256);
+            let buffer = AudioBuffer {
+                sample_rate: 48000,
+                channels: 1,
+                format,
+                data: vec![samples.repeat(256)],
+            };
+
+            let decoded = codec.decode(&codec.encode(&Some(buffer)).unwrap()).unwrap();
+            assert_eq!(decoded.data[0], expected, "{} {:?}", codec.as_str(), format);
+        }
+    }
+
+    #[test]
+    fn test_integer_samples_roundtrip_through_every_encoder() {
+        let codecs: [&dyn Codec; 6] = [
+            &WavCodec, &AifCodec, &FlacCodec, &WvCodec, &CafCodec, &W64Codec,
+        ];
+        let formats = [
+            SampleFormat::U8,
+            SampleFormat::I16,
+            SampleFormat::I24,
+            SampleFormat::I32,
+        ];
+        for format in formats {
+            // Values on the format's grid spread over the whole range, both extremes included
+            let full = 1i64 << format.bits_per_sample();
+            let half = (full / 2) as f32;
+            let mut samples: Vec<f32> = (0..2000i64)
+                .map(|i| ((i * (full / 1999 + 1)) % full - full / 2) as f32 / half)
+                .collect();
+            samples.push((full / 2 - 1) as f32 / half);
+            let buffer = AudioBuffer {
+                sample_rate: 48000,
+                channels: 2,
+                format,
+                data: vec![samples.clone(), samples.iter().rev().copied().collect()],
+            };
+
+            for codec in codecs {
+                // flacenc and claxon are only relied on up to 24 bits
+                if format == SampleFormat::I32 && codec.as_str() == FlacCodec.as_str() {
+                    continue;
+                }
+                let encoded = codec.encode(&Some(buffer.clone())).unwrap();
+                let decoded = codec.decode(&encoded).unwrap();
+                assert_eq!(decoded.data, buffer.data, "{} {:?}", codec.as_str(), format);
+            }
+        }
+    }
+
+    #[test]
+    fn test_i24_source_decodes_to_f32_samples() {
+        let mut buffer = test_buffer(2, 512);
+        buffer.format = SampleFormat::I24;
+        let decoded = WavCodec
+            .decode(&WavCodec.encode(&Some(buffer)).unwrap())
+            .unwrap();
+
+        assert_eq!(decoded.source_format(), SampleFormat::I24);
+        assert_eq!(decoded.working_precision(), SampleFormat::F32);
+        assert!(
+            decoded
+                .data
+                .iter()
+                .flatten()
+                .all(|s| (-1.0..=1.0).contains(s))
+        );
+    }
+
+    #[test]
+    fn test_soft_clip_rounds_off_overs() {
+        let mut buffer = test_buffer(1, 2000);
+        buffer.format = SampleFormat::I16;
+        for sample in buffer.data[0].iter_mut() {
+            *sample *= 3.0; // peaks at 1.5
+        }
+
+        let mut hard = buffer.clone();
+        hard.clip(ClipMode::Hard);
+        let options = EncodeOptions {
+            clip_mode: ClipMode::Soft,
+            ..Default::default()
+        };
+        let soft = options.prepare_buffer(&buffer).unwrap();
+
+        // Hard clipping flattens the peaks at full scale; soft clipping never reaches it
+        assert!(hard.data[0].iter().filter(|s| s.abs() == 1.0).count() > 10);
+        assert!(soft.data[0].iter().all(|s| s.abs() < 1.0));
+        for (input, output) in buffer.data[0].iter().zip(&soft.data[0]) {
+            if input.abs() <= SOFT_CLIP_KNEE {
+                assert_eq!(input, output);
+            }
+        }
+        // The curve's slope never exceeds 1, so it adds no steps the input lacked
+        for (inputs, outputs) in buffer.data[0].windows(2).zip(soft.data[0].windows(2)) {
+            assert!((outputs[1] - outputs[0]).abs() <= (inputs[1] - inputs[0]).abs() + 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_pad_adds_silence_to_every_channel() {
+        let mut buffer = test_buffer(2, 100);
+        buffer.data[1].truncate(90);
+        let original = buffer.data[0].clone();
+        buffer.pad(10, 5);
+
+        for channel in &buffer.data {
+            assert_eq!(channel.len(), 115);
+            assert!(
+                channel[..10]
+                    .iter()
+                    .chain(&channel[110..])
+                    .all(|&s| s == 0.0)
+            );
+        }
+        assert_eq!(buffer.data[0][10..110], original[..]);
+        assert!(buffer.data[1][100..].iter().all(|&s| s == 0.0));
+    }
+
+    #[test]
+    fn test_gain_per_channel_and_clamping() {
+        let mut buffer = AudioBuffer {
+            data: vec![vec![0.5; 10], vec![0.5; 10]],
+            ..test_buffer(2, 0)
+        };
+        buffer.apply_gain(&[-6.0206, 0.0], false).unwrap();
+        assert!((buffer.data[0][0] - 0.25).abs() < 1e-5);
+        assert_eq!(buffer.data[1][0], 0.5);
+        assert!(buffer.apply_gain(&[0.0], false).is_err());
+
+        buffer.apply_gain_all(12.0, false);
+        assert!(buffer.data[1][0] > 1.0);
+        buffer.apply_gain_all(0.0, true);
+        assert_eq!(buffer.data[1][0], 1.0);
+    }
+
+    #[test]
+    fn test_fades_meet_when_longer_than_buffer() {
+        for curve in [
+            FadeCurve::Linear,
+            FadeCurve::EqualPower,
+            FadeCurve::Logarithmic,
+        ] {
+            let mut buffer = AudioBuffer {
+                data: vec![vec![0.5; 100], vec![-0.5; 100]],
+                ..test_buffer(2, 0)
+            };
+            buffer.apply_fade(150, 50, curve);
+            let left = &buffer.data[0];
+            assert_eq!(left[0], 0.0);
+            assert_eq!(left[99], 0.0);
+            // 150:50 of 100 frames splits into a 75 frame fade-in and 25 frame fade-out
+            assert!(left[..75].windows(2).all(|w| w[0] < w[1]));
+            assert!(left[74..].windows(2).all(|w| w[0] > w[1]));
+            assert!(left.iter().zip(&buffer.data[1]).all(|(l, r)| *l == -*r));
+            assert!(left.iter().all(|s| *s == 0.0 || s.is_normal()));
+        }
+    }
+
+    #[test]
+    fn test_content_key_ignores_container() {
+        let buffer = AudioBuffer {
+            format: SampleFormat::I24,
+            ..test_buffer(2, 4800)
+        };
+        let from_wav = WavCodec
+            .decode(&WavCodec.encode(&Some(buffer.clone())).unwrap())
+            .unwrap();
+        let from_flac = FlacCodec
+            .decode(&FlacCodec.encode(&Some(buffer.clone())).unwrap())
+            .unwrap();
+        assert_eq!(from_wav.content_key(), from_flac.content_key());
+        assert_eq!(from_wav.content_key(), buffer.content_key());
+
+        let mut other = buffer.clone();
+        other.data[0][1600] += 0.1;
+        assert_ne!(other.content_key(), buffer.content_key());
+    }
+
+    #[test]
+    fn test_slice_copies_frame_range() {
+        let buffer = test_buffer(2, 100);
+        let region = buffer.slice(10, 30).unwrap();
+        assert_eq!(region.channels, 2);
+        assert_eq!(region.data[1], buffer.data[1][10..30]);
+
+        let empty = buffer.slice(100, 100).unwrap();
+        assert_eq!(empty.format, SampleFormat::F32);
+        assert!(empty.data.iter().all(Vec::is_empty));
+        assert!(buffer.slice(30, 10).is_err());
+        assert!(buffer.slice(0, 101).is_err());
+    }
+
+    #[test]
+    fn test_active_channels() {
+        let mut buffer = test_buffer(4, 512);
+        buffer.data[1] = vec![0.0; 512];
+        buffer.data[3] = vec![0.00001; 512]; // -100 dBFS noise floor
+
+        let active = buffer.active_channels(-60.0);
+        assert_eq!(active, vec![0, 2]);
+
+        buffer.reorder_channels(&active).unwrap();
+        assert_eq!(buffer.channels, 2);
+        assert_eq!(buffer.extract_channel(1).unwrap().data[0].len(), 512);
+    }
+
+    #[test]
+    fn test_split_channels() {
+        let buffer = test_buffer(4, 512);
+        let expected = buffer.data.clone();
+
+        let mono = buffer.clone().split_channels();
+        assert_eq!(mono.len(), 4);
+        for (split, channel) in mono.iter().zip(expected) {
+            assert_eq!(split.channels, 1);
+            assert_eq!(split.sample_rate, buffer.sample_rate);
+            assert_eq!(split.format, buffer.format);
+            assert_eq!(split.data, vec![channel]);
+        }
+    }
+
+    #[test]
+    fn test_downmix_to_stereo() {
+        // Each channel holds a distinct constant so the output shows its gains
+        let constant = |levels: &[f32]| AudioBuffer {
+            sample_rate: 48000,
+            channels: levels.len() as u16,
+            format: SampleFormat::F32,
+            data: levels.iter().map(|&level| vec![level; 8]).collect(),
+        };
+        let minus_3db = 10f32.powf(-3.0 / 20.0);
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+
+        // 5.1 in WAV order: L R C LFE Ls Rs, LFE dropped by default
+        let surround = constant(&[0.1, 0.2, 0.3, 0.4, 0.05, 0.06]);
+        let mut loro = surround.clone();
+        loro.downmix_to_stereo(DownmixCoefficients::default())
+            .unwrap();
+        assert_eq!(loro.channels, 2);
+        assert!(close(loro.data[0][0], 0.1 + minus_3db * (0.3 + 0.05)));
+        assert!(close(loro.data[1][0], 0.2 + minus_3db * (0.3 + 0.06)));
+
+        let mut ltrt = surround.clone();
+        ltrt.downmix_to_stereo(DownmixCoefficients {
+            matrix: DownmixMatrix::LtRt,
+            lfe_db: Some(0.0),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(close(ltrt.data[0][0], 0.1 + 0.4 + minus_3db * (0.3 - 0.11)));
+        assert!(close(ltrt.data[1][0], 0.2 + 0.4 + minus_3db * (0.3 + 0.11)));
+
+        // Four channels are only quad when the mask says so (FL FR BL BR)
+        let four = constant(&[0.1, 0.2, 0.3, 0.4]);
+        assert!(
+            four.clone()
+                .downmix_to_stereo(DownmixCoefficients::default())
+                .is_err()
+        );
+        let mut quad = four.clone();
+        quad.downmix_to_stereo_with_mask(DownmixCoefficients::default(), 0x33)
+            .unwrap();
+        assert!(close(quad.data[0][0], 0.1 + minus_3db * 0.3));
+        assert!(close(quad.data[1][0], 0.2 + minus_3db * 0.4));
+    }
+
+    #[test]
+    fn test_to_mono() {
+        let stereo = AudioBuffer {
+            sample_rate: 48000,
+            channels: 2,
+            format: SampleFormat::F32,
+            data: vec![vec![0.5, 0.8, -0.4], vec![0.5, -0.8, 0.2]],
+        };
+
+        // Correlated samples keep their level, anti-phase ones cancel
+        let mut averaged = stereo.clone();
+        averaged.to_mono(MonoMode::Average).unwrap();
+        assert_eq!(averaged.channels, 1);
+        assert_eq!(averaged.data, vec![vec![0.5, 0.0, -0.1]]);
+
+        let mut right = stereo.clone();
+        right.to_mono(MonoMode::SelectChannel(1)).unwrap();
+        assert_eq!(right.channels, 1);
+        assert_eq!(right.data, vec![stereo.data[1].clone()]);
+
+        assert!(stereo.clone().to_mono(MonoMode::SelectChannel(2)).is_err());
+    }
+
+    #[test]
+    fn test_resample_with_custom_engine() {
+        struct Decimate {
+            calls: std::cell::Cell<usize>,
+        }
+        impl resample::Resample for Decimate {
+            fn process(&self, input: &[f32], in_rate: u32, out_rate: u32) -> Vec<f32> {
+                self.calls.set(self.calls.get() + 1);
+                let step = (in_rate / out_rate) as usize;
+                input.iter().step_by(step).copied().collect()
+            }
+        }
+
+        let engine = Decimate {
+            calls: std::cell::Cell::new(0),
+        };
+        let mut buffer = test_buffer(2, 1000);
+        buffer.resample_with_engine(&engine, 24000);
+
+        assert_eq!(engine.calls.get(), 2);
+        assert_eq!(buffer.sample_rate, 24000);
+        assert_eq!(buffer.data[0].len(), 500);
+    }
+
+    #[test]
+    fn test_invert_polarity_nulls() {
+        let buffer = test_buffer(2, 1024);
+        let mut inverted = buffer.clone();
+        inverted.invert_polarity(None);
+
+        let mut mixed = buffer.clone();
+        mixed.mix(&inverted).unwrap();
+        assert!(mixed.data.iter().flatten().all(|s| *s == 0.0));
+
+        let mut left_only = buffer.clone();
+        left_only.invert_polarity(Some(&[0]));
+        assert_eq!(left_only.data[0][10], -buffer.data[0][10]);
+        assert_eq!(left_only.data[1], buffer.data[1]);
+    }
+
+    #[test]
+    fn test_cached_stats_match_direct_measurements() {
+        let mut buffer = test_buffer(2, 4800);
+        buffer.data[0][10] = 1.0;
+        buffer.data[1][20] = -1.2;
+        buffer.data[1].iter_mut().for_each(|sample| *sample += 0.05);
+
+        let stats = buffer.stats();
+        assert_eq!(stats.peak, buffer.peak());
+        assert_eq!(stats.clip_count, buffer.count_clipped());
+        assert_eq!(stats.clip_count, 2);
+        assert!((stats.rms - buffer.rms()).abs() < 1e-6);
+        assert!((stats.dc_offset - buffer.dc_offset()).abs() < 1e-6);
+        assert_eq!(stats.sample_count, 9600);
+
+        let mut codex = Codex {
+            buffer: Some(buffer.clone()),
+            ..Default::default()
+        };
+        assert_eq!(codex.audio_stats().unwrap(), stats);
+        codex.convert_dual_mono().unwrap();
+        assert_eq!(
+            codex.audio_stats().unwrap(),
+            codex.buffer.as_ref().unwrap().stats()
+        );
+    }
+
+    #[test]
+    fn test_change_bit_depth_dither_is_seedable() {
+        use rand::SeedableRng;
+
+        let modes = [
+            DitherMode::Rectangular,
+            DitherMode::Triangular,
+            DitherMode::NoiseShaped,
+        ];
+        let lsb = 1.0 / 32767.0;
+        for mode in modes {
+            let convert = |seed| {
+                let mut buffer = test_buffer(2, 2048);
+                buffer.data[1].fill(0.0);
+                let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+                buffer.change_bit_depth_with_rng(16, mode, &mut rng);
+                buffer
+            };
+            let first = convert(7);
+            assert_eq!(first.format, SampleFormat::I16);
+            assert_eq!(first.data, convert(7).data, "{:?}", mode);
+            assert_ne!(first.data, convert(8).data, "{:?}", mode);
+            assert!(first.data[1].iter().any(|s| *s != 0.0), "{:?}", mode);
+            assert!(first.data.iter().flatten().all(|s| {
+                let steps = s / lsb;
+                (steps - steps.round()).abs() < 1e-3
+            }));
+        }
+
+        // Going up in bit depth never adds noise
+        let mut silent = test_buffer(1, 2048);
+        silent.format = SampleFormat::I16;
+        silent.data[0].fill(0.0);
+        silent.change_bit_depth(24, DitherMode::Triangular);
+        assert_eq!(silent.format, SampleFormat::I24);
+        assert!(silent.data[0].iter().all(|s| *s == 0.0));
+    }
+
+    #[test]
+    fn test_remove_field_drops_aliases_and_derived_fields() {
+        let mut metadata = Metadata::new();
+        metadata.set_field("USER_CATEGORY", "DOORS").unwrap();
+        metadata
+            .set_field("USER_CATEGORYFULL", "DOORS-WOOD")
+            .unwrap();
+        metadata.set_field("USER_LOCATION", "Hollywood").unwrap();
+        metadata
+            .add_field_value("USER_LOCATION", "Burbank")
+            .unwrap();
+        metadata.set_field("WAVPACK_MODE", "0x2").unwrap();
+        metadata.add_image(ImageChunk::new(
+            "image/png".to_string(),
+            String::new(),
+            vec![0x89, b'P', b'N', b'G'],
+        ));
+
+        assert_eq!(
+            metadata.remove_field("USER_LOCATION").as_deref(),
+            Some("Hollywood")
+        );
+        assert!(metadata.get_field_all("USER_LOCATION").is_empty());
+        assert_eq!(metadata.remove_field("USER_LOCATION"), None);
+
+        assert_eq!(
+            metadata.remove_field("ASWG_category").as_deref(),
+            Some("DOORS")
+        );
+        assert_eq!(metadata.get_field("USER_CATEGORY"), None);
+        assert_eq!(metadata.get_field("TAG_CategoryFull"), None);
+
+        metadata.set_field("Description", "Hinge squeak").unwrap();
+        metadata.clear_fields();
+        assert!(metadata.is_empty());
+        assert_eq!(metadata.get_field("WAVPACK_MODE").as_deref(), Some("0x2"));
+    }
+
+    #[test]
+    fn test_merge_strategies() {
+        let mut recording = Metadata::new();
+        recording
+            .set_field("Description", "Door slam, take 3")
+            .unwrap();
+        recording.set_field("Show", "").unwrap();
+        recording.sample_rate = 96000;
+        recording.channels = 2;
+        recording.add_image(ImageChunk::new("image/png".into(), "Cover".into(), vec![1]));
+
+        let mut template = Metadata::new();
+        template.set_field("Description", "House template").unwrap();
+        template.set_field("Show", "Night Shift").unwrap();
+        template.set_field("Library", "Studio B").unwrap();
+        template.set_field("WAVPACK_MODE", "0x20").unwrap();
+        template.sample_rate = 44100;
+        template.channels = 1;
+        template.add_image(ImageChunk::new("image/png".into(), "Cover".into(), vec![2]));
+        template.add_image(ImageChunk::new("image/jpeg".into(), "Logo".into(), vec![3]));
+
+        let merged = |strategy| {
+            let mut merged = recording.clone();
+            merged.merge(&template, strategy).unwrap();
+            merged
+        };
+        let field = |metadata: &Metadata, key: &str| metadata.get_field(key).unwrap();
+
+        let prefer_self = merged(MergeStrategy::PreferSelf);
+        assert_eq!(field(&prefer_self, "Description"), "Door slam, take 3");
+        assert_eq!(field(&prefer_self, "Show"), "");
+        assert_eq!(field(&prefer_self, "Library"), "Studio B");
+        assert_eq!(prefer_self.get_images()[0].data(), [1]);
+        assert_eq!(prefer_self.get_images().len(), 2);
+
+        let fill_empty = merged(MergeStrategy::FillEmptyOnly);
+        assert_eq!(field(&fill_empty, "Description"), "Door slam, take 3");
+        assert_eq!(field(&fill_empty, "Show"), "Night Shift");
+
+        let prefer_other = merged(MergeStrategy::PreferOther);
+        assert_eq!(field(&prefer_other, "Description"), "House template");
+        assert_eq!(prefer_other.get_images()[0].data(), [2]);
+
+        // The technical side always comes from the recording's own audio
+        for metadata in [prefer_self, fill_empty, prefer_other] {
+            assert_eq!((metadata.sample_rate, metadata.channels), (96000, 2));
+            assert!(metadata.get_field("WAVPACK_MODE").is_none());
+        }
+    }
+
+    #[test]
+    fn test_json_roundtrip_is_lossless() {
+        let mut metadata = Metadata::new();
+        metadata
+            .set_field("Description", "Rain on tin — café roof")
+            .unwrap();
+        metadata.add_field_value("TAG_Artist", "Tim").unwrap();
+        metadata.add_field_value("TAG_Artist", "Sam").unwrap();
+        metadata.set_field("WAVPACK_MODE", "0x20").unwrap();
+        metadata.sample_rate = 96000;
+        metadata.channels = 6;
+        metadata.bit_depth = 24;
+        metadata.format_tag = 0xFFFE;
+        metadata.channel_mask = 0x3F;
+        metadata.add_image(
+            ImageChunk::new("image/png".into(), "Cover".into(), vec![0, 159, 255])
+                .with_picture_type(PictureType::FrontCover),
+        );
+        metadata.add_marker(CueMarker {
+            id: 2,
+            position: 4800,
+            length: 960,
+            purpose: "rgn ".into(),
+            label: Some("Hit".into()),
+            ..Default::default()
+        });
+        metadata.add_raw_chunk(*b"junk", vec![1, 2, 3]).unwrap();
+        metadata.add_application_data([b'a', 0xE9, b'c', 0], vec![9]);
+
+        let json = metadata.to_json().unwrap();
+        let parsed = Metadata::from_json(&json).unwrap();
+        assert!(parsed.content_eq(&metadata));
+        assert_eq!(
+            (parsed.sample_rate, parsed.channels, parsed.bit_depth),
+            (96000, 6, 24)
+        );
+        assert_eq!((parsed.format_tag, parsed.channel_mask), (0xFFFE, 0x3F));
+        assert_eq!(parsed.to_json().unwrap(), json);
+
+        assert!(Metadata::from_json("{\"version\": 99}").is_err());
+    }
+
+    #[test]
+    fn test_resample_to_same_rate_is_bit_identical() {
+        let original = test_buffer(2, 4800);
+        let mut resampled = original.clone();
+        resampled.resample(48000);
+        assert_eq!(resampled.sample_rate, 48000);
+        assert!(
+            resampled
+                .data
+                .iter()
+                .flatten()
+                .zip(original.data.iter().flatten())
+                .all(|(a, b)| a.to_bits() == b.to_bits())
+        );
+        assert_eq!(resampled.data[0].len(), original.data[0].len());
+    }
+}
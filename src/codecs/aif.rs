@@ -1,10 +1,17 @@
+use super::chunk::{IffReader, IffWriter, padded_size, read_chunk_header};
+use super::quantize_sample;
+use crate::ixml::join_ixml_chunk;
 use crate::prelude::*;
 
 // Chunk Identifiers
 const FORM_CHUNK_ID: &[u8; 4] = b"FORM";
 const AIFF_FORMAT_ID: &[u8; 4] = b"AIFF";
+const AIFC_FORMAT_ID: &[u8; 4] = b"AIFC";
 const FMT_CHUNK_ID: &[u8; 4] = b"COMM";
 const DATA_CHUNK_ID: &[u8; 4] = b"SSND";
+const FVER_CHUNK_ID: &[u8; 4] = b"FVER";
+// FVER timestamp of the AIFC version 1 specification
+const AIFC_VERSION_1: u32 = 0xA280_5140;
 
 // AIFF Metadata Chunk Identifiers
 const ANNO_CHUNK_ID: &[u8; 4] = b"ANNO";
@@ -16,105 +23,91 @@ const APPL_CHUNK_ID: &[u8; 4] = b"APPL";
 const ID3_CHUNK_ID: &[u8; 4] = b"ID3 ";
 const IXML_CHUNK_ID: &[u8; 4] = b"iXML";
 
+// APPL signature of Soundminer's chunk, which is not carried through embeds
+const SOUNDMINER_SIGNATURE: &[u8; 4] = b"SMED";
+
 // Chunk Structures
 const HEADER_SIZE: usize = 12; // FORM + size + AIFF
 const MIN_VALID_FILE_SIZE: usize = 12;
 
 // Sample conversion constants
-const I16_MAX_F: f32 = 32767.0;
-const I24_MAX_F: f32 = 8388607.0;
-const I32_MAX_F: f32 = 2147483647.0;
 const BYTE_MASK: i32 = 0xFF;
 
+// Rates that a decoded COMM sample rate snaps to when it lands this close
+const STANDARD_SAMPLE_RATES: [u32; 14] = [
+    8000, 11025, 16000, 22050, 24000, 32000, 44100, 48000, 88200, 96000, 176400, 192000, 352800,
+    384000,
+];
+const SAMPLE_RATE_EPSILON: f64 = 1.0;
+
 pub struct AifCodec;
 
 impl Codec for AifCodec {
     fn extract_metadata_from_file(&self, file_path: &str) -> R<Metadata> {
-        // Optimized two-phase metadata extraction for AIF files
+        // Walk the chunk headers, reading only metadata chunk bodies. Metadata may
+        // sit on either side of SSND: embedding appends it after the audio when it
+        // doesn't fit in the space before.
         use std::fs::File;
         use std::io::{Read, Seek, SeekFrom};
-        
+
         let mut file = File::open(file_path)?;
         let file_size = file.metadata()?.len();
-        
+
         // Validate FORM/AIFF header first
         let mut header = [0u8; 12];
         file.read_exact(&mut header)?;
-        if &header[0..4] != b"FORM" || &header[8..12] != b"AIFF" {
+        if &header[0..4] != b"FORM" || !is_aiff_form(&header[8..12]) {
             return Err(anyhow!("Not a valid AIFF file"));
         }
-        
+
         let mut metadata = Metadata::new();
-        
-        // PHASE 1: Read first 1MB (or until SSND chunk) for standard metadata
-        let mut pos = 12u64;
-        let first_phase_limit = std::cmp::min(file_size, 1024 * 1024); // 1MB limit
-        let mut found_ssnd_chunk = false;
-        let mut ssnd_chunk_start = 0u64;
-        
-        while pos < first_phase_limit {
+        let mut pending_ixml = String::new();
+        let mut pos = HEADER_SIZE as u64;
+        while pos + 8 <= file_size {
             file.seek(SeekFrom::Start(pos))?;
-            
             let mut chunk_header = [0u8; 8];
-            if file.read_exact(&mut chunk_header).is_err() {
-                break;
-            }
-            
+            file.read_exact(&mut chunk_header)?;
             let chunk_id = &chunk_header[0..4];
-            let chunk_size = u32::from_be_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]) as u64;
-            
-            // Track SSND chunk location
-            if chunk_id == b"SSND" {
-                found_ssnd_chunk = true;
-                ssnd_chunk_start = pos;
-                break; // Stop at SSND chunk for phase 1
-            }
-            
-            // Parse metadata chunks in phase 1
-            if self.is_valid_chunk_id(chunk_id) && chunk_size <= 16 * 1024 * 1024 { // Reasonable size limit
+            let chunk_size = u32::from_be_bytes([
+                chunk_header[4],
+                chunk_header[5],
+                chunk_header[6],
+                chunk_header[7],
+            ]) as u64;
+
+            // Reasonable size limit for a metadata chunk
+            if self.is_valid_chunk_id(chunk_id)
+                && chunk_size <= 16 * 1024 * 1024
+                && pos + 8 + chunk_size <= file_size
+            {
                 let mut chunk_data = vec![0u8; chunk_size as usize];
                 if file.read_exact(&mut chunk_data).is_ok() {
-                    self.parse_metadata_chunk(chunk_id, &chunk_data, &mut metadata)?;
-                }
-            }
-            
-            // Move to next chunk (with padding)
-            pos += 8 + chunk_size + (chunk_size % 2);
-        }
-        
-        // PHASE 2: Read last 1MB if file is large enough and we found SSND chunk
-        if found_ssnd_chunk && file_size > 2 * 1024 * 1024 { // Only for files > 2MB
-            let last_mb_start = std::cmp::max(ssnd_chunk_start + 1024 * 1024, file_size - 1024 * 1024);
-            
-            if last_mb_start < file_size {
-                pos = last_mb_start;
-                
-                // Align to potential chunk boundary
-                file.seek(SeekFrom::Start(pos))?;
-                
-                while pos < file_size {
-                    let mut chunk_header = [0u8; 8];
-                    if file.read_exact(&mut chunk_header).is_err() {
-                        break;
-                    }
-                    
-                    let chunk_id = &chunk_header[0..4];
-                    let chunk_size = u32::from_be_bytes([chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]]) as u64;
-                    
-                    // Parse any metadata chunks found at the end
-                    if self.is_valid_chunk_id(chunk_id) && chunk_size <= 16 * 1024 * 1024 && pos + 8 + chunk_size <= file_size {
-                        let mut chunk_data = vec![0u8; chunk_size as usize];
-                        if file.read_exact(&mut chunk_data).is_ok() {
-                            self.parse_metadata_chunk(chunk_id, &chunk_data, &mut metadata)?;
+                    if chunk_id == b"iXML" {
+                        let payload = String::from_utf8_lossy(&chunk_data);
+                        if let Some(ixml) = join_ixml_chunk(&mut pending_ixml, &payload) {
+                            metadata.parse_ixml(&ixml)?;
                         }
+                    } else {
+                        self.parse_metadata_chunk(chunk_id, &chunk_data, &mut metadata)?;
                     }
-                    
-                    pos += 8 + chunk_size + (chunk_size % 2);
-                    if pos + 8 > file_size { break; } // Not enough space for another chunk header
                 }
             }
+
+            pos += 8 + padded_size(chunk_size);
         }
-        
+        if !pending_ixml.is_empty() {
+            metadata.parse_ixml(&pending_ixml)?;
+        }
+
+        // Legacy ID3v1 tag appended after the FORM body
+        let form_end = 8 + u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as u64;
+        if file_size >= form_end + ID3V1_TAG_SIZE as u64 {
+            let mut tag = [0u8; ID3V1_TAG_SIZE];
+            file.seek(SeekFrom::Start(file_size - ID3V1_TAG_SIZE as u64))?;
+            file.read_exact(&mut tag)?;
+            metadata.parse_id3v1(&tag)?;
+        }
+
         Ok(metadata)
     }
     
@@ -147,13 +140,75 @@ impl Codec for AifCodec {
         cursor.read_u32::<BigEndian>()?; // File size
         let mut aiff = [0u8; 4];
         cursor.read_exact(&mut aiff)?;
-        if &aiff != AIFF_FORMAT_ID {
+        if !is_aiff_form(&aiff) {
             return Err(anyhow!("Not an AIFF file"));
         }
 
         Ok(())
     }
 
+    fn validate_structure(&self, input: &[u8], report: &mut ValidationReport) {
+        let form_size = u32::from_be_bytes([input[4], input[5], input[6], input[7]]) as usize;
+        if form_size + 8 > input.len() {
+            report.add_issue(format!(
+                "File truncated: FORM header declares {} bytes but only {} present",
+                form_size + 8,
+                input.len()
+            ));
+        }
+
+        let aifc = is_aifc(input);
+        let mut comm_found = false;
+        let mut ssnd_found = false;
+        for chunk in IffReader::new(input, HEADER_SIZE) {
+            let chunk_id = &chunk.id;
+            let chunk_size = chunk.size as usize;
+
+            if chunk.is_truncated() {
+                report.add_issue(format!(
+                    "Chunk '{}' truncated: declares {} bytes but only {} remain",
+                    String::from_utf8_lossy(chunk_id),
+                    chunk_size,
+                    chunk.data.len()
+                ));
+                if chunk_id == DATA_CHUNK_ID {
+                    ssnd_found = true;
+                }
+                break;
+            }
+
+            if chunk_id == FMT_CHUNK_ID {
+                comm_found = true;
+                if chunk_size < 18 {
+                    report.add_issue(format!("COMM chunk too small: {} bytes", chunk_size));
+                } else {
+                    match read_comm(chunk.data, aifc) {
+                        Ok(comm)
+                            if comm.encoding != SampleEncoding::Float
+                                && !matches!(comm.bits, 8 | 16 | 24 | 32) =>
+                        {
+                            report.add_issue(format!("Unsupported bit depth: {}", comm.bits));
+                        }
+                        Ok(_) => {}
+                        Err(e) => report.add_issue(e.to_string()),
+                    }
+                }
+            } else if chunk_id == DATA_CHUNK_ID {
+                ssnd_found = true;
+                if chunk_size < 8 {
+                    report.add_issue(format!("SSND chunk too small: {} bytes", chunk_size));
+                }
+            }
+        }
+
+        if !comm_found {
+            report.add_issue("Missing COMM chunk");
+        }
+        if !ssnd_found {
+            report.add_issue("Missing SSND chunk");
+        }
+    }
+
     fn get_file_info(&self, file_path: &str) -> R<FileInfo> {
         use memmap2::MmapOptions;
         use std::fs;
@@ -164,10 +219,11 @@ impl Codec for AifCodec {
 
         self.validate_file_format(&mapped_file)?;
 
+        let aifc = is_aifc(&mapped_file);
         let mut cursor = Cursor::new(&mapped_file[..]);
         cursor.set_position(HEADER_SIZE as u64);
 
-        let mut sample_rate = 0u16;
+        let mut sample_rate = 0u32;
         let mut channels = 0u16;
         let mut bits_per_sample = 0u16;
         let mut total_frames = 0u32;
@@ -190,12 +246,13 @@ impl Codec for AifCodec {
             match &chunk_id {
                 FMT_CHUNK_ID => {
                     // Found COMM chunk - extract format information
-                    channels = cursor.read_u16::<BigEndian>()?;
-                    total_frames = cursor.read_u32::<BigEndian>()?;
-                    bits_per_sample = cursor.read_u16::<BigEndian>()?;
-
-                    // Read the 80-bit IEEE extended sample rate
-                    sample_rate = read_ieee_extended(&mut cursor)? as u16;
+                    let start = cursor.position() as usize;
+                    let comm = read_comm(&mapped_file[start..start + chunk_size], aifc)?;
+                    channels = comm.channels;
+                    total_frames = comm.frames;
+                    bits_per_sample = comm.bits;
+                    sample_rate = comm.sample_rate;
+                    cursor.set_position((start + chunk_size) as u64);
                 }
                 ANNO_CHUNK_ID => {
                     // AIFF annotation chunk contains description
@@ -285,95 +342,24 @@ impl Codec for AifCodec {
             channels,
             bit_depth: bits_per_sample,
             duration,
+            duration_secs: duration_seconds,
             description,
         })
     }
 
+    /// Float audio is written as AIFC with `fl32` samples, since plain AIFF
+    /// only holds integer PCM; everything else is written as AIFF
     fn encode(&self, buffer: &Option<AudioBuffer>) -> R<Vec<u8>> {
-        let mut output = Cursor::new(Vec::new());
-
-        let Some(buffer) = buffer else {
-            return Err(anyhow!("Cannot encode None AudioBuffer"));
-        };
-
-        // Validate input buffer
-        if buffer.data.is_empty() {
-            return Err(anyhow!("Cannot encode empty audio buffer"));
-        }
-
-        // Ensure all channels have the same length
-        let frame_count = buffer.data[0].len();
-        for (i, channel) in buffer.data.iter().enumerate() {
-            if channel.len() != frame_count {
-                return Err(anyhow!(
-                    "Channel {} has {} samples, expected {}",
-                    i,
-                    channel.len(),
-                    frame_count
-                ));
-            }
-        }
-
-        // Write FORM header
-        output.write_all(FORM_CHUNK_ID)?;
-        output.write_u32::<BigEndian>(0)?; // Placeholder for file size
-        output.write_all(AIFF_FORMAT_ID)?;
-
-        // Write COMM chunk
-        output.write_all(FMT_CHUNK_ID)?;
-        output.write_u32::<BigEndian>(18)?; // COMM chunk size
-        output.write_u16::<BigEndian>(buffer.channels)?;
-
-        // Write number of sample frames
-        let num_frames = frame_count as u32;
-        output.write_u32::<BigEndian>(num_frames)?;
-
-        // Get bit depth from format
-        let bits_per_sample = match buffer.format {
-            SampleFormat::F32 => 32,
-            SampleFormat::I16 => 16,
-            SampleFormat::I24 => 24,
-            SampleFormat::I32 => 32,
-            SampleFormat::U8 => 8,
-        };
-        output.write_u16::<BigEndian>(bits_per_sample)?;
-
-        // Write extended 80-bit IEEE 754 format for sample rate
-        // This is required by AIFF spec
-        write_ieee_extended_simple(&mut output, buffer.sample_rate as f64)?;
-
-        // Write SSND chunk header
-        output.write_all(DATA_CHUNK_ID)?;
-        let ssnd_chunk_size_pos = output.position();
-        output.write_u32::<BigEndian>(0)?; // Placeholder for chunk size
-        output.write_u32::<BigEndian>(0)?; // Offset
-        output.write_u32::<BigEndian>(0)?; // Block size
-
-        let start_data = output.position();
-
-        let mut interleaved_bytes = Vec::new();
-        encode_samples(&mut interleaved_bytes, buffer, bits_per_sample)?;
-        output.write_all(&interleaved_bytes)?;
-
-        let end_data = output.position();
-        let data_size = (end_data - start_data) as u32;
-        let ssnd_chunk_size = data_size + 8; // Add 8 bytes for offset and block size
-
-        // Fill in SSND chunk size
-        let mut out = output.into_inner();
-        (&mut out[ssnd_chunk_size_pos as usize..(ssnd_chunk_size_pos + 4) as usize])
-            .write_u32::<BigEndian>(ssnd_chunk_size)?;
-
-        // Fill in FORM file size
-        let form_size = out.len() as u32 - 8;
-        (&mut out[4..8]).write_u32::<BigEndian>(form_size)?;
-
-        Ok(out)
+        let float = buffer
+            .as_ref()
+            .is_some_and(|buffer| buffer.format == SampleFormat::F32);
+        self.encode_form(buffer, float)
     }
 
     fn decode(&self, input: &[u8]) -> R<AudioBuffer> {
         self.validate_file_format(input)?;
 
+        let aifc = is_aifc(input);
         let mut cursor = Cursor::new(input);
         cursor.set_position(HEADER_SIZE as u64);
 
@@ -383,6 +369,7 @@ impl Codec for AifCodec {
         let mut channels = 0;
         let mut sample_rate = 0;
         let mut bits_per_sample = 0;
+        let mut encoding = SampleEncoding::BigEndian;
         let mut audio_data = vec![];
 
         while (cursor.position() as usize) < input.len() {
@@ -400,27 +387,16 @@ impl Codec for AifCodec {
                 break;
             }
 
+            let chunk_start = cursor.position() as usize;
             match &chunk_id {
                 FMT_CHUNK_ID => {
                     fmt_chunk_found = true;
-                    channels = cursor.read_u16::<BigEndian>()?;
-                    let _frames = cursor.read_u32::<BigEndian>()?; // Total frames - we read but don't use
-                    bits_per_sample = cursor.read_u16::<BigEndian>()?;
-
-                    // Use read_ieee_extended to get the sample rate (80-bit extended precision)
-                    sample_rate = read_ieee_extended(&mut cursor)? as u32;
-
-                    // For AIFF, 32-bit samples are typically integers unless specifically IEEE float
-                    // AIFF format doesn't have a format tag like WAV, so we assume integer for most cases
-                    sample_format = match bits_per_sample {
-                        8 => SampleFormat::U8,
-                        16 => SampleFormat::I16,
-                        24 => SampleFormat::I24,
-                        32 => SampleFormat::I32, // Default to integer; could be float but rare in AIFF
-                        _ => {
-                            return Err(anyhow!("Unsupported bit depth: {}", bits_per_sample));
-                        }
-                    };
+                    let comm = read_comm(&input[chunk_start..chunk_start + chunk_size], aifc)?;
+                    channels = comm.channels;
+                    bits_per_sample = comm.bits;
+                    sample_rate = comm.sample_rate;
+                    encoding = comm.encoding;
+                    sample_format = sample_format_for_bits(bits_per_sample, encoding)?;
                 }
                 DATA_CHUNK_ID => {
                     data_chunk_found = true;
@@ -435,19 +411,14 @@ impl Codec for AifCodec {
                     let mut raw_data = vec![0u8; audio_data_size];
                     cursor.read_exact(&mut raw_data)?;
 
-                    audio_data = decode_samples(
-                        &raw_data,
-                        channels,
-                        bits_per_sample,
-                        sample_format == SampleFormat::F32,
-                    )?;
+                    audio_data = decode_samples(&raw_data, channels, bits_per_sample, encoding)?;
                 }
 
                 _ => {
-                    // Skip unknown chunks safely
-                    cursor.set_position(cursor.position() + chunk_size as u64);
+                    // Skip unknown chunks (FVER, MARK, ...)
                 }
             }
+            cursor.set_position(chunk_start as u64 + padded_size(chunk_size as u64));
         }
 
         if !fmt_chunk_found || !data_chunk_found {
@@ -462,6 +433,42 @@ impl Codec for AifCodec {
         })
     }
 
+    fn decode_stream<'a>(&self, input: &'a [u8]) -> R<(StreamHeader, FrameStream<'a>)> {
+        let (comm, sound) = self.sound_data(input)?;
+        let CommChunk {
+            channels,
+            frames,
+            bits,
+            sample_rate,
+            encoding,
+        } = comm;
+        let header = StreamHeader {
+            sample_rate,
+            channels,
+            format: sample_format_for_bits(bits, encoding)?,
+            total_frames: Some(frames as u64),
+        };
+        let block_align = channels as usize * bits as usize / 8;
+        let blocks = sound
+            .chunks(STREAM_BLOCK_FRAMES * block_align)
+            .map(move |block| decode_samples(block, channels, bits, encoding));
+        Ok((header, Box::new(blocks)))
+    }
+
+    fn decode_range(&self, input: &[u8], start: u64, end: u64) -> R<AudioBuffer> {
+        let (comm, sound) = self.sound_data(input)?;
+        let block_align = comm.channels as usize * comm.bits as usize / 8;
+        let end = end.min(comm.frames as u64) as usize;
+        let end = (end * block_align).min(sound.len());
+        let start = (start as usize).saturating_mul(block_align).min(end);
+        Ok(AudioBuffer {
+            sample_rate: comm.sample_rate,
+            channels: comm.channels,
+            format: sample_format_for_bits(comm.bits, comm.encoding)?,
+            data: decode_samples(&sound[start..end], comm.channels, comm.bits, comm.encoding)?,
+        })
+    }
+
     fn parse_metadata(&self, input: &[u8]) -> R<Metadata> {
         let mut metadata = Metadata::new();
         let mut cursor = Cursor::new(input);
@@ -470,9 +477,10 @@ impl Codec for AifCodec {
         let mut header = [0u8; 12];
         cursor.read_exact(&mut header)?;
 
-        if &header[0..4] != b"FORM" || &header[8..12] != b"AIFF" {
+        if &header[0..4] != b"FORM" || !is_aiff_form(&header[8..12]) {
             return Err(anyhow!("Invalid AIFF header"));
         }
+        let mut pending_ixml = String::new();
 
         // Parse chunks
         while cursor.position() < input.len() as u64 {
@@ -533,12 +541,17 @@ impl Codec for AifCodec {
                 b"iXML" => {
                     // iXML chunk
                     let xml_str = String::from_utf8_lossy(chunk_data);
-                    metadata.parse_ixml(&xml_str)?;
+                    if let Some(ixml) = join_ixml_chunk(&mut pending_ixml, &xml_str) {
+                        metadata.parse_ixml(&ixml)?;
+                    }
                 }
                 b"ID3 " | b"id3 " => {
                     // ID3 chunk
                     metadata.parse_id3(chunk_data)?;
                 }
+                b"APPL" => {
+                    parse_appl_chunk(chunk_data, &mut metadata);
+                }
                 _ => {
                     // Skip audio and binary chunks - only process known text chunks
                     let chunk_id_bytes = chunk_id.to_be_bytes();
@@ -576,6 +589,16 @@ impl Codec for AifCodec {
                 cursor.set_position(cursor.position() + 1);
             }
         }
+        if !pending_ixml.is_empty() {
+            metadata.parse_ixml(&pending_ixml)?;
+        }
+
+        // Legacy ID3v1 tag appended after the FORM body
+        let form_end =
+            8 + u32::from_be_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        if input.len() >= form_end + ID3V1_TAG_SIZE {
+            metadata.parse_id3v1(&input[input.len() - ID3V1_TAG_SIZE..])?;
+        }
 
         Ok(metadata)
     }
@@ -629,6 +652,35 @@ impl Codec for AifCodec {
         
         Ok(())
     }
+
+    /// Copies every chunk through except the metadata chunks this codec writes,
+    /// whose new versions follow COMM
+    fn embed_metadata(&self, input: &[u8], metadata: &Metadata) -> R<Vec<u8>> {
+        self.validate_file_format(input)?;
+        let new_metadata = self.create_aif_metadata_chunks(metadata)?;
+
+        let mut output = Vec::with_capacity(input.len() + new_metadata.len());
+        output.extend_from_slice(&input[..HEADER_SIZE]);
+        let mut found_comm = false;
+        for chunk in IffReader::new(input, HEADER_SIZE) {
+            if self.is_rewritten_chunk(&chunk.id, metadata) {
+                continue;
+            }
+            IffWriter::new(&mut output).write_chunk(&chunk.id, chunk.data)?;
+            if &chunk.id == FMT_CHUNK_ID {
+                output.extend_from_slice(&new_metadata);
+                found_comm = true;
+            }
+        }
+        if !found_comm {
+            return Err(anyhow!("No COMM chunk found"));
+        }
+
+        let form_size = u32::try_from(output.len() - 8)
+            .map_err(|_| anyhow!("AIFF with metadata exceeds the 4 GB FORM limit"))?;
+        output[4..8].copy_from_slice(&form_size.to_be_bytes());
+        Ok(output)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -640,6 +692,153 @@ struct AifChunk {
 }
 
 impl AifCodec {
+    /// Encode as AIFC even for integer audio (compression type `NONE`), as a
+    /// `.aifc` export expects
+    pub fn encode_aifc(&self, buffer: &Option<AudioBuffer>) -> R<Vec<u8>> {
+        self.encode_form(buffer, true)
+    }
+
+    fn encode_form(&self, buffer: &Option<AudioBuffer>, aifc: bool) -> R<Vec<u8>> {
+        let mut output = Cursor::new(Vec::new());
+
+        let Some(buffer) = buffer else {
+            return Err(anyhow!("Cannot encode None AudioBuffer"));
+        };
+
+        // Validate input buffer
+        if buffer.data.is_empty() {
+            return Err(anyhow!("Cannot encode empty audio buffer"));
+        }
+
+        if buffer.data.len() != buffer.channels as usize {
+            return Err(anyhow!(
+                "Buffer declares {} channels but holds {}",
+                buffer.channels,
+                buffer.data.len()
+            ));
+        }
+
+        // Ensure all channels have the same length
+        let frame_count = buffer.data[0].len();
+        for (i, channel) in buffer.data.iter().enumerate() {
+            if channel.len() != frame_count {
+                return Err(anyhow!(
+                    "Channel {} has {} samples, expected {}",
+                    i,
+                    channel.len(),
+                    frame_count
+                ));
+            }
+        }
+
+        // Get bit depth from format
+        let bits_per_sample = match buffer.format {
+            SampleFormat::F32 => 32,
+            SampleFormat::I16 => 16,
+            SampleFormat::I24 => 24,
+            SampleFormat::I32 => 32,
+            SampleFormat::U8 => 8,
+        };
+
+        // AIFC's COMM adds the compression type and its pascal-string name
+        let (compression, compression_name) = aifc_compression(buffer.format);
+        let mut name = vec![compression_name.len() as u8];
+        name.extend_from_slice(compression_name.as_bytes());
+        if name.len() % 2 == 1 {
+            name.push(0);
+        }
+
+        // Refuse outputs whose sizes would overflow the 32-bit chunk fields. AIFC
+        // adds the FVER chunk and the compression fields to the header.
+        let data_bytes = frame_count as u64 * buffer.channels as u64 * (bits_per_sample as u64 / 8);
+        let aifc_bytes = if aifc { 12 + 4 + name.len() as u64 } else { 0 };
+        check_aiff_size(data_bytes + aifc_bytes)?;
+
+        // Write FORM header
+        output.write_all(FORM_CHUNK_ID)?;
+        output.write_u32::<BigEndian>(0)?; // Placeholder for file size
+        if aifc {
+            output.write_all(AIFC_FORMAT_ID)?;
+            output.write_all(FVER_CHUNK_ID)?;
+            output.write_u32::<BigEndian>(4)?;
+            output.write_u32::<BigEndian>(AIFC_VERSION_1)?;
+        } else {
+            output.write_all(AIFF_FORMAT_ID)?;
+        }
+
+        // Write COMM chunk
+        output.write_all(FMT_CHUNK_ID)?;
+        let comm_size = if aifc { 18 + 4 + name.len() as u32 } else { 18 };
+        output.write_u32::<BigEndian>(comm_size)?;
+        output.write_u16::<BigEndian>(buffer.channels)?;
+
+        // Write number of sample frames
+        let num_frames = frame_count as u32;
+        output.write_u32::<BigEndian>(num_frames)?;
+        output.write_u16::<BigEndian>(bits_per_sample)?;
+
+        // Write extended 80-bit IEEE 754 format for sample rate
+        // This is required by AIFF spec
+        write_ieee_extended_simple(&mut output, buffer.sample_rate as f64)?;
+        if aifc {
+            output.write_all(compression)?;
+            output.write_all(&name)?;
+        }
+
+        // Write SSND chunk header
+        output.write_all(DATA_CHUNK_ID)?;
+        let ssnd_chunk_size_pos = output.position();
+        output.write_u32::<BigEndian>(0)?; // Placeholder for chunk size
+        output.write_u32::<BigEndian>(0)?; // Offset
+        output.write_u32::<BigEndian>(0)?; // Block size
+
+        let start_data = output.position();
+
+        let mut interleaved_bytes = Vec::new();
+        encode_samples(&mut interleaved_bytes, buffer, bits_per_sample)?;
+        output.write_all(&interleaved_bytes)?;
+
+        let end_data = output.position();
+        let data_size = (end_data - start_data) as u32;
+        let ssnd_chunk_size = data_size + 8; // Add 8 bytes for offset and block size
+
+        // Fill in SSND chunk size
+        let mut out = output.into_inner();
+        (&mut out[ssnd_chunk_size_pos as usize..(ssnd_chunk_size_pos + 4) as usize])
+            .write_u32::<BigEndian>(ssnd_chunk_size)?;
+
+        // Fill in FORM file size
+        let form_size = out.len() as u32 - 8;
+        (&mut out[4..8]).write_u32::<BigEndian>(form_size)?;
+
+        Ok(out)
+    }
+
+    /// Read the COMM chunk and locate the sample bytes of the SSND chunk
+    fn sound_data<'a>(&self, input: &'a [u8]) -> R<(CommChunk, &'a [u8])> {
+        self.validate_file_format(input)?;
+
+        let aifc = is_aifc(input);
+        let mut comm = None;
+        let mut sound = None;
+        for chunk in IffReader::new(input, HEADER_SIZE) {
+            match &chunk.id {
+                FMT_CHUNK_ID => comm = Some(read_comm(chunk.data, aifc)?),
+                // Skip the offset and block size fields, as `decode` does
+                DATA_CHUNK_ID if chunk.data.len() >= 8 => sound = Some(&chunk.data[8..]),
+                _ => {}
+            }
+        }
+
+        let (Some(comm), Some(sound)) = (comm, sound) else {
+            return Err(anyhow!("Missing 'COMM' or 'SSND' chunk"));
+        };
+        if comm.channels as usize * comm.bits as usize / 8 == 0 {
+            return Err(anyhow!("Invalid AIFF format: {} channels", comm.channels));
+        }
+        Ok((comm, sound))
+    }
+
     fn is_valid_chunk_id(&self, chunk_id: &[u8]) -> bool {
         // Check if this is a known metadata chunk type for AIF
         matches!(chunk_id, 
@@ -657,8 +856,7 @@ impl AifCodec {
                     cursor.read_u32::<BigEndian>()?; // num_sample_frames - skip
                     metadata.bit_depth = cursor.read_u16::<BigEndian>()?;
                     // Sample rate is stored as IEEE 754 80-bit extended precision
-                    let sample_rate_f64 = read_ieee_extended(&mut cursor)?;
-                    metadata.sample_rate = sample_rate_f64 as u32;
+                    metadata.sample_rate = read_sample_rate(&mut cursor)?;
                 }
             }
             b"NAME" => {
@@ -677,13 +875,12 @@ impl AifCodec {
                 let annotation_str = String::from_utf8_lossy(&chunk_data);
                 metadata.set_field("ANNO", annotation_str.trim_end_matches('\0'))?;
             }
-            b"iXML" => {
-                let xml_str = String::from_utf8_lossy(&chunk_data);
-                metadata.parse_ixml(&xml_str)?;
-            }
             b"ID3 " => {
                 metadata.parse_id3(&chunk_data)?;
             }
+            b"APPL" => {
+                parse_appl_chunk(chunk_data, metadata);
+            }
             _ => {
                 // Skip unknown chunks
             }
@@ -700,7 +897,7 @@ impl AifCodec {
         let mut header = [0u8; 12];
         file.read_exact(&mut header)?;
         
-        if &header[0..4] != b"FORM" || &header[8..12] != b"AIFF" {
+        if &header[0..4] != b"FORM" || !is_aiff_form(&header[8..12]) {
             return Err(anyhow!("Invalid AIF file"));
         }
 
@@ -709,22 +906,13 @@ impl AifCodec {
 
         loop {
             file.seek(SeekFrom::Start(pos))?;
-            
-            let mut chunk_header = [0u8; 8];
-            match file.read_exact(&mut chunk_header) {
-                Ok(_) => {},
-                Err(_) => break, // End of file
-            }
 
-            let mut chunk_id = [0u8; 4];
-            chunk_id.copy_from_slice(&chunk_header[0..4]);
-            let chunk_size = u32::from_be_bytes([
-                chunk_header[4], chunk_header[5], chunk_header[6], chunk_header[7]
-            ]);
+            let Some((chunk_id, chunk_size)) = read_chunk_header::<BigEndian, _>(file) else {
+                break; // End of file
+            };
 
             let data_start = pos + 8;
-            let data_end = data_start + chunk_size as u64;
-            let padded_end = if chunk_size % 2 == 1 { data_end + 1 } else { data_end };
+            let padded_end = data_start + padded_size(chunk_size as u64);
 
             chunks.push(AifChunk {
                 id: chunk_id,
@@ -967,7 +1155,8 @@ impl AifCodec {
                     
                     audio_chunks.extend_from_slice(&chunk_data);
                 }
-                b"COMM" | b"NAME" | b"AUTH" | b"(c) " | b"ANNO" | b"APPL" | b"iXML" => {
+                b"COMM" | b"NAME" | b"AUTH" | b"(c) " | b"ANNO" | b"APPL" | b"iXML" | b"ID3 "
+                | b"id3 " => {
                     // Skip metadata chunks - we'll recreate these
                     cursor.seek(SeekFrom::Current(chunk_size as i64))?;
                     if chunk_size % 2 == 1 {
@@ -1034,66 +1223,51 @@ impl AifCodec {
         Err(anyhow!("No COMM chunk found"))
     }
 
+    /// Whether `create_aif_metadata_chunks` writes its own version of a chunk,
+    /// so the old one is dropped
+    fn is_rewritten_chunk(&self, id: &[u8; 4], metadata: &Metadata) -> bool {
+        matches!(
+            id,
+            NAME_CHUNK_ID
+                | AUTH_CHUNK_ID
+                | COPYRIGHT_CHUNK_ID
+                | ANNO_CHUNK_ID
+                | IXML_CHUNK_ID
+                | ID3_CHUNK_ID
+                | b"id3 "
+                | APPL_CHUNK_ID
+        ) || metadata.get_raw_chunks().iter().any(|(raw, _)| raw == id)
+    }
+
     fn create_aif_metadata_chunks(&self, metadata: &Metadata) -> R<Vec<u8>> {
-        let mut chunks = Vec::new();
+        let mut chunks = IffWriter::new(Vec::new());
 
-        // NAME chunk
-        if let Some(name) = metadata.get_field("NAME") {
-            let name_bytes = name.as_bytes();
-            chunks.extend_from_slice(b"NAME");
-            chunks.extend_from_slice(&(name_bytes.len() as u32).to_be_bytes());
-            chunks.extend_from_slice(name_bytes);
-            if name_bytes.len() % 2 == 1 {
-                chunks.push(0); // Padding
+        // NAME, AUTH (Author), (c) (Copyright) and ANNO (Annotation) text chunks
+        for id in ["NAME", "AUTH", "(c) ", "ANNO"] {
+            if let Some(text) = metadata.get_field(id) {
+                chunks.write_chunk(id.as_bytes(), text.as_bytes())?;
             }
         }
 
-        // AUTH chunk (Author)
-        if let Some(auth) = metadata.get_field("AUTH") {
-            let auth_bytes = auth.as_bytes();
-            chunks.extend_from_slice(b"AUTH");
-            chunks.extend_from_slice(&(auth_bytes.len() as u32).to_be_bytes());
-            chunks.extend_from_slice(auth_bytes);
-            if auth_bytes.len() % 2 == 1 {
-                chunks.push(0); // Padding
-            }
+        // iXML chunk
+        let ixml_content = self.create_ixml(metadata)?;
+        if !ixml_content.trim().is_empty() {
+            chunks.write_chunk(b"iXML", ixml_content.as_bytes())?;
         }
 
-        // (c) chunk (Copyright)
-        if let Some(copyright) = metadata.get_field("(c) ") {
-            let copyright_bytes = copyright.as_bytes();
-            chunks.extend_from_slice(b"(c) ");
-            chunks.extend_from_slice(&(copyright_bytes.len() as u32).to_be_bytes());
-            chunks.extend_from_slice(copyright_bytes);
-            if copyright_bytes.len() % 2 == 1 {
-                chunks.push(0); // Padding
-            }
+        if let Some(tag) = crate::id3::create_tag(metadata)? {
+            chunks.write_chunk(ID3_CHUNK_ID, &tag)?;
         }
 
-        // ANNO chunk (Annotation)
-        if let Some(anno) = metadata.get_field("ANNO") {
-            let anno_bytes = anno.as_bytes();
-            chunks.extend_from_slice(b"ANNO");
-            chunks.extend_from_slice(&(anno_bytes.len() as u32).to_be_bytes());
-            chunks.extend_from_slice(anno_bytes);
-            if anno_bytes.len() % 2 == 1 {
-                chunks.push(0); // Padding
-            }
+        for (signature, data) in metadata.get_application_data() {
+            chunks.write_chunk(APPL_CHUNK_ID, &[&signature[..], data].concat())?;
         }
 
-        // iXML chunk
-        let ixml_content = self.create_ixml(metadata)?;
-        if !ixml_content.trim().is_empty() {
-            let ixml_bytes = ixml_content.as_bytes();
-            chunks.extend_from_slice(b"iXML");
-            chunks.extend_from_slice(&(ixml_bytes.len() as u32).to_be_bytes());
-            chunks.extend_from_slice(ixml_bytes);
-            if ixml_bytes.len() % 2 == 1 {
-                chunks.push(0); // Padding
-            }
+        for (id, data) in metadata.get_raw_chunks() {
+            chunks.write_chunk(id, data)?;
         }
 
-        Ok(chunks)
+        Ok(chunks.into_inner())
     }
 
     fn embed_metadata_from_hashmap(&self, input: &[u8], metadata: &Metadata) -> R<Vec<u8>> {
@@ -1217,16 +1391,34 @@ impl AifCodec {
         chunk_id: &[u8; 4],
         data: &[u8],
     ) -> R<()> {
-        output.write_all(chunk_id)?;
-        output.write_u32::<BigEndian>(data.len() as u32)?;
-        output.write_all(data)?;
-        if data.len() % 2 == 1 {
-            output.write_all(&[0])?; // padding
-        }
-        Ok(())
+        IffWriter::new(output).write_chunk(chunk_id, data)
     }
 }
 
+/// Keep an `APPL` chunk's OSType signature and data, except Soundminer's
+fn parse_appl_chunk(data: &[u8], metadata: &mut Metadata) {
+    if let Some((signature, body)) = data.split_first_chunk::<4>()
+        && signature != SOUNDMINER_SIGNATURE
+    {
+        metadata.add_application_data(*signature, body.to_vec());
+    }
+}
+
+/// Errors if an AIFF holding `data_bytes` of sample data would not fit the
+/// 32-bit FORM and SSND chunk sizes.
+fn check_aiff_size(data_bytes: u64) -> R<()> {
+    // "AIFF" + COMM chunk (8 + 18) + SSND header (8 + offset + block size)
+    let form_size = 4 + 26 + 16 + data_bytes;
+    if form_size > u32::MAX as u64 {
+        return Err(anyhow!(
+            "AIFF output would be {} bytes, exceeding the 4GB limit of 32-bit chunk sizes; \
+             split the audio or export to a 64-bit container such as CAF or W64",
+            form_size + 8
+        ));
+    }
+    Ok(())
+}
+
 // Helper function to read IEEE 754 extended precision numbers (80-bit)
 fn read_ieee_extended(cursor: &mut Cursor<&[u8]>) -> R<f64> {
     let mut extended = [0u8; 10];
@@ -1264,19 +1456,129 @@ fn read_ieee_extended(cursor: &mut Cursor<&[u8]>) -> R<f64> {
     Ok(if sign { -result } else { result })
 }
 
+// Plain AIFF is always integer PCM; only an AIFC compression type marks float
+fn sample_format_for_bits(bits_per_sample: u16, encoding: SampleEncoding) -> R<SampleFormat> {
+    if encoding == SampleEncoding::Float {
+        return match bits_per_sample {
+            32 | 64 => Ok(SampleFormat::F32),
+            _ => Err(anyhow!("Unsupported float bit depth: {}", bits_per_sample)),
+        };
+    }
+    match bits_per_sample {
+        8 => Ok(SampleFormat::U8),
+        16 => Ok(SampleFormat::I16),
+        24 => Ok(SampleFormat::I24),
+        32 => Ok(SampleFormat::I32), // Default to integer; could be float but rare in AIFF
+        _ => Err(anyhow!("Unsupported bit depth: {}", bits_per_sample)),
+    }
+}
+
+/// Read the COMM sample rate, undoing the small errors some writers leave in
+/// the 80-bit float: within `SAMPLE_RATE_EPSILON` of a standard rate snaps to
+/// it, anything else rounds to the nearest integer
+fn read_sample_rate(cursor: &mut Cursor<&[u8]>) -> R<u32> {
+    let rate = read_ieee_extended(cursor)?;
+    if !rate.is_finite() || rate < 0.0 {
+        return Err(anyhow!("Invalid AIFF sample rate: {}", rate));
+    }
+    Ok(STANDARD_SAMPLE_RATES
+        .into_iter()
+        .find(|&standard| (rate - standard as f64).abs() < SAMPLE_RATE_EPSILON)
+        .unwrap_or_else(|| rate.round() as u32))
+}
+
 // Note: aiff_write_chunk function removed as unused
 
+/// How SSND sample bytes are laid out, from the AIFC compression type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SampleEncoding {
+    /// Plain AIFF, and AIFC `NONE`/`twos`
+    BigEndian,
+    /// AIFC `sowt`: byte-swapped integer PCM
+    LittleEndian,
+    /// AIFC `fl32`/`fl64`: big-endian IEEE float
+    Float,
+}
+
+struct CommChunk {
+    channels: u16,
+    frames: u32,
+    bits: u16,
+    sample_rate: u32,
+    encoding: SampleEncoding,
+}
+
+fn is_aiff_form(form_type: &[u8]) -> bool {
+    form_type == AIFF_FORMAT_ID || form_type == AIFC_FORMAT_ID
+}
+
+/// AIFC compression type and name for samples written in `format`
+fn aifc_compression(format: SampleFormat) -> (&'static [u8; 4], &'static str) {
+    match format {
+        SampleFormat::F32 => (b"fl32", "32-bit floating point"),
+        _ => (b"NONE", "not compressed"),
+    }
+}
+
+fn is_aifc(input: &[u8]) -> bool {
+    input.len() >= HEADER_SIZE && &input[8..12] == AIFC_FORMAT_ID
+}
+
+/// Parse a COMM body. AIFC adds a compression type after the sample rate
+/// (then a pascal-string name, which is ignored); float types set the bit
+/// depth themselves, since some writers leave the sample size at 0.
+fn read_comm(data: &[u8], aifc: bool) -> R<CommChunk> {
+    if data.len() < 18 || (aifc && data.len() < 22) {
+        return Err(anyhow!("COMM chunk too small: {} bytes", data.len()));
+    }
+    let mut cursor = Cursor::new(data);
+    let channels = cursor.read_u16::<BigEndian>()?;
+    let frames = cursor.read_u32::<BigEndian>()?;
+    let bits = cursor.read_u16::<BigEndian>()?;
+    let sample_rate = read_sample_rate(&mut cursor)?;
+
+    let (encoding, bits) = if aifc {
+        let mut compression = [0u8; 4];
+        cursor.read_exact(&mut compression)?;
+        match &compression {
+            b"NONE" | b"twos" => (SampleEncoding::BigEndian, bits),
+            b"sowt" => (SampleEncoding::LittleEndian, bits),
+            b"fl32" | b"FL32" => (SampleEncoding::Float, 32),
+            b"fl64" | b"FL64" => (SampleEncoding::Float, 64),
+            other => {
+                return Err(anyhow!(
+                    "Unsupported AIFC compression type '{}'",
+                    String::from_utf8_lossy(other)
+                ));
+            }
+        }
+    } else {
+        (SampleEncoding::BigEndian, bits)
+    };
+
+    Ok(CommChunk {
+        channels,
+        frames,
+        bits,
+        sample_rate,
+        encoding,
+    })
+}
+
 fn decode_samples(
     input: &[u8],
     channels: u16,
     bits_per_sample: u16,
-    is_float_format: bool,
+    encoding: SampleEncoding,
 ) -> R<Vec<Vec<f32>>> {
-    let bytes_per_sample = match bits_per_sample {
-        8 => 1,
-        16 => 2,
-        24 => 3,
-        32 => 4,
+    let bytes_per_sample = match (encoding, bits_per_sample) {
+        (SampleEncoding::Float, 32) => 4,
+        (SampleEncoding::Float, 64) => 8,
+        (SampleEncoding::Float, _) => return Err(anyhow!("Unsupported float bit depth")),
+        (_, 8) => 1,
+        (_, 16) => 2,
+        (_, 24) => 3,
+        (_, 32) => 4,
         _ => return Err(anyhow!("Unsupported bit depth")),
     };
 
@@ -1294,37 +1596,22 @@ fn decode_samples(
             let sample_idx = pos * bytes_per_sample;
 
             if sample_idx + bytes_per_sample - 1 < input.len() {
-                let val = match bits_per_sample {
-                    8 => {
-                        let sample = input[sample_idx] as i8;
-                        sample as f32 / 128.0
-                    }
-                    16 => {
-                        let sample = i16::from_be_bytes([input[sample_idx], input[sample_idx + 1]]);
-                        sample as f32 / 32768.0
-                    }
-                    24 => {
-                        let mut sample = i32::from_be_bytes([
-                            0,
-                            input[sample_idx],
-                            input[sample_idx + 1],
-                            input[sample_idx + 2],
-                        ]);
-                        if sample & 0x800000 != 0 {
-                            sample |= -0x01000000i32;
-                        }
-                        sample as u32 as f32 / 8388608.0
-                    }
-                    32 => {
-                        let sample = i32::from_be_bytes([
-                            input[sample_idx],
-                            input[sample_idx + 1],
-                            input[sample_idx + 2],
-                            input[sample_idx + 3],
-                        ]);
-                        sample as f32 / 2147483648.0
-                    }
-                    _ => 0.0,
+                // Gather the sample big-endian first, swapping `sowt` data into place
+                let mut b = [0u8; 8];
+                b[..bytes_per_sample]
+                    .copy_from_slice(&input[sample_idx..sample_idx + bytes_per_sample]);
+                if encoding == SampleEncoding::LittleEndian {
+                    b[..bytes_per_sample].reverse();
+                }
+
+                let val = match (encoding, bytes_per_sample) {
+                    (SampleEncoding::Float, 4) => f32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+                    (SampleEncoding::Float, _) => f64::from_be_bytes(b) as f32,
+                    (_, 1) => b[0] as i8 as f32 / 128.0,
+                    (_, 2) => i16::from_be_bytes([b[0], b[1]]) as f32 / 32768.0,
+                    // Shift the top-aligned 24-bit value back down, keeping its sign
+                    (_, 3) => (i32::from_be_bytes([b[0], b[1], b[2], 0]) >> 8) as f32 / 8388608.0,
+                    _ => i32::from_be_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2147483648.0,
                 };
                 channel_data[i] = val;
             }
@@ -1358,15 +1645,15 @@ fn encode_samples<W: Write>(out: &mut W, buffer: &AudioBuffer, bits_per_sample:
             match bits_per_sample {
                 8 => {
                     // AIFF 8-bit samples are signed
-                    let val = (sample.clamp(-1.0, 1.0) * 127.0) as i8;
+                    let val = quantize_sample(sample, 8) as i8;
                     out.write_i8(val)?;
                 }
                 16 => {
-                    let val = (sample.clamp(-1.0, 1.0) * I16_MAX_F) as i16;
+                    let val = quantize_sample(sample, 16) as i16;
                     out.write_i16::<BigEndian>(val)?;
                 }
                 24 => {
-                    let val = (sample.clamp(-1.0, 1.0) * I24_MAX_F) as i32;
+                    let val = quantize_sample(sample, 24);
                     // For big-endian, we need to write the most significant bytes first
                     let bytes = [
                         ((val >> 16) & BYTE_MASK) as u8,
@@ -1379,7 +1666,7 @@ fn encode_samples<W: Write>(out: &mut W, buffer: &AudioBuffer, bits_per_sample:
                     if buffer.format == SampleFormat::F32 {
                         out.write_f32::<BigEndian>(sample)?;
                     } else {
-                        let val = (sample.clamp(-1.0, 1.0) * I32_MAX_F) as i32;
+                        let val = quantize_sample(sample, 32);
                         out.write_i32::<BigEndian>(val)?;
                     }
                 }
@@ -1496,3 +1783,241 @@ fn write_ieee_extended_simple<W: Write>(writer: &mut W, value: f64) -> R<()> {
 
     writer.write_all(&buffer).map_err(|e| anyhow::anyhow!(e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_rounds_and_clamps_integer_samples() {
+        crate::codecs::tests::assert_encoder_quantizes(
+            &AifCodec,
+            &[
+                SampleFormat::U8,
+                SampleFormat::I16,
+                SampleFormat::I24,
+                SampleFormat::I32,
+            ],
+        );
+    }
+
+    fn multichannel_roundtrip(channels: u16, format: SampleFormat) {
+        // Give each channel a distinct signed ramp so swaps or sign errors show up
+        let data: Vec<Vec<f32>> = (0..channels)
+            .map(|ch| {
+                (0..512)
+                    .map(|i| ((i as f32 / 512.0) - 0.5) * (ch as f32 + 1.0) / channels as f32)
+                    .collect()
+            })
+            .collect();
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels,
+            format,
+            data: data.clone(),
+        };
+
+        let encoded = AifCodec.encode(&Some(buffer)).unwrap();
+        let decoded = AifCodec.decode(&encoded).unwrap();
+
+        assert_eq!(decoded.channels, channels);
+        assert_eq!(decoded.format, format);
+        for (ch, (expected, actual)) in data.iter().zip(&decoded.data).enumerate() {
+            assert_eq!(actual.len(), expected.len(), "channel {ch} length");
+            for (a, b) in expected.iter().zip(actual) {
+                assert!((a - b).abs() < 1e-3, "channel {ch}: {a} != {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_six_channel_roundtrip() {
+        multichannel_roundtrip(6, SampleFormat::I16);
+        multichannel_roundtrip(6, SampleFormat::I24);
+    }
+
+    #[test]
+    fn test_eight_channel_roundtrip() {
+        multichannel_roundtrip(8, SampleFormat::I16);
+        multichannel_roundtrip(8, SampleFormat::I24);
+    }
+
+    #[test]
+    fn test_oversize_export_errors() {
+        assert!(check_aiff_size(48_000 * 2 * 3 * 60).is_ok());
+
+        let five_gb = 5 * 1024 * 1024 * 1024;
+        let err = check_aiff_size(five_gb).unwrap_err();
+        assert!(err.to_string().contains("4GB"));
+    }
+
+    #[test]
+    fn test_appl_chunk_survives_metadata_edit() {
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 1,
+            format: SampleFormat::I16,
+            data: vec![vec![0.25; 256]],
+        };
+        let encoded = AifCodec.encode(&Some(buffer)).unwrap();
+        let ssnd = IffReader::new(&encoded, HEADER_SIZE)
+            .find(|chunk| &chunk.id == DATA_CHUNK_ID)
+            .unwrap();
+        let mut appl = IffWriter::new(Vec::new());
+        appl.write_chunk(APPL_CHUNK_ID, b"Lgic\x01\x02region")
+            .unwrap();
+        appl.write_chunk(APPL_CHUNK_ID, b"SMED\x00\x00").unwrap();
+        let mut bytes = encoded[..ssnd.offset].to_vec();
+        bytes.extend(appl.into_inner());
+        bytes.extend_from_slice(&encoded[ssnd.offset..]);
+        let form_size = bytes.len() as u32 - 8;
+        bytes[4..8].copy_from_slice(&form_size.to_be_bytes());
+
+        let path = std::env::temp_dir().join(format!("ffcodex_appl_{}.aif", rand::random::<u32>()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, &bytes).unwrap();
+        let mut metadata = AifCodec.extract_metadata_from_file(path).unwrap();
+        let logic = (*b"Lgic", b"\x01\x02region".to_vec());
+        assert_eq!(metadata.get_application_data(), [logic.clone()]);
+
+        metadata.set_field("USER_SCENE", "Harbor").unwrap();
+        AifCodec.embed_metadata_to_file(path, &metadata).unwrap();
+        let edited = std::fs::read(path).unwrap();
+        let _ = std::fs::remove_file(path);
+        let reparsed = AifCodec.parse_metadata(&edited).unwrap();
+        assert_eq!(reparsed.get_field("USER_SCENE").as_deref(), Some("Harbor"));
+        assert_eq!(reparsed.get_application_data(), [logic]);
+    }
+
+    #[test]
+    fn test_standard_rates_decode_exactly() {
+        for rate in STANDARD_SAMPLE_RATES {
+            let buffer = AudioBuffer {
+                sample_rate: rate,
+                channels: 1,
+                format: SampleFormat::I16,
+                data: vec![vec![0.0; 64]],
+            };
+            let decoded = AifCodec
+                .decode(&AifCodec.encode(&Some(buffer)).unwrap())
+                .unwrap();
+            assert_eq!(decoded.sample_rate, rate);
+        }
+
+        // A rate stored slightly low still reads back as the standard rate
+        let mut extended = Vec::new();
+        write_ieee_extended(&mut extended, 44099.7).unwrap();
+        assert_eq!(
+            read_sample_rate(&mut Cursor::new(&extended[..])).unwrap(),
+            44100
+        );
+        let mut extended = Vec::new();
+        write_ieee_extended(&mut extended, 12345.6).unwrap();
+        assert_eq!(
+            read_sample_rate(&mut Cursor::new(&extended[..])).unwrap(),
+            12346
+        );
+    }
+
+    fn aifc_file(compression: &[u8; 4], bits: u16, sound: &[u8], frames: u32) -> Vec<u8> {
+        let mut comm = Vec::new();
+        comm.write_u16::<BigEndian>(1).unwrap();
+        comm.write_u32::<BigEndian>(frames).unwrap();
+        comm.write_u16::<BigEndian>(bits).unwrap();
+        write_ieee_extended(&mut comm, 48000.0).unwrap();
+        comm.extend_from_slice(compression);
+        comm.extend_from_slice(&[0, 0]); // empty compression name, padded
+
+        let mut ssnd = vec![0u8; 8];
+        ssnd.extend_from_slice(sound);
+        let mut body = IffWriter::new(AIFC_FORMAT_ID.to_vec());
+        body.write_chunk(b"FVER", &0xA280_5140u32.to_be_bytes())
+            .unwrap();
+        body.write_chunk(FMT_CHUNK_ID, &comm).unwrap();
+        body.write_chunk(DATA_CHUNK_ID, &ssnd).unwrap();
+        let body = body.into_inner();
+
+        let mut file = FORM_CHUNK_ID.to_vec();
+        file.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        file.extend_from_slice(&body);
+        file
+    }
+
+    #[test]
+    fn test_aifc_compression_types_decode() {
+        let samples = [0.5f32, -0.25, 0.125, -1.0];
+        let sowt: Vec<u8> = samples
+            .iter()
+            .flat_map(|&s| ((s * 32768.0) as i16).to_le_bytes())
+            .collect();
+        let fl32: Vec<u8> = samples.iter().flat_map(|s| s.to_be_bytes()).collect();
+        let fl64: Vec<u8> = samples
+            .iter()
+            .flat_map(|&s| (s as f64).to_be_bytes())
+            .collect();
+
+        for (compression, bits, sound, format) in [
+            (b"sowt", 16, sowt, SampleFormat::I16),
+            (b"fl32", 32, fl32, SampleFormat::F32),
+            (b"fl64", 64, fl64, SampleFormat::F32),
+        ] {
+            let file = aifc_file(compression, bits, &sound, samples.len() as u32);
+            let decoded = AifCodec.decode(&file).unwrap();
+            assert_eq!(decoded.format, format);
+            assert_eq!(decoded.sample_rate, 48000);
+            assert_eq!(decoded.data, vec![samples.to_vec()]);
+
+            let (header, blocks) = AifCodec.decode_stream(&file).unwrap();
+            assert_eq!(header.format, format);
+            let streamed: Vec<f32> = blocks.flat_map(|block| block.unwrap().remove(0)).collect();
+            assert_eq!(streamed, samples);
+        }
+
+        let ulaw = aifc_file(b"ulaw", 16, &[0; 8], 4);
+        assert!(AifCodec.decode(&ulaw).is_err());
+    }
+
+    #[test]
+    fn test_float_and_aifc_exports_write_aifc_and_take_metadata() {
+        let samples = vec![0.5f32, -0.25, 0.125, -1.0, 0.75];
+        let float = AudioBuffer {
+            sample_rate: 48000,
+            channels: 1,
+            format: SampleFormat::F32,
+            data: vec![samples.clone()],
+        };
+        let integer = AudioBuffer {
+            format: SampleFormat::I16,
+            ..float.clone()
+        };
+
+        for (encoded, format) in [
+            (AifCodec.encode(&Some(float)).unwrap(), SampleFormat::F32),
+            (
+                AifCodec.encode_aifc(&Some(integer)).unwrap(),
+                SampleFormat::I16,
+            ),
+        ] {
+            assert_eq!(&encoded[8..12], AIFC_FORMAT_ID);
+            let decoded = AifCodec.decode(&encoded).unwrap();
+            assert_eq!(decoded.format, format);
+            assert_eq!(decoded.data, vec![samples.clone()]);
+
+            let path = std::env::temp_dir()
+                .join(format!("ffcodex_aifc_{}.aifc", rand::random::<u32>()))
+                .to_string_lossy()
+                .to_string();
+            std::fs::write(&path, &encoded).unwrap();
+            let mut metadata = Metadata::new();
+            metadata.set_field("USER_SCENE", "Harbor").unwrap();
+            let result = AifCodec.embed_metadata_to_file(&path, &metadata);
+            let tagged = std::fs::read(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            result.unwrap();
+
+            let parsed = AifCodec.parse_metadata(&tagged).unwrap();
+            assert_eq!(parsed.get_field("USER_SCENE").as_deref(), Some("Harbor"));
+            assert_eq!(AifCodec.decode(&tagged).unwrap().data, decoded.data);
+        }
+    }
+}
@@ -0,0 +1,648 @@
+use super::quantize_sample;
+use crate::prelude::*;
+
+// File header: 'caff', version, flags
+const CAFF_FILE_TYPE: &[u8; 4] = b"caff";
+const CAF_FILE_VERSION: u16 = 1;
+const HEADER_SIZE: usize = 8;
+
+// Chunk Identifiers
+const DESC_CHUNK_ID: &[u8; 4] = b"desc";
+const DATA_CHUNK_ID: &[u8; 4] = b"data";
+const INFO_CHUNK_ID: &[u8; 4] = b"info";
+
+// Chunk Structures
+const CHUNK_HEADER_SIZE: usize = 12; // id + 64-bit size
+const DESC_CHUNK_SIZE: usize = 32;
+const EDIT_COUNT_SIZE: usize = 4; // data chunks start with a 32-bit edit count
+// A data chunk size of -1 means the chunk runs to the end of the file
+const UNKNOWN_CHUNK_SIZE: i64 = -1;
+
+// Audio Description
+const LPCM_FORMAT_ID: &[u8; 4] = b"lpcm";
+const FORMAT_FLAG_IS_FLOAT: u32 = 1 << 0;
+const FORMAT_FLAG_IS_LITTLE_ENDIAN: u32 = 1 << 1;
+
+/// Core Audio Format. Linear PCM only: 8 to 32-bit integer and 32/64-bit float,
+/// in either byte order. Encodes big-endian, the CAF default.
+pub struct CafCodec;
+
+/// The `desc` chunk (an `AudioStreamBasicDescription`, stored big-endian)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CafDesc {
+    sample_rate: f64,
+    format_id: [u8; 4],
+    format_flags: u32,
+    bytes_per_packet: u32,
+    frames_per_packet: u32,
+    channels: u32,
+    bits_per_channel: u32,
+}
+
+impl CafDesc {
+    fn from_body(body: &[u8]) -> R<Self> {
+        if body.len() < DESC_CHUNK_SIZE {
+            return Err(anyhow!("CAF desc chunk too small: {} bytes", body.len()));
+        }
+        let mut cursor = Cursor::new(body);
+        let sample_rate = cursor.read_f64::<BigEndian>()?;
+        let mut format_id = [0u8; 4];
+        cursor.read_exact(&mut format_id)?;
+        Ok(Self {
+            sample_rate,
+            format_id,
+            format_flags: cursor.read_u32::<BigEndian>()?,
+            bytes_per_packet: cursor.read_u32::<BigEndian>()?,
+            frames_per_packet: cursor.read_u32::<BigEndian>()?,
+            channels: cursor.read_u32::<BigEndian>()?,
+            bits_per_channel: cursor.read_u32::<BigEndian>()?,
+        })
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(DESC_CHUNK_SIZE);
+        out.extend_from_slice(&self.sample_rate.to_be_bytes());
+        out.extend_from_slice(&self.format_id);
+        for value in [
+            self.format_flags,
+            self.bytes_per_packet,
+            self.frames_per_packet,
+            self.channels,
+            self.bits_per_channel,
+        ] {
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+        out
+    }
+
+    fn is_float(&self) -> bool {
+        self.format_flags & FORMAT_FLAG_IS_FLOAT != 0
+    }
+
+    fn is_little_endian(&self) -> bool {
+        self.format_flags & FORMAT_FLAG_IS_LITTLE_ENDIAN != 0
+    }
+
+    /// Bytes of one sample of one channel; only packed PCM is supported
+    fn sample_width(&self) -> R<usize> {
+        if &self.format_id != LPCM_FORMAT_ID {
+            return Err(anyhow!(
+                "Unsupported CAF format: {}",
+                String::from_utf8_lossy(&self.format_id)
+            ));
+        }
+        if self.channels == 0 || self.frames_per_packet != 1 {
+            return Err(anyhow!("Invalid CAF linear PCM description"));
+        }
+        let width = (self.bytes_per_packet / self.channels) as usize;
+        let supported = if self.is_float() {
+            matches!(width, 4 | 8)
+        } else {
+            matches!(width, 1..=4)
+        };
+        if !supported || width * 8 != self.bits_per_channel as usize {
+            return Err(anyhow!(
+                "Unsupported CAF sample layout: {} bits in {} bytes",
+                self.bits_per_channel,
+                width
+            ));
+        }
+        Ok(width)
+    }
+
+    fn sample_format(&self) -> R<SampleFormat> {
+        if self.is_float() {
+            // 64-bit float is decoded to f32 like every other source
+            return Ok(SampleFormat::F32);
+        }
+        match self.bits_per_channel {
+            8 => Ok(SampleFormat::U8),
+            16 => Ok(SampleFormat::I16),
+            24 => Ok(SampleFormat::I24),
+            32 => Ok(SampleFormat::I32),
+            bits => Err(anyhow!("Unsupported CAF bit depth: {}", bits)),
+        }
+    }
+
+    /// Frames held in `audio_bytes` of the data chunk (excluding the edit count)
+    fn frame_count(&self, audio_bytes: usize) -> u64 {
+        if self.bytes_per_packet == 0 {
+            return 0;
+        }
+        (audio_bytes / self.bytes_per_packet as usize) as u64 * self.frames_per_packet as u64
+    }
+}
+
+/// A chunk of a CAF file; `data` is cut short when the file is truncated
+struct CafChunk<'a> {
+    id: [u8; 4],
+    data: &'a [u8],
+}
+
+/// Split a CAF file into its chunks. Sizes are 64-bit and there is no padding.
+fn caf_chunks(input: &[u8]) -> R<Vec<CafChunk<'_>>> {
+    let mut chunks = Vec::new();
+    let mut pos = HEADER_SIZE;
+    while pos + CHUNK_HEADER_SIZE <= input.len() {
+        let mut id = [0u8; 4];
+        id.copy_from_slice(&input[pos..pos + 4]);
+        let size = i64::from_be_bytes(input[pos + 4..pos + 12].try_into()?);
+        let start = pos + CHUNK_HEADER_SIZE;
+        let end = if size == UNKNOWN_CHUNK_SIZE {
+            input.len()
+        } else if size < 0 {
+            return Err(anyhow!(
+                "Invalid size {} for CAF chunk '{}'",
+                size,
+                String::from_utf8_lossy(&id)
+            ));
+        } else {
+            start.saturating_add(size as usize).min(input.len())
+        };
+        chunks.push(CafChunk {
+            id,
+            data: &input[start..end],
+        });
+        pos = end;
+    }
+    Ok(chunks)
+}
+
+/// Key/value pairs of an `info` chunk: an entry count followed by
+/// NUL-terminated UTF-8 strings, alternating key and value
+fn parse_info(body: &[u8]) -> Vec<(String, String)> {
+    if body.len() < 4 {
+        return Vec::new();
+    }
+    let count = u32::from_be_bytes([body[0], body[1], body[2], body[3]]) as usize;
+    let mut strings = body[4..]
+        .split(|&b| b == 0)
+        .map(|s| String::from_utf8_lossy(s).into_owned());
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let (Some(key), Some(value)) = (strings.next(), strings.next()) else {
+            break;
+        };
+        entries.push((key, value));
+    }
+    entries
+}
+
+fn write_info(entries: &[(String, String)]) -> Vec<u8> {
+    let mut out = (entries.len() as u32).to_be_bytes().to_vec();
+    for (key, value) in entries {
+        out.extend_from_slice(key.as_bytes());
+        out.push(0);
+        out.extend_from_slice(value.as_bytes());
+        out.push(0);
+    }
+    out
+}
+
+fn write_caf_chunk<W: Write>(writer: &mut W, id: &[u8; 4], data: &[u8]) -> R<()> {
+    writer.write_all(id)?;
+    writer.write_i64::<BigEndian>(data.len() as i64)?;
+    writer.write_all(data)?;
+    Ok(())
+}
+
+fn decode_samples(input: &[u8], desc: &CafDesc) -> R<Vec<Vec<f32>>> {
+    let width = desc.sample_width()?;
+    let channels = desc.channels as usize;
+    let frames = input.len() / (width * channels);
+
+    let mut output = vec![Vec::with_capacity(frames); channels];
+    let mut bytes = [0u8; 8];
+    for frame in input.chunks_exact(width * channels) {
+        for (channel, sample) in output.iter_mut().zip(frame.chunks_exact(width)) {
+            // Normalise to big-endian, right-aligned in `bytes[8 - width..]`
+            let be = &mut bytes[8 - width..];
+            be.copy_from_slice(sample);
+            if desc.is_little_endian() {
+                be.reverse();
+            }
+            let value = match (desc.is_float(), width) {
+                (true, 4) => f32::from_be_bytes(be.try_into()?),
+                (true, _) => f64::from_be_bytes(be.try_into()?) as f32,
+                (false, 1) => be[0] as i8 as f32 / 128.0,
+                (false, 2) => i16::from_be_bytes(be.try_into()?) as f32 / 32768.0,
+                (false, 3) => {
+                    // Sign-extend through the top byte of an i32
+                    i32::from_be_bytes([be[0], be[1], be[2], 0]) as f32 / 2147483648.0
+                }
+                (false, _) => i32::from_be_bytes(be.try_into()?) as f32 / 2147483648.0,
+            };
+            channel.push(value);
+        }
+    }
+    Ok(output)
+}
+
+fn encode_samples<W: Write>(out: &mut W, buffer: &AudioBuffer, desc: &CafDesc) -> R<()> {
+    let width = desc.sample_width()?;
+    let frames = buffer.data.first().map_or(0, Vec::len);
+
+    for i in 0..frames {
+        for channel in &buffer.data {
+            let sample = channel[i];
+            let mut be = if desc.is_float() {
+                match width {
+                    4 => sample.to_be_bytes().to_vec(),
+                    _ => (sample as f64).to_be_bytes().to_vec(),
+                }
+            } else {
+                let value = quantize_sample(sample, (width * 8) as u16);
+                value.to_be_bytes()[4 - width..].to_vec()
+            };
+            if desc.is_little_endian() {
+                be.reverse();
+            }
+            out.write_all(&be)?;
+        }
+    }
+    Ok(())
+}
+
+impl Codec for CafCodec {
+    fn as_str(&self) -> &'static str {
+        "CAF"
+    }
+
+    fn file_extension(&self) -> &'static str {
+        "caf"
+    }
+
+    fn tag_name_for(&self, logical: &str) -> String {
+        self.map_to_caf_key(logical)
+    }
+
+    fn logical_for(&self, tag: &str) -> String {
+        self.normalize_caf_key(tag)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn validate_file_format(&self, data: &[u8]) -> R<()> {
+        if data.len() < HEADER_SIZE {
+            return Err(anyhow!("File too small to be a valid CAF"));
+        }
+        if &data[0..4] != CAFF_FILE_TYPE {
+            return Err(anyhow!("Not a valid CAF file: Missing caff header"));
+        }
+        let version = u16::from_be_bytes([data[4], data[5]]);
+        if version != CAF_FILE_VERSION {
+            return Err(anyhow!("Unsupported CAF file version: {}", version));
+        }
+        Ok(())
+    }
+
+    fn validate_structure(&self, input: &[u8], report: &mut ValidationReport) {
+        let chunks = match caf_chunks(input) {
+            Ok(chunks) => chunks,
+            Err(e) => {
+                report.add_issue(e.to_string());
+                return;
+            }
+        };
+        match chunks.first() {
+            Some(chunk) if &chunk.id == DESC_CHUNK_ID => {
+                if chunk.data.len() < DESC_CHUNK_SIZE {
+                    report.add_issue("desc chunk truncated");
+                }
+            }
+            _ => report.add_issue("First chunk is not desc"),
+        }
+        if !chunks.iter().any(|chunk| &chunk.id == DATA_CHUNK_ID) {
+            report.add_issue("No data chunk found");
+        }
+    }
+
+    fn get_file_info(&self, file_path: &str) -> R<FileInfo> {
+        let file = std::fs::File::open(file_path)?;
+        let file_size = file.metadata()?.len() as usize;
+        let mapped_file = unsafe { MmapOptions::new().map(&file)? };
+
+        self.validate_file_format(&mapped_file)?;
+
+        let mut desc = None;
+        let mut audio_bytes = 0;
+        let mut description = String::new();
+        for chunk in caf_chunks(&mapped_file)? {
+            match &chunk.id {
+                DESC_CHUNK_ID => desc = Some(CafDesc::from_body(chunk.data)?),
+                DATA_CHUNK_ID => audio_bytes = chunk.data.len().saturating_sub(EDIT_COUNT_SIZE),
+                INFO_CHUNK_ID => {
+                    if let Some((_, comment)) = parse_info(chunk.data)
+                        .into_iter()
+                        .find(|(key, value)| key == "comments" && !value.trim().is_empty())
+                    {
+                        description = comment.trim().to_string();
+                    }
+                }
+                _ => {}
+            }
+        }
+        let desc = desc.ok_or_else(|| anyhow!("Could not find desc chunk in CAF file"))?;
+
+        let duration_seconds = if desc.sample_rate > 0.0 {
+            desc.frame_count(audio_bytes) as f64 / desc.sample_rate
+        } else {
+            0.0
+        };
+
+        let duration = if duration_seconds >= 3600.0 {
+            format!(
+                "{:.0}:{:02.0}:{:02.0}",
+                duration_seconds / 3600.0,
+                (duration_seconds % 3600.0) / 60.0,
+                duration_seconds % 60.0
+            )
+        } else {
+            format!(
+                "{:.0}:{:02.0}",
+                duration_seconds / 60.0,
+                duration_seconds % 60.0
+            )
+        };
+
+        Ok(FileInfo {
+            path: file_path.to_string(),
+            size: file_size,
+            sample_rate: desc.sample_rate.round() as u32,
+            channels: desc.channels as u16,
+            bit_depth: desc.bits_per_channel as u16,
+            duration,
+            duration_secs: duration_seconds,
+            description,
+        })
+    }
+
+    fn encode(&self, buffer: &Option<AudioBuffer>) -> R<Vec<u8>> {
+        let Some(buffer) = buffer else {
+            return Err(anyhow!("Cannot encode None AudioBuffer"));
+        };
+        self.encode_caf(buffer, false)
+    }
+
+    fn decode(&self, input: &[u8]) -> R<AudioBuffer> {
+        self.validate_file_format(input)?;
+
+        let chunks = caf_chunks(input)?;
+        let desc = chunks
+            .iter()
+            .find(|chunk| &chunk.id == DESC_CHUNK_ID)
+            .map(|chunk| CafDesc::from_body(chunk.data))
+            .transpose()?;
+        let data = chunks.iter().find(|chunk| &chunk.id == DATA_CHUNK_ID);
+        let (Some(desc), Some(data)) = (desc, data) else {
+            return Err(anyhow!("Missing 'desc' or 'data' chunk"));
+        };
+
+        let audio = data.data.get(EDIT_COUNT_SIZE..).unwrap_or_default();
+        Ok(AudioBuffer {
+            sample_rate: desc.sample_rate.round() as u32,
+            channels: desc.channels as u16,
+            format: desc.sample_format()?,
+            data: decode_samples(audio, &desc)?,
+        })
+    }
+
+    fn parse_metadata(&self, input: &[u8]) -> R<Metadata> {
+        self.validate_file_format(input)?;
+
+        let mut metadata = Metadata::new();
+        for chunk in caf_chunks(input)? {
+            if &chunk.id == INFO_CHUNK_ID {
+                for (key, value) in parse_info(chunk.data) {
+                    let prefixed_key = format!("TAG_{}", self.logical_for(&key));
+                    metadata.add_field_value(&prefixed_key, &value)?;
+                }
+            }
+        }
+        Ok(metadata)
+    }
+
+    fn embed_metadata_to_file(&self, file_path: &str, metadata: &Metadata) -> R<()> {
+        let output = self.embed_metadata(&std::fs::read(file_path)?, metadata)?;
+        std::fs::write(file_path, output)
+            .map_err(|e| anyhow!("No write permission for file '{}': {}", file_path, e))?;
+        Ok(())
+    }
+
+    /// Replaces the `info` chunk with the `TAG_` fields, placed right after
+    /// `desc`; every other chunk is copied through unchanged.
+    fn embed_metadata(&self, input: &[u8], metadata: &Metadata) -> R<Vec<u8>> {
+        self.validate_file_format(input)?;
+
+        let mut keys: Vec<&String> = metadata
+            .get_all_fields()
+            .keys()
+            .filter(|key| key.starts_with("TAG_"))
+            .collect();
+        keys.sort();
+        let entries: Vec<(String, String)> = keys
+            .into_iter()
+            .flat_map(|key| {
+                let tag = self.tag_name_for(&key[4..]);
+                metadata
+                    .get_field_all(key)
+                    .into_iter()
+                    .map(move |value| (tag.clone(), value))
+            })
+            .collect();
+
+        let mut output = Vec::with_capacity(input.len());
+        output.extend_from_slice(&input[..HEADER_SIZE]);
+        for chunk in caf_chunks(input)? {
+            if &chunk.id == INFO_CHUNK_ID {
+                continue;
+            }
+            write_caf_chunk(&mut output, &chunk.id, chunk.data)?;
+            if &chunk.id == DESC_CHUNK_ID && !entries.is_empty() {
+                write_caf_chunk(&mut output, INFO_CHUNK_ID, &write_info(&entries))?;
+            }
+        }
+        Ok(output)
+    }
+}
+
+impl CafCodec {
+    fn encode_caf(&self, buffer: &AudioBuffer, little_endian: bool) -> R<Vec<u8>> {
+        if buffer.data.is_empty() {
+            return Err(anyhow!("Cannot encode empty audio buffer"));
+        }
+        if buffer.data.len() != buffer.channels as usize {
+            return Err(anyhow!(
+                "Buffer declares {} channels but holds {}",
+                buffer.channels,
+                buffer.data.len()
+            ));
+        }
+        let frame_count = buffer.data[0].len();
+        if let Some(i) = buffer.data.iter().position(|ch| ch.len() != frame_count) {
+            return Err(anyhow!(
+                "Channel {} has {} samples, expected {}",
+                i,
+                buffer.data[i].len(),
+                frame_count
+            ));
+        }
+
+        let bits_per_channel = buffer.format.bits_per_sample() as u32;
+        let mut format_flags = 0;
+        if buffer.format == SampleFormat::F32 {
+            format_flags |= FORMAT_FLAG_IS_FLOAT;
+        }
+        if little_endian {
+            format_flags |= FORMAT_FLAG_IS_LITTLE_ENDIAN;
+        }
+        let desc = CafDesc {
+            sample_rate: buffer.sample_rate as f64,
+            format_id: *LPCM_FORMAT_ID,
+            format_flags,
+            bytes_per_packet: bits_per_channel / 8 * buffer.channels as u32,
+            frames_per_packet: 1,
+            channels: buffer.channels as u32,
+            bits_per_channel,
+        };
+
+        let mut data = vec![0u8; EDIT_COUNT_SIZE];
+        encode_samples(&mut data, buffer, &desc)?;
+
+        let mut output =
+            Vec::with_capacity(HEADER_SIZE + 2 * CHUNK_HEADER_SIZE + DESC_CHUNK_SIZE + data.len());
+        output.extend_from_slice(CAFF_FILE_TYPE);
+        output.write_u16::<BigEndian>(CAF_FILE_VERSION)?;
+        output.write_u16::<BigEndian>(0)?; // File flags
+        write_caf_chunk(&mut output, DESC_CHUNK_ID, &desc.to_bytes())?;
+        write_caf_chunk(&mut output, DATA_CHUNK_ID, &data)?;
+        Ok(output)
+    }
+
+    fn normalize_caf_key(&self, key: &str) -> String {
+        match key {
+            "title" => "Title".to_string(),
+            "artist" => "Artist".to_string(),
+            "album" => "Album".to_string(),
+            "year" => "Year".to_string(),
+            "genre" => "Genre".to_string(),
+            "track number" => "Track".to_string(),
+            "composer" => "Composer".to_string(),
+            "lyricist" => "Lyricist".to_string(),
+            "comments" => "Comment".to_string(),
+            "copyright" => "Copyright".to_string(),
+            "tempo" => "Tempo".to_string(),
+            "key signature" => "Key".to_string(),
+            "time signature" => "TimeSignature".to_string(),
+            "recorded date" => "RecordedDate".to_string(),
+            "encoding application" => "EncodingSettings".to_string(),
+            _ => key.to_string(),
+        }
+    }
+
+    fn map_to_caf_key(&self, key: &str) -> String {
+        match key {
+            "Title" => "title".to_string(),
+            "Artist" => "artist".to_string(),
+            "Album" => "album".to_string(),
+            "Year" => "year".to_string(),
+            "Genre" => "genre".to_string(),
+            "Track" => "track number".to_string(),
+            "Composer" => "composer".to_string(),
+            "Lyricist" => "lyricist".to_string(),
+            "Comment" => "comments".to_string(),
+            "Copyright" => "copyright".to_string(),
+            "Tempo" => "tempo".to_string(),
+            "Key" => "key signature".to_string(),
+            "TimeSignature" => "time signature".to_string(),
+            "RecordedDate" => "recorded date".to_string(),
+            "EncodingSettings" => "encoding application".to_string(),
+            _ => key.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_rounds_and_clamps_integer_samples() {
+        crate::codecs::tests::assert_encoder_quantizes(
+            &CafCodec,
+            &[
+                SampleFormat::U8,
+                SampleFormat::I16,
+                SampleFormat::I24,
+                SampleFormat::I32,
+            ],
+        );
+    }
+
+    fn ramp(channels: u16, format: SampleFormat, frames: usize) -> AudioBuffer {
+        // Values spread over the full range that land exactly on the 24-bit grid
+        let data = (0..channels as i32)
+            .map(|ch| {
+                (0..frames as i32)
+                    .map(|i| {
+                        let value = (i * 7919 + ch * 104729) % 16777216 - 8388608;
+                        value as f32 / 8388608.0
+                    })
+                    .collect()
+            })
+            .collect();
+        AudioBuffer {
+            sample_rate: 48000,
+            channels,
+            format,
+            data,
+        }
+    }
+
+    #[test]
+    fn test_24_bit_caf_roundtrips_losslessly() {
+        let source = ramp(2, SampleFormat::I24, 4096);
+        let caf = CafCodec.encode(&Some(source.clone())).unwrap();
+        let decoded = CafCodec.decode(&caf).unwrap();
+        assert_eq!(decoded.format, SampleFormat::I24);
+        assert_eq!(decoded.data, source.data);
+    }
+
+    #[test]
+    fn test_little_endian_float_with_info_chunk() {
+        let mut source = ramp(1, SampleFormat::F32, 22050);
+        source.sample_rate = 44100;
+        let caf = CafCodec.encode_caf(&source, true).unwrap();
+        let decoded = CafCodec.decode(&caf).unwrap();
+        assert_eq!(
+            (decoded.sample_rate, decoded.format),
+            (44100, SampleFormat::F32)
+        );
+        assert_eq!(decoded.data, source.data);
+
+        let path = std::env::temp_dir().join("ffcodex_caf_info_test.caf");
+        let path = path.to_str().unwrap();
+        std::fs::write(path, &caf).unwrap();
+        let mut metadata = Metadata::new();
+        metadata.set_field("TAG_Title", "Rain on Tin").unwrap();
+        metadata
+            .set_field("TAG_Comment", "Close perspective")
+            .unwrap();
+        CafCodec.embed_metadata_to_file(path, &metadata).unwrap();
+
+        let info = CafCodec.get_file_info(path).unwrap();
+        let tagged = std::fs::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(info.duration_secs, 0.5);
+        assert_eq!(info.description, "Close perspective");
+
+        let metadata = CafCodec.parse_metadata(&tagged).unwrap();
+        assert_eq!(
+            metadata.get_field("TAG_Title").as_deref(),
+            Some("Rain on Tin")
+        );
+        assert_eq!(CafCodec.decode(&tagged).unwrap().data, source.data);
+    }
+}
@@ -0,0 +1,195 @@
+//! Chunk reading and writing shared by the RIFF (WAV, little-endian) and
+//! IFF (AIFF, big-endian) codecs.
+//!
+//! Both formats use the same layout: a 4-byte id, a 4-byte size that excludes
+//! the header and the pad byte, the body, and one pad byte when the body length
+//! is odd. Only the byte order of the size field differs.
+
+use crate::prelude::*;
+use byteorder::ByteOrder;
+use std::marker::PhantomData;
+
+pub(crate) type RiffReader<'a> = ChunkReader<'a, LittleEndian>;
+pub(crate) type IffReader<'a> = ChunkReader<'a, BigEndian>;
+pub(crate) type RiffWriter<W> = ChunkWriter<W, LittleEndian>;
+pub(crate) type IffWriter<W> = ChunkWriter<W, BigEndian>;
+
+/// Size of a chunk on disk once the pad byte is included
+pub(crate) fn padded_size(size: u64) -> u64 {
+    size + (size % 2)
+}
+
+/// Read one chunk header (id and size) from a stream; `None` at end of input
+pub(crate) fn read_chunk_header<E: ByteOrder, Rd: Read>(reader: &mut Rd) -> Option<([u8; 4], u32)> {
+    let mut id = [0u8; 4];
+    reader.read_exact(&mut id).ok()?;
+    let size = reader.read_u32::<E>().ok()?;
+    Some((id, size))
+}
+
+/// A chunk found by `ChunkReader`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Chunk<'a> {
+    pub id: [u8; 4],
+    /// Offset of the chunk header within the scanned bytes
+    pub offset: usize,
+    /// Size as declared in the header
+    pub size: u32,
+    /// Body bytes, cut short if the input ends before the declared size
+    pub data: &'a [u8],
+}
+
+impl Chunk<'_> {
+    /// Offset of the body within the scanned bytes
+    pub fn data_start(&self) -> usize {
+        self.offset + 8
+    }
+
+    /// Offset just past the body and its pad byte
+    pub fn end(&self) -> usize {
+        self.data_start() + padded_size(self.size as u64) as usize
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.data.len() < self.size as usize
+    }
+}
+
+/// Iterates the chunks of an in-memory RIFF/IFF body
+pub(crate) struct ChunkReader<'a, E: ByteOrder> {
+    input: &'a [u8],
+    pos: usize,
+    _order: PhantomData<E>,
+}
+
+impl<'a, E: ByteOrder> ChunkReader<'a, E> {
+    /// Start reading chunks at `start` (12 to skip a RIFF/FORM header)
+    pub fn new(input: &'a [u8], start: usize) -> Self {
+        Self {
+            input,
+            pos: start,
+            _order: PhantomData,
+        }
+    }
+}
+
+impl<'a, E: ByteOrder> Iterator for ChunkReader<'a, E> {
+    type Item = Chunk<'a>;
+
+    fn next(&mut self) -> Option<Chunk<'a>> {
+        let offset = self.pos;
+        let header = self.input.get(offset..offset.checked_add(8)?)?;
+        let mut id = [0u8; 4];
+        id.copy_from_slice(&header[0..4]);
+        let size = E::read_u32(&header[4..8]);
+
+        let data_start = offset + 8;
+        let data_end = data_start
+            .saturating_add(size as usize)
+            .min(self.input.len());
+        let chunk = Chunk {
+            id,
+            offset,
+            size,
+            data: &self.input[data_start..data_end],
+        };
+
+        // A truncated chunk is still reported, but nothing can follow it
+        self.pos = if chunk.is_truncated() {
+            self.input.len()
+        } else {
+            chunk.end()
+        };
+        Some(chunk)
+    }
+}
+
+/// Writes chunks with the size field in `E` byte order and the pad byte added
+pub(crate) struct ChunkWriter<W: Write, E: ByteOrder> {
+    out: W,
+    _order: PhantomData<E>,
+}
+
+impl<W: Write, E: ByteOrder> ChunkWriter<W, E> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            _order: PhantomData,
+        }
+    }
+
+    /// Write a complete chunk: header, body and pad byte
+    pub fn write_chunk(&mut self, id: &[u8], data: &[u8]) -> R<()> {
+        if id.len() != 4 {
+            return Err(anyhow!("Chunk id must be 4 bytes, got {}", id.len()));
+        }
+        let size = u32::try_from(data.len())
+            .map_err(|_| anyhow!("Chunk '{}' is too large", String::from_utf8_lossy(id)))?;
+        self.out.write_all(id)?;
+        self.out.write_u32::<E>(size)?;
+        self.out.write_all(data)?;
+        if data.len() % 2 == 1 {
+            self.out.write_all(&[0])?; // padding
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODIES: [(&[u8; 4], &[u8]); 4] = [
+        (b"fmt ", b"sixteen bytes!!!"),
+        (b"odd ", b"abc"),
+        (b"none", b""),
+        (b"tail", b"x"),
+    ];
+
+    fn roundtrip<E: ByteOrder>() -> Vec<u8> {
+        let mut writer = ChunkWriter::<_, E>::new(Vec::new());
+        for (id, body) in BODIES {
+            writer.write_chunk(id, body).unwrap();
+        }
+        let bytes = writer.into_inner();
+        assert_eq!(bytes.len() % 2, 0);
+
+        let chunks: Vec<_> = ChunkReader::<E>::new(&bytes, 0).collect();
+        assert_eq!(chunks.len(), BODIES.len());
+        for (chunk, (id, body)) in chunks.iter().zip(BODIES) {
+            assert_eq!(&chunk.id, id);
+            assert_eq!(chunk.data, body);
+            assert!(!chunk.is_truncated());
+        }
+        assert_eq!(chunks.last().unwrap().end(), bytes.len());
+        bytes
+    }
+
+    #[test]
+    fn test_riff_and_iff_read_back_what_they_write() {
+        let riff = roundtrip::<LittleEndian>();
+        let iff = roundtrip::<BigEndian>();
+        assert_eq!(&riff[4..8], &16u32.to_le_bytes());
+        assert_eq!(&iff[4..8], &16u32.to_be_bytes());
+
+        let header = read_chunk_header::<BigEndian, _>(&mut Cursor::new(&iff[24..])).unwrap();
+        assert_eq!(header, (*b"odd ", 3));
+    }
+
+    #[test]
+    fn test_truncated_chunk_ends_iteration() {
+        let mut bytes = RiffWriter::new(Vec::new());
+        bytes.write_chunk(b"LIST", &[1; 10]).unwrap();
+        let mut bytes = bytes.into_inner();
+        bytes.truncate(14);
+
+        let chunks: Vec<_> = RiffReader::new(&bytes, 0).collect();
+        assert_eq!(chunks.len(), 1);
+        assert!(chunks[0].is_truncated());
+        assert_eq!(chunks[0].data.len(), 6);
+    }
+}
@@ -1,3 +1,4 @@
+use super::quantize_sample;
 use crate::prelude::*;
 use claxon::FlacReader;
 use flacenc::component::BitRepr;
@@ -12,15 +13,114 @@ const VORBIS_COMMENT_BLOCK_TYPE: u8 = 4;
 // Note: PICTURE_BLOCK_TYPE and LAST_METADATA_BLOCK_FLAG removed as unused
 
 // Sample normalization constants
-const I16_MAX_F: f32 = 32767.0;
 const I16_DIVISOR: f32 = 32768.0;
-const I24_MAX_F: f32 = 8388607.0;
 const I24_DIVISOR: f32 = 8388608.0;
-const I32_MAX_F: f32 = 2147483647.0;
 const I32_DIVISOR: f32 = 2147483648.0;
 
 pub struct FlacCodec;
 
+// Per compression level: block size, maximum LPC order (0 = fixed predictors
+// only) and stereo decorrelation, after the reference encoder's presets
+const COMPRESSION_LEVELS: [(usize, usize, bool); 9] = [
+    (1152, 0, false),
+    (1152, 0, true),
+    (1152, 0, true),
+    (4096, 6, false),
+    (4096, 8, true),
+    (4096, 8, true),
+    (4096, 8, true),
+    (4096, 8, true),
+    (4096, 12, true),
+];
+
+/// FLAC settings for `FlacCodec::encode_with_options` and `EncodeOptions::flac`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlacEncodeOptions {
+    /// 0 (fastest) to 8 (smallest), as with the reference `flac` tool
+    pub compression_level: u8,
+    /// Samples per block; None uses the level's block size
+    pub block_size: Option<u32>,
+}
+
+impl Default for FlacEncodeOptions {
+    fn default() -> Self {
+        Self {
+            compression_level: 5,
+            block_size: None,
+        }
+    }
+}
+
+impl FlacEncodeOptions {
+    fn encoder_config(&self) -> R<flacenc::config::Encoder> {
+        let &(block_size, lpc_order, stereo) = COMPRESSION_LEVELS
+            .get(self.compression_level as usize)
+            .ok_or_else(|| {
+                anyhow!(
+                    "FLAC compression level must be 0-8, got {}",
+                    self.compression_level
+                )
+            })?;
+
+        let mut config = flacenc::config::Encoder::default();
+        config.block_size = self.block_size.map_or(block_size, |size| size as usize);
+        config.stereo_coding.use_leftside = stereo;
+        config.stereo_coding.use_rightside = stereo;
+        config.stereo_coding.use_midside = stereo;
+        config.subframe_coding.use_lpc = lpc_order > 0;
+        if lpc_order > 0 {
+            config.subframe_coding.qlpc.lpc_order = lpc_order;
+        }
+        Ok(config)
+    }
+}
+
+/// Stream parameters from the FLAC STREAMINFO block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlacStreamInfo {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub total_samples: u64,
+    pub md5: [u8; 16],
+}
+
+impl FlacStreamInfo {
+    /// Parse the 34-byte STREAMINFO block body according to the FLAC spec
+    pub fn from_block(streaminfo: &[u8]) -> R<Self> {
+        if streaminfo.len() < 34 {
+            return Err(anyhow!("Invalid STREAMINFO block size"));
+        }
+
+        let sample_rate = ((streaminfo[10] as u32) << 12)
+            | ((streaminfo[11] as u32) << 4)
+            | ((streaminfo[12] as u32) >> 4);
+
+        let channels = (((streaminfo[12] as u16) >> 1) & 0x07) + 1;
+
+        let bits_per_sample =
+            ((((streaminfo[12] as u16) & 0x01) << 4) | ((streaminfo[13] as u16) >> 4)) + 1;
+
+        // Total samples (36-bit value)
+        let total_samples = ((streaminfo[13] as u64 & 0x0F) << 32)
+            | ((streaminfo[14] as u64) << 24)
+            | ((streaminfo[15] as u64) << 16)
+            | ((streaminfo[16] as u64) << 8)
+            | (streaminfo[17] as u64);
+
+        let mut md5 = [0u8; 16];
+        md5.copy_from_slice(&streaminfo[18..34]);
+
+        Ok(Self {
+            sample_rate,
+            channels,
+            bits_per_sample,
+            total_samples,
+            md5,
+        })
+    }
+}
+
 impl Codec for FlacCodec {
     fn extract_metadata_from_file(&self, file_path: &str) -> R<Metadata> {
         // Optimized two-phase metadata extraction for FLAC files
@@ -106,6 +206,14 @@ impl Codec for FlacCodec {
         "flac"
     }
 
+    fn tag_name_for(&self, logical: &str) -> String {
+        self.map_to_vorbis_key(&self.normalize_vorbis_key(logical))
+    }
+
+    fn logical_for(&self, tag: &str) -> String {
+        self.normalize_vorbis_key(tag)
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {
         self
     }
@@ -123,6 +231,52 @@ impl Codec for FlacCodec {
 
         Ok(())
     }
+    fn validate_structure(&self, input: &[u8], report: &mut ValidationReport) {
+        let mut pos = 4;
+        let mut first_block = true;
+        loop {
+            if pos + 4 > input.len() {
+                report.add_issue("File truncated inside metadata block headers");
+                return;
+            }
+
+            let header = input[pos];
+            let is_last = (header & 0x80) != 0;
+            let block_type = header & 0x7F;
+            let block_size = ((input[pos + 1] as usize) << 16)
+                | ((input[pos + 2] as usize) << 8)
+                | (input[pos + 3] as usize);
+
+            if first_block && (block_type != 0 || block_size != 34) {
+                report.add_issue("First metadata block is not a valid STREAMINFO");
+            }
+            if block_type == 127 {
+                report.add_issue("Invalid metadata block type 127");
+            }
+            if pos + 4 + block_size > input.len() {
+                report.add_issue(format!(
+                    "Metadata block type {} truncated: declares {} bytes but only {} remain",
+                    block_type,
+                    block_size,
+                    input.len() - pos - 4
+                ));
+                return;
+            }
+
+            first_block = false;
+            pos += 4 + block_size;
+            if is_last {
+                break;
+            }
+        }
+
+        // Audio frames start with the 14-bit sync code 0b11111111111110
+        if pos + 2 > input.len() {
+            report.add_issue("No audio frames after metadata blocks");
+        } else if input[pos] != 0xFF || (input[pos + 1] & 0xFC) != 0xF8 {
+            report.add_issue("Missing frame sync code after metadata blocks");
+        }
+    }
     fn get_file_info(&self, file_path: &str) -> R<FileInfo> {
         use memmap2::MmapOptions;
         use std::fs;
@@ -161,23 +315,11 @@ impl Codec for FlacCodec {
                     let mut streaminfo = vec![0u8; block_size];
                     cursor.read_exact(&mut streaminfo)?;
 
-                    // Parse STREAMINFO according to FLAC spec
-                    sample_rate = ((streaminfo[10] as u32) << 12)
-                        | ((streaminfo[11] as u32) << 4)
-                        | ((streaminfo[12] as u32) >> 4);
-
-                    channels = (((streaminfo[12] as u16) >> 1) & 0x07) + 1;
-
-                    bits_per_sample = ((((streaminfo[12] as u16) & 0x01) << 4)
-                        | ((streaminfo[13] as u16) >> 4))
-                        + 1;
-
-                    // Total samples (36-bit value)
-                    total_samples = ((streaminfo[13] as u64 & 0x0F) << 32)
-                        | ((streaminfo[14] as u64) << 24)
-                        | ((streaminfo[15] as u64) << 16)
-                        | ((streaminfo[16] as u64) << 8)
-                        | (streaminfo[17] as u64);
+                    let info = FlacStreamInfo::from_block(&streaminfo)?;
+                    sample_rate = info.sample_rate;
+                    channels = info.channels;
+                    bits_per_sample = info.bits_per_sample;
+                    total_samples = info.total_samples;
                 }
                 VORBIS_COMMENT_BLOCK_TYPE => {
                     // VORBIS_COMMENT block contains metadata including possible description
@@ -265,10 +407,11 @@ impl Codec for FlacCodec {
         Ok(FileInfo {
             path: file_path.to_string(),
             size: file_size,
-            sample_rate: sample_rate as u16,
+            sample_rate,
             channels,
             bit_depth: bits_per_sample,
             duration,
+            duration_secs: duration_seconds,
             description,
         })
     }
@@ -292,12 +435,7 @@ impl Codec for FlacCodec {
         };
 
         // Calculate the appropriate divisor once, outside the loop
-        let divisor = match bits_per_sample {
-            16 => I16_DIVISOR,
-            24 => I24_DIVISOR,
-            32 => I32_DIVISOR,
-            _ => (1 << (bits_per_sample - 1)) as f32,
-        };
+        let divisor = sample_divisor(bits_per_sample);
 
         let channel_count = channels as usize;
         let mut audio_data: Vec<Vec<f32>> =
@@ -359,126 +497,56 @@ impl Codec for FlacCodec {
             data: audio_data,
         })
     }
-    fn encode(&self, buffer: &Option<AudioBuffer>) -> R<Vec<u8>> {
-        let Some(buffer) = buffer else {
-            return Err(anyhow!("Cannot encode None AudioBuffer"));
-        };
-        // Get audio parameters
-        let bits_per_sample = get_bits_per_sample(buffer.format);
-        let channels = buffer.channels as usize;
-        let sample_rate = buffer.sample_rate as usize;
-
-        if buffer.data.is_empty() || buffer.data[0].is_empty() {
-            return Err(anyhow!("Cannot encode empty audio buffer"));
-        }
-
-        let num_samples = buffer.data[0].len();
 
-        // Pre-calculate conversion factors outside of the loop for better performance
-        let scale_factor = match bits_per_sample {
-            8 => 127.0,
-            16 => I16_MAX_F,
-            24 => I24_MAX_F,
-            32 => I32_MAX_F,
-            _ => {
-                return Err(anyhow!(
-                    "Unsupported bit depth for FLAC encoding: {}",
-                    bits_per_sample
-                ));
-            }
+    fn decode_stream<'a>(&self, input: &'a [u8]) -> R<(StreamHeader, FrameStream<'a>)> {
+        let reader = FlacReader::new(Cursor::new(input))?;
+        let streaminfo = reader.streaminfo();
+        let bits_per_sample = streaminfo.bits_per_sample as u16;
+        let channel_count = streaminfo.channels as usize;
+        let divisor = sample_divisor(bits_per_sample);
+
+        let header = StreamHeader {
+            sample_rate: streaminfo.sample_rate,
+            channels: channel_count as u16,
+            format: select_sample_format(bits_per_sample),
+            total_frames: streaminfo.samples,
         };
 
-        // Create the interleaved samples vector using either parallel or sequential approach
-        let interleaved_samples = if num_samples > 100_000 {
-            // For large files, use parallel processing with thread-local data
-            let chunk_size = (num_samples / rayon::current_num_threads()).max(1024);
-
-            // Use parallel iterator with collect to build the final vector
-            (0..num_samples)
-                .into_par_iter()
-                .chunks(chunk_size)
-                .flat_map(|chunk_indices| {
-                    // Create a local buffer for each thread
-                    let mut local_buffer = Vec::with_capacity(chunk_indices.len() * channels);
-
-                    // Process samples in this chunk
-                    for i in chunk_indices {
-                        for ch in 0..channels {
-                            let sample = buffer.data[ch][i];
-                            let val = (sample * scale_factor).round() as i32;
-                            local_buffer.push(val);
-                        }
-                    }
-
-                    local_buffer
-                })
-                .collect()
-        } else {
-            // For smaller files, use a straightforward sequential approach
-            // which avoids overhead of parallelism for small datasets
-            let mut samples = Vec::with_capacity(num_samples * channels);
-            for i in 0..num_samples {
-                for ch in 0..channels {
-                    let sample = buffer.data[ch][i];
-                    let val = (sample * scale_factor).round() as i32;
-                    samples.push(val);
+        let mut samples = reader.into_samples();
+        let blocks = std::iter::from_fn(move || {
+            let mut block = vec![Vec::with_capacity(STREAM_BLOCK_FRAMES); channel_count];
+            let interleaved = samples.by_ref().take(STREAM_BLOCK_FRAMES * channel_count);
+            for (i, sample) in interleaved.enumerate() {
+                match sample {
+                    Ok(sample) => block[i % channel_count].push(sample as f32 / divisor),
+                    Err(e) => return Some(Err(e.into())),
                 }
             }
-            samples
-        };
-
-        // Configure the encoder with optimized settings
+            (!block[0].is_empty()).then_some(Ok(block))
+        });
+        Ok((header, Box::new(blocks)))
+    }
+    fn encode(&self, buffer: &Option<AudioBuffer>) -> R<Vec<u8>> {
         let mut config = flacenc::config::Encoder::default();
 
         // Set larger block size for better throughput and compression
         config.block_size = 8192;
 
-        // Create a verified config
-        let config = config
-            .into_verified()
-            .map_err(|e| anyhow!("Invalid FLAC encoder configuration: {:?}", e))?;
-
-        // Create a source from the interleaved samples
-        let source = flacenc::source::MemSource::from_samples(
-            &interleaved_samples,
-            channels,
-            bits_per_sample as usize,
-            sample_rate,
-        );
-
-        // Use a fixed block size for consistent performance
-        let flac_stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
-            .map_err(|e| anyhow!("FLAC encoding error: {:?}", e))?;
-
-        // Estimate final buffer size (typically FLAC is ~50-60% of raw PCM)
-        let estimated_size = (num_samples * channels * (bits_per_sample as usize / 8) / 2) + 8192;
-
-        // Create a byte sink with sufficient capacity
-        let mut sink = flacenc::bitsink::ByteSink::new();
-        sink.reserve(estimated_size);
-
-        // Write the encoded stream
-        flac_stream.write(&mut sink)?;
-
-        // Return the encoded FLAC data
-        Ok(sink.as_slice().to_vec())
+        self.encode_with_config(buffer, config, false)
     }
 
     fn parse_metadata(&self, input: &[u8]) -> R<Metadata> {
         let mut metadata = Metadata::new();
 
         // First, try to parse FLAC metadata blocks using metaflac
-        let temp_file = std::env::temp_dir().join("temp_flac_metadata");
-        std::fs::write(&temp_file, input)?;
-
-        if let Ok(tag) = Tag::read_from_path(&temp_file) {
+        if let Ok(tag) = Tag::read_from(&mut Cursor::new(input)) {
             // Parse Vorbis comments
             if let Some(comments) = tag.vorbis_comments() {
                 for (key, values) in &comments.comments {
-                    if !values.is_empty() {
-                        let standard_key = self.normalize_vorbis_key(key);
-                        let prefixed_key = format!("TAG_{}", standard_key);
-                        metadata.set_field(&prefixed_key, &values[0])?;
+                    let standard_key = self.normalize_vorbis_key(key);
+                    let prefixed_key = format!("TAG_{}", standard_key);
+                    for value in values {
+                        metadata.add_field_value(&prefixed_key, value)?;
                     }
                 }
             }
@@ -494,9 +562,6 @@ impl Codec for FlacCodec {
             }
         }
 
-        // Clean up temp file
-        let _ = std::fs::remove_file(&temp_file);
-
         // Also parse any embedded chunks manually from the FLAC stream
         let mut cursor = Cursor::new(input);
 
@@ -571,20 +636,63 @@ impl Codec for FlacCodec {
             .open(file_path)
             .map_err(|e| anyhow!("No write permission for file '{}': {}", file_path, e))?;
 
+        // Skip the rewrite entirely when nothing changed, so the file stays byte-for-byte
+        // identical (metaflac would otherwise reorder blocks and adjust padding)
+        if let Ok(existing) = self.extract_metadata_from_file(file_path)
+            && existing.content_eq(metadata)
+        {
+            dprintln!(
+                "FLAC embed: metadata unchanged, leaving {} untouched",
+                file_path
+            );
+            return Ok(());
+        }
+
         // Use metaflac to safely write metadata blocks
         let mut dest_tag = Tag::read_from_path(file_path).unwrap_or_else(|_| Tag::new());
+        self.replace_metadata_blocks(&mut dest_tag, metadata)?;
+
+        // Write the metadata back to the file
+        dest_tag
+            .write_to_path(file_path)
+            .map_err(|e| anyhow!("Failed to write FLAC metadata: {}", e))?;
 
+        Ok(())
+    }
+
+    fn embed_metadata(&self, input: &[u8], metadata: &Metadata) -> R<Vec<u8>> {
+        self.validate_file_format(input)?;
+        if self.parse_metadata(input)?.content_eq(metadata) {
+            return Ok(input.to_vec());
+        }
+
+        let mut tag = Tag::read_from(&mut Cursor::new(input))
+            .map_err(|e| anyhow!("Failed to read FLAC metadata: {}", e))?;
+        self.replace_metadata_blocks(&mut tag, metadata)?;
+
+        let mut output = Vec::with_capacity(input.len());
+        tag.write_to(&mut output)
+            .map_err(|e| anyhow!("Failed to write FLAC metadata: {}", e))?;
+        output.extend_from_slice(&input[audio_frames_start(input)?..]);
+        Ok(output)
+    }
+}
+
+impl FlacCodec {
+    /// Swap the Vorbis comment, picture and application blocks of `tag` for
+    /// ones built from `metadata`
+    fn replace_metadata_blocks(&self, tag: &mut Tag, metadata: &Metadata) -> R<()> {
         // Clear existing metadata blocks that we're about to replace
-        dest_tag.remove_blocks(metaflac::BlockType::VorbisComment);
-        dest_tag.remove_blocks(metaflac::BlockType::Picture);
-        dest_tag.remove_blocks(metaflac::BlockType::Application);
+        tag.remove_blocks(metaflac::BlockType::VorbisComment);
+        tag.remove_blocks(metaflac::BlockType::Picture);
+        tag.remove_blocks(metaflac::BlockType::Application);
 
         // Create a new VorbisComment block from the hashmap
         let mut vorbis_comment = metaflac::block::VorbisComment::new();
         vorbis_comment.vendor_string = "FFCodex".to_string();
 
         // Add all fields from the hashmap to VorbisComment
-        for (key, value) in metadata.get_all_fields().iter() {
+        for key in metadata.get_all_fields().keys() {
             // Check if this is a TAG_ prefixed key (from Vorbis comments)
             let vorbis_key = if key.starts_with("TAG_") {
                 // Remove TAG_ prefix and map the remaining key to Vorbis format
@@ -595,20 +703,21 @@ impl Codec for FlacCodec {
                 continue;
             };
 
+            // Repeated keys (multiple ARTIST, GENRE, ...) are written back as separate comments
             vorbis_comment
                 .comments
                 .entry(vorbis_key)
                 .or_default()
-                .push(value.clone());
+                .extend(metadata.get_field_all(key));
         }
 
         // Add the VorbisComment block
-        dest_tag.push_block(Block::VorbisComment(vorbis_comment));
+        tag.push_block(Block::VorbisComment(vorbis_comment));
 
         // Add image chunks as Picture blocks
         for image in metadata.get_images() {
             let picture_block = metaflac::block::Picture {
-                picture_type: metaflac::block::PictureType::Other,
+                picture_type: flac_picture_type(image.picture_type()),
                 mime_type: image.mime_type().to_string(),
                 description: image.description().to_string(),
                 width: 0,
@@ -617,7 +726,7 @@ impl Codec for FlacCodec {
                 num_colors: 0,
                 data: image.data().to_vec(),
             };
-            dest_tag.push_block(Block::Picture(picture_block));
+            tag.push_block(Block::Picture(picture_block));
         }
 
         // Add iXML as Application block (BWF chunk)
@@ -626,18 +735,153 @@ impl Codec for FlacCodec {
             id: b"iXML".to_vec(),
             data: ixml_content.as_bytes().to_vec(),
         };
-        dest_tag.push_block(Block::Application(ixml_block));
-
-        // Write the metadata back to the file
-        dest_tag
-            .write_to_path(file_path)
-            .map_err(|e| anyhow!("Failed to write FLAC metadata: {}", e))?;
+        tag.push_block(Block::Application(ixml_block));
 
         Ok(())
     }
-}
 
-impl FlacCodec {
+    /// Encode at a chosen compression level (0 fastest, 8 smallest) and block size
+    pub fn encode_with_options(
+        &self,
+        buffer: &Option<AudioBuffer>,
+        options: &FlacEncodeOptions,
+    ) -> R<Vec<u8>> {
+        self.encode_with_config(buffer, options.encoder_config()?, false)
+    }
+
+    /// `encode_with_options`, also writing the MD5 of the encoded samples into
+    /// STREAMINFO so decoders can check the audio on unpack
+    pub fn encode_with_md5(
+        &self,
+        buffer: &Option<AudioBuffer>,
+        options: &FlacEncodeOptions,
+    ) -> R<Vec<u8>> {
+        self.encode_with_config(buffer, options.encoder_config()?, true)
+    }
+
+    /// Encode with explicit encoder settings
+    fn encode_with_config(
+        &self,
+        buffer: &Option<AudioBuffer>,
+        config: flacenc::config::Encoder,
+        store_md5: bool,
+    ) -> R<Vec<u8>> {
+        let Some(buffer) = buffer else {
+            return Err(anyhow!("Cannot encode None AudioBuffer"));
+        };
+        // Get audio parameters
+        let bits_per_sample = get_bits_per_sample(buffer.format);
+        let channels = buffer.channels as usize;
+        let sample_rate = buffer.sample_rate as usize;
+
+        if buffer.data.is_empty() || buffer.data[0].is_empty() {
+            return Err(anyhow!("Cannot encode empty audio buffer"));
+        }
+
+        let num_samples = buffer.data[0].len();
+
+        if !matches!(bits_per_sample, 8 | 16 | 24 | 32) {
+            return Err(anyhow!(
+                "Unsupported bit depth for FLAC encoding: {}",
+                bits_per_sample
+            ));
+        }
+
+        // Create the interleaved samples vector using either parallel or sequential approach
+        let interleaved_samples = if num_samples > 100_000 {
+            // For large files, use parallel processing with thread-local data
+            let chunk_size = (num_samples / rayon::current_num_threads()).max(1024);
+
+            // Use parallel iterator with collect to build the final vector
+            (0..num_samples)
+                .into_par_iter()
+                .chunks(chunk_size)
+                .flat_map(|chunk_indices| {
+                    // Create a local buffer for each thread
+                    let mut local_buffer = Vec::with_capacity(chunk_indices.len() * channels);
+
+                    // Process samples in this chunk
+                    for i in chunk_indices {
+                        for ch in 0..channels {
+                            let sample = buffer.data[ch][i];
+                            let val = quantize_sample(sample, bits_per_sample);
+                            local_buffer.push(val);
+                        }
+                    }
+
+                    local_buffer
+                })
+                .collect()
+        } else {
+            // For smaller files, use a straightforward sequential approach
+            // which avoids overhead of parallelism for small datasets
+            let mut samples = Vec::with_capacity(num_samples * channels);
+            for i in 0..num_samples {
+                for ch in 0..channels {
+                    let sample = buffer.data[ch][i];
+                    let val = quantize_sample(sample, bits_per_sample);
+                    samples.push(val);
+                }
+            }
+            samples
+        };
+
+        // Create a verified config
+        let config = config
+            .into_verified()
+            .map_err(|e| anyhow!("Invalid FLAC encoder configuration: {:?}", e))?;
+
+        // Create a source from the interleaved samples
+        let source = flacenc::source::MemSource::from_samples(
+            &interleaved_samples,
+            channels,
+            bits_per_sample as usize,
+            sample_rate,
+        );
+
+        // Use a fixed block size for consistent performance
+        let flac_stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+            .map_err(|e| anyhow!("FLAC encoding error: {:?}", e))?;
+
+        // Estimate final buffer size (typically FLAC is ~50-60% of raw PCM)
+        let estimated_size = (num_samples * channels * (bits_per_sample as usize / 8) / 2) + 8192;
+
+        // Create a byte sink with sufficient capacity
+        let mut sink = flacenc::bitsink::ByteSink::new();
+        sink.reserve(estimated_size);
+
+        // Write the encoded stream
+        flac_stream.write(&mut sink)?;
+
+        let mut encoded = sink.as_slice().to_vec();
+        if store_md5 {
+            // STREAMINFO follows the marker and its block header; the MD5 is its last 16 bytes
+            encoded
+                .get_mut(26..42)
+                .ok_or_else(|| anyhow!("Encoded FLAC stream has no STREAMINFO"))?
+                .copy_from_slice(&samples_md5(&interleaved_samples, bits_per_sample));
+        }
+
+        // Return the encoded FLAC data
+        Ok(encoded)
+    }
+
+    /// Fast path for library scans: read only the marker and STREAMINFO block (42 bytes)
+    /// without touching Vorbis comments, pictures or audio frames.
+    pub fn peek_streaminfo(&self, file_path: &str) -> R<FlacStreamInfo> {
+        let mut file = std::fs::File::open(file_path)?;
+        let mut header = [0u8; 42];
+        file.read_exact(&mut header)
+            .map_err(|e| anyhow!("File too small to contain STREAMINFO: {}", e))?;
+        self.validate_file_format(&header)?;
+
+        if header[4] & 0x7F != STREAMINFO_BLOCK_TYPE {
+            return Err(anyhow!("First metadata block is not STREAMINFO"));
+        }
+
+        FlacStreamInfo::from_block(&header[8..42])
+    }
+
     fn normalize_vorbis_key(&self, key: &str) -> String {
         match key.to_uppercase().as_str() {
             "TITLE" => "Title".to_string(),
@@ -717,6 +961,36 @@ impl FlacCodec {
 }
 
 // Helper function to get bits per sample from SampleFormat
+/// Offset of the first audio frame, just past the last metadata block
+fn audio_frames_start(input: &[u8]) -> R<usize> {
+    let mut pos = FLAC_MARKER.len();
+    loop {
+        let header = input
+            .get(pos..pos + 4)
+            .ok_or_else(|| anyhow!("FLAC stream truncated inside metadata block headers"))?;
+        let block_size =
+            ((header[1] as usize) << 16) | ((header[2] as usize) << 8) | (header[3] as usize);
+        pos += 4 + block_size;
+        if header[0] & 0x80 != 0 {
+            return Ok(pos.min(input.len()));
+        }
+    }
+}
+
+// STREAMINFO's MD5: every sample signed, little-endian, in its whole byte width
+fn samples_md5(interleaved: &[i32], bits_per_sample: u16) -> [u8; 16] {
+    use md5::{Digest, Md5};
+
+    let bytes_per_sample = bits_per_sample as usize / 8;
+    let mut hasher = Md5::new();
+    for sample in interleaved {
+        hasher.update(&sample.to_le_bytes()[..bytes_per_sample]);
+    }
+    let mut md5 = [0u8; 16];
+    md5.copy_from_slice(&hasher.finalize());
+    md5
+}
+
 fn get_bits_per_sample(format: SampleFormat) -> u16 {
     match format {
         SampleFormat::U8 => 8,
@@ -726,6 +1000,26 @@ fn get_bits_per_sample(format: SampleFormat) -> u16 {
     }
 }
 
+/// PICTURE block type for an image, using the same ID3v2 codes
+fn flac_picture_type(picture_type: PictureType) -> metaflac::block::PictureType {
+    match picture_type {
+        PictureType::Other => metaflac::block::PictureType::Other,
+        PictureType::FrontCover => metaflac::block::PictureType::CoverFront,
+        PictureType::BackCover => metaflac::block::PictureType::CoverBack,
+        PictureType::Artist => metaflac::block::PictureType::Artist,
+    }
+}
+
+// Scale that maps a decoded integer sample to +/-1.0
+fn sample_divisor(bits_per_sample: u16) -> f32 {
+    match bits_per_sample {
+        16 => I16_DIVISOR,
+        24 => I24_DIVISOR,
+        32 => I32_DIVISOR,
+        _ => (1 << (bits_per_sample - 1)) as f32,
+    }
+}
+
 // Helper function to select sample format based on bit depth
 fn select_sample_format(bits_per_sample: u16) -> SampleFormat {
     match bits_per_sample {
@@ -740,3 +1034,180 @@ fn select_sample_format(bits_per_sample: u16) -> SampleFormat {
         _ => SampleFormat::I32,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_rounds_and_clamps_integer_samples() {
+        // flacenc and claxon are only relied on up to 24 bits
+        crate::codecs::tests::assert_encoder_quantizes(
+            &FlacCodec,
+            &[SampleFormat::U8, SampleFormat::I16, SampleFormat::I24],
+        );
+    }
+
+    #[test]
+    fn test_peek_streaminfo_stops_after_streaminfo() {
+        let path = std::env::temp_dir()
+            .join(format!("ffcodex_peek_{}.flac", rand::random::<u32>()))
+            .to_string_lossy()
+            .to_string();
+        let buffer = AudioBuffer {
+            sample_rate: 96000,
+            channels: 2,
+            format: SampleFormat::I24,
+            data: vec![vec![0.1; 9600], vec![-0.1; 9600]],
+        };
+        let encoded = FlacCodec.encode(&Some(buffer)).unwrap();
+
+        // Keep only the marker and STREAMINFO, then append junk that a full parse would choke on
+        let mut truncated = encoded[..42].to_vec();
+        truncated[4] &= 0x7F;
+        truncated.extend_from_slice(&[0xFF; 16]);
+        std::fs::write(&path, &truncated).unwrap();
+
+        let info = FlacCodec.peek_streaminfo(&path).unwrap();
+        assert_eq!(info.sample_rate, 96000);
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.bits_per_sample, 24);
+        assert_eq!(info.total_samples, 9600);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_noop_embed_preserves_bytes() {
+        let path = std::env::temp_dir()
+            .join(format!("ffcodex_noop_{}.flac", rand::random::<u32>()))
+            .to_string_lossy()
+            .to_string();
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 1,
+            format: SampleFormat::I16,
+            data: vec![vec![0.2; 4800]],
+        };
+        let codec = FlacCodec;
+        codec.encode_file(&Some(buffer), &path).unwrap();
+
+        let mut metadata = Metadata::new();
+        metadata.set_field("TAG_Title", "Rain on tin roof").unwrap();
+        codec.embed_metadata_to_file(&path, &metadata).unwrap();
+        let before = std::fs::read(&path).unwrap();
+
+        let existing = codec.extract_metadata_from_file(&path).unwrap();
+        codec.embed_metadata_to_file(&path, &existing).unwrap();
+        assert_eq!(before, std::fs::read(&path).unwrap());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_picture_blocks_keep_their_type() {
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 1,
+            format: SampleFormat::I16,
+            data: vec![vec![0.2; 4800]],
+        };
+        let encoded = FlacCodec.encode(&Some(buffer)).unwrap();
+        let mut metadata = Metadata::new();
+        for picture_type in [PictureType::FrontCover, PictureType::BackCover] {
+            let image = ImageChunk::new("image/png".to_string(), String::new(), vec![1, 2, 3])
+                .with_picture_type(picture_type);
+            metadata.add_image(image);
+        }
+
+        let embedded = FlacCodec.embed_metadata(&encoded, &metadata).unwrap();
+        let tag = Tag::read_from(&mut Cursor::new(&embedded)).unwrap();
+        let types: Vec<_> = tag.pictures().map(|picture| picture.picture_type).collect();
+        assert_eq!(
+            types,
+            [
+                metaflac::block::PictureType::CoverFront,
+                metaflac::block::PictureType::CoverBack
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multi_value_vorbis_roundtrip() {
+        let path = std::env::temp_dir()
+            .join(format!("ffcodex_multi_{}.flac", rand::random::<u32>()))
+            .to_string_lossy()
+            .to_string();
+        let buffer = AudioBuffer {
+            sample_rate: 44100,
+            channels: 1,
+            format: SampleFormat::I16,
+            data: vec![(0..4410).map(|i| (i as f32 * 0.05).sin() * 0.25).collect()],
+        };
+
+        let codec = FlacCodec;
+        codec.encode_file(&Some(buffer), &path).unwrap();
+
+        let mut metadata = Metadata::new();
+        for artist in ["Alpha", "Bravo", "Charlie"] {
+            metadata.add_field_value("TAG_Artist", artist).unwrap();
+        }
+        codec.embed_metadata_to_file(&path, &metadata).unwrap();
+
+        let parsed = codec.extract_metadata_from_file(&path).unwrap();
+        assert_eq!(
+            parsed.get_field_all("TAG_Artist"),
+            vec!["Alpha", "Bravo", "Charlie"]
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_higher_compression_level_is_smaller() {
+        // Correlated stereo tones with a little noise, so prediction and
+        // mid/side coding both have something to gain
+        let frames = 96000;
+        let left: Vec<f32> = (0..frames)
+            .map(|i| {
+                let t = i as f32 / 96000.0;
+                0.4 * (t * 440.0 * std::f32::consts::TAU).sin()
+                    + 0.2 * (t * 1250.0 * std::f32::consts::TAU).sin()
+                    + (rand::random::<f32>() - 0.5) * 0.001
+            })
+            .collect();
+        let right: Vec<f32> = left.iter().map(|s| s * 0.9).collect();
+        let buffer = Some(AudioBuffer {
+            sample_rate: 96000,
+            channels: 2,
+            format: SampleFormat::I24,
+            data: vec![left, right],
+        });
+
+        let encode = |compression_level| {
+            let options = FlacEncodeOptions {
+                compression_level,
+                block_size: None,
+            };
+            FlacCodec.encode_with_options(&buffer, &options).unwrap()
+        };
+        let fastest = encode(0);
+        let smallest = encode(8);
+        assert!(
+            smallest.len() < fastest.len(),
+            "level 8 ({} bytes) not smaller than level 0 ({} bytes)",
+            smallest.len(),
+            fastest.len()
+        );
+        assert_eq!(
+            FlacCodec.decode(&smallest).unwrap().data,
+            FlacCodec.decode(&fastest).unwrap().data
+        );
+
+        let invalid = FlacEncodeOptions {
+            compression_level: 9,
+            block_size: None,
+        };
+        assert!(FlacCodec.encode_with_options(&buffer, &invalid).is_err());
+    }
+}
@@ -0,0 +1,637 @@
+use crate::prelude::*;
+use audiopus::coder::{Decoder, Encoder};
+use audiopus::{Application, Bitrate, Channels, SampleRate};
+use ogg::reading::PacketReader;
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+const OGG_CAPTURE_PATTERN: &[u8; 4] = b"OggS";
+const OPUS_HEAD_MAGIC: &[u8; 8] = b"OpusHead";
+const OPUS_TAGS_MAGIC: &[u8; 8] = b"OpusTags";
+/// Ogg page header up to and including the segment count
+const OGG_PAGE_HEADER_SIZE: usize = 27;
+// A first Ogg page with a single segment: 27-byte header + 1 lacing value
+const FIRST_PACKET_OFFSET: usize = OGG_PAGE_HEADER_SIZE + 1;
+const OPUS_HEAD_SIZE: usize = 19;
+
+/// Opus always decodes at 48 kHz, whatever rate the source had
+const OPUS_SAMPLE_RATE: u32 = 48000;
+/// 20 ms frames
+const OPUS_FRAME_SIZE: usize = 960;
+/// Largest frame a packet can decode to (120 ms)
+const OPUS_MAX_FRAME_SIZE: usize = 5760;
+const OPUS_MAX_PACKET_SIZE: usize = 4000;
+const DEFAULT_BITRATE: u32 = 96_000;
+/// Audio packets per Ogg page when encoding (one second)
+const PACKETS_PER_PAGE: usize = 50;
+const OPUS_STREAM_SERIAL: u32 = 0x4646_4358; // "FFCX"
+
+/// Ogg Opus (RFC 7845). Mono and stereo streams only (channel mapping family 0).
+pub struct OpusCodec {
+    /// Target bitrate for `encode`, in bits per second
+    pub bitrate: u32,
+}
+
+impl Default for OpusCodec {
+    fn default() -> Self {
+        Self {
+            bitrate: DEFAULT_BITRATE,
+        }
+    }
+}
+
+/// The OpusHead identification header
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OpusHead {
+    channels: u8,
+    pre_skip: u16,
+    /// Rate of the audio before encoding; informational only, 0 when unknown
+    input_sample_rate: u32,
+    /// R128 output gain in Q7.8 dB, applied by the decoder
+    output_gain: i16,
+    mapping_family: u8,
+}
+
+impl OpusHead {
+    fn parse(packet: &[u8]) -> R<Self> {
+        if packet.len() < OPUS_HEAD_SIZE || &packet[0..8] != OPUS_HEAD_MAGIC {
+            return Err(anyhow!("Missing OpusHead identification header"));
+        }
+        let mut cursor = Cursor::new(&packet[8..]);
+        let version = cursor.read_u8()?;
+        if version >> 4 != 0 {
+            return Err(anyhow!("Unsupported Opus version: {}", version));
+        }
+        Ok(Self {
+            channels: cursor.read_u8()?,
+            pre_skip: cursor.read_u16::<LittleEndian>()?,
+            input_sample_rate: cursor.read_u32::<LittleEndian>()?,
+            output_gain: cursor.read_i16::<LittleEndian>()?,
+            mapping_family: cursor.read_u8()?,
+        })
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(OPUS_HEAD_SIZE);
+        out.extend_from_slice(OPUS_HEAD_MAGIC);
+        out.push(1);
+        out.push(self.channels);
+        out.extend_from_slice(&self.pre_skip.to_le_bytes());
+        out.extend_from_slice(&self.input_sample_rate.to_le_bytes());
+        out.extend_from_slice(&self.output_gain.to_le_bytes());
+        out.push(self.mapping_family);
+        out
+    }
+
+    fn output_gain_db(&self) -> f32 {
+        self.output_gain as f32 / 256.0
+    }
+
+    fn opus_channels(&self) -> R<Channels> {
+        if self.mapping_family != 0 {
+            return Err(anyhow!(
+                "Unsupported Opus channel mapping family: {}",
+                self.mapping_family
+            ));
+        }
+        opus_channels(self.channels as u16)
+    }
+}
+
+fn opus_channels(channels: u16) -> R<Channels> {
+    match channels {
+        1 => Ok(Channels::Mono),
+        2 => Ok(Channels::Stereo),
+        _ => Err(anyhow!(
+            "Opus supports mono or stereo only, got {} channels",
+            channels
+        )),
+    }
+}
+
+/// Vendor string and `KEY=value` comments of an OpusTags header
+fn parse_opus_tags(packet: &[u8]) -> R<(String, Vec<(String, String)>)> {
+    if packet.len() < 16 || &packet[0..8] != OPUS_TAGS_MAGIC {
+        return Err(anyhow!("Missing OpusTags comment header"));
+    }
+    fn read_string(cursor: &mut Cursor<&[u8]>) -> R<String> {
+        let len = cursor.read_u32::<LittleEndian>()? as usize;
+        let start = cursor.position() as usize;
+        let bytes = cursor
+            .get_ref()
+            .get(start..start + len)
+            .ok_or_else(|| anyhow!("OpusTags string runs past end of header"))?;
+        cursor.set_position((start + len) as u64);
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    let mut cursor = Cursor::new(&packet[8..]);
+
+    let vendor = read_string(&mut cursor)?;
+    let count = cursor.read_u32::<LittleEndian>()?;
+    let mut comments = Vec::new();
+    for _ in 0..count {
+        let comment = read_string(&mut cursor)?;
+        if let Some((key, value)) = comment.split_once('=') {
+            comments.push((key.to_string(), value.to_string()));
+        }
+    }
+    Ok((vendor, comments))
+}
+
+fn write_opus_tags(vendor: &str, comments: &[(String, String)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(OPUS_TAGS_MAGIC);
+    out.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    out.extend_from_slice(vendor.as_bytes());
+    out.extend_from_slice(&(comments.len() as u32).to_le_bytes());
+    for (key, value) in comments {
+        let comment = format!("{}={}", key, value);
+        out.extend_from_slice(&(comment.len() as u32).to_le_bytes());
+        out.extend_from_slice(comment.as_bytes());
+    }
+    out
+}
+
+/// Read the OpusHead and OpusTags packets at the start of the stream
+fn read_headers(
+    reader: &mut PacketReader<Cursor<&[u8]>>,
+) -> R<(OpusHead, String, Vec<(String, String)>)> {
+    let mut next = || -> R<Vec<u8>> {
+        reader
+            .read_packet()
+            .map_err(|e| anyhow!("Failed to read Ogg page: {}", e))?
+            .map(|packet| packet.data)
+            .ok_or_else(|| anyhow!("Ogg stream ended inside the Opus headers"))
+    };
+    let head = OpusHead::parse(&next()?)?;
+    let (vendor, comments) = parse_opus_tags(&next()?)?;
+    Ok((head, vendor, comments))
+}
+
+/// Granule position of the last page, i.e. the end of the stream in 48 kHz samples.
+/// Pages are walked by their headers, so `OggS` inside packet data can't be
+/// mistaken for a page.
+fn last_granule(input: &[u8]) -> Option<u64> {
+    let mut pos = 0;
+    let mut granule = None;
+    while let Some(header) = input.get(pos..pos + OGG_PAGE_HEADER_SIZE) {
+        if &header[0..4] != OGG_CAPTURE_PATTERN {
+            break;
+        }
+        let segments = header[26] as usize;
+        let lacing_start = pos + OGG_PAGE_HEADER_SIZE;
+        let Some(lacing) = input.get(lacing_start..lacing_start + segments) else {
+            break;
+        };
+        // -1 marks a page on which no packet ends
+        let page_granule = u64::from_le_bytes(header[6..14].try_into().ok()?);
+        if page_granule != u64::MAX {
+            granule = Some(page_granule);
+        }
+        let body: usize = lacing.iter().map(|&len| len as usize).sum();
+        pos = lacing_start + segments + body;
+    }
+    granule
+}
+
+/// Each header goes on a page of its own, as RFC 7845 requires
+fn write_headers(
+    writer: &mut PacketWriter<Vec<u8>>,
+    head: OpusHead,
+    vendor: &str,
+    comments: &[(String, String)],
+) -> R<()> {
+    writer.write_packet(
+        head.to_bytes().into_boxed_slice(),
+        OPUS_STREAM_SERIAL,
+        PacketWriteEndInfo::EndPage,
+        0,
+    )?;
+    writer.write_packet(
+        write_opus_tags(vendor, comments).into_boxed_slice(),
+        OPUS_STREAM_SERIAL,
+        PacketWriteEndInfo::EndPage,
+        0,
+    )?;
+    Ok(())
+}
+
+/// Write new headers followed by the remaining packets of `reader`, keeping
+/// their page boundaries and granule positions
+fn remux(
+    reader: &mut PacketReader<Cursor<&[u8]>>,
+    head: OpusHead,
+    vendor: &str,
+    comments: &[(String, String)],
+) -> R<Vec<u8>> {
+    let mut writer = PacketWriter::new(Vec::new());
+    write_headers(&mut writer, head, vendor, comments)?;
+    while let Some(packet) = reader
+        .read_packet()
+        .map_err(|e| anyhow!("Failed to read Ogg page: {}", e))?
+    {
+        let end_info = if packet.last_in_stream() {
+            PacketWriteEndInfo::EndStream
+        } else if packet.last_in_page() {
+            PacketWriteEndInfo::EndPage
+        } else {
+            PacketWriteEndInfo::NormalPacket
+        };
+        let granule = packet.absgp_page();
+        writer.write_packet(
+            packet.data.into_boxed_slice(),
+            OPUS_STREAM_SERIAL,
+            end_info,
+            granule,
+        )?;
+    }
+    Ok(writer.into_inner())
+}
+
+impl Codec for OpusCodec {
+    fn as_str(&self) -> &'static str {
+        "Opus"
+    }
+    fn file_extension(&self) -> &'static str {
+        "opus"
+    }
+
+    fn tag_name_for(&self, logical: &str) -> String {
+        FlacCodec.tag_name_for(logical)
+    }
+
+    fn logical_for(&self, tag: &str) -> String {
+        FlacCodec.logical_for(tag)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn is_lossless(&self, _file_path: &str) -> R<bool> {
+        Ok(false)
+    }
+
+    fn validate_file_format(&self, data: &[u8]) -> R<()> {
+        if data.len() < FIRST_PACKET_OFFSET + OPUS_HEAD_MAGIC.len() {
+            return Err(anyhow!("File too small to be a valid Opus file"));
+        }
+        if &data[0..4] != OGG_CAPTURE_PATTERN {
+            return Err(anyhow!(
+                "Not a valid Opus file: Missing OggS capture pattern"
+            ));
+        }
+        if &data[FIRST_PACKET_OFFSET..FIRST_PACKET_OFFSET + 8] != OPUS_HEAD_MAGIC {
+            return Err(anyhow!("Not a valid Opus file: Ogg stream is not Opus"));
+        }
+        Ok(())
+    }
+
+    fn get_file_info(&self, file_path: &str) -> R<FileInfo> {
+        let file = std::fs::File::open(file_path)?;
+        let file_size = file.metadata()?.len() as usize;
+        let mapped_file = unsafe { MmapOptions::new().map(&file)? };
+
+        self.validate_file_format(&mapped_file)?;
+
+        let mut reader = PacketReader::new(Cursor::new(&mapped_file[..]));
+        let (head, _, comments) = read_headers(&mut reader)?;
+
+        let total_samples = last_granule(&mapped_file)
+            .unwrap_or(0)
+            .saturating_sub(head.pre_skip as u64);
+        let duration_seconds = total_samples as f64 / OPUS_SAMPLE_RATE as f64;
+
+        let duration = if duration_seconds >= 3600.0 {
+            format!(
+                "{:.0}:{:02.0}:{:02.0}",
+                duration_seconds / 3600.0,
+                (duration_seconds % 3600.0) / 60.0,
+                duration_seconds % 60.0
+            )
+        } else {
+            format!(
+                "{:.0}:{:02.0}",
+                duration_seconds / 60.0,
+                duration_seconds % 60.0
+            )
+        };
+
+        let description = comments
+            .iter()
+            .find(|(key, value)| {
+                matches!(key.to_lowercase().as_str(), "description" | "comment")
+                    && !value.trim().is_empty()
+            })
+            .map(|(_, value)| value.trim().to_string())
+            .unwrap_or_default();
+
+        Ok(FileInfo {
+            path: file_path.to_string(),
+            size: file_size,
+            sample_rate: OPUS_SAMPLE_RATE,
+            channels: head.channels as u16,
+            bit_depth: SampleFormat::F32.bits_per_sample(),
+            duration,
+            duration_secs: duration_seconds,
+            description,
+        })
+    }
+
+    fn decode(&self, input: &[u8]) -> R<AudioBuffer> {
+        self.validate_file_format(input)?;
+
+        let mut reader = PacketReader::new(Cursor::new(input));
+        let (head, _, _) = read_headers(&mut reader)?;
+        let channels = head.channels as usize;
+        let mut decoder = Decoder::new(SampleRate::Hz48000, head.opus_channels()?)
+            .map_err(|e| anyhow!("Failed to create Opus decoder: {}", e))?;
+
+        let mut interleaved = Vec::new();
+        let mut decoded = vec![0.0f32; OPUS_MAX_FRAME_SIZE * channels];
+        let mut end_granule = None;
+        while let Some(packet) = reader
+            .read_packet()
+            .map_err(|e| anyhow!("Failed to read Ogg page: {}", e))?
+        {
+            if packet.data.is_empty() {
+                continue;
+            }
+            let frames = decoder
+                .decode_float(Some(&packet.data[..]), &mut decoded[..], false)
+                .map_err(|e| anyhow!("Failed to decode Opus packet: {}", e))?;
+            interleaved.extend_from_slice(&decoded[..frames * channels]);
+
+            if packet.last_in_stream() {
+                end_granule = Some(packet.absgp_page());
+                break;
+            }
+        }
+
+        // Drop the encoder delay, and the padding of the last packet
+        let mut total_frames = interleaved.len() / channels;
+        if let Some(granule) = end_granule {
+            total_frames = total_frames.min(granule as usize);
+        }
+        let start = (head.pre_skip as usize).min(total_frames);
+        let gain = 10f32.powf(head.output_gain_db() / 20.0);
+
+        let mut data = vec![Vec::with_capacity(total_frames - start); channels];
+        for frame in interleaved[start * channels..total_frames * channels].chunks_exact(channels) {
+            for (channel, &sample) in data.iter_mut().zip(frame) {
+                channel.push(sample * gain);
+            }
+        }
+
+        Ok(AudioBuffer {
+            sample_rate: OPUS_SAMPLE_RATE,
+            channels: channels as u16,
+            format: SampleFormat::F32,
+            data,
+        })
+    }
+
+    /// Encodes at `self.bitrate`. Opus only runs at 48 kHz, so other rates are
+    /// resampled first; the original rate is kept in the header and read back
+    /// as `OPUS_ORIGINAL_RATE`.
+    fn encode(&self, buffer: &Option<AudioBuffer>) -> R<Vec<u8>> {
+        let Some(buffer) = buffer else {
+            return Err(anyhow!("No audio buffer to encode"));
+        };
+        let opus_channels = opus_channels(buffer.channels)?;
+        let channels = buffer.channels as usize;
+
+        let mut audio = buffer.clone();
+        if audio.sample_rate != OPUS_SAMPLE_RATE {
+            dprintln!(
+                "Opus encode: resampling {} Hz to {} Hz",
+                audio.sample_rate,
+                OPUS_SAMPLE_RATE
+            );
+            audio.resample(OPUS_SAMPLE_RATE);
+        }
+
+        let mut encoder = Encoder::new(SampleRate::Hz48000, opus_channels, Application::Audio)
+            .map_err(|e| anyhow!("Failed to create Opus encoder: {}", e))?;
+        encoder
+            .set_bitrate(Bitrate::BitsPerSecond(self.bitrate as i32))
+            .map_err(|e| anyhow!("Invalid Opus bitrate {}: {}", self.bitrate, e))?;
+        let pre_skip = encoder
+            .lookahead()
+            .map_err(|e| anyhow!("Failed to query Opus lookahead: {}", e))?
+            as usize;
+
+        let frames = audio.data.first().map_or(0, Vec::len);
+        let head = OpusHead {
+            channels: channels as u8,
+            pre_skip: pre_skip as u16,
+            input_sample_rate: buffer.sample_rate,
+            output_gain: 0,
+            mapping_family: 0,
+        };
+
+        let mut writer = PacketWriter::new(Vec::new());
+        write_headers(&mut writer, head, "FFCodex", &[])?;
+
+        // Feed pre_skip samples of extra silence so the encoder flushes its lookahead
+        let end_granule = (pre_skip + frames) as u64;
+        let packet_count = (frames + pre_skip).div_ceil(OPUS_FRAME_SIZE).max(1);
+        let mut pcm = vec![0.0f32; OPUS_FRAME_SIZE * channels];
+        let mut packet = vec![0u8; OPUS_MAX_PACKET_SIZE];
+        for index in 0..packet_count {
+            let offset = index * OPUS_FRAME_SIZE;
+            for (i, frame) in pcm.chunks_exact_mut(channels).enumerate() {
+                for (ch, sample) in frame.iter_mut().enumerate() {
+                    *sample = audio.data[ch].get(offset + i).copied().unwrap_or(0.0);
+                }
+            }
+            let len = encoder
+                .encode_float(&pcm, &mut packet)
+                .map_err(|e| anyhow!("Failed to encode Opus packet: {}", e))?;
+
+            let is_last = index + 1 == packet_count;
+            let end_info = if is_last {
+                PacketWriteEndInfo::EndStream
+            } else if (index + 1) % PACKETS_PER_PAGE == 0 {
+                PacketWriteEndInfo::EndPage
+            } else {
+                PacketWriteEndInfo::NormalPacket
+            };
+            let granule = ((offset + OPUS_FRAME_SIZE) as u64).min(end_granule);
+            writer.write_packet(
+                packet[..len].to_vec().into_boxed_slice(),
+                OPUS_STREAM_SERIAL,
+                end_info,
+                granule,
+            )?;
+        }
+
+        Ok(writer.into_inner())
+    }
+
+    fn parse_metadata(&self, input: &[u8]) -> R<Metadata> {
+        self.validate_file_format(input)?;
+
+        let mut reader = PacketReader::new(Cursor::new(input));
+        let (head, _, comments) = read_headers(&mut reader)?;
+
+        let mut metadata = Metadata::new();
+        for (key, value) in &comments {
+            let prefixed_key = format!("TAG_{}", self.logical_for(key));
+            metadata.add_field_value(&prefixed_key, value)?;
+        }
+
+        if head.input_sample_rate != 0 {
+            metadata.set_field("OPUS_ORIGINAL_RATE", &head.input_sample_rate.to_string())?;
+        }
+        metadata.set_field(
+            "OPUS_OUTPUT_GAIN",
+            &format!("{:.2} dB", head.output_gain_db()),
+        )?;
+        metadata.set_field("OPUS_PRE_SKIP", &head.pre_skip.to_string())?;
+
+        Ok(metadata)
+    }
+
+    /// Rewrites the OpusTags header with the `TAG_` fields; the audio pages are
+    /// copied through with their granule positions unchanged.
+    fn embed_metadata_to_file(&self, file_path: &str, metadata: &Metadata) -> R<()> {
+        let output = self.embed_metadata(&std::fs::read(file_path)?, metadata)?;
+        std::fs::write(file_path, output)?;
+        Ok(())
+    }
+
+    fn embed_metadata(&self, input: &[u8], metadata: &Metadata) -> R<Vec<u8>> {
+        self.validate_file_format(input)?;
+
+        let mut reader = PacketReader::new(Cursor::new(input));
+        let (head, vendor, _) = read_headers(&mut reader)?;
+
+        let mut keys: Vec<&String> = metadata
+            .get_all_fields()
+            .keys()
+            .filter(|key| key.starts_with("TAG_"))
+            .collect();
+        keys.sort();
+        let comments: Vec<(String, String)> = keys
+            .into_iter()
+            .flat_map(|key| {
+                let tag = self.tag_name_for(&key[4..]);
+                metadata
+                    .get_field_all(key)
+                    .into_iter()
+                    .map(move |value| (tag.clone(), value))
+            })
+            .collect();
+
+        remux(&mut reader, head, &vendor, &comments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(sample_rate: u32, channels: u16, seconds: f32) -> AudioBuffer {
+        let frames = (sample_rate as f32 * seconds) as usize;
+        let samples: Vec<f32> = (0..frames)
+            .map(|i| {
+                0.5 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / sample_rate as f32).sin()
+            })
+            .collect();
+        AudioBuffer {
+            sample_rate,
+            channels,
+            format: SampleFormat::I16,
+            data: vec![samples; channels as usize],
+        }
+    }
+
+    #[test]
+    fn test_encode_resamples_to_48k_and_records_original_rate() {
+        let codec = OpusCodec::default();
+        let encoded = codec.encode(&Some(sine(44100, 2, 1.0))).unwrap();
+
+        let decoded = codec.decode(&encoded).unwrap();
+        assert_eq!(decoded.sample_rate, 48000);
+        assert_eq!(decoded.channels, 2);
+        assert!(decoded.data[0].len().abs_diff(48000) <= 1);
+
+        let metadata = codec.parse_metadata(&encoded).unwrap();
+        assert_eq!(
+            metadata.get_field("OPUS_ORIGINAL_RATE").as_deref(),
+            Some("44100")
+        );
+        assert_eq!(
+            metadata.get_field("OPUS_OUTPUT_GAIN").as_deref(),
+            Some("0.00 dB")
+        );
+    }
+
+    #[test]
+    fn test_last_granule_walks_page_headers() {
+        let encoded = OpusCodec::default()
+            .encode(&Some(sine(48000, 1, 0.5)))
+            .unwrap();
+        let mut reader = PacketReader::new(Cursor::new(&encoded[..]));
+        let (head, _, _) = read_headers(&mut reader).unwrap();
+        let end = head.pre_skip as u64 + 24000;
+        assert_eq!(last_granule(&encoded), Some(end));
+
+        // A capture pattern that doesn't start a whole page is not a page
+        let mut trailing = encoded.clone();
+        trailing.extend_from_slice(b"OggS\0\0\xff\xff");
+        assert_eq!(last_granule(&trailing), Some(end));
+    }
+
+    #[test]
+    fn test_tags_and_output_gain_roundtrip() {
+        let codec = OpusCodec::default();
+        let encoded = codec.encode(&Some(sine(48000, 1, 0.5))).unwrap();
+
+        let path = std::env::temp_dir()
+            .join(format!("ffcodex_opus_tags_{}.opus", rand::random::<u32>()))
+            .to_string_lossy()
+            .to_string();
+        let path = path.as_str();
+        std::fs::write(path, &encoded).unwrap();
+        let mut metadata = Metadata::new();
+        metadata.set_field("TAG_Title", "Door Slam").unwrap();
+        metadata.set_field("TAG_R128_TRACK_GAIN", "-512").unwrap();
+        codec.embed_metadata_to_file(path, &metadata).unwrap();
+        let tagged = std::fs::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let metadata = codec.parse_metadata(&tagged).unwrap();
+        assert_eq!(
+            metadata.get_field("TAG_Title").as_deref(),
+            Some("Door Slam")
+        );
+        assert_eq!(
+            metadata.get_field("TAG_R128_TRACK_GAIN").as_deref(),
+            Some("-512")
+        );
+
+        // Raise the header output gain by 6 dB (Q7.8) and decode the same packets again
+        let mut reader = PacketReader::new(Cursor::new(&tagged[..]));
+        let (mut head, vendor, comments) = read_headers(&mut reader).unwrap();
+        head.output_gain = 6 * 256;
+        let louder = remux(&mut reader, head, &vendor, &comments).unwrap();
+
+        assert_eq!(
+            codec
+                .parse_metadata(&louder)
+                .unwrap()
+                .get_field("OPUS_OUTPUT_GAIN")
+                .as_deref(),
+            Some("6.00 dB")
+        );
+        let plain = codec.decode(&tagged).unwrap();
+        let loud = codec.decode(&louder).unwrap();
+        assert_eq!(plain.data[0].len(), 24000);
+        let gain = 10f32.powf(6.0 / 20.0);
+        for (a, b) in plain.data[0].iter().zip(&loud.data[0]) {
+            assert!((a * gain - b).abs() < 1e-5);
+        }
+    }
+}
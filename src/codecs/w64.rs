@@ -0,0 +1,428 @@
+use super::chunk::{RiffReader, RiffWriter};
+use super::wav::{decode_samples, encode_samples, read_fmt};
+use crate::prelude::*;
+
+// Sony Wave64 names chunks with GUIDs. Apart from riff and list, every GUID is
+// the RIFF FourCC followed by this fixed suffix (-ACF3-11D3-8CD1-00C04F8EDB8A);
+// chunks the spec doesn't list (iXML, ID3, cue) follow the same pattern.
+const GUID_SUFFIX: [u8; 12] = [
+    0xF3, 0xAC, 0xD3, 0x11, 0x8C, 0xD1, 0x00, 0xC0, 0x4F, 0x8E, 0xDB, 0x8A,
+];
+// 66666972-912E-11CF-A5D6-28DB04C10000
+const RIFF_GUID: [u8; 16] = [
+    b'r', b'i', b'f', b'f', 0x2E, 0x91, 0xCF, 0x11, 0xA5, 0xD6, 0x28, 0xDB, 0x04, 0xC1, 0x00, 0x00,
+];
+// 7473696C-912E-11CF-A5D6-28DB04C10000
+const LIST_GUID: [u8; 16] = [
+    b'l', b'i', b's', b't', 0x2E, 0x91, 0xCF, 0x11, 0xA5, 0xD6, 0x28, 0xDB, 0x04, 0xC1, 0x00, 0x00,
+];
+// 6B6E756A-ACF3-11D3-8CD1-00C04F8EDB8A, lower case unlike the WAV FourCC
+const JUNK_GUID: [u8; 16] = [
+    b'j', b'u', b'n', b'k', 0xF3, 0xAC, 0xD3, 0x11, 0x8C, 0xD1, 0x00, 0xC0, 0x4F, 0x8E, 0xDB, 0x8A,
+];
+/// WAV FourCCs whose spec GUID isn't the FourCC plus `GUID_SUFFIX`
+const SPEC_GUIDS: [(&[u8; 4], [u8; 16]); 2] = [(b"LIST", LIST_GUID), (b"JUNK", JUNK_GUID)];
+const WAVE_FORMAT_ID: &[u8; 4] = b"wave";
+const FMT_CHUNK_ID: &[u8; 4] = b"fmt ";
+const DATA_CHUNK_ID: &[u8; 4] = b"data";
+
+// Chunk Structures
+const HEADER_SIZE: usize = 40; // riff GUID + 64-bit size + wave GUID
+const CHUNK_HEADER_SIZE: usize = 24; // GUID + 64-bit size, counted in the size
+const CHUNK_ALIGNMENT: u64 = 8;
+const STANDARD_FMT_CHUNK_SIZE: usize = 16;
+
+// Format tags
+const FORMAT_PCM: u16 = 1;
+const FORMAT_IEEE_FLOAT: u16 = 3;
+
+/// Sony Wave64: WAV with GUID chunk ids, 64-bit sizes and 8-byte alignment.
+/// Metadata chunks (bext, iXML, cue, ...) are read and written as in WAV.
+pub struct W64Codec;
+
+fn guid_for(fourcc: &[u8; 4]) -> [u8; 16] {
+    if let Some((_, guid)) = SPEC_GUIDS.iter().find(|(id, _)| *id == fourcc) {
+        return *guid;
+    }
+    let mut guid = [0u8; 16];
+    guid[..4].copy_from_slice(fourcc);
+    guid[4..].copy_from_slice(&GUID_SUFFIX);
+    guid
+}
+
+/// The RIFF FourCC behind a Wave64 chunk GUID, for the spec GUIDs and those
+/// that follow the pattern
+fn fourcc_of(guid: &[u8; 16]) -> Option<[u8; 4]> {
+    if let Some((id, _)) = SPEC_GUIDS.iter().find(|(_, spec)| spec == guid) {
+        return Some(**id);
+    }
+    (guid[4..] == GUID_SUFFIX).then(|| [guid[0], guid[1], guid[2], guid[3]])
+}
+
+fn aligned_size(size: u64) -> u64 {
+    size.div_ceil(CHUNK_ALIGNMENT) * CHUNK_ALIGNMENT
+}
+
+/// A Wave64 chunk; `data` is cut short when the file is truncated
+struct W64Chunk<'a> {
+    guid: [u8; 16],
+    /// Body size as declared in the header
+    size: u64,
+    data: &'a [u8],
+}
+
+impl W64Chunk<'_> {
+    fn is(&self, fourcc: &[u8; 4]) -> bool {
+        self.guid == guid_for(fourcc)
+    }
+}
+
+fn w64_chunks(input: &[u8]) -> R<Vec<W64Chunk<'_>>> {
+    let mut chunks = Vec::new();
+    let mut pos = HEADER_SIZE;
+    while pos + CHUNK_HEADER_SIZE <= input.len() {
+        let mut guid = [0u8; 16];
+        guid.copy_from_slice(&input[pos..pos + 16]);
+        let size = u64::from_le_bytes(input[pos + 16..pos + 24].try_into()?);
+        if size < CHUNK_HEADER_SIZE as u64 {
+            return Err(anyhow!(
+                "Invalid Wave64 chunk size {} at offset {}",
+                size,
+                pos
+            ));
+        }
+        let start = pos + CHUNK_HEADER_SIZE;
+        let end = (pos as u64).saturating_add(size).min(input.len() as u64) as usize;
+        chunks.push(W64Chunk {
+            guid,
+            size: size - CHUNK_HEADER_SIZE as u64,
+            data: &input[start..end],
+        });
+        // Chunks start on 8-byte boundaries; the padding is not counted in the size
+        pos = (pos as u64)
+            .saturating_add(aligned_size(size))
+            .min(input.len() as u64) as usize;
+    }
+    Ok(chunks)
+}
+
+fn write_w64_chunk<W: Write>(writer: &mut W, guid: &[u8; 16], data: &[u8]) -> R<()> {
+    let size = (CHUNK_HEADER_SIZE + data.len()) as u64;
+    writer.write_all(guid)?;
+    writer.write_u64::<LittleEndian>(size)?;
+    writer.write_all(data)?;
+    let padding = aligned_size(size) - size;
+    writer.write_all(&[0u8; CHUNK_ALIGNMENT as usize][..padding as usize])?;
+    Ok(())
+}
+
+/// Patch the riff size (the whole file) once every chunk has been written
+fn finish_header(out: &mut [u8]) {
+    let size = out.len() as u64;
+    out[16..24].copy_from_slice(&size.to_le_bytes());
+}
+
+fn write_header(out: &mut Vec<u8>) {
+    out.extend_from_slice(&RIFF_GUID);
+    out.extend_from_slice(&[0u8; 8]); // filled in by finish_header
+    out.extend_from_slice(&guid_for(WAVE_FORMAT_ID));
+}
+
+impl Codec for W64Codec {
+    fn as_str(&self) -> &'static str {
+        "W64"
+    }
+
+    fn file_extension(&self) -> &'static str {
+        "w64"
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn validate_file_format(&self, data: &[u8]) -> R<()> {
+        if data.len() < HEADER_SIZE {
+            return Err(anyhow!("File too small to be a valid Wave64"));
+        }
+        if data[0..16] != RIFF_GUID {
+            return Err(anyhow!("Invalid Wave64 File: Missing riff GUID"));
+        }
+        if data[24..40] != guid_for(WAVE_FORMAT_ID) {
+            return Err(anyhow!("Invalid Wave64 File: Missing wave GUID"));
+        }
+        Ok(())
+    }
+
+    fn validate_structure(&self, input: &[u8], report: &mut ValidationReport) {
+        let chunks = match w64_chunks(input) {
+            Ok(chunks) => chunks,
+            Err(e) => {
+                report.add_issue(e.to_string());
+                return;
+            }
+        };
+        for chunk in chunks
+            .iter()
+            .filter(|chunk| chunk.size > chunk.data.len() as u64)
+        {
+            report.add_issue(format!(
+                "Chunk '{}' truncated: declares {} bytes but only {} remain",
+                String::from_utf8_lossy(&chunk.guid[..4]),
+                chunk.size,
+                chunk.data.len()
+            ));
+        }
+        if !chunks.iter().any(|chunk| chunk.is(FMT_CHUNK_ID)) {
+            report.add_issue("No fmt chunk found");
+        }
+        if !chunks.iter().any(|chunk| chunk.is(DATA_CHUNK_ID)) {
+            report.add_issue("No data chunk found");
+        }
+    }
+
+    fn get_file_info(&self, file_path: &str) -> R<FileInfo> {
+        let file = std::fs::File::open(file_path)?;
+        let file_size = file.metadata()?.len() as usize;
+        let mapped_file = unsafe { MmapOptions::new().map(&file)? };
+
+        self.validate_file_format(&mapped_file)?;
+
+        let chunks = w64_chunks(&mapped_file)?;
+        let fmt = chunks
+            .iter()
+            .find(|chunk| chunk.is(FMT_CHUNK_ID))
+            .ok_or_else(|| anyhow!("No fmt chunk found"))?;
+        let (_, channels, sample_rate, bits_per_sample) = read_fmt(fmt.data)?;
+        let data_size = chunks
+            .iter()
+            .find(|chunk| chunk.is(DATA_CHUNK_ID))
+            .map_or(0, |chunk| chunk.data.len());
+
+        let block_align = channels as usize * bits_per_sample as usize / 8;
+        let duration_seconds = if block_align > 0 && sample_rate > 0 {
+            (data_size / block_align) as f64 / sample_rate as f64
+        } else {
+            0.0
+        };
+
+        let duration = if duration_seconds >= 3600.0 {
+            format!(
+                "{:.0}:{:02.0}:{:02.0}",
+                duration_seconds / 3600.0,
+                (duration_seconds % 3600.0) / 60.0,
+                duration_seconds % 60.0
+            )
+        } else {
+            format!(
+                "{:.0}:{:02.0}",
+                duration_seconds / 60.0,
+                duration_seconds % 60.0
+            )
+        };
+
+        let description = self
+            .parse_metadata(&mapped_file)
+            .ok()
+            .and_then(|metadata| metadata.get_field("DESCRIPTION"))
+            .unwrap_or_default();
+
+        Ok(FileInfo {
+            path: file_path.to_string(),
+            size: file_size,
+            sample_rate,
+            channels,
+            bit_depth: bits_per_sample,
+            duration,
+            duration_secs: duration_seconds,
+            description,
+        })
+    }
+
+    fn encode(&self, buffer: &Option<AudioBuffer>) -> R<Vec<u8>> {
+        let Some(buffer) = buffer else {
+            return Err(anyhow!("Cannot encode None AudioBuffer"));
+        };
+        let channels = buffer.data.len() as u16;
+        let bits_per_sample = buffer.format.bits_per_sample();
+        let format_tag = match buffer.format {
+            SampleFormat::F32 => FORMAT_IEEE_FLOAT,
+            _ => FORMAT_PCM,
+        };
+        let block_align = channels * bits_per_sample / 8;
+
+        let mut fmt = Vec::with_capacity(STANDARD_FMT_CHUNK_SIZE);
+        fmt.write_u16::<LittleEndian>(format_tag)?;
+        fmt.write_u16::<LittleEndian>(channels)?;
+        fmt.write_u32::<LittleEndian>(buffer.sample_rate)?;
+        fmt.write_u32::<LittleEndian>(buffer.sample_rate * block_align as u32)?;
+        fmt.write_u16::<LittleEndian>(block_align)?;
+        fmt.write_u16::<LittleEndian>(bits_per_sample)?;
+
+        let mut samples = Vec::new();
+        encode_samples(&mut samples, buffer, bits_per_sample)?;
+
+        let mut out = Vec::with_capacity(HEADER_SIZE + 2 * CHUNK_HEADER_SIZE + 16 + samples.len());
+        write_header(&mut out);
+        write_w64_chunk(&mut out, &guid_for(FMT_CHUNK_ID), &fmt)?;
+        write_w64_chunk(&mut out, &guid_for(DATA_CHUNK_ID), &samples)?;
+        finish_header(&mut out);
+        Ok(out)
+    }
+
+    fn decode(&self, input: &[u8]) -> R<AudioBuffer> {
+        self.validate_file_format(input)?;
+
+        let chunks = w64_chunks(input)?;
+        let fmt = chunks.iter().find(|chunk| chunk.is(FMT_CHUNK_ID));
+        let data = chunks.iter().find(|chunk| chunk.is(DATA_CHUNK_ID));
+        let (Some(fmt), Some(data)) = (fmt, data) else {
+            return Err(anyhow!("Missing 'fmt ' or 'data' chunk"));
+        };
+
+        let (format, channels, sample_rate, bits_per_sample) = read_fmt(fmt.data)?;
+        Ok(AudioBuffer {
+            sample_rate,
+            channels,
+            format,
+            data: decode_samples(
+                data.data,
+                channels,
+                bits_per_sample,
+                format == SampleFormat::F32,
+            )?,
+        })
+    }
+
+    /// Metadata chunks are the WAV ones under FourCC GUIDs, so they are handed to
+    /// the WAV parser as a RIFF file holding everything but the audio
+    fn parse_metadata(&self, input: &[u8]) -> R<Metadata> {
+        self.validate_file_format(input)?;
+
+        let mut riff = RiffWriter::new(Vec::new());
+        for chunk in w64_chunks(input)? {
+            if let Some(fourcc) = fourcc_of(&chunk.guid)
+                && &fourcc != DATA_CHUNK_ID
+            {
+                riff.write_chunk(&fourcc, chunk.data)?;
+            }
+        }
+        let body = riff.into_inner();
+
+        let mut wav = Vec::with_capacity(12 + body.len());
+        wav.extend_from_slice(b"RIFF");
+        wav.write_u32::<LittleEndian>(4 + body.len() as u32)?;
+        wav.extend_from_slice(b"WAVE");
+        wav.extend_from_slice(&body);
+        WavCodec.parse_metadata(&wav)
+    }
+
+    fn embed_warnings(&self, metadata: &Metadata) -> Vec<String> {
+        WavCodec.bext_truncations(metadata)
+    }
+
+    /// Rewrites the file with fresh metadata chunks between `fmt ` and `data`.
+    /// Existing FourCC chunks other than `fmt `, `fact` and `data` are replaced;
+    /// chunks with other GUIDs (markers, summary lists) are copied through.
+    fn embed_metadata_to_file(&self, file_path: &str, metadata: &Metadata) -> R<()> {
+        let out = self.embed_metadata(&std::fs::read(file_path)?, metadata)?;
+        std::fs::write(file_path, out)
+            .map_err(|e| anyhow!("No write permission for file '{}': {}", file_path, e))?;
+        Ok(())
+    }
+
+    fn embed_metadata(&self, input: &[u8], metadata: &Metadata) -> R<Vec<u8>> {
+        self.validate_file_format(input)?;
+
+        let mut metadata_chunks = Vec::new();
+        for chunk in RiffReader::new(&WavCodec.create_metadata_chunks(metadata)?, 0) {
+            metadata_chunks.push((guid_for(&chunk.id), chunk.data.to_vec()));
+        }
+
+        let mut out = Vec::with_capacity(input.len());
+        write_header(&mut out);
+        for chunk in w64_chunks(input)? {
+            let keep = match fourcc_of(&chunk.guid) {
+                Some(fourcc) => matches!(&fourcc, b"fmt " | b"fact" | b"data"),
+                None => true,
+            };
+            if chunk.is(DATA_CHUNK_ID) {
+                for (guid, data) in &metadata_chunks {
+                    write_w64_chunk(&mut out, guid, data)?;
+                }
+            }
+            if keep {
+                write_w64_chunk(&mut out, &chunk.guid, chunk.data)?;
+            }
+        }
+        finish_header(&mut out);
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_rounds_and_clamps_integer_samples() {
+        crate::codecs::tests::assert_encoder_quantizes(
+            &W64Codec,
+            &[
+                SampleFormat::U8,
+                SampleFormat::I16,
+                SampleFormat::I24,
+                SampleFormat::I32,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_with_odd_sized_chunks_and_metadata() {
+        // 3 mono 24-bit frames = 9 bytes of data, so the data chunk needs padding
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 1,
+            format: SampleFormat::I24,
+            data: vec![vec![0.5, -0.25, 0.125]],
+        };
+        let encoded = W64Codec.encode(&Some(buffer.clone())).unwrap();
+        assert_eq!(encoded.len() % 8, 0);
+        assert_eq!(
+            u64::from_le_bytes(encoded[16..24].try_into().unwrap()),
+            encoded.len() as u64
+        );
+
+        let path = std::env::temp_dir()
+            .join(format!("ffcodex_w64_{}.w64", rand::random::<u32>()))
+            .to_string_lossy()
+            .to_string();
+        let path = path.as_str();
+        std::fs::write(path, &encoded).unwrap();
+        let mut metadata = Metadata::new();
+        metadata.set_field("USER_SCENE", "Harbor").unwrap();
+        metadata.set_field("INFO_INAM", "Door Slam").unwrap();
+        W64Codec.embed_metadata_to_file(path, &metadata).unwrap();
+        let tagged = std::fs::read(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        // LIST INFO goes under the spec's list GUID, not a FourCC-pattern one
+        let guids: Vec<_> = w64_chunks(&tagged)
+            .unwrap()
+            .iter()
+            .map(|chunk| chunk.guid)
+            .collect();
+        assert!(guids.contains(&LIST_GUID));
+        assert!(!guids.iter().any(|guid| guid[..4] == *b"LIST"));
+
+        let decoded = W64Codec.decode(&tagged).unwrap();
+        assert_eq!(decoded.format, SampleFormat::I24);
+        assert_eq!(decoded.data, buffer.data);
+        let metadata = W64Codec.parse_metadata(&tagged).unwrap();
+        assert_eq!(metadata.get_field("USER_SCENE").as_deref(), Some("Harbor"));
+        assert_eq!(
+            metadata.get_field("INFO_INAM").as_deref(),
+            Some("Door Slam")
+        );
+    }
+}
@@ -1,3 +1,6 @@
+use super::chunk::{Chunk, RiffReader, RiffWriter, padded_size, read_chunk_header};
+use super::quantize_sample;
+use crate::ixml::join_ixml_chunk;
 use crate::prelude::*;
 
 // Format tags
@@ -7,13 +10,44 @@ const FORMAT_EXTENSIBLE: u16 = 65534; // 0xFFFE
 
 // Chunk Identifiers
 const RIFF_CHUNK_ID: &[u8; 4] = b"RIFF";
+const RF64_CHUNK_ID: &[u8; 4] = b"RF64";
+const BW64_CHUNK_ID: &[u8; 4] = b"BW64";
+const DS64_CHUNK_ID: &[u8; 4] = b"ds64";
 const WAVE_FORMAT_ID: &[u8; 4] = b"WAVE";
 const FMT_CHUNK_ID: &[u8; 4] = b"fmt ";
 const DATA_CHUNK_ID: &[u8; 4] = b"data";
+const FACT_CHUNK_ID: &[u8; 4] = b"fact";
 
 // Chunk Structures
+/// bext text slots: name, byte offset, usable bytes (leaving room for a
+/// terminating null where the spec asks for one) and the fields tried in order
+const BEXT_TEXT_FIELDS: [(&str, usize, usize, &[&str]); 5] = [
+    (
+        "Description",
+        0,
+        255,
+        &["DESCRIPTION", "BEXT_BWF_DESCRIPTION", "Description"],
+    ),
+    (
+        "Originator",
+        256,
+        31,
+        &["USER_DESIGNER", "BEXT_BWF_ORIGINATOR"],
+    ),
+    (
+        "OriginatorReference",
+        288,
+        31,
+        &["BEXT_BWF_ORIGINATOR_REFERENCE"],
+    ),
+    ("OriginationDate", 320, 10, &["OriginationDate"]),
+    ("OriginationTime", 330, 8, &["OriginationTime"]),
+];
 const STANDARD_FMT_CHUNK_SIZE: u32 = 16;
 const HEADER_SIZE: usize = 12; // RIFF + size + WAVE
+const DS64_CHUNK_SIZE: u32 = 28; // riffSize + dataSize + sampleCount + tableLength
+// 32-bit size fields of an RF64 file hold this and defer to the ds64 chunk
+const RF64_SIZE_PLACEHOLDER: u32 = u32::MAX;
 
 // Bit depth constants
 const BIT_DEPTH_8: u16 = 8;
@@ -22,21 +56,20 @@ const BIT_DEPTH_24: u16 = 24;
 const BIT_DEPTH_32: u16 = 32;
 
 // Sample conversion constants
-const U8_SCALE: f32 = 127.0;
 const U8_OFFSET: f32 = 128.0;
-const I16_MAX_F: f32 = 32767.0;
 // const I16_DIVISOR: f32 = 32768.0;
 const I16_DIVISOR_RECIP: f32 = 1.0 / 32768.0;
-const I24_MAX_F: f32 = 8388607.0;
 // const I24_DIVISOR: f32 = 8388608.0;
 const I24_DIVISOR_RECIP: f32 = 1.0 / 8388608.0;
-const I32_MAX_F: f32 = 2147483647.0;
 // const I32_DIVISOR: f32 = 2147483648.0;
 const I32_DIVISOR_RECIP: f32 = 1.0 / 2147483648.0;
 const I24_SIGN_BIT: i32 = 0x800000;
 const I24_SIGN_EXTENSION_MASK: i32 = -16777216; // 0xFF000000 as i32
 const BYTE_MASK: i32 = 0xFF;
 
+// Number of samples inspected when sanity-checking data against the fmt chunk
+const FORMAT_CHECK_WINDOW: usize = 4096;
+
 pub struct WavCodec;
 
 #[derive(Debug, Clone)]
@@ -49,6 +82,123 @@ struct AudioInfo {
     bits_per_sample: u16,
 }
 
+/// 64-bit sizes carried by the `ds64` chunk of an RF64/BW64 file
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Ds64 {
+    riff_size: u64,
+    data_size: u64,
+    sample_count: u64,
+}
+
+impl Ds64 {
+    fn from_body(body: &[u8]) -> Option<Self> {
+        let field = |i: usize| Some(u64::from_le_bytes(body.get(i..i + 8)?.try_into().ok()?));
+        Some(Self {
+            riff_size: field(0)?,
+            data_size: field(8)?,
+            sample_count: field(16)?,
+        })
+    }
+
+    /// Find the `ds64` chunk, which RF64 requires to be the first chunk
+    fn find(input: &[u8]) -> Option<Self> {
+        if !input.starts_with(RF64_CHUNK_ID) && !input.starts_with(BW64_CHUNK_ID) {
+            return None;
+        }
+        let chunk = RiffReader::new(input, HEADER_SIZE).next()?;
+        if &chunk.id != DS64_CHUNK_ID {
+            return None;
+        }
+        Self::from_body(chunk.data)
+    }
+
+    /// Real size of a chunk whose 32-bit size field may be the RF64 placeholder
+    fn resolve(ds64: Option<Self>, id: &[u8], size: u32) -> u64 {
+        match ds64 {
+            Some(ds64) if size == RF64_SIZE_PLACEHOLDER && id == DATA_CHUNK_ID => ds64.data_size,
+            _ => size as u64,
+        }
+    }
+
+    /// The chunks of a RIFF/RF64 body, with a placeholder data size resolved
+    /// through the ds64 chunk so the chunks stored after the audio are found too
+    fn chunks(ds64: Option<Self>, input: &[u8]) -> Vec<Chunk<'_>> {
+        let mut chunks = Vec::new();
+        let mut reader = RiffReader::new(input, HEADER_SIZE);
+        while let Some(mut chunk) = reader.next() {
+            let size = Self::resolve(ds64, &chunk.id, chunk.size);
+            if size != chunk.size as u64 {
+                let data_end = chunk.data_start().saturating_add(size as usize);
+                chunk.data = &input[chunk.data_start()..data_end.min(input.len())];
+                let next = chunk
+                    .data_start()
+                    .saturating_add(padded_size(size) as usize);
+                reader = RiffReader::new(input, next);
+            }
+            chunks.push(chunk);
+        }
+        chunks
+    }
+
+    /// Offset just past the RIFF/RF64 body
+    fn riff_end(ds64: Option<Self>, header: &[u8]) -> u64 {
+        let riff_size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        match ds64 {
+            Some(ds64) if riff_size == RF64_SIZE_PLACEHOLDER => 8 + ds64.riff_size,
+            _ => 8 + riff_size as u64,
+        }
+    }
+}
+
+/// Sample format for a PCM or IEEE float format tag (or extensible subformat)
+fn sample_format_for(format_tag: u16, bits_per_sample: u16) -> Option<SampleFormat> {
+    match (format_tag, bits_per_sample) {
+        (FORMAT_PCM, BIT_DEPTH_8) => Some(SampleFormat::U8),
+        (FORMAT_PCM, BIT_DEPTH_16) => Some(SampleFormat::I16),
+        (FORMAT_PCM, BIT_DEPTH_24) => Some(SampleFormat::I24),
+        (FORMAT_PCM, BIT_DEPTH_32) => Some(SampleFormat::I32),
+        (FORMAT_IEEE_FLOAT, BIT_DEPTH_32) => Some(SampleFormat::F32),
+        _ => None,
+    }
+}
+
+/// Sample format, channels, sample rate and bits per sample from a `fmt ` chunk
+/// body, looking through WAVE_FORMAT_EXTENSIBLE to its subformat
+pub(super) fn read_fmt(body: &[u8]) -> R<(SampleFormat, u16, u32, u16)> {
+    if body.len() < STANDARD_FMT_CHUNK_SIZE as usize {
+        return Err(anyhow!("fmt chunk too small: {} bytes", body.len()));
+    }
+    let tag = u16::from_le_bytes([body[0], body[1]]);
+    let bits = u16::from_le_bytes([body[14], body[15]]);
+    let subformat = if tag == FORMAT_EXTENSIBLE && body.len() >= 26 {
+        u16::from_le_bytes([body[24], body[25]])
+    } else {
+        tag
+    };
+    let sample_format = sample_format_for(subformat, bits)
+        .ok_or_else(|| anyhow!("Unsupported format: tag {}, bits {}", subformat, bits))?;
+    let channels = u16::from_le_bytes([body[2], body[3]]);
+    let sample_rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+    Ok((sample_format, channels, sample_rate, bits))
+}
+
+/// Speaker mask of a WAVE_FORMAT_EXTENSIBLE `fmt ` chunk body; 0 for other formats
+fn extensible_channel_mask(body: &[u8]) -> u32 {
+    match (body.get(0..2), body.get(20..24)) {
+        (Some(tag), Some(mask)) if tag == FORMAT_EXTENSIBLE.to_le_bytes() => {
+            u32::from_le_bytes([mask[0], mask[1], mask[2], mask[3]])
+        }
+        _ => 0,
+    }
+}
+
+/// `RIFF`, `RF64` or `BW64` followed by the `WAVE` form type
+fn is_wave_header(header: &[u8]) -> bool {
+    header.len() >= HEADER_SIZE
+        && matches!(&header[0..4], b"RIFF" | b"RF64" | b"BW64")
+        && &header[8..12] == WAVE_FORMAT_ID
+}
+
 impl Codec for WavCodec {
     fn extract_metadata_from_file(&self, file_path: &str) -> R<Metadata> {
         // Optimized two-phase metadata extraction for WAV files
@@ -61,19 +211,19 @@ impl Codec for WavCodec {
         // Validate RIFF/WAVE header first
         let mut header = [0u8; 12];
         file.read_exact(&mut header)?;
-        if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        if !is_wave_header(&header) {
             return Err(anyhow!("Not a valid WAV file"));
         }
 
         let mut metadata = Metadata::new();
+        let mut ds64 = None;
+        let mut pending_ixml = String::new();
 
-        // PHASE 1: Read first 1MB (or until data chunk) for standard metadata
+        // Walk the chunk headers, reading only metadata bodies. The data chunk (and
+        // any other large chunk) is stepped over by offset, so chunks appended after
+        // gigabytes of audio cost one seek rather than a read of the audio.
         let mut pos = 12u64;
-        let first_phase_limit = std::cmp::min(file_size, 1024 * 1024); // 1MB limit
-        let mut found_data_chunk = false;
-        let mut data_chunk_start = 0u64;
-
-        while pos < first_phase_limit {
+        while pos + 8 <= file_size {
             file.seek(SeekFrom::Start(pos))?;
 
             let mut chunk_header = [0u8; 8];
@@ -82,76 +232,53 @@ impl Codec for WavCodec {
             }
 
             let chunk_id = &chunk_header[0..4];
-            let chunk_size = u32::from_le_bytes([
+            let declared_size = u32::from_le_bytes([
                 chunk_header[4],
                 chunk_header[5],
                 chunk_header[6],
                 chunk_header[7],
-            ]) as u64;
+            ]);
 
-            // Track data chunk location
-            if chunk_id == b"data" {
-                found_data_chunk = true;
-                data_chunk_start = pos;
-                break; // Stop at data chunk for phase 1
+            if chunk_id == DS64_CHUNK_ID {
+                let mut body = [0u8; 24];
+                if file.read_exact(&mut body).is_ok() {
+                    ds64 = Ds64::from_body(&body);
+                }
             }
-
-            // Parse metadata chunks in phase 1
-            if self.is_valid_chunk_id(chunk_id) && chunk_size <= 16 * 1024 * 1024 {
-                // Reasonable size limit
+            let chunk_size = Ds64::resolve(ds64, chunk_id, declared_size);
+
+            // Parse metadata chunks within a reasonable size limit
+            if chunk_id != b"data"
+                && self.is_valid_chunk_id(chunk_id)
+                && chunk_size <= 16 * 1024 * 1024
+                && pos + 8 + chunk_size <= file_size
+            {
                 let mut chunk_data = vec![0u8; chunk_size as usize];
-                if file.read_exact(&mut chunk_data).is_ok() {
+                file.read_exact(&mut chunk_data)?;
+                if chunk_id == b"iXML" {
+                    let payload = String::from_utf8_lossy(&chunk_data);
+                    if let Some(ixml) = join_ixml_chunk(&mut pending_ixml, &payload) {
+                        metadata.parse_ixml(&ixml)?;
+                    }
+                } else {
                     self.parse_metadata_chunk(chunk_id, &chunk_data, &mut metadata)?;
                 }
             }
 
             // Move to next chunk (with padding)
-            pos += 8 + chunk_size + (chunk_size % 2);
+            pos += 8 + padded_size(chunk_size);
+        }
+        if !pending_ixml.is_empty() {
+            metadata.parse_ixml(&pending_ixml)?;
         }
 
-        // PHASE 2: Read last 1MB if file is large enough and we found a data chunk
-        if found_data_chunk && file_size > 2 * 1024 * 1024 {
-            // Only for files > 2MB
-            let last_mb_start =
-                std::cmp::max(data_chunk_start + 1024 * 1024, file_size - 1024 * 1024);
-
-            if last_mb_start < file_size {
-                pos = last_mb_start;
-
-                // Align to potential chunk boundary
-                file.seek(SeekFrom::Start(pos))?;
-
-                while pos < file_size {
-                    let mut chunk_header = [0u8; 8];
-                    if file.read_exact(&mut chunk_header).is_err() {
-                        break;
-                    }
-
-                    let chunk_id = &chunk_header[0..4];
-                    let chunk_size = u32::from_le_bytes([
-                        chunk_header[4],
-                        chunk_header[5],
-                        chunk_header[6],
-                        chunk_header[7],
-                    ]) as u64;
-
-                    // Parse any metadata chunks found at the end
-                    if self.is_valid_chunk_id(chunk_id)
-                        && chunk_size <= 16 * 1024 * 1024
-                        && pos + 8 + chunk_size <= file_size
-                    {
-                        let mut chunk_data = vec![0u8; chunk_size as usize];
-                        if file.read_exact(&mut chunk_data).is_ok() {
-                            self.parse_metadata_chunk(chunk_id, &chunk_data, &mut metadata)?;
-                        }
-                    }
-
-                    pos += 8 + chunk_size + (chunk_size % 2);
-                    if pos + 8 > file_size {
-                        break;
-                    } // Not enough space for another chunk header
-                }
-            }
+        // Legacy ID3v1 tag appended after the RIFF body
+        let riff_end = Ds64::riff_end(ds64, &header);
+        if file_size >= riff_end + ID3V1_TAG_SIZE as u64 {
+            let mut tag = [0u8; ID3V1_TAG_SIZE];
+            file.seek(SeekFrom::Start(file_size - ID3V1_TAG_SIZE as u64))?;
+            file.read_exact(&mut tag)?;
+            metadata.parse_id3v1(&tag)?;
         }
 
         Ok(metadata)
@@ -174,13 +301,94 @@ impl Codec for WavCodec {
             return Err(anyhow!("File too small to be a valid WAV"));
         }
 
-        // Check for 'RIFF....WAVE' header
-        if &data[0..4] != RIFF_CHUNK_ID || &data[8..12] != WAVE_FORMAT_ID {
+        // Check for 'RIFF....WAVE' header (or its RF64/BW64 variants)
+        if !is_wave_header(data) {
             return Err(anyhow!("Invalid WAV File: Missing RIFF/WAVE signature"));
         }
 
         Ok(())
     }
+    fn validate_structure(&self, input: &[u8], report: &mut ValidationReport) {
+        let ds64 = Ds64::find(input);
+        let riff_end = Ds64::riff_end(ds64, input);
+        if riff_end > input.len() as u64 {
+            report.add_issue(format!(
+                "File truncated: RIFF header declares {} bytes but only {} present",
+                riff_end,
+                input.len()
+            ));
+        }
+
+        let mut fmt_found = false;
+        let mut data_found = false;
+        let mut declared_format = None;
+        for chunk in RiffReader::new(input, HEADER_SIZE) {
+            let chunk_id = &chunk.id;
+            let chunk_size = Ds64::resolve(ds64, chunk_id, chunk.size) as usize;
+
+            if chunk_size > chunk.data.len() {
+                report.add_issue(format!(
+                    "Chunk '{}' truncated: declares {} bytes but only {} remain",
+                    String::from_utf8_lossy(chunk_id),
+                    chunk_size,
+                    chunk.data.len()
+                ));
+                if chunk_id == DATA_CHUNK_ID {
+                    data_found = true;
+                }
+                break;
+            }
+
+            match chunk_id {
+                id if id == FMT_CHUNK_ID => {
+                    fmt_found = true;
+                    if chunk_size < STANDARD_FMT_CHUNK_SIZE as usize {
+                        report.add_issue(format!("fmt chunk too small: {} bytes", chunk_size));
+                    } else {
+                        let fmt = chunk.data;
+                        let format_tag = u16::from_le_bytes([fmt[0], fmt[1]]);
+                        let bits = u16::from_le_bytes([fmt[14], fmt[15]]);
+                        let supported = matches!(
+                            (format_tag, bits),
+                            (FORMAT_PCM, BIT_DEPTH_8 | BIT_DEPTH_16 | BIT_DEPTH_24 | BIT_DEPTH_32)
+                                | (FORMAT_IEEE_FLOAT, BIT_DEPTH_32)
+                                | (FORMAT_EXTENSIBLE, _)
+                        );
+                        if !supported {
+                            report.add_issue(format!(
+                                "Unsupported format: tag {} with {} bits",
+                                format_tag, bits
+                            ));
+                        }
+
+                        let subformat = if format_tag == FORMAT_EXTENSIBLE && fmt.len() >= 26 {
+                            u16::from_le_bytes([fmt[24], fmt[25]])
+                        } else {
+                            format_tag
+                        };
+                        declared_format = Some((bits, subformat == FORMAT_IEEE_FLOAT));
+                    }
+                }
+                id if id == DATA_CHUNK_ID => {
+                    data_found = true;
+                    if let Some((bits, is_float)) = declared_format
+                        && let Some(issue) =
+                            check_format_consistency(&chunk.data[..chunk_size], bits, is_float)
+                    {
+                        report.add_issue(issue);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if !fmt_found {
+            report.add_issue("Missing fmt chunk");
+        }
+        if !data_found {
+            report.add_issue("Missing data chunk");
+        }
+    }
     fn get_file_info(&self, file_path: &str) -> R<FileInfo> {
         use std::fs::metadata;
         use std::io::{Cursor, Read, Seek, SeekFrom};
@@ -195,6 +403,7 @@ impl Codec for WavCodec {
 
         // Validate file format
         self.validate_file_format(&mapped_file)?;
+        let ds64 = Ds64::find(&mapped_file);
 
         let mut cursor = Cursor::new(&*mapped_file);
 
@@ -205,7 +414,7 @@ impl Codec for WavCodec {
         let mut sample_rate = 0u32;
         let mut channels = 0u16;
         let mut bits_per_sample = 0u16;
-        let mut data_size = 0u32;
+        let mut data_size = 0u64;
 
         // Description candidates in priority order
         let mut bext_description = String::new();
@@ -219,7 +428,7 @@ impl Codec for WavCodec {
                 break;
             }
 
-            let chunk_size = cursor.read_u32::<LittleEndian>()?;
+            let chunk_size = Ds64::resolve(ds64, &chunk_id, cursor.read_u32::<LittleEndian>()?);
 
             match &chunk_id {
                 FMT_CHUNK_ID => {
@@ -319,11 +528,14 @@ impl Codec for WavCodec {
         };
 
         // Calculate duration
-        let duration = if sample_rate > 0 && channels > 0 && bits_per_sample > 0 {
+        let duration_seconds = if sample_rate > 0 && channels > 0 && bits_per_sample >= 8 {
             let bytes_per_sample = bits_per_sample / 8;
-            let bytes_per_second = sample_rate * channels as u32 * bytes_per_sample as u32;
-            let duration_seconds = data_size as f64 / bytes_per_second as f64;
-
+            let bytes_per_second = sample_rate as u64 * channels as u64 * bytes_per_sample as u64;
+            data_size as f64 / bytes_per_second as f64
+        } else {
+            0.0
+        };
+        let duration = if duration_seconds > 0.0 {
             let hours = (duration_seconds / 3600.0) as u32;
             let minutes = ((duration_seconds % 3600.0) / 60.0) as u32;
             let seconds = (duration_seconds % 60.0) as u32;
@@ -344,10 +556,11 @@ impl Codec for WavCodec {
         Ok(FileInfo {
             path: file_path.to_string(),
             size: file_size,
-            sample_rate: sample_rate as u16,
+            sample_rate,
             channels,
             bit_depth: bits_per_sample,
             duration,
+            duration_secs: duration_seconds,
             description,
         })
     }
@@ -374,10 +587,13 @@ impl Codec for WavCodec {
         let mut sample_rate = 0;
         let mut bits_per_sample = 0;
         let mut audio_data = vec![];
+        let mut fact_frames = None;
+        let ds64 = Ds64::find(input);
 
         while let Ok(chunk_id) = cursor.read_u32::<LittleEndian>() {
             let chunk_id = u32::to_le_bytes(chunk_id);
-            let chunk_size = cursor.read_u32::<LittleEndian>()? as usize;
+            let chunk_size =
+                Ds64::resolve(ds64, &chunk_id, cursor.read_u32::<LittleEndian>()?) as usize;
             match &chunk_id {
                 FMT_CHUNK_ID => {
                     fmt_chunk_found = true;
@@ -390,11 +606,6 @@ impl Codec for WavCodec {
                     bits_per_sample = cursor.read_u16::<LittleEndian>()?;
 
                     sample_format = match (format_tag, bits_per_sample) {
-                        (FORMAT_PCM, BIT_DEPTH_8) => SampleFormat::U8,
-                        (FORMAT_PCM, BIT_DEPTH_16) => SampleFormat::I16,
-                        (FORMAT_PCM, BIT_DEPTH_24) => SampleFormat::I24,
-                        (FORMAT_PCM, BIT_DEPTH_32) => SampleFormat::I32,
-                        (FORMAT_IEEE_FLOAT, BIT_DEPTH_32) => SampleFormat::F32,
                         (FORMAT_EXTENSIBLE, bits) => {
                             // For WAVE_FORMAT_EXTENSIBLE, we need to read the extended format data
                             // The format is a 22-byte structure after the standard fmt chunk
@@ -422,19 +633,13 @@ impl Codec for WavCodec {
                             let subformat = u16::from_le_bytes([guid[0], guid[1]]);
 
                             // Return the correct format for the subformat
-                            let format = match (subformat, bits) {
-                                (FORMAT_PCM, BIT_DEPTH_8) => SampleFormat::U8,
-                                (FORMAT_PCM, BIT_DEPTH_16) => SampleFormat::I16,
-                                (FORMAT_PCM, BIT_DEPTH_24) => SampleFormat::I24,
-                                (FORMAT_PCM, BIT_DEPTH_32) => SampleFormat::I32,
-                                (FORMAT_IEEE_FLOAT, BIT_DEPTH_32) => SampleFormat::F32,
-                                _ => {
-                                    return Err(anyhow!(format!(
-                                        "Unsupported extensible format: subformat {}, bits {}",
-                                        subformat, bits
-                                    )));
-                                }
-                            };
+                            let format = sample_format_for(subformat, bits).ok_or_else(|| {
+                                anyhow!(
+                                    "Unsupported extensible format: subformat {}, bits {}",
+                                    subformat,
+                                    bits
+                                )
+                            })?;
 
                             // Check if there are more bytes in the extension that we need to skip
                             if extension_size > bytes_read {
@@ -445,12 +650,9 @@ impl Codec for WavCodec {
 
                             format
                         }
-                        _ => {
-                            return Err(anyhow!(format!(
-                                "Unsupported format: tag {}, bits {}",
-                                format_tag, bits_per_sample
-                            )));
-                        }
+                        (tag, bits) => sample_format_for(tag, bits).ok_or_else(|| {
+                            anyhow!("Unsupported format: tag {}, bits {}", tag, bits)
+                        })?,
                     };
 
                     // Skip any extra bytes in the fmt chunk and handle padding in one operation
@@ -475,6 +677,14 @@ impl Codec for WavCodec {
                     let mut raw_data = vec![0u8; chunk_size];
                     cursor.read_exact(&mut raw_data)?;
 
+                    if let Some(warning) = check_format_consistency(
+                        &raw_data,
+                        bits_per_sample,
+                        sample_format == SampleFormat::F32,
+                    ) {
+                        dprintln!("WAV decode: Warning - {}", warning);
+                    }
+
                     audio_data = decode_samples(
                         &raw_data,
                         channels,
@@ -488,6 +698,18 @@ impl Codec for WavCodec {
                     }
                 }
 
+                FACT_CHUNK_ID if chunk_size >= 4 => {
+                    let frames = cursor.read_u32::<LittleEndian>()?;
+                    fact_frames = match ds64 {
+                        Some(ds64) if frames == RF64_SIZE_PLACEHOLDER => {
+                            Some(ds64.sample_count as usize)
+                        }
+                        _ => Some(frames as usize),
+                    };
+                    let skip_bytes = chunk_size - 4 + (chunk_size % 2);
+                    cursor.seek(SeekFrom::Current(skip_bytes as i64))?;
+                }
+
                 _ => {
                     // Skip chunk data and padding in one operation
                     let skip_bytes = chunk_size + (chunk_size % 2);
@@ -500,6 +722,16 @@ impl Codec for WavCodec {
             return Err(anyhow!("Missing 'fmt ' or 'data' chunk"));
         }
 
+        // For non-PCM (float) data the fact sample count is authoritative, so any
+        // trailing partial or padded frames in the data chunk are dropped
+        if sample_format == SampleFormat::F32
+            && let Some(frames) = fact_frames
+        {
+            for channel in audio_data.iter_mut() {
+                channel.truncate(frames);
+            }
+        }
+
         Ok(AudioBuffer {
             sample_rate,
             channels,
@@ -509,70 +741,36 @@ impl Codec for WavCodec {
     }
 
     fn encode(&self, buffer: &Option<AudioBuffer>) -> R<Vec<u8>> {
-        let Some(buffer) = buffer else {
-            return Err(anyhow!("Cannot encode None AudioBuffer"));
-        };
-        let mut output = Cursor::new(Vec::new());
-
-        // Ensure channel count in buffer is consistent with data
-        let actual_channels = buffer.data.len() as u16;
-        let channels = if actual_channels != buffer.channels {
-            actual_channels
-        } else {
-            buffer.channels
-        };
-
-        // Placeholder for header
-        output.write_all(RIFF_CHUNK_ID)?;
-        output.write_u32::<LittleEndian>(0)?; // placeholder file size
-        output.write_all(WAVE_FORMAT_ID)?;
-
-        // ---- fmt chunk ----
-        output.write_all(FMT_CHUNK_ID)?;
-        output.write_u32::<LittleEndian>(STANDARD_FMT_CHUNK_SIZE)?; // PCM = 16 bytes
-        let (format_tag, bits_per_sample) = match buffer.format {
-            SampleFormat::F32 => (FORMAT_IEEE_FLOAT, BIT_DEPTH_32),
-            SampleFormat::I16 => (FORMAT_PCM, BIT_DEPTH_16),
-            SampleFormat::I24 => (FORMAT_PCM, BIT_DEPTH_24),
-            SampleFormat::I32 => (FORMAT_PCM, BIT_DEPTH_32),
-            SampleFormat::U8 => (FORMAT_PCM, BIT_DEPTH_8),
-        };
-        let sample_rate = buffer.sample_rate;
-        let byte_rate = sample_rate * channels as u32 * (bits_per_sample as u32 / 8);
-        let block_align = channels * bits_per_sample / 8;
-
-        output.write_u16::<LittleEndian>(format_tag)?;
-        output.write_u16::<LittleEndian>(channels)?; // Use the verified channel count
-        output.write_u32::<LittleEndian>(sample_rate)?;
-        output.write_u32::<LittleEndian>(byte_rate)?;
-        output.write_u16::<LittleEndian>(block_align)?;
-        output.write_u16::<LittleEndian>(bits_per_sample)?;
-
-        // ---- data chunk ----
-        output.write_all(DATA_CHUNK_ID)?;
-        let data_pos = output.position();
-        output.write_u32::<LittleEndian>(0)?; // placeholder
-
-        let start_data = output.position();
-
-        let mut interleaved_bytes = Vec::new();
-        encode_samples(&mut interleaved_bytes, buffer, bits_per_sample)?;
-
-        output.write_all(&interleaved_bytes)?;
-
-        let end_data = output.position();
-        let data_size = (end_data - start_data) as u32;
-
-        // Fill in data chunk size
-        let mut out = output.into_inner();
-        (&mut out[(data_pos as usize)..(data_pos as usize + 4)])
-            .write_u32::<LittleEndian>(data_size)?;
+        self.encode_riff(buffer, u32::MAX as u64)
+    }
 
-        // Fill in RIFF file size
-        let riff_size = out.len() as u32 - 8;
-        (&mut out[4..8]).write_u32::<LittleEndian>(riff_size)?;
+    fn decode_stream<'a>(&self, input: &'a [u8]) -> R<(StreamHeader, FrameStream<'a>)> {
+        let (header, bits, data) = self.sample_data(input)?;
+        let block_align = header.channels as usize * bits as usize / 8;
+        let (channels, is_float) = (header.channels, header.format == SampleFormat::F32);
+        let blocks = data
+            .chunks(STREAM_BLOCK_FRAMES * block_align)
+            .map(move |block| decode_samples(block, channels, bits, is_float));
+        Ok((header, Box::new(blocks)))
+    }
 
-        Ok(out)
+    fn decode_range(&self, input: &[u8], start: u64, end: u64) -> R<AudioBuffer> {
+        let (header, bits, data) = self.sample_data(input)?;
+        let block_align = header.channels as usize * bits as usize / 8;
+        let end = (end as usize).saturating_mul(block_align).min(data.len());
+        let start = (start as usize).saturating_mul(block_align).min(end);
+        let data = decode_samples(
+            &data[start..end],
+            header.channels,
+            bits,
+            header.format == SampleFormat::F32,
+        )?;
+        Ok(AudioBuffer {
+            sample_rate: header.sample_rate,
+            channels: header.channels,
+            format: header.format,
+            data,
+        })
     }
 
     fn parse_metadata(&self, input: &[u8]) -> R<Metadata> {
@@ -582,6 +780,9 @@ impl Codec for WavCodec {
         // Validate WAV header
         self.validate_file_format(input)?;
 
+        let ds64 = Ds64::find(input);
+        let mut pending_ixml = String::new();
+
         // Skip RIFF header (12 bytes)
         cursor.set_position(12);
 
@@ -594,7 +795,7 @@ impl Codec for WavCodec {
             };
 
             let chunk_size = match cursor.read_u32::<LittleEndian>() {
-                Ok(size) => size as usize,
+                Ok(size) => Ds64::resolve(ds64, &chunk_id.to_le_bytes(), size) as usize,
                 Err(_) => break,
             };
 
@@ -619,6 +820,7 @@ impl Codec for WavCodec {
                         fmt_cursor.read_u32::<LittleEndian>()?; // byte_rate - skip
                         fmt_cursor.read_u16::<LittleEndian>()?; // block_align - skip
                         metadata.bit_depth = fmt_cursor.read_u16::<LittleEndian>()?;
+                        metadata.channel_mask = extensible_channel_mask(chunk_data);
                     }
                 }
                 b"bext" => {
@@ -626,11 +828,16 @@ impl Codec for WavCodec {
                 }
                 b"iXML" => {
                     let xml_str = String::from_utf8_lossy(chunk_data);
-                    metadata.parse_ixml(&xml_str)?;
+                    if let Some(ixml) = join_ixml_chunk(&mut pending_ixml, &xml_str) {
+                        metadata.parse_ixml(&ixml)?;
+                    }
                 }
                 b"ID3 " | b"id3 " => {
                     metadata.parse_id3(chunk_data)?;
                 }
+                b"cue " | b"LIST" => {
+                    self.parse_metadata_chunk(&chunk_id.to_le_bytes(), chunk_data, &mut metadata)?;
+                }
                 b"SMED" | b"SMRD" | b"SMPL" | b"APIC" => {
                     // Skip binary metadata chunks - these contain non-text data
                     // SMED = Soundminer metadata (binary)
@@ -650,10 +857,23 @@ impl Codec for WavCodec {
                 cursor.set_position(cursor.position() + 1);
             }
         }
+        if !pending_ixml.is_empty() {
+            metadata.parse_ixml(&pending_ixml)?;
+        }
+
+        // Legacy ID3v1 tag appended after the RIFF body
+        let riff_end = Ds64::riff_end(ds64, input) as usize;
+        if input.len() >= riff_end + ID3V1_TAG_SIZE {
+            metadata.parse_id3v1(&input[input.len() - ID3V1_TAG_SIZE..])?;
+        }
 
         Ok(metadata)
     }
 
+    fn embed_warnings(&self, metadata: &Metadata) -> Vec<String> {
+        self.bext_truncations(metadata)
+    }
+
     fn embed_metadata_to_file(&self, file_path: &str, metadata: &Metadata) -> R<()> {
         use std::fs::OpenOptions;
         use std::io::Read;
@@ -692,9 +912,19 @@ impl Codec for WavCodec {
         // New metadata insertion point (after fmt chunk)
         let metadata_insert_pos = fmt_chunk.end_position;
 
+        // Chunks between fmt and data that this embed doesn't rewrite (fact, smpl,
+        // acid, ...) are carried over after the new metadata, still ahead of data
+        let data_header_pos = data_chunk.start_position - 8; // -8 for data chunk header
+        let mut old_metadata = vec![0u8; (data_header_pos - metadata_insert_pos) as usize];
+        file.seek(SeekFrom::Start(metadata_insert_pos))?;
+        file.read_exact(&mut old_metadata)?;
+        let preserved = self.preserved_chunks(RiffReader::new(&old_metadata, 0), metadata)?;
+        let mut leading_metadata = new_metadata.clone();
+        leading_metadata.extend(preserved);
+
         // Calculate size difference
-        let old_metadata_size = (data_chunk.start_position - metadata_insert_pos - 8) as usize; // -8 for data chunk header
-        let new_metadata_size = new_metadata.len();
+        let old_metadata_size = old_metadata.len();
+        let new_metadata_size = leading_metadata.len();
         let size_diff = new_metadata_size as i64 - old_metadata_size as i64;
 
         if size_diff == 0 && new_metadata_size <= old_metadata_size {
@@ -702,16 +932,68 @@ impl Codec for WavCodec {
             self.update_metadata_in_place(
                 &mut file,
                 metadata_insert_pos,
-                &new_metadata,
+                &leading_metadata,
                 data_chunk,
             )?;
         } else {
             // Size changed - use fixed append-at-end strategy (keeps data intact!)
-            self.update_metadata_append_strategy(&mut file, &chunks, &new_metadata)?;
+            self.update_metadata_append_strategy(&mut file, &chunks, metadata, &new_metadata)?;
         }
 
         Ok(())
     }
+
+    /// Lays the file out as `embed_metadata_to_file` does in place: `fmt `, the
+    /// new metadata, the carried-over chunks that preceded `data`, `data`, then
+    /// the carried-over chunks that followed it. An RF64/BW64 file keeps its
+    /// `ds64` chunk first, with the RIFF size updated there.
+    fn embed_metadata(&self, input: &[u8], metadata: &Metadata) -> R<Vec<u8>> {
+        self.validate_file_format(input)?;
+        let ds64 = Ds64::find(input);
+
+        let chunks = Ds64::chunks(ds64, input);
+        let data_index = chunks
+            .iter()
+            .position(|chunk| &chunk.id == DATA_CHUNK_ID)
+            .ok_or_else(|| anyhow!("No data chunk found"))?;
+        let fmt_chunk = chunks[..data_index]
+            .iter()
+            .find(|chunk| &chunk.id == FMT_CHUNK_ID)
+            .ok_or_else(|| anyhow!("No fmt chunk found"))?;
+
+        let mut output = Vec::with_capacity(input.len());
+        output.extend_from_slice(&input[..HEADER_SIZE]);
+        if ds64.is_some() {
+            // `Ds64::find` only succeeds when ds64 is the first chunk
+            RiffWriter::new(&mut output).write_chunk(DS64_CHUNK_ID, chunks[0].data)?;
+        }
+        RiffWriter::new(&mut output).write_chunk(FMT_CHUNK_ID, fmt_chunk.data)?;
+        output.extend(self.create_metadata_chunks(metadata)?);
+        output.extend(self.preserved_chunks(chunks[..data_index].iter().copied(), metadata)?);
+        let data_chunk = chunks[data_index];
+        if ds64.is_some() {
+            // The size field may be the placeholder, so it is copied rather than recomputed
+            output.extend_from_slice(DATA_CHUNK_ID);
+            output.extend_from_slice(&data_chunk.size.to_le_bytes());
+            output.extend_from_slice(data_chunk.data);
+            if data_chunk.data.len() % 2 == 1 {
+                output.push(0);
+            }
+        } else {
+            RiffWriter::new(&mut output).write_chunk(DATA_CHUNK_ID, data_chunk.data)?;
+        }
+        output.extend(self.preserved_chunks(chunks[data_index + 1..].iter().copied(), metadata)?);
+
+        let riff_size = output.len() as u64 - 8;
+        if ds64.is_some() {
+            output[HEADER_SIZE + 8..HEADER_SIZE + 16].copy_from_slice(&riff_size.to_le_bytes());
+        } else {
+            let riff_size = u32::try_from(riff_size)
+                .map_err(|_| anyhow!("WAV with metadata exceeds the 4 GB RIFF limit"))?;
+            output[4..8].copy_from_slice(&riff_size.to_le_bytes());
+        }
+        Ok(output)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -723,6 +1005,183 @@ struct WavChunk {
 }
 
 impl WavCodec {
+    /// Locate the sample bytes of the data chunk, trimmed to the fact count for
+    /// float data, along with the stream header and bits per sample
+    fn sample_data<'a>(&self, input: &'a [u8]) -> R<(StreamHeader, u16, &'a [u8])> {
+        self.validate_file_format(input)?;
+        let ds64 = Ds64::find(input);
+
+        let mut format = None;
+        let mut data = None;
+        let mut fact_frames = None;
+        for chunk in RiffReader::new(input, HEADER_SIZE) {
+            let size = Ds64::resolve(ds64, &chunk.id, chunk.size) as usize;
+            let body = &chunk.data[..size.min(chunk.data.len())];
+            match &chunk.id {
+                FMT_CHUNK_ID if body.len() >= 16 => format = Some(read_fmt(body)?),
+                FACT_CHUNK_ID if body.len() >= 4 => {
+                    fact_frames = Some(u32::from_le_bytes([body[0], body[1], body[2], body[3]]));
+                }
+                DATA_CHUNK_ID => {
+                    data = Some(body);
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        let (Some((sample_format, channels, sample_rate, bits)), Some(mut data)) = (format, data)
+        else {
+            return Err(anyhow!("Missing 'fmt ' or 'data' chunk"));
+        };
+        let is_float = sample_format == SampleFormat::F32;
+        let block_align = channels as usize * bits as usize / 8;
+        if block_align == 0 {
+            return Err(anyhow!("Invalid WAV format: {} channels", channels));
+        }
+        // As in `decode`, the fact sample count is authoritative for float data
+        if is_float && let Some(frames) = fact_frames {
+            data = &data[..data.len().min(frames as usize * block_align)];
+        }
+
+        let header = StreamHeader {
+            sample_rate,
+            channels,
+            format: sample_format,
+            total_frames: Some((data.len() / block_align) as u64),
+        };
+        Ok((header, bits, data))
+    }
+
+    /// Encode as RIFF/WAVE, promoting to RF64 (with a `ds64` chunk and
+    /// placeholder 32-bit sizes) when the RIFF body would exceed `riff_limit`
+    fn encode_riff(&self, buffer: &Option<AudioBuffer>, riff_limit: u64) -> R<Vec<u8>> {
+        let Some(buffer) = buffer else {
+            return Err(anyhow!("Cannot encode None AudioBuffer"));
+        };
+        let mut output = Cursor::new(Vec::new());
+
+        // The channel data is authoritative over a stale `channels` count
+        let channels = buffer.data.len() as u16;
+
+        let (format_tag, bits_per_sample) = match buffer.format {
+            SampleFormat::F32 => (FORMAT_IEEE_FLOAT, BIT_DEPTH_32),
+            SampleFormat::I16 => (FORMAT_PCM, BIT_DEPTH_16),
+            SampleFormat::I24 => (FORMAT_PCM, BIT_DEPTH_24),
+            SampleFormat::I32 => (FORMAT_PCM, BIT_DEPTH_32),
+            SampleFormat::U8 => (FORMAT_PCM, BIT_DEPTH_8),
+        };
+        let sample_rate = buffer.sample_rate;
+        let byte_rate = sample_rate * channels as u32 * (bits_per_sample as u32 / 8);
+        let block_align = channels * bits_per_sample / 8;
+
+        // WAVE id + fmt chunk + data chunk, known before any samples are written
+        let frames = buffer.data.first().map_or(0, |channel| channel.len()) as u64;
+        let expected_data_size = frames * block_align as u64;
+        let riff_body =
+            4 + 8 + STANDARD_FMT_CHUNK_SIZE as u64 + 8 + padded_size(expected_data_size);
+        let rf64 = riff_body > riff_limit;
+
+        // Placeholder for header
+        if rf64 {
+            output.write_all(RF64_CHUNK_ID)?;
+            output.write_u32::<LittleEndian>(RF64_SIZE_PLACEHOLDER)?;
+            output.write_all(WAVE_FORMAT_ID)?;
+            output.write_all(DS64_CHUNK_ID)?;
+            output.write_u32::<LittleEndian>(DS64_CHUNK_SIZE)?;
+            output.write_all(&[0u8; DS64_CHUNK_SIZE as usize])?; // filled in below
+        } else {
+            output.write_all(RIFF_CHUNK_ID)?;
+            output.write_u32::<LittleEndian>(0)?; // placeholder file size
+            output.write_all(WAVE_FORMAT_ID)?;
+        }
+
+        // ---- fmt chunk ----
+        output.write_all(FMT_CHUNK_ID)?;
+        output.write_u32::<LittleEndian>(STANDARD_FMT_CHUNK_SIZE)?; // PCM = 16 bytes
+        output.write_u16::<LittleEndian>(format_tag)?;
+        output.write_u16::<LittleEndian>(channels)?; // Use the verified channel count
+        output.write_u32::<LittleEndian>(sample_rate)?;
+        output.write_u32::<LittleEndian>(byte_rate)?;
+        output.write_u16::<LittleEndian>(block_align)?;
+        output.write_u16::<LittleEndian>(bits_per_sample)?;
+
+        // ---- data chunk ----
+        output.write_all(DATA_CHUNK_ID)?;
+        let data_pos = output.position();
+        output.write_u32::<LittleEndian>(0)?; // placeholder
+
+        let start_data = output.position();
+
+        let mut interleaved_bytes = Vec::new();
+        encode_samples(&mut interleaved_bytes, buffer, bits_per_sample)?;
+
+        output.write_all(&interleaved_bytes)?;
+
+        let end_data = output.position();
+        let data_size = end_data - start_data;
+
+        // Fill in data chunk size
+        let mut out = output.into_inner();
+        let data_size_field = if rf64 {
+            RF64_SIZE_PLACEHOLDER
+        } else {
+            data_size as u32
+        };
+        (&mut out[(data_pos as usize)..(data_pos as usize + 4)])
+            .write_u32::<LittleEndian>(data_size_field)?;
+
+        // RIFF chunks are word aligned; the pad byte is not counted in the size
+        if data_size % 2 == 1 {
+            out.push(0);
+        }
+
+        // Fill in RIFF file size, or the ds64 fields that replace it
+        let riff_size = out.len() as u64 - 8;
+        if rf64 {
+            let mut ds64 = &mut out[HEADER_SIZE + 8..HEADER_SIZE + 8 + 24];
+            ds64.write_u64::<LittleEndian>(riff_size)?;
+            ds64.write_u64::<LittleEndian>(data_size)?;
+            ds64.write_u64::<LittleEndian>(frames)?;
+        } else {
+            (&mut out[4..8]).write_u32::<LittleEndian>(riff_size as u32)?;
+        }
+
+        Ok(out)
+    }
+
+    /// Decode a RIFF file whose form type is not `WAVE`, as long as it carries
+    /// `fmt ` and `data` chunks. Standard WAV files decode as usual.
+    pub fn decode_lenient(&self, input: &[u8]) -> R<AudioBuffer> {
+        if self.validate_file_format(input).is_ok() {
+            return self.decode(input);
+        }
+        if input.len() < HEADER_SIZE || &input[0..4] != RIFF_CHUNK_ID {
+            return Err(anyhow!("Invalid WAV File: Missing RIFF signature"));
+        }
+
+        let mut fmt_found = false;
+        let mut data_found = false;
+        for chunk in RiffReader::new(input, HEADER_SIZE) {
+            fmt_found |= &chunk.id == FMT_CHUNK_ID;
+            data_found |= &chunk.id == DATA_CHUNK_ID;
+        }
+        if !fmt_found || !data_found {
+            return Err(anyhow!(
+                "RIFF form type '{}' is not WAVE and lacks 'fmt ' or 'data' chunks",
+                String::from_utf8_lossy(&input[8..12])
+            ));
+        }
+
+        dprintln!(
+            "WAV decode: Warning - RIFF form type '{}' is not WAVE, decoding leniently",
+            String::from_utf8_lossy(&input[8..12])
+        );
+        let mut patched = input.to_vec();
+        patched[8..12].copy_from_slice(WAVE_FORMAT_ID);
+        self.decode(&patched)
+    }
+
     fn is_valid_chunk_id(&self, chunk_id: &[u8]) -> bool {
         // Check if this is a known metadata chunk type
         matches!(
@@ -762,26 +1221,183 @@ impl WavCodec {
                     cursor.read_u32::<LittleEndian>()?; // byte_rate - skip
                     cursor.read_u16::<LittleEndian>()?; // block_align - skip
                     metadata.bit_depth = cursor.read_u16::<LittleEndian>()?;
+                    metadata.channel_mask = extensible_channel_mask(chunk_data);
+                }
+            }
+            b"bext" => {
+                metadata.parse_bext(&chunk_data)?;
+            }
+            b"cue " => {
+                self.parse_cue_chunk(chunk_data, metadata)?;
+            }
+            b"LIST" if chunk_data.len() >= 4 => match &chunk_data[0..4] {
+                b"adtl" => self.parse_adtl_list(&chunk_data[4..], metadata)?,
+                b"INFO" => self.parse_list_info(&chunk_data[4..], metadata)?,
+                _ => {}
+            },
+            b"id3 " | b"ID3 " => {
+                metadata.parse_id3(&chunk_data)?;
+            }
+            _ => {
+                // Skip unknown chunks
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_cue_chunk(&self, data: &[u8], metadata: &mut Metadata) -> R<()> {
+        if data.len() < 4 {
+            return Ok(());
+        }
+        let mut cursor = Cursor::new(data);
+        let count = cursor.read_u32::<LittleEndian>()? as usize;
+
+        // Each cue point: ID, position, fccChunk, chunkStart, blockStart, sampleOffset
+        for _ in 0..count.min((data.len() - 4) / 24) {
+            let id = cursor.read_u32::<LittleEndian>()?;
+            let _position = cursor.read_u32::<LittleEndian>()?;
+            cursor.seek(SeekFrom::Current(12))?; // fccChunk, chunkStart, blockStart
+            let sample_offset = cursor.read_u32::<LittleEndian>()?;
+            metadata.marker_mut(id).position = sample_offset;
+        }
+        Ok(())
+    }
+
+    fn parse_adtl_list(&self, data: &[u8], metadata: &mut Metadata) -> R<()> {
+        let mut pos = 0;
+        while pos + 8 <= data.len() {
+            let sub_id = &data[pos..pos + 4];
+            let sub_size =
+                u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]])
+                    as usize;
+            let start = pos + 8;
+            if start + sub_size > data.len() || sub_size < 4 {
+                break;
+            }
+            let sub_data = &data[start..start + sub_size];
+            let cue_id = u32::from_le_bytes([sub_data[0], sub_data[1], sub_data[2], sub_data[3]]);
+
+            match sub_id {
+                b"labl" => {
+                    metadata.marker_mut(cue_id).label = super::clean_text_field(&sub_data[4..]);
                 }
+                b"note" => {
+                    metadata.marker_mut(cue_id).note = super::clean_text_field(&sub_data[4..]);
+                }
+                b"ltxt" if sub_data.len() >= 20 => {
+                    let marker = metadata.marker_mut(cue_id);
+                    marker.length =
+                        u32::from_le_bytes([sub_data[4], sub_data[5], sub_data[6], sub_data[7]]);
+                    marker.purpose = String::from_utf8_lossy(&sub_data[8..12]).to_string();
+                    marker.text = super::clean_text_field(&sub_data[20..]);
+                }
+                _ => {}
             }
-            b"bext" => {
-                metadata.parse_bext(&chunk_data)?;
+
+            pos = start + sub_size + (sub_size % 2);
+        }
+        Ok(())
+    }
+
+    fn parse_list_info(&self, data: &[u8], metadata: &mut Metadata) -> R<()> {
+        let mut pos = 0;
+        while pos + 8 <= data.len() {
+            let sub_id = &data[pos..pos + 4];
+            let sub_size =
+                u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]])
+                    as usize;
+            let start = pos + 8;
+            if start + sub_size > data.len() {
+                break;
             }
-            b"iXML" => {
-                let xml_str = String::from_utf8_lossy(&chunk_data);
-                metadata.parse_ixml(&xml_str)?;
+            if let Some(text) = super::clean_text_field(&data[start..start + sub_size]) {
+                let field_name = format!("INFO_{}", String::from_utf8_lossy(sub_id));
+                metadata.set_field(&field_name, &text)?;
             }
-            b"LIST" => {
-                // Skip LIST chunk parsing for now - would need more complex implementation
+            pos = start + sub_size + (sub_size % 2);
+        }
+        Ok(())
+    }
+
+    /// LIST INFO body from the `INFO_xxxx` fields. INAM/ICMT/IART fall back to
+    /// the title, description and designer so Explorer's Title, Comments and
+    /// Authors columns show our metadata.
+    fn create_list_info_chunk(&self, metadata: &Metadata) -> R<Option<Vec<u8>>> {
+        let mut entries = std::collections::BTreeMap::new();
+        for (key, value) in metadata.get_all_fields() {
+            if let Some(id) = key.strip_prefix("INFO_")
+                && id.len() == 4
+                && id.is_ascii()
+                && !value.is_empty()
+            {
+                entries.insert(id.to_string(), value.clone());
             }
-            b"id3 " | b"ID3 " => {
-                metadata.parse_id3(&chunk_data)?;
+        }
+        for (id, field) in [
+            ("INAM", "TrackTitle"),
+            ("ICMT", "Description"),
+            ("IART", "Designer"),
+        ] {
+            if let Some(value) = metadata.get_field(field).filter(|v| !v.is_empty()) {
+                entries.entry(id.to_string()).or_insert(value);
             }
-            _ => {
-                // Skip unknown chunks
+        }
+
+        if entries.is_empty() {
+            return Ok(None);
+        }
+
+        let mut list = RiffWriter::new(b"INFO".to_vec());
+        for (id, value) in entries {
+            let mut text = value.into_bytes();
+            text.push(0);
+            list.write_chunk(id.as_bytes(), &text)?;
+        }
+        Ok(Some(list.into_inner()))
+    }
+
+    fn create_cue_chunk_data(&self, metadata: &Metadata) -> R<Vec<u8>> {
+        let markers = metadata.get_markers();
+        let mut data = Vec::with_capacity(4 + markers.len() * 24);
+        data.write_u32::<LittleEndian>(markers.len() as u32)?;
+        for marker in markers {
+            data.write_u32::<LittleEndian>(marker.id)?;
+            data.write_u32::<LittleEndian>(marker.position)?;
+            data.extend_from_slice(DATA_CHUNK_ID);
+            data.write_u32::<LittleEndian>(0)?; // chunkStart
+            data.write_u32::<LittleEndian>(0)?; // blockStart
+            data.write_u32::<LittleEndian>(marker.position)?;
+        }
+        Ok(data)
+    }
+
+    fn create_adtl_list_data(&self, metadata: &Metadata) -> R<Vec<u8>> {
+        let mut data = b"adtl".to_vec();
+        for marker in metadata.get_markers() {
+            if let Some(label) = &marker.label {
+                write_chunk(&mut data, b"labl", &adtl_text(marker.id, label))?;
+            }
+            if let Some(note) = &marker.note {
+                write_chunk(&mut data, b"note", &adtl_text(marker.id, note))?;
+            }
+            if marker.length > 0 || marker.text.is_some() {
+                let mut ltxt = Vec::new();
+                ltxt.write_u32::<LittleEndian>(marker.id)?;
+                ltxt.write_u32::<LittleEndian>(marker.length)?;
+                let mut purpose = *b"rgn ";
+                for (dst, src) in purpose.iter_mut().zip(marker.purpose.bytes()) {
+                    *dst = src;
+                }
+                ltxt.extend_from_slice(&purpose);
+                ltxt.extend_from_slice(&[0u8; 8]); // country, language, dialect, code page
+                if let Some(text) = &marker.text {
+                    ltxt.extend_from_slice(text.as_bytes());
+                    ltxt.push(0);
+                }
+                write_chunk(&mut data, b"ltxt", &ltxt)?;
             }
         }
-        Ok(())
+        Ok(data)
     }
 
     fn parse_wav_structure(&self, file: &mut std::fs::File) -> R<Vec<WavChunk>> {
@@ -793,11 +1409,12 @@ impl WavCodec {
         let mut header = [0u8; 12];
         file.read_exact(&mut header)?;
 
-        if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        if !is_wave_header(&header) {
             return Err(anyhow!("Invalid WAV file"));
         }
 
         let mut chunks = Vec::new();
+        let mut ds64 = None;
         let mut pos = 12u64;
 
         // Only parse chunks until we find the data chunk
@@ -807,28 +1424,20 @@ impl WavCodec {
         loop {
             file.seek(SeekFrom::Start(pos))?;
 
-            let mut chunk_header = [0u8; 8];
-            match file.read_exact(&mut chunk_header) {
-                Ok(_) => {}
-                Err(_) => break, // End of file
-            }
+            let Some((chunk_id, chunk_size)) = read_chunk_header::<LittleEndian, _>(file) else {
+                break; // End of file
+            };
 
-            let mut chunk_id = [0u8; 4];
-            chunk_id.copy_from_slice(&chunk_header[0..4]);
-            let chunk_size = u32::from_le_bytes([
-                chunk_header[4],
-                chunk_header[5],
-                chunk_header[6],
-                chunk_header[7],
-            ]);
+            if &chunk_id == DS64_CHUNK_ID {
+                let mut body = [0u8; 24];
+                file.read_exact(&mut body)?;
+                ds64 = Ds64::from_body(&body);
+            }
 
+            // `size` keeps the declared value, which is rewritten verbatim when
+            // the data chunk moves; the end comes from the real (ds64) size
             let data_start = pos + 8;
-            let data_end = data_start + chunk_size as u64;
-            let padded_end = if chunk_size % 2 == 1 {
-                data_end + 1
-            } else {
-                data_end
-            };
+            let padded_end = data_start + padded_size(Ds64::resolve(ds64, &chunk_id, chunk_size));
 
             chunks.push(WavChunk {
                 id: chunk_id,
@@ -929,7 +1538,8 @@ impl WavCodec {
     ) -> R<()> {
         use std::io::{Read, Seek, SeekFrom, Write};
 
-        let data_size = data_chunk.size as u64;
+        let old_data_start = data_chunk.start_position;
+        let data_size = self.data_tail_size(file, data_chunk)?;
 
         // Move data in chunks to avoid loading entire file into memory
         // Use larger buffers for big files to reduce I/O operations
@@ -942,7 +1552,6 @@ impl WavCodec {
         } else {
             4 * 1024 * 1024 // 4MB buffer for smaller files
         };
-        let old_data_start = data_chunk.start_position;
         let new_data_start = old_data_start + growth;
 
         // Check if we have enough disk space for the operation
@@ -982,6 +1591,7 @@ impl WavCodec {
 
         // Update file length
         file.set_len(new_data_start + data_size)?;
+        self.ensure_data_padding(file, data_chunk, new_data_start)?;
 
         Ok(())
     }
@@ -1003,7 +1613,7 @@ impl WavCodec {
         // Calculate new data position
         let old_data_start = data_chunk.start_position;
         let new_data_start = old_data_start - shrink;
-        let data_size = data_chunk.size as u64;
+        let data_size = self.data_tail_size(file, data_chunk)?;
 
         // Write new data chunk header
         file.seek(SeekFrom::Start(new_data_start - 8))?;
@@ -1039,18 +1649,50 @@ impl WavCodec {
 
         // Truncate file to new size
         file.set_len(new_data_start + data_size)?;
+        self.ensure_data_padding(file, data_chunk, new_data_start)?;
+
+        Ok(())
+    }
+
+    /// Bytes from the start of the data payload to end of file: the samples, the
+    /// pad byte when the size is odd, and any chunks stored after the audio.
+    /// Moving all of them keeps trailing metadata word aligned.
+    fn data_tail_size(&self, file: &std::fs::File, data_chunk: &WavChunk) -> R<u64> {
+        let file_len = file.metadata()?.len();
+        Ok(file_len.saturating_sub(data_chunk.start_position))
+    }
 
+    /// Add the missing pad byte to an odd-sized data chunk that ends the file
+    fn ensure_data_padding(
+        &self,
+        file: &mut std::fs::File,
+        data_chunk: &WavChunk,
+        data_start: u64,
+    ) -> R<()> {
+        let padded_end = data_start + (data_chunk.end_position - data_chunk.start_position);
+        if file.metadata()?.len() < padded_end {
+            file.set_len(padded_end)?;
+        }
         Ok(())
     }
 
+    /// Rewrite the RIFF size, or for RF64/BW64 the ds64 field that replaces it
     fn update_riff_size(&self, file: &mut std::fs::File) -> R<()> {
-        use std::io::{Seek, SeekFrom, Write};
+        use std::io::{Read, Seek, SeekFrom, Write};
 
         let file_size = file.metadata()?.len();
-        let riff_size = (file_size - 8) as u32; // Exclude RIFF header itself
+        let riff_size = file_size - 8; // Exclude RIFF header itself
 
-        file.seek(SeekFrom::Start(4))?;
-        file.write_all(&riff_size.to_le_bytes())?;
+        let mut form = [0u8; 4];
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut form)?;
+        if &form == RIFF_CHUNK_ID {
+            file.seek(SeekFrom::Start(4))?;
+            file.write_all(&(riff_size as u32).to_le_bytes())?;
+        } else {
+            file.seek(SeekFrom::Start(HEADER_SIZE as u64 + 8))?;
+            file.write_all(&riff_size.to_le_bytes())?;
+        }
 
         Ok(())
     }
@@ -1059,6 +1701,7 @@ impl WavCodec {
         &self,
         file: &mut std::fs::File,
         chunks: &[WavChunk],
+        metadata: &Metadata,
         new_metadata: &[u8],
     ) -> R<()> {
         use std::io::{Seek, SeekFrom, Write};
@@ -1071,22 +1714,81 @@ impl WavCodec {
             .find(|chunk| &chunk.id == b"data")
             .ok_or_else(|| anyhow!("No data chunk found"))?;
 
+        // Old copies of the chunks being rewritten become JUNK so they can't shadow
+        // the new ones; everything else before data stays as it is
+        for chunk in chunks.iter().filter(|chunk| chunk.id != *b"data") {
+            let mut list_type = [0u8; 4];
+            if &chunk.id == b"LIST" && chunk.size >= 4 {
+                file.seek(SeekFrom::Start(chunk.start_position))?;
+                file.read_exact(&mut list_type)?;
+            }
+            if self.is_rewritten_chunk(&chunk.id, &list_type, metadata) {
+                file.seek(SeekFrom::Start(chunk.start_position - 8))?;
+                file.write_all(b"JUNK")?;
+            }
+        }
+
         // The audio data and everything before it stays exactly where it is
         // We just append new metadata after the data chunk
         let append_position = data_chunk.end_position;
 
+        // Chunks already after data that this embed doesn't rewrite follow the new metadata
+        let mut trailing = Vec::new();
+        file.seek(SeekFrom::Start(append_position))?;
+        file.read_to_end(&mut trailing)?;
+        let trailing = self.preserved_chunks(RiffReader::new(&trailing, 0), metadata)?;
+
         // Append new metadata chunks at the end
         file.seek(SeekFrom::Start(append_position))?;
         file.write_all(new_metadata)?;
+        file.write_all(&trailing)?;
 
         // Update file size and RIFF header
-        let new_file_size = append_position + new_metadata.len() as u64;
+        let new_file_size = append_position + (new_metadata.len() + trailing.len()) as u64;
         file.set_len(new_file_size)?;
         self.update_riff_size(file)?;
 
         Ok(())
     }
 
+    /// Whether embedding `metadata` writes its own version of a chunk, so the old
+    /// one is dropped. `LIST INFO` is always regenerated from the parsed fields;
+    /// `cue `/`LIST adtl` are only rewritten when there are markers to write.
+    /// `list_type` is the first four body bytes of a `LIST`.
+    fn is_rewritten_chunk(&self, id: &[u8; 4], list_type: &[u8], metadata: &Metadata) -> bool {
+        let has_markers = !metadata.get_markers().is_empty();
+        match id {
+            b"bext" | b"iXML" | b"APIC" | b"id3 " | b"ID3 " | b"JUNK" | b"PAD " => true,
+            b"cue " => has_markers,
+            b"LIST" => {
+                list_type.starts_with(b"INFO") || (has_markers && list_type.starts_with(b"adtl"))
+            }
+            _ => metadata.get_raw_chunks().iter().any(|(raw, _)| raw == id),
+        }
+    }
+
+    /// Serialize, in order, the complete chunks that embedding `metadata` doesn't
+    /// rewrite (smpl, inst, acid, fact, ...), so a metadata write carries them over
+    fn preserved_chunks<'a>(
+        &self,
+        chunks: impl Iterator<Item = Chunk<'a>>,
+        metadata: &Metadata,
+    ) -> R<Vec<u8>> {
+        let mut preserved = RiffWriter::new(Vec::new());
+        for chunk in chunks {
+            if chunk.is_truncated()
+                || &chunk.id == DS64_CHUNK_ID
+                || &chunk.id == FMT_CHUNK_ID
+                || &chunk.id == DATA_CHUNK_ID
+                || self.is_rewritten_chunk(&chunk.id, chunk.data, metadata)
+            {
+                continue;
+            }
+            preserved.write_chunk(&chunk.id, chunk.data)?;
+        }
+        Ok(preserved.into_inner())
+    }
+
     fn find_data_chunk_position(&self, input: &[u8]) -> R<(usize, usize)> {
         let mut cursor = Cursor::new(input);
         cursor.seek(SeekFrom::Start(12))?; // Skip RIFF header
@@ -1149,88 +1851,77 @@ impl WavCodec {
         Err(anyhow!("No fmt chunk found"))
     }
 
-    fn create_metadata_chunks(&self, metadata: &Metadata) -> R<Vec<u8>> {
-        let mut chunks = Vec::new();
+    pub(super) fn create_metadata_chunks(&self, metadata: &Metadata) -> R<Vec<u8>> {
+        let mut chunks = RiffWriter::new(Vec::new());
 
         // Create BEXT chunk
         let bext_data = self.create_bext_chunk_data(metadata)?;
         if !bext_data.is_empty() {
-            chunks.extend_from_slice(b"bext");
-            chunks.extend_from_slice(&(bext_data.len() as u32).to_le_bytes());
-            chunks.extend_from_slice(&bext_data);
-            if bext_data.len() % 2 == 1 {
-                chunks.push(0); // Padding
-            }
+            chunks.write_chunk(b"bext", &bext_data)?;
         }
 
         // Create iXML chunk
         let ixml_content = self.create_ixml(metadata)?;
         if !ixml_content.trim().is_empty() {
-            let ixml_bytes = ixml_content.as_bytes();
-            chunks.extend_from_slice(b"iXML");
-            chunks.extend_from_slice(&(ixml_bytes.len() as u32).to_le_bytes());
-            chunks.extend_from_slice(ixml_bytes);
-            if ixml_bytes.len() % 2 == 1 {
-                chunks.push(0); // Padding
-            }
+            chunks.write_chunk(b"iXML", ixml_content.as_bytes())?;
         }
 
-        // Create image chunks
-        for image in metadata.get_images() {
-            chunks.extend_from_slice(b"APIC");
-            chunks.extend_from_slice(&(image.data.len() as u32).to_le_bytes());
-            chunks.extend_from_slice(&image.data);
-            if image.data.len() % 2 == 1 {
-                chunks.push(0); // Padding
-            }
+        if let Some(info_data) = self.create_list_info_chunk(metadata)? {
+            chunks.write_chunk(b"LIST", &info_data)?;
         }
 
-        Ok(chunks)
-    }
-
-    fn create_bext_chunk_data(&self, metadata: &Metadata) -> R<Vec<u8>> {
-        let mut bext_data = vec![0u8; 602]; // BWF spec minimum size
-
-        // Description (256 bytes)
-        if let Some(description) = metadata
-            .get_field("DESCRIPTION")
-            .or_else(|| metadata.get_field("BEXT_BWF_DESCRIPTION"))
-            .or_else(|| metadata.get_field("Description"))
-        {
-            let bytes = description.as_bytes();
-            let len = std::cmp::min(bytes.len(), 255);
-            bext_data[..len].copy_from_slice(&bytes[..len]);
+        // ID3v2 tag for players that read neither bext nor LIST INFO
+        if let Some(tag) = crate::id3::create_tag(metadata)? {
+            chunks.write_chunk(b"id3 ", &tag)?;
         }
 
-        // Originator (32 bytes)
-        if let Some(originator) = metadata
-            .get_field("USER_DESIGNER")
-            .or_else(|| metadata.get_field("BEXT_BWF_ORIGINATOR"))
-        {
-            let bytes = originator.as_bytes();
-            let len = std::cmp::min(bytes.len(), 31);
-            bext_data[256..256 + len].copy_from_slice(&bytes[..len]);
+        // Create cue + LIST adtl chunks for markers
+        if !metadata.get_markers().is_empty() {
+            let cue_data = self.create_cue_chunk_data(metadata)?;
+            chunks.write_chunk(b"cue ", &cue_data)?;
+            let adtl_data = self.create_adtl_list_data(metadata)?;
+            chunks.write_chunk(b"LIST", &adtl_data)?;
         }
 
-        // OriginatorReference (32 bytes)
-        if let Some(orig_ref) = metadata.get_field("BEXT_BWF_ORIGINATOR_REFERENCE") {
-            let bytes = orig_ref.as_bytes();
-            let len = std::cmp::min(bytes.len(), 31);
-            bext_data[288..288 + len].copy_from_slice(&bytes[..len]);
+        // Create image chunks
+        for image in metadata.get_images() {
+            chunks.write_chunk(b"APIC", &image.data)?;
         }
 
-        // OriginationDate (10 bytes)
-        if let Some(date) = metadata.get_field("OriginationDate") {
-            let bytes = date.as_bytes();
-            let len = std::cmp::min(bytes.len(), 10);
-            bext_data[320..320 + len].copy_from_slice(&bytes[..len]);
+        for (id, data) in metadata.get_raw_chunks() {
+            chunks.write_chunk(id, data)?;
         }
 
-        // OriginationTime (8 bytes)
-        if let Some(time) = metadata.get_field("OriginationTime") {
-            let bytes = time.as_bytes();
-            let len = std::cmp::min(bytes.len(), 8);
-            bext_data[330..330 + len].copy_from_slice(&bytes[..len]);
+        Ok(chunks.into_inner())
+    }
+
+    /// One warning per bext text field too long for its fixed-size slot, which
+    /// `create_bext_chunk_data` cuts to fit
+    pub(super) fn bext_truncations(&self, metadata: &Metadata) -> Vec<String> {
+        BEXT_TEXT_FIELDS
+            .iter()
+            .filter_map(|(label, _, capacity, keys)| {
+                let value = bext_text(metadata, keys)?;
+                (value.len() > *capacity).then(|| {
+                    format!(
+                        "bext {} truncated to {} of {} bytes",
+                        label,
+                        capacity,
+                        value.len()
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn create_bext_chunk_data(&self, metadata: &Metadata) -> R<Vec<u8>> {
+        let mut bext_data = vec![0u8; 602]; // BWF spec minimum size
+
+        for (_, offset, capacity, keys) in BEXT_TEXT_FIELDS {
+            if let Some(value) = bext_text(metadata, keys) {
+                let len = value.len().min(capacity);
+                bext_data[offset..offset + len].copy_from_slice(&value.as_bytes()[..len]);
+            }
         }
 
         // TimeReference (8 bytes)
@@ -1279,6 +1970,13 @@ impl WavCodec {
         // Write image chunks if any exist
         self.write_image_chunks(&mut output, metadata)?;
 
+        // Carry over every other chunk (cue, smpl, inst, acid, ...) on the same
+        // side of the data chunk it was found on
+        let (data_start, data_size) = self.find_data_chunk_position(input)?;
+        let data_end = (data_start + padded_size(data_size as u64) as usize).min(input.len());
+        let leading = RiffReader::new(&input[..data_start - 8], HEADER_SIZE);
+        output.write_all(&self.preserved_chunks(leading, metadata)?)?;
+
         // Write data chunk
         output.write_all(b"data")?;
         output.write_u32::<LittleEndian>(data_chunk.len() as u32)?;
@@ -1287,6 +1985,9 @@ impl WavCodec {
             output.write_all(&[0])?; // Padding
         }
 
+        let trailing = RiffReader::new(&input[data_end..], 0);
+        output.write_all(&self.preserved_chunks(trailing, metadata)?)?;
+
         // Update RIFF file size
         let total_size = output.position() as u32 - 8; // Exclude RIFF header itself
         output.seek(SeekFrom::Start(4))?;
@@ -1522,7 +2223,46 @@ impl WavCodec {
     }
 }
 
-fn decode_samples(
+/// Sanity-check a window of 32-bit sample data against the declared format.
+///
+/// Integer PCM reinterpreted as IEEE float lands almost entirely on absurdly
+/// large or tiny magnitudes, while real float audio sits within a few units of
+/// full scale. Returns a description of the mismatch when the data clearly
+/// disagrees with the fmt chunk.
+fn check_format_consistency(data: &[u8], bits_per_sample: u16, is_float: bool) -> Option<String> {
+    if bits_per_sample != BIT_DEPTH_32 {
+        return None;
+    }
+
+    let mut non_zero = 0;
+    let mut plausible_float = 0;
+    for bytes in data.chunks_exact(4).take(FORMAT_CHECK_WINDOW) {
+        let bits = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        if bits == 0 {
+            continue;
+        }
+        non_zero += 1;
+        let magnitude = f32::from_bits(bits).abs();
+        if magnitude.is_finite() && (1e-10..=16.0).contains(&magnitude) {
+            plausible_float += 1;
+        }
+    }
+
+    // Too little signal to judge
+    if non_zero < 16 {
+        return None;
+    }
+
+    if is_float && plausible_float * 10 <= non_zero {
+        Some("fmt declares IEEE float but the data looks like integer PCM".to_string())
+    } else if !is_float && plausible_float * 10 >= non_zero * 9 {
+        Some("fmt declares integer PCM but the data looks like IEEE float".to_string())
+    } else {
+        None
+    }
+}
+
+pub(super) fn decode_samples(
     input: &[u8],
     channels: u16,
     bits_per_sample: u16,
@@ -1571,7 +2311,7 @@ fn decode_samples(
             let val = match bits_per_sample {
                 8 => {
                     let sample = input[sample_idx] as f32;
-                    (sample - U8_OFFSET) / U8_SCALE
+                    (sample - U8_OFFSET) / U8_OFFSET
                 }
                 16 => {
                     let sample =
@@ -1591,13 +2331,17 @@ fn decode_samples(
                     sample as f32 * I24_DIVISOR_RECIP
                 }
                 32 => {
-                    let sample = i32::from_le_bytes([
+                    let bytes = [
                         input[sample_idx],
                         input[sample_idx + 1],
                         input[sample_idx + 2],
                         input[sample_idx + 3],
-                    ]) as f32;
-                    sample * I32_DIVISOR_RECIP
+                    ];
+                    if is_float_format {
+                        f32::from_le_bytes(bytes)
+                    } else {
+                        i32::from_le_bytes(bytes) as f32 * I32_DIVISOR_RECIP
+                    }
                 }
                 _ => 0.0,
             };
@@ -1621,12 +2365,13 @@ fn decode_samples(
 
 // ...existing code...
 
-fn encode_samples<W: Write>(out: &mut W, buffer: &AudioBuffer, bits_per_sample: u16) -> R<()> {
-    // Ensure channel count doesn't exceed available data channels
-    let available_channels = buffer.data.len();
-    let channels = std::cmp::min(buffer.channels as usize, available_channels);
-
-    // Ensure consistent channel count between metadata and actual data
+pub(super) fn encode_samples<W: Write>(
+    out: &mut W,
+    buffer: &AudioBuffer,
+    bits_per_sample: u16,
+) -> R<()> {
+    // Interleave every data channel, matching the count the callers write in fmt
+    let channels = buffer.data.len();
     let frames = buffer.data[0].len();
 
     for i in 0..frames {
@@ -1634,15 +2379,15 @@ fn encode_samples<W: Write>(out: &mut W, buffer: &AudioBuffer, bits_per_sample:
             let sample = buffer.data[ch][i];
             match bits_per_sample {
                 BIT_DEPTH_8 => {
-                    let val = ((sample * U8_SCALE + U8_OFFSET).clamp(0.0, 255.0)) as u8;
-                    out.write_u8(val)?;
+                    let val = quantize_sample(sample, BIT_DEPTH_8) + U8_OFFSET as i32;
+                    out.write_u8(val as u8)?;
                 }
                 BIT_DEPTH_16 => {
-                    let val = (sample.clamp(-1.0, 1.0) * I16_MAX_F) as i16;
+                    let val = quantize_sample(sample, BIT_DEPTH_16) as i16;
                     out.write_i16::<LittleEndian>(val)?;
                 }
                 BIT_DEPTH_24 => {
-                    let val = (sample.clamp(-1.0, 1.0) * I24_MAX_F) as i32;
+                    let val = quantize_sample(sample, BIT_DEPTH_24);
                     let bytes = [
                         (val & BYTE_MASK) as u8,
                         ((val >> 8) & BYTE_MASK) as u8,
@@ -1654,7 +2399,7 @@ fn encode_samples<W: Write>(out: &mut W, buffer: &AudioBuffer, bits_per_sample:
                     if buffer.format == SampleFormat::F32 {
                         out.write_f32::<LittleEndian>(sample)?;
                     } else {
-                        let val = (sample.clamp(-1.0, 1.0) * I32_MAX_F) as i32;
+                        let val = quantize_sample(sample, BIT_DEPTH_32);
                         out.write_i32::<LittleEndian>(val)?;
                     }
                 }
@@ -1666,65 +2411,30 @@ fn encode_samples<W: Write>(out: &mut W, buffer: &AudioBuffer, bits_per_sample:
     Ok(())
 }
 
+fn bext_text(metadata: &Metadata, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| metadata.get_field(key))
+}
+
 fn write_chunk<W: Write>(writer: &mut W, id: &[u8], data: &[u8]) -> R<()> {
-    writer.write_all(id)?;
-    writer.write_u32::<LittleEndian>(data.len() as u32)?;
-    writer.write_all(data)?;
-    if data.len() % 2 == 1 {
-        writer.write_all(&[0])?; // padding
-    }
-    Ok(())
+    RiffWriter::new(writer).write_chunk(id, data)
+}
+
+// adtl labl/note body: cue ID followed by null-terminated text
+fn adtl_text(cue_id: u32, text: &str) -> Vec<u8> {
+    let mut data = cue_id.to_le_bytes().to_vec();
+    data.extend_from_slice(text.as_bytes());
+    data.push(0);
+    data
 }
 
 // Helper function to extract comment from ID3 data
 fn extract_id3_comment(id3_data: &[u8]) -> String {
-    // Check if it's ID3v2
     if id3_data.len() >= 10 && &id3_data[0..3] == b"ID3" {
-        // ID3v2 format - skip header and look for frames
-        let mut offset = 10; // Skip ID3v2 header
-
-        while offset + 10 < id3_data.len() {
-            // ID3v2 frame header: frame_id (4 bytes) + size (4 bytes) + flags (2 bytes)
-            let frame_id = &id3_data[offset..offset + 4];
-
-            // Read frame size (big-endian for ID3v2.3/2.4)
-            let frame_size = ((id3_data[offset + 4] as u32) << 24)
-                | ((id3_data[offset + 5] as u32) << 16)
-                | ((id3_data[offset + 6] as u32) << 8)
-                | (id3_data[offset + 7] as u32);
-
-            if frame_size == 0 || offset + 10 + frame_size as usize > id3_data.len() {
-                break;
-            }
-
-            // Check for comment frames (COMM)
-            if frame_id == b"COMM" {
-                let frame_data = &id3_data[offset + 10..offset + 10 + frame_size as usize];
-
-                if frame_data.len() > 4 {
-                    // Skip encoding byte (1), language (3), and short description
-                    let mut text_start = 4;
-
-                    // Find the end of the short description (null terminated)
-                    while text_start < frame_data.len() && frame_data[text_start] != 0 {
-                        text_start += 1;
-                    }
-                    text_start += 1; // Skip the null terminator
-
-                    if text_start < frame_data.len() {
-                        let text = String::from_utf8_lossy(&frame_data[text_start..])
-                            .trim_end_matches('\0')
-                            .trim()
-                            .to_string();
-
-                        if !text.is_empty() {
-                            return text;
-                        }
-                    }
-                }
-            }
-
-            offset += 10 + frame_size as usize;
+        let mut metadata = Metadata::new();
+        if crate::id3::parse_tag(id3_data, &mut metadata).is_ok()
+            && let Some(comment) = metadata.get_field("TAG_Comment")
+        {
+            return comment;
         }
     } else {
         // Try to parse as raw comment data
@@ -1740,3 +2450,553 @@ fn extract_id3_comment(id3_data: &[u8]) -> String {
 
     String::new()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_rounds_and_clamps_integer_samples() {
+        crate::codecs::tests::assert_encoder_quantizes(
+            &WavCodec,
+            &[
+                SampleFormat::U8,
+                SampleFormat::I16,
+                SampleFormat::I24,
+                SampleFormat::I32,
+            ],
+        );
+    }
+
+    fn test_buffer() -> AudioBuffer {
+        AudioBuffer {
+            sample_rate: 48000,
+            channels: 2,
+            format: SampleFormat::I16,
+            data: vec![vec![0.25; 1000], vec![-0.25; 1000]],
+        }
+    }
+
+    #[test]
+    fn test_fact_limits_float_frames() {
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 2,
+            format: SampleFormat::F32,
+            data: vec![vec![0.5; 1000], vec![-0.5; 1000]],
+        };
+        let encoded = WavCodec.encode(&Some(buffer)).unwrap();
+
+        // Insert a fact chunk after fmt declaring fewer frames than the data chunk holds
+        let fmt_end = HEADER_SIZE + 8 + STANDARD_FMT_CHUNK_SIZE as usize;
+        let mut with_fact = encoded[..fmt_end].to_vec();
+        write_chunk(&mut with_fact, FACT_CHUNK_ID, &990u32.to_le_bytes()).unwrap();
+        with_fact.extend_from_slice(&encoded[fmt_end..]);
+        let riff_size = with_fact.len() as u32 - 8;
+        with_fact[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+        let decoded = WavCodec.decode(&with_fact).unwrap();
+        assert_eq!(decoded.data[0].len(), 990);
+        assert_eq!(decoded.data[0][0], 0.5);
+        assert_eq!(decoded.data[1][989], -0.5);
+    }
+
+    #[test]
+    fn test_labeled_region_roundtrip() {
+        let path = std::env::temp_dir()
+            .join(format!("ffcodex_markers_{}.wav", rand::random::<u32>()))
+            .to_string_lossy()
+            .to_string();
+        WavCodec.encode_file(&Some(test_buffer()), &path).unwrap();
+
+        let region = CueMarker {
+            id: 7,
+            position: 250,
+            length: 400,
+            purpose: "rgn ".to_string(),
+            label: Some("Impact".to_string()),
+            note: Some("Use the tail".to_string()),
+            text: Some("Door slam region".to_string()),
+        };
+        let mut metadata = Metadata::new();
+        metadata.add_marker(region.clone());
+        WavCodec.embed_metadata_to_file(&path, &metadata).unwrap();
+
+        let parsed = WavCodec
+            .parse_metadata(&std::fs::read(&path).unwrap())
+            .unwrap();
+        assert_eq!(parsed.get_markers(), &[region]);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_validate_audio_reports_truncation() {
+        let encoded = WavCodec.encode(&Some(test_buffer())).unwrap();
+
+        let healthy = WavCodec.validate_audio(&encoded).unwrap();
+        assert!(healthy.is_healthy(), "{:?}", healthy.issues);
+        assert!(healthy.decoded);
+
+        let truncated = &encoded[..encoded.len() - 500];
+        let report = WavCodec.validate_audio(truncated).unwrap();
+        assert!(!report.is_healthy());
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|issue| issue.contains("truncated"))
+        );
+    }
+
+    #[test]
+    fn test_mislabeled_format_is_reported() {
+        let signal: Vec<f32> = (0..2000).map(|i| (i as f32 * 0.05).sin() * 0.7).collect();
+        // Offset of the format tag: RIFF header + fmt chunk header
+        let format_tag_offset = HEADER_SIZE + 8;
+
+        let mut int_as_float = WavCodec
+            .encode(&Some(AudioBuffer {
+                sample_rate: 48000,
+                channels: 1,
+                format: SampleFormat::I32,
+                data: vec![signal.clone()],
+            }))
+            .unwrap();
+        int_as_float[format_tag_offset..format_tag_offset + 2]
+            .copy_from_slice(&FORMAT_IEEE_FLOAT.to_le_bytes());
+        let report = WavCodec.validate_audio(&int_as_float).unwrap();
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|i| i.contains("looks like integer"))
+        );
+
+        let mut float_as_int = WavCodec
+            .encode(&Some(AudioBuffer {
+                sample_rate: 48000,
+                channels: 1,
+                format: SampleFormat::F32,
+                data: vec![signal],
+            }))
+            .unwrap();
+        float_as_int[format_tag_offset..format_tag_offset + 2]
+            .copy_from_slice(&FORMAT_PCM.to_le_bytes());
+        let report = WavCodec.validate_audio(&float_as_int).unwrap();
+        assert!(
+            report
+                .issues
+                .iter()
+                .any(|i| i.contains("looks like IEEE float"))
+        );
+
+        assert!(
+            WavCodec
+                .validate_audio(&WavCodec.encode(&Some(test_buffer())).unwrap())
+                .unwrap()
+                .is_healthy()
+        );
+    }
+
+    #[test]
+    fn test_extensible_channel_mask_is_read() {
+        let quad = AudioBuffer {
+            sample_rate: 48000,
+            channels: 4,
+            format: SampleFormat::I16,
+            data: vec![vec![0.25; 100]; 4],
+        };
+        let plain = WavCodec.encode(&Some(quad)).unwrap();
+        assert_eq!(WavCodec.parse_metadata(&plain).unwrap().channel_mask, 0);
+
+        // Swap the 16-byte PCM fmt chunk for its EXTENSIBLE form with a quad mask
+        let fmt_end = HEADER_SIZE + 8 + STANDARD_FMT_CHUNK_SIZE as usize;
+        let mut fmt = plain[HEADER_SIZE + 8..fmt_end].to_vec();
+        fmt[0..2].copy_from_slice(&FORMAT_EXTENSIBLE.to_le_bytes());
+        fmt.extend_from_slice(&22u16.to_le_bytes());
+        fmt.extend_from_slice(&16u16.to_le_bytes());
+        fmt.extend_from_slice(&0x33u32.to_le_bytes());
+        fmt.extend_from_slice(&FORMAT_PCM.to_le_bytes());
+        fmt.extend_from_slice(b"\x00\x00\x00\x00\x10\x00\x80\x00\x00\xAA\x00\x38\x9B\x71");
+
+        let mut extensible = plain[..HEADER_SIZE].to_vec();
+        extensible.extend_from_slice(b"fmt ");
+        extensible.extend_from_slice(&(fmt.len() as u32).to_le_bytes());
+        extensible.extend_from_slice(&fmt);
+        extensible.extend_from_slice(&plain[fmt_end..]);
+        let riff_size = extensible.len() as u32 - 8;
+        extensible[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+        let metadata = WavCodec.parse_metadata(&extensible).unwrap();
+        assert_eq!(metadata.channel_mask, 0x33);
+        assert_eq!(WavCodec.decode(&extensible).unwrap().channels, 4);
+    }
+
+    #[test]
+    fn test_lenient_decode_of_nonstandard_form_type() {
+        let wav = WavCodec.encode(&Some(test_buffer())).unwrap();
+        let mut oddball = wav.clone();
+        oddball[8..12].copy_from_slice(b"WAVX");
+
+        assert!(WavCodec.decode(&oddball).is_err());
+        let decoded = WavCodec.decode_lenient(&oddball).unwrap();
+        assert_eq!(decoded.data, WavCodec.decode(&wav).unwrap().data);
+
+        // Without audio chunks there is nothing to salvage
+        let mut empty = b"RIFF\x04\x00\x00\x00AVI ".to_vec();
+        empty.extend_from_slice(b"LIST\x00\x00\x00\x00");
+        assert!(WavCodec.decode_lenient(&empty).is_err());
+    }
+
+    #[test]
+    fn test_trailing_id3v1_tag() {
+        let mut tag = vec![0u8; ID3V1_TAG_SIZE];
+        tag[0..3].copy_from_slice(b"TAG");
+        tag[3..14].copy_from_slice(b"Rain on Tin");
+        tag[33..40].copy_from_slice(b"Foley B");
+        tag[63..72].copy_from_slice(b"Weather 2");
+        tag[93..97].copy_from_slice(b"1998");
+        tag[97..104].copy_from_slice(b"Roof mic");
+        tag[126] = 7; // ID3v1.1 track
+        tag[127] = 26; // Ambient
+
+        let mut bytes = WavCodec.encode(&Some(test_buffer())).unwrap();
+        bytes.extend_from_slice(&tag);
+
+        let path =
+            std::env::temp_dir().join(format!("ffcodex_id3v1_{}.wav", rand::random::<u32>()));
+        std::fs::write(&path, &bytes).unwrap();
+        let from_file = WavCodec
+            .extract_metadata_from_file(path.to_str().unwrap())
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        for metadata in [WavCodec.parse_metadata(&bytes).unwrap(), from_file] {
+            assert_eq!(metadata.get_field("Title").as_deref(), Some("Rain on Tin"));
+            assert_eq!(metadata.get_field("Artist").as_deref(), Some("Foley B"));
+            assert_eq!(metadata.get_field("Album").as_deref(), Some("Weather 2"));
+            assert_eq!(metadata.get_field("Comment").as_deref(), Some("Roof mic"));
+            assert_eq!(metadata.get_field("Genre").as_deref(), Some("Ambient"));
+            assert_eq!(metadata.get_field("Track").as_deref(), Some("7"));
+            assert_eq!(
+                metadata.get_field("TAG_Title").as_deref(),
+                Some("Rain on Tin")
+            );
+            assert_eq!(
+                metadata.get_field("TAG_Album").as_deref(),
+                Some("Weather 2")
+            );
+            assert_eq!(metadata.get_field("TAG_Genre").as_deref(), Some("Ambient"));
+        }
+    }
+
+    #[test]
+    fn test_metadata_after_large_data_chunk_is_found_by_seeking() {
+        use std::io::{Seek, SeekFrom, Write};
+
+        // An odd-sized 64 MiB data chunk left sparse on disk, followed by iXML
+        let data_size = 64 * 1024 * 1024 + 1;
+        let encoded = WavCodec.encode(&Some(test_buffer())).unwrap();
+        let data = RiffReader::new(&encoded, HEADER_SIZE)
+            .find(|chunk| &chunk.id == DATA_CHUNK_ID)
+            .unwrap();
+        let mut header = encoded[..data.data_start()].to_vec();
+        header[data.offset + 4..data.offset + 8].copy_from_slice(&(data_size as u32).to_le_bytes());
+
+        let path = std::env::temp_dir().join(format!("ffcodex_seek_{}.wav", rand::random::<u32>()));
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.write_all(&header).unwrap();
+        file.set_len((header.len() + data_size + 1) as u64).unwrap();
+        file.seek(SeekFrom::End(0)).unwrap();
+        write_chunk(
+            &mut file,
+            b"iXML",
+            b"<BWFXML>\n<USER>\n<SCENE>Harbor</SCENE>\n</USER>\n</BWFXML>\n",
+        )
+        .unwrap();
+        let riff_size = file.metadata().unwrap().len() as u32 - 8;
+        file.seek(SeekFrom::Start(4)).unwrap();
+        file.write_all(&riff_size.to_le_bytes()).unwrap();
+        drop(file);
+
+        let metadata = WavCodec
+            .extract_metadata_from_file(path.to_str().unwrap())
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(metadata.get_field("USER_SCENE").as_deref(), Some("Harbor"));
+        assert_eq!(metadata.channels, 2);
+    }
+
+    #[test]
+    fn test_split_ixml_chunks_are_merged() {
+        let mut bytes = WavCodec.encode(&Some(test_buffer())).unwrap();
+        let mut chunks = RiffWriter::new(Vec::new());
+        chunks
+            .write_chunk(b"iXML", b"<BWFXML>\n<USER>\n<TAKE>12</TAKE>\n<SCE")
+            .unwrap();
+        chunks
+            .write_chunk(b"iXML", b"NE>Harbor</SCENE>\n</USER>\n</BWFXML>\n")
+            .unwrap();
+        bytes.extend_from_slice(&chunks.into_inner());
+        let riff_size = bytes.len() as u32 - 8;
+        bytes[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+        let metadata = WavCodec.parse_metadata(&bytes).unwrap();
+        assert_eq!(metadata.get_field("USER_TAKE").as_deref(), Some("12"));
+        assert_eq!(metadata.get_field("USER_SCENE").as_deref(), Some("Harbor"));
+    }
+
+    #[test]
+    fn test_moving_odd_data_chunk_keeps_trailing_metadata_aligned() {
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 1,
+            format: SampleFormat::U8,
+            data: vec![(0..1001).map(|i| ((i % 50) as f32 / 50.0) - 0.5).collect()],
+        };
+        let mut wav = WavCodec.encode(&Some(buffer)).unwrap();
+        assert_eq!(wav.len() % 2, 0, "odd data chunk must be padded");
+
+        let mut trailing = Metadata::new();
+        trailing.set_field("Description", "Creaky hinge").unwrap();
+        wav.extend(WavCodec.create_metadata_chunks(&trailing).unwrap());
+        let riff_size = wav.len() as u32 - 8;
+        wav[4..8].copy_from_slice(&riff_size.to_le_bytes());
+        let original_audio = WavCodec.decode(&wav).unwrap().data;
+
+        let path = std::env::temp_dir().join(format!("ffcodex_pad_{}.wav", rand::random::<u32>()));
+        std::fs::write(&path, &wav).unwrap();
+        let fmt_end = (HEADER_SIZE + 8 + STANDARD_FMT_CHUNK_SIZE as usize) as u64;
+
+        // Grow the space before data, then shrink it again
+        for junk_size in [64usize, 16] {
+            let mut file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(&path)
+                .unwrap();
+            let chunks = WavCodec.parse_wav_structure(&mut file).unwrap();
+            let data_start = chunks
+                .iter()
+                .find(|c| &c.id == b"data")
+                .unwrap()
+                .start_position;
+            let mut junk = Vec::new();
+            write_chunk(&mut junk, b"JUNK", &vec![0u8; junk_size]).unwrap();
+            let size_diff = junk.len() as i64 - (data_start - 8 - fmt_end) as i64;
+            WavCodec
+                .update_metadata_with_move(&mut file, &chunks, fmt_end, &junk, size_diff)
+                .unwrap();
+            drop(file);
+
+            let bytes = std::fs::read(&path).unwrap();
+            assert!(WavCodec.validate_audio(&bytes).unwrap().is_healthy());
+            assert_eq!(WavCodec.decode(&bytes).unwrap().data, original_audio);
+            let metadata = WavCodec.parse_metadata(&bytes).unwrap();
+            assert_eq!(
+                metadata.get_field("Description").as_deref(),
+                Some("Creaky hinge")
+            );
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_rf64_promotion_roundtrip() {
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 1,
+            format: SampleFormat::I24,
+            data: vec![vec![0.25; 1001]],
+        };
+        // A zero limit forces the RF64 layout that files over 4 GB get
+        let encoded = WavCodec.encode_riff(&Some(buffer), 0).unwrap();
+        assert_eq!(&encoded[0..4], RF64_CHUNK_ID);
+        assert_eq!(&encoded[4..8], &RF64_SIZE_PLACEHOLDER.to_le_bytes());
+        let ds64 = Ds64::find(&encoded).unwrap();
+        assert_eq!(ds64.riff_size, encoded.len() as u64 - 8);
+        assert_eq!((ds64.data_size, ds64.sample_count), (3003, 1001));
+
+        let decoded = WavCodec.decode(&encoded).unwrap();
+        assert_eq!(decoded.data[0].len(), 1001);
+        let report = WavCodec.validate_audio(&encoded).unwrap();
+        assert!(report.is_healthy(), "{:?}", report.issues);
+
+        let path = std::env::temp_dir().join(format!("ffcodex_rf64_{}.wav", rand::random::<u32>()));
+        std::fs::write(&path, &encoded).unwrap();
+        let info = WavCodec.get_file_info(path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!((info.duration_secs - 1001.0 / 48000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rf64_metadata_embeds_in_memory_and_in_place() {
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 1,
+            format: SampleFormat::I24,
+            data: vec![(0..1001).map(|i| (i % 40) as f32 / 40.0 - 0.5).collect()],
+        };
+        let mut rf64 = WavCodec.encode_riff(&Some(buffer), 0).unwrap();
+        let mut trailing = RiffWriter::new(Vec::new());
+        trailing.write_chunk(b"smpl", &[7; 36]).unwrap();
+        rf64.extend(trailing.into_inner());
+        let riff_size = rf64.len() as u64 - 8;
+        rf64[HEADER_SIZE + 8..HEADER_SIZE + 16].copy_from_slice(&riff_size.to_le_bytes());
+        let original_audio = WavCodec.decode(&rf64).unwrap().data;
+
+        let mut metadata = Metadata::new();
+        metadata.set_field("Description", "Ferry horn").unwrap();
+        metadata.set_field("USER_TAKE", "4").unwrap();
+        let check = |wav: &[u8]| {
+            assert_eq!(&wav[0..4], RF64_CHUNK_ID);
+            let ds64 = Ds64::find(wav).unwrap();
+            assert_eq!(ds64.riff_size, wav.len() as u64 - 8);
+            assert_eq!(ds64.data_size, 3003);
+            assert_eq!(WavCodec.decode(wav).unwrap().data, original_audio);
+            let parsed = WavCodec.parse_metadata(wav).unwrap();
+            assert_eq!(
+                parsed.get_field("DESCRIPTION").as_deref(),
+                Some("Ferry horn")
+            );
+            assert_eq!(parsed.get_field("USER_TAKE").as_deref(), Some("4"));
+            let chunks = Ds64::chunks(Some(ds64), wav);
+            assert!(
+                chunks
+                    .iter()
+                    .any(|chunk| &chunk.id == b"smpl" && chunk.data == [7; 36])
+            );
+        };
+
+        check(&WavCodec.embed_metadata(&rf64, &metadata).unwrap());
+
+        let path =
+            std::env::temp_dir().join(format!("ffcodex_rf64_meta_{}.wav", rand::random::<u32>()));
+        std::fs::write(&path, &rf64).unwrap();
+        let result = WavCodec.embed_metadata_to_file(path.to_str().unwrap(), &metadata);
+        let embedded = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+        check(&embedded);
+    }
+
+    #[test]
+    fn test_removed_description_leaves_bext_empty() {
+        let mut metadata = Metadata::new();
+        metadata.set_field("Description", "Car pass by").unwrap();
+        metadata.set_field("USER_DESIGNER", "Tim").unwrap();
+        assert_eq!(
+            metadata.remove_field("DESCRIPTION").as_deref(),
+            Some("Car pass by")
+        );
+
+        let bext = WavCodec.create_bext_chunk_data(&metadata).unwrap();
+        assert!(bext[..256].iter().all(|&b| b == 0));
+        assert_eq!(&bext[256..259], b"Tim");
+    }
+
+    #[test]
+    fn test_embed_preserves_unknown_chunks_in_order() {
+        let encoded = WavCodec.encode(&Some(test_buffer())).unwrap();
+        let fmt_end = HEADER_SIZE + 8 + STANDARD_FMT_CHUNK_SIZE as usize;
+        let smpl = [7u8; 36];
+        let acid = [3u8; 24];
+        let mut wav = encoded[..fmt_end].to_vec();
+        write_chunk(&mut wav, b"smpl", &smpl).unwrap();
+        wav.extend_from_slice(&encoded[fmt_end..]);
+        write_chunk(&mut wav, b"acid", &acid).unwrap();
+        let riff_size = wav.len() as u32 - 8;
+        wav[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+        let path = std::env::temp_dir().join(format!("ffcodex_keep_{}.wav", rand::random::<u32>()));
+        let path_str = path.to_str().unwrap();
+        std::fs::write(&path, &wav).unwrap();
+
+        for description in ["Loop A", "Loop A, take two"] {
+            let mut metadata = Metadata::new();
+            metadata.set_field("Description", description).unwrap();
+            WavCodec
+                .embed_metadata_to_file(path_str, &metadata)
+                .unwrap();
+
+            let bytes = std::fs::read(&path).unwrap();
+            let ids: Vec<[u8; 4]> = RiffReader::new(&bytes, HEADER_SIZE)
+                .map(|chunk| chunk.id)
+                .filter(|id| id != b"JUNK")
+                .collect();
+            let position = |id: &[u8; 4]| ids.iter().position(|found| found == id).unwrap();
+            assert!(position(b"smpl") < position(b"data"));
+            assert!(position(b"acid") > position(b"data"));
+            assert_eq!(ids.iter().filter(|id| *id == b"smpl").count(), 1);
+            assert_eq!(ids.iter().filter(|id| *id == b"acid").count(), 1);
+            assert_eq!(ids.iter().filter(|id| *id == b"bext").count(), 1);
+
+            let chunk = |id: &[u8; 4]| {
+                RiffReader::new(&bytes, HEADER_SIZE)
+                    .find(|chunk| &chunk.id == id)
+                    .unwrap()
+                    .data
+                    .to_vec()
+            };
+            assert_eq!(chunk(b"smpl"), smpl);
+            assert_eq!(chunk(b"acid"), acid);
+            assert_eq!(
+                WavCodec.decode(&bytes).unwrap().data,
+                WavCodec.decode(&encoded).unwrap().data
+            );
+            assert_eq!(
+                WavCodec
+                    .extract_metadata_from_file(path_str)
+                    .unwrap()
+                    .get_field("Description")
+                    .as_deref(),
+                Some(description)
+            );
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_list_info_roundtrip() {
+        let path = std::env::temp_dir().join(format!("ffcodex_info_{}.wav", rand::random::<u32>()));
+        let path_str = path.to_str().unwrap();
+        std::fs::write(&path, WavCodec.encode(&Some(test_buffer())).unwrap()).unwrap();
+
+        let mut metadata = Metadata::new();
+        metadata.set_field("TrackTitle", "Door Slam").unwrap();
+        metadata.set_field("INFO_ICOP", "2026 Sonic").unwrap();
+        WavCodec
+            .embed_metadata_to_file(path_str, &metadata)
+            .unwrap();
+
+        // Write twice: the old LIST INFO must be replaced, not duplicated
+        let parsed = WavCodec.extract_metadata_from_file(path_str).unwrap();
+        WavCodec.embed_metadata_to_file(path_str, &parsed).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let lists: Vec<Vec<u8>> = RiffReader::new(&bytes, HEADER_SIZE)
+            .filter(|chunk| &chunk.id == b"LIST")
+            .map(|chunk| chunk.data.to_vec())
+            .collect();
+        assert_eq!(lists.len(), 1);
+        let mut expected = b"INFO".to_vec();
+        write_chunk(&mut expected, b"ICOP", b"2026 Sonic\0").unwrap();
+        write_chunk(&mut expected, b"INAM", b"Door Slam\0").unwrap();
+        assert_eq!(lists[0], expected);
+
+        let metadata = WavCodec.extract_metadata_from_file(path_str).unwrap();
+        assert_eq!(
+            metadata.get_field("INFO_INAM").as_deref(),
+            Some("Door Slam")
+        );
+        assert_eq!(
+            metadata.get_field("INFO_ICOP").as_deref(),
+            Some("2026 Sonic")
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -23,42 +23,279 @@ enum SourceFormat {
     Unknown,
 }
 
-/// Safe wrapper around WavPack context for decoding
-pub struct WavpackDecoder {
+/// Which APEv2 tag items carry the text metadata of an encoded WavPack file.
+/// Wrapper data, raw chunks and pictures are written in every mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WavpackTags {
+    /// Native items (Title, Artist, ...) plus a full iXML item
+    #[default]
+    NativeAndIxml,
+    /// Native items only, for tools that read the iXML item as conflicting values
+    NativeOnly,
+    /// The iXML item only
+    IxmlOnly,
+}
+
+/// WavPack compression effort: slower modes give smaller files, and every
+/// mode decodes to the same samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WavpackQuality {
+    /// `CONFIG_FAST_FLAG`, for bulk throughput
+    Fast,
+    /// `CONFIG_HIGH_FLAG`
+    #[default]
+    High,
+    /// `CONFIG_VERY_HIGH_FLAG`, for archives
+    VeryHigh,
+}
+
+impl WavpackQuality {
+    fn config_flag(self) -> c_int {
+        match self {
+            WavpackQuality::Fast => CONFIG_FAST_FLAG,
+            WavpackQuality::High => CONFIG_HIGH_FLAG,
+            WavpackQuality::VeryHigh => CONFIG_VERY_HIGH_FLAG,
+        }
+    }
+}
+
+/// WavPack hybrid mode for `EncodeOptions::wavpack_hybrid`. The `.wv` alone is
+/// lossy; only together with its `.wvc` correction file does it decode to the
+/// original samples, so keep the correction file wherever the source must survive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WavpackHybrid {
+    /// Target bitrate of the `.wv`, in kbps
+    pub bitrate_kbps: f32,
+    /// Also write the `.wvc` correction stream
+    pub create_correction_file: bool,
+}
+
+/// Offset of the first WavPack block when `data` starts with a DOS/PE executable stub
+fn find_sfx_payload(data: &[u8]) -> Option<usize> {
+    if !data.starts_with(b"MZ") {
+        return None;
+    }
+    data.windows(4).position(|window| window == b"wvpk")
+}
+
+/// True when `reorder` names every channel index exactly once
+fn is_channel_permutation(reorder: &[u8]) -> bool {
+    let mut seen = vec![false; reorder.len()];
+    reorder.iter().all(|&ch| match seen.get_mut(ch as usize) {
+        Some(slot) if !*slot => {
+            *slot = true;
+            true
+        }
+        _ => false,
+    })
+}
+
+/// Move stream channel `i` to standard position `reorder[i]`
+fn reorder_to_standard(stream: Vec<Vec<f32>>, reorder: &[u8]) -> Vec<Vec<f32>> {
+    let mut standard = vec![Vec::new(); stream.len()];
+    for (channel, &target) in stream.into_iter().zip(reorder) {
+        standard[target as usize] = channel;
+    }
+    standard
+}
+
+/// Inverse of `reorder_to_standard`: stream channel `i` is standard channel `reorder[i]`
+fn reorder_to_stream(standard: &[Vec<f32>], reorder: &[u8]) -> Vec<Vec<f32>> {
+    reorder
+        .iter()
+        .map(|&source| standard[source as usize].clone())
+        .collect()
+}
+
+/// APEv2 binary item that holds a picture of the given type
+fn cover_art_item(picture_type: PictureType) -> &'static str {
+    match picture_type {
+        PictureType::FrontCover => "Cover Art (Front)",
+        PictureType::BackCover => "Cover Art (Back)",
+        PictureType::Artist => "Cover Art (Artist)",
+        PictureType::Other => "Cover Art (Other)",
+    }
+}
+
+/// Picture type of an APEv2 cover art item; `APIC` is a front cover
+fn cover_art_type(item_name: &str) -> Option<PictureType> {
+    match item_name {
+        "Cover Art (Front)" | "APIC" => Some(PictureType::FrontCover),
+        "Cover Art (Back)" => Some(PictureType::BackCover),
+        "Cover Art (Artist)" => Some(PictureType::Artist),
+        "Cover Art (Other)" => Some(PictureType::Other),
+        _ => None,
+    }
+}
+
+/// The hybrid `.wvc` correction file WavPack expects beside `file_path`
+fn correction_path(file_path: &str) -> std::path::PathBuf {
+    std::path::Path::new(file_path).with_extension("wvc")
+}
+
+/// Contents of the correction file beside `file_path`, if there is one
+fn read_correction(file_path: &str) -> R<Option<Vec<u8>>> {
+    let path = correction_path(file_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(std::fs::read(path)?))
+}
+
+/// Borrowed WavPack stream the library reads through the `memory_*` callbacks
+struct MemoryStream<'a> {
+    cursor: Cursor<&'a [u8]>,
+}
+
+impl MemoryStream<'_> {
+    /// Recover the stream from the `id` pointer the library passes back
+    unsafe fn from_id<'s>(id: *mut c_void) -> &'s mut Cursor<&'s [u8]> {
+        unsafe { &mut (*(id as *mut MemoryStream<'s>)).cursor }
+    }
+}
+
+unsafe extern "C" fn memory_read_bytes(
+    id: *mut c_void,
+    data: *mut c_void,
+    bcount: int32_t,
+) -> int32_t {
+    if data.is_null() || bcount <= 0 {
+        return 0;
+    }
+    let cursor = unsafe { MemoryStream::from_id(id) };
+    let out = unsafe { std::slice::from_raw_parts_mut(data as *mut u8, bcount as usize) };
+    cursor.read(out).unwrap_or(0) as int32_t
+}
+
+unsafe extern "C" fn memory_write_bytes(
+    _id: *mut c_void,
+    _data: *mut c_void,
+    _bcount: int32_t,
+) -> int32_t {
+    0 // read-only
+}
+
+unsafe extern "C" fn memory_get_pos(id: *mut c_void) -> int64_t {
+    unsafe { MemoryStream::from_id(id) }.position() as int64_t
+}
+
+unsafe extern "C" fn memory_set_pos_abs(id: *mut c_void, pos: int64_t) -> c_int {
+    unsafe { memory_set_pos_rel(id, pos, libc::SEEK_SET) }
+}
+
+unsafe extern "C" fn memory_set_pos_rel(id: *mut c_void, delta: int64_t, mode: c_int) -> c_int {
+    let cursor = unsafe { MemoryStream::from_id(id) };
+    let target = match mode {
+        libc::SEEK_SET => match u64::try_from(delta) {
+            Ok(pos) => SeekFrom::Start(pos),
+            Err(_) => return -1,
+        },
+        libc::SEEK_CUR => SeekFrom::Current(delta),
+        libc::SEEK_END => SeekFrom::End(delta),
+        _ => return -1,
+    };
+    if cursor.seek(target).is_ok() { 0 } else { -1 }
+}
+
+unsafe extern "C" fn memory_push_back_byte(id: *mut c_void, c: c_int) -> c_int {
+    let cursor = unsafe { MemoryStream::from_id(id) };
+    if cursor.position() == 0 {
+        return libc::EOF;
+    }
+    cursor.set_position(cursor.position() - 1);
+    c
+}
+
+unsafe extern "C" fn memory_get_length(id: *mut c_void) -> int64_t {
+    unsafe { MemoryStream::from_id(id) }.get_ref().len() as int64_t
+}
+
+unsafe extern "C" fn memory_can_seek(_id: *mut c_void) -> c_int {
+    1
+}
+
+/// Safe wrapper around WavPack context for decoding. The context reads
+/// straight from the borrowed input, so nothing is copied or written to disk.
+pub struct WavpackDecoder<'a> {
     context: *mut WavpackContext,
-    temp_file: Option<std::path::PathBuf>,
+    // The context reads through these, so they live (at fixed addresses) as long as it does
+    _reader: Box<WavpackStreamReader64>,
+    _stream: Box<MemoryStream<'a>>,
+    _correction: Option<Box<MemoryStream<'a>>>,
+    self_extracting: bool,
 }
 
-impl WavpackDecoder {
+impl<'a> WavpackDecoder<'a> {
     /// Create a new decoder from file data
-    pub fn new(data: &[u8]) -> R<Self> {
-        // WavPack C API requires file-based access, so we need to write to a temp file
-        let temp_dir = std::env::temp_dir();
-        let temp_file = temp_dir.join(format!("ffcodex_wavpack_{}.wv", rand::random::<u32>()));
+    pub fn new(data: &'a [u8]) -> R<Self> {
+        // OPEN_NORMALIZE makes the library rescale float streams to +/-1.0 (exponent 127)
+        Self::with_flags(data, OPEN_NORMALIZE | OPEN_WRAPPER | OPEN_TAGS)
+    }
 
-        std::fs::write(&temp_file, data)
-            .map_err(|e| anyhow!("Failed to write temp WavPack file: {}", e))?;
+    /// Create a decoder that reads the hybrid `.wvc` correction stream
+    /// alongside `data`, restoring the lossless audio
+    pub fn with_correction(data: &'a [u8], correction: &'a [u8]) -> R<Self> {
+        Self::open(
+            data,
+            Some(correction),
+            OPEN_NORMALIZE | OPEN_WRAPPER | OPEN_TAGS | OPEN_WVC,
+        )
+    }
 
-        let temp_path_str = temp_file
-            .to_str()
-            .ok_or_else(|| anyhow!("Invalid temp file path"))?;
+    /// Create a decoder with explicit `WavpackOpenFileInput` flags
+    pub fn with_flags(data: &'a [u8], open_flags: c_int) -> R<Self> {
+        Self::open(data, None, open_flags)
+    }
+
+    fn open(data: &'a [u8], correction: Option<&'a [u8]>, open_flags: c_int) -> R<Self> {
+        // Self-extracting files carry an executable stub ahead of the first block;
+        // only the WavPack stream is handed to the library and the stub is never run
+        let (data, self_extracting) = match find_sfx_payload(data) {
+            Some(offset) => {
+                dprintln!("WavPack: skipping {} byte self-extractor stub", offset);
+                (&data[offset..], true)
+            }
+            None => (data, false),
+        };
 
-        let c_filename =
-            CString::new(temp_path_str).map_err(|e| anyhow!("Invalid filename: {}", e))?;
+        // The library reads the stream from memory through callbacks; the 64-bit
+        // reader keeps files over 4 GB seekable
+        let mut stream = Box::new(MemoryStream {
+            cursor: Cursor::new(data),
+        });
+        let mut correction = correction.map(|correction| {
+            Box::new(MemoryStream {
+                cursor: Cursor::new(correction),
+            })
+        });
+        let mut reader = Box::new(WavpackStreamReader64 {
+            read_bytes: Some(memory_read_bytes),
+            write_bytes: Some(memory_write_bytes),
+            get_pos: Some(memory_get_pos),
+            set_pos_abs: Some(memory_set_pos_abs),
+            set_pos_rel: Some(memory_set_pos_rel),
+            push_back_byte: Some(memory_push_back_byte),
+            get_length: Some(memory_get_length),
+            can_seek: Some(memory_can_seek),
+            ..Default::default()
+        });
 
         let mut error_buffer = [0i8; 256];
 
         let context = unsafe {
-            WavpackOpenFileInput(
-                c_filename.as_ptr(),
+            WavpackOpenFileInputEx64(
+                reader.as_mut(),
+                stream.as_mut() as *mut MemoryStream as *mut c_void,
+                correction.as_mut().map_or(ptr::null_mut(), |correction| {
+                    correction.as_mut() as *mut MemoryStream as *mut c_void
+                }),
                 error_buffer.as_mut_ptr(),
-                OPEN_NORMALIZE | OPEN_WRAPPER | OPEN_TAGS,
+                open_flags,
                 0,
             )
         };
 
         if context.is_null() {
-            let _ = std::fs::remove_file(&temp_file);
             let error_str = unsafe { CStr::from_ptr(error_buffer.as_ptr()) };
             return Err(anyhow!(
                 "Failed to open WavPack file: {}",
@@ -68,7 +305,10 @@ impl WavpackDecoder {
 
         Ok(Self {
             context,
-            temp_file: Some(temp_file),
+            _reader: reader,
+            _stream: stream,
+            _correction: correction,
+            self_extracting,
         })
     }
 
@@ -87,6 +327,11 @@ impl WavpackDecoder {
         unsafe { WavpackGetBitsPerSample(self.context) as u32 }
     }
 
+    /// Get the container size of each sample in bytes (e.g. 3 for 20-bit audio)
+    pub fn bytes_per_sample(&self) -> u32 {
+        unsafe { WavpackGetBytesPerSample(self.context) as u32 }
+    }
+
     /// Get the total number of samples per channel
     pub fn total_samples(&self) -> u64 {
         unsafe { WavpackGetNumSamples64(self.context) as u64 }
@@ -98,12 +343,114 @@ impl WavpackDecoder {
         (mode & MODE_FLOAT) != 0
     }
 
+    /// Check if the source was a self-extracting executable (MODE_SFX)
+    pub fn is_self_extracting(&self) -> bool {
+        let mode = unsafe { WavpackGetMode(self.context) };
+        self.self_extracting || (mode & MODE_SFX) != 0
+    }
+
+    /// Channel layout tag and, when the stream stores its channels out of
+    /// standard order, the reorder map: stream channel `i` belongs at `reorder[i]`
+    pub fn channel_layout(&self) -> (u32, Option<Vec<u8>>) {
+        let channels = self.channels() as usize;
+        // The library copies one byte per channel of the layout, at most 255
+        let mut reorder = vec![0u8; 256];
+        let layout_tag = unsafe { WavpackGetChannelLayout(self.context, reorder.as_mut_ptr()) };
+        reorder.truncate(channels);
+        let reordered = is_channel_permutation(&reorder)
+            && reorder.iter().enumerate().any(|(i, &ch)| ch as usize != i);
+        (layout_tag, reordered.then_some(reorder))
+    }
+
+    /// Extension of the file this stream was packed from (ID_ALT_EXTENSION)
+    pub fn file_extension(&self) -> String {
+        let extension = unsafe { WavpackGetFileExtension(self.context) };
+        if extension.is_null() {
+            return String::new();
+        }
+        unsafe { CStr::from_ptr(extension) }
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Take the wrapper bytes the library currently holds and release them
+    fn take_wrapper(&mut self) -> Vec<u8> {
+        let size = unsafe { WavpackGetWrapperBytes(self.context) } as usize;
+        let data = unsafe { WavpackGetWrapperData(self.context) };
+        let wrapper = if size > 0 && !data.is_null() {
+            unsafe { std::slice::from_raw_parts(data, size) }.to_vec()
+        } else {
+            Vec::new()
+        };
+        unsafe { WavpackFreeWrapper(self.context) };
+        wrapper
+    }
+
+    /// Rebuild the original file: header wrapper, raw sample bytes, trailer wrapper
+    ///
+    /// The decoder must be opened with `OPEN_WRAPPER` and without
+    /// `OPEN_NORMALIZE` so samples come back exactly as they were packed.
+    pub fn unpack_original(&mut self) -> R<Vec<u8>> {
+        let header = self.take_wrapper();
+        if !header.windows(4).any(|id| id == b"data" || id == b"SSND") {
+            return Err(anyhow!(
+                "WavPack file does not store its original container header"
+            ));
+        }
+
+        let channels = self.channels() as usize;
+        let bytes_per_sample = unsafe { WavpackGetBytesPerSample(self.context) } as usize;
+        let qmode = unsafe { WavpackGetQualifyMode(self.context) };
+        let big_endian = (qmode & QMODE_BIG_ENDIAN) != 0;
+        let signed_bytes = (qmode & QMODE_SIGNED_BYTES) != 0;
+
+        let mut output = header;
+        const CHUNK_SIZE: usize = 4096;
+        let mut sample_buffer = vec![0i32; CHUNK_SIZE * channels];
+
+        loop {
+            let unpacked = unsafe {
+                WavpackUnpackSamples(
+                    self.context,
+                    sample_buffer.as_mut_ptr(),
+                    CHUNK_SIZE as uint32_t,
+                )
+            } as usize;
+            if unpacked == 0 {
+                break;
+            }
+
+            for &sample in &sample_buffer[..unpacked * channels] {
+                if bytes_per_sample == 1 {
+                    output.push(if signed_bytes {
+                        sample as u8
+                    } else {
+                        (sample + 128) as u8
+                    });
+                } else {
+                    let bytes = &sample.to_le_bytes()[..bytes_per_sample];
+                    if big_endian {
+                        output.extend(bytes.iter().rev());
+                    } else {
+                        output.extend_from_slice(bytes);
+                    }
+                }
+            }
+        }
+
+        unsafe { WavpackSeekTrailingWrapper(self.context) };
+        output.extend(self.take_wrapper());
+        Ok(output)
+    }
+
     /// Decode all samples into an AudioBuffer
     pub fn decode(&mut self) -> R<AudioBuffer> {
         let channels = self.channels();
         let sample_rate = self.sample_rate();
         let total_samples = self.total_samples() as usize;
-        let bits_per_sample = self.bits_per_sample();
+        // Samples are unpacked right-justified in their container, so scale by
+        // the container size (a 20-bit stream decodes like 24-bit)
+        let bits_per_sample = self.bytes_per_sample() * 8;
         let is_float = self.is_float();
 
         if channels == 0 || sample_rate == 0 {
@@ -112,15 +459,7 @@ impl WavpackDecoder {
             ));
         }
 
-        // Determine sample format
-        let sample_format = match (bits_per_sample, is_float) {
-            (8, false) => SampleFormat::U8,
-            (16, false) => SampleFormat::I16,
-            (24, false) => SampleFormat::I24,
-            (32, false) => SampleFormat::I32,
-            (32, true) => SampleFormat::F32,
-            _ => SampleFormat::I16, // Default fallback
-        };
+        let sample_format = self.stream_header().format;
 
         // Initialize output buffer
         let mut audio_data: Vec<Vec<f32>> =
@@ -158,6 +497,11 @@ impl WavpackDecoder {
             samples_decoded += unpacked as usize;
         }
 
+        // Surround streams may store channels out of order; hand them back in standard order
+        if let (_, Some(reorder)) = self.channel_layout() {
+            audio_data = reorder_to_standard(audio_data, &reorder);
+        }
+
         Ok(AudioBuffer {
             sample_rate,
             channels,
@@ -166,6 +510,58 @@ impl WavpackDecoder {
         })
     }
 
+    /// Stream layout of the open file; `total_frames` is `None` when unknown
+    pub fn stream_header(&self) -> StreamHeader {
+        let total = self.total_samples();
+        StreamHeader {
+            sample_rate: self.sample_rate(),
+            channels: self.channels(),
+            format: match (self.bytes_per_sample() * 8, self.is_float()) {
+                (8, false) => SampleFormat::U8,
+                (16, false) => SampleFormat::I16,
+                (24, false) => SampleFormat::I24,
+                (32, false) => SampleFormat::I32,
+                (32, true) => SampleFormat::F32,
+                _ => SampleFormat::I16, // Default fallback
+            },
+            total_frames: (total as i64 >= 0).then_some(total),
+        }
+    }
+
+    /// Move the read position to `frame`, which must be within the stream
+    pub fn seek(&mut self, frame: u64) -> R<()> {
+        if unsafe { WavpackSeekSample64(self.context, frame as i64) } == 0 {
+            return Err(anyhow!("Failed to seek WavPack stream to frame {}", frame));
+        }
+        Ok(())
+    }
+
+    /// Decode up to `frames` frames from the current position, in standard
+    /// channel order; `None` once the stream is exhausted
+    pub fn read_frames(&mut self, frames: usize) -> R<Option<Vec<Vec<f32>>>> {
+        let channels = self.channels();
+        let mut sample_buffer = vec![0i32; frames * channels as usize];
+        let unpacked = unsafe {
+            WavpackUnpackSamples(self.context, sample_buffer.as_mut_ptr(), frames as uint32_t)
+        } as usize;
+        if unpacked == 0 {
+            return Ok(None);
+        }
+
+        let mut block = vec![Vec::with_capacity(unpacked); channels as usize];
+        self.convert_samples(
+            &sample_buffer[..unpacked * channels as usize],
+            &mut block,
+            self.bytes_per_sample() * 8,
+            self.is_float(),
+            channels,
+        )?;
+        if let (_, Some(reorder)) = self.channel_layout() {
+            block = reorder_to_standard(block, &reorder);
+        }
+        Ok(Some(block))
+    }
+
     /// Convert interleaved i32 samples to f32 and de-interleave by channel
     fn convert_samples(
         &self,
@@ -186,16 +582,14 @@ impl WavpackDecoder {
 
                 let sample_i32 = interleaved[sample_idx];
                 let sample_f32 = match (bits_per_sample, is_float) {
-                    (8, false) => {
-                        // 8-bit is typically unsigned in WAV, but WavPack may store it as signed
-                        let unsigned_val = (sample_i32 + 128) as u8;
-                        (unsigned_val as f32 / 127.5) - 1.0
-                    }
+                    // WavPack hands back 8-bit samples signed, already offset from WAV's unsigned bytes
+                    (8, false) => sample_i32 as f32 / 128.0,
                     (16, false) => sample_i32 as f32 / 32768.0,
                     (24, false) => sample_i32 as f32 / 8388608.0,
                     (32, false) => sample_i32 as f32 / 2147483648.0,
                     (32, true) => {
-                        // 32-bit float samples are stored as the bit pattern in the i32
+                        // 32-bit float samples are stored as the bit pattern in the i32;
+                        // OPEN_NORMALIZE has already scaled them to +/-1.0
                         f32::from_bits(sample_i32 as u32)
                     }
                     _ => sample_i32 as f32 / 32768.0, // Default to 16-bit conversion
@@ -209,17 +603,13 @@ impl WavpackDecoder {
     }
 }
 
-impl Drop for WavpackDecoder {
+impl Drop for WavpackDecoder<'_> {
     fn drop(&mut self) {
         if !self.context.is_null() {
             unsafe {
                 WavpackCloseFile(self.context);
             }
         }
-
-        if let Some(ref temp_file) = self.temp_file {
-            let _ = std::fs::remove_file(temp_file);
-        }
     }
 }
 
@@ -228,7 +618,9 @@ pub struct WavpackEncoder {
     context: *mut WavpackContext,
     config: WavpackConfig,
     output_buffer: Vec<u8>,
-    temp_file: Option<std::path::PathBuf>,
+    /// `.wvc` blocks, written only in hybrid mode with a correction file
+    correction_buffer: Vec<u8>,
+    channel_layout: Option<(u32, Option<Vec<u8>>)>,
 }
 
 impl WavpackEncoder {
@@ -239,10 +631,8 @@ impl WavpackEncoder {
         bits_per_sample: u32,
         is_float: bool,
         lossless: bool,
+        quality: WavpackQuality,
     ) -> R<Self> {
-        let temp_dir = std::env::temp_dir();
-        let temp_file = temp_dir.join(format!("ffcodex_wavpack_out_{}.wv", rand::random::<u32>()));
-
         let mut config = WavpackConfig::default();
         config.sample_rate = sample_rate as int32_t;
         config.num_channels = channels as c_int;
@@ -257,10 +647,11 @@ impl WavpackEncoder {
 
         if is_float {
             config.flags |= CONFIG_EXTRA_MODE; // Enable float support
+            // Our float samples are normalized to +/-1.0, which WavPack expresses as exponent 127
+            config.float_norm_exp = 127;
         }
 
-        // Set high quality mode by default
-        config.flags |= CONFIG_HIGH_FLAG;
+        config.flags |= quality.config_flag();
 
         let output_buffer = Vec::new();
 
@@ -268,10 +659,59 @@ impl WavpackEncoder {
             context: ptr::null_mut(),
             config,
             output_buffer,
-            temp_file: Some(temp_file),
+            correction_buffer: Vec::new(),
+            channel_layout: None,
         })
     }
 
+    /// Switch to hybrid (lossy) mode at `bitrate_kbps`. With `create_correction`
+    /// the difference from the source goes to a correction stream, returned by
+    /// `take_correction` after encoding; together they decode losslessly.
+    pub fn set_hybrid(&mut self, bitrate_kbps: f32, create_correction: bool) {
+        self.config.flags |= CONFIG_HYBRID_FLAG | CONFIG_BITRATE_KBPS;
+        self.config.bitrate = bitrate_kbps;
+        if create_correction {
+            self.config.flags |= CONFIG_CREATE_WVC;
+        } else {
+            self.config.flags &= !CONFIG_CREATE_WVC;
+        }
+    }
+
+    /// The `.wvc` correction stream, or None when hybrid mode didn't create one
+    pub fn take_correction(&mut self) -> Option<Vec<u8>> {
+        ((self.config.flags & CONFIG_CREATE_WVC) != 0)
+            .then(|| std::mem::take(&mut self.correction_buffer))
+    }
+
+    /// Compute an MD5 of the audio while encoding and store it in the file
+    /// (`CONFIG_MD5_CHECKSUM`), so it can be verified on decode
+    pub fn set_store_md5(&mut self, enabled: bool) {
+        if enabled {
+            self.config.flags |= CONFIG_MD5_CHECKSUM;
+        } else {
+            self.config.flags &= !CONFIG_MD5_CHECKSUM;
+        }
+    }
+
+    /// Encode on `threads` threads in total, or on every available core when 0.
+    /// The output is byte-identical whatever the count; one thread is the default.
+    pub fn set_threads(&mut self, threads: usize) {
+        let threads = match threads {
+            0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        };
+        // `worker_threads` counts the threads beyond the calling one
+        self.config.worker_threads = (threads - 1).min(i32::MAX as usize) as int32_t;
+    }
+
+    /// Store a channel layout tag and reorder map (see `WavpackDecoder::channel_layout`).
+    /// Buffers passed to `encode` stay in standard order.
+    pub fn set_channel_layout(&mut self, layout_tag: u32, reorder: Option<Vec<u8>>) {
+        if layout_tag != 0 || reorder.is_some() {
+            self.channel_layout = Some((layout_tag, reorder));
+        }
+    }
+
     /// Initialize the encoder and begin encoding
     pub fn init(&mut self) -> R<()> {
         // Create output callback that writes to our buffer
@@ -284,12 +724,13 @@ impl WavpackEncoder {
                 return 0;
             }
 
-            let encoder = unsafe { &mut *(id as *mut WavpackEncoder) };
+            // `id` is the output or the correction buffer, as passed below
+            let output = unsafe { &mut *(id as *mut Vec<u8>) };
             let slice = unsafe { std::slice::from_raw_parts(data as *const u8, bcount as usize) };
 
             // Add some debug info about what's being written
-            let current_len = encoder.output_buffer.len();
-            encoder.output_buffer.extend_from_slice(slice);
+            let current_len = output.len();
+            output.extend_from_slice(slice);
 
             if current_len == 0 {
                 // dprintln!("WavPack output_callback: First write of {} bytes", bcount);
@@ -301,7 +742,7 @@ impl WavpackEncoder {
                 // dprintln!(
                 //     "WavPack output_callback: Large write of {} bytes (total: {})",
                 //     bcount,
-                //     encoder.output_buffer.len()
+                //     output.len()
                 // );
             }
 
@@ -311,8 +752,8 @@ impl WavpackEncoder {
         self.context = unsafe {
             WavpackOpenFileOutput(
                 block_output_callback,
-                self as *mut Self as *mut c_void,
-                ptr::null_mut(),
+                &mut self.output_buffer as *mut Vec<u8> as *mut c_void,
+                &mut self.correction_buffer as *mut Vec<u8> as *mut c_void,
             )
         };
 
@@ -358,6 +799,21 @@ impl WavpackEncoder {
             return Err(anyhow!("Failed to set WavPack configuration"));
         }
 
+        let mut reordered = None;
+        if let Some((layout_tag, reorder)) = &self.channel_layout {
+            let reorder_ptr = reorder.as_ref().map_or(ptr::null(), |r| r.as_ptr());
+            if unsafe { WavpackSetChannelLayout(self.context, *layout_tag, reorder_ptr) } == 0 {
+                return Err(anyhow!("Failed to set WavPack channel layout"));
+            }
+            if let Some(reorder) = reorder {
+                reordered = Some(AudioBuffer {
+                    data: reorder_to_stream(&buffer.data, reorder),
+                    ..buffer.clone()
+                });
+            }
+        }
+        let buffer = reordered.as_ref().unwrap_or(buffer);
+
         // Initialize packing
         let init_result = unsafe { WavpackPackInit(self.context) };
         if init_result == 0 {
@@ -373,9 +829,51 @@ impl WavpackEncoder {
         let mut interleaved_samples = vec![0i32; samples_per_channel * channels];
 
         self.interleave_and_convert_samples(buffer, &mut interleaved_samples)?;
+        let md5 = ((self.config.flags & CONFIG_MD5_CHECKSUM) != 0)
+            .then(|| self.audio_md5(&interleaved_samples));
+        self.pack_samples(&mut interleaved_samples, channels)?;
+
+        if let Some(mut md5) = md5 {
+            // The checksum rides in the block written by the final flush
+            let stored = unsafe { WavpackStoreMD5Sum(self.context, md5.as_mut_ptr()) };
+            if stored == 0 || unsafe { WavpackFlushSamples(self.context) } == 0 {
+                return Err(anyhow!("Failed to store WavPack MD5 checksum"));
+            }
+        }
 
-        // Pack samples in chunks
+        // CRITICAL: Write metadata tags AFTER all audio data has been encoded
+        // This ensures the WavPack header comes first, then audio data, then metadata
+        if metadata.is_some() {
+            dprintln!("WavPack encode: Writing metadata tags to output stream...");
+            let write_result = unsafe { WavpackWriteTag(self.context) };
+            if write_result == 0 {
+                dprintln!("WavPack encode: WARNING - WavpackWriteTag() failed");
+            } else {
+                dprintln!("WavPack encode: WavpackWriteTag() successful");
+            }
+        }
+
+        dprintln!(
+            "WavPack encode: After flush, output buffer has {} bytes",
+            self.output_buffer.len()
+        );
+
+        // Verify metadata is still in the context after encoding
+        let final_text_tags = unsafe { WavpackGetNumTagItems(self.context) };
+        let final_binary_tags = unsafe { WavpackGetNumBinaryTagItems(self.context) };
+        dprintln!(
+            "WavPack encode: Final verification - context has {} text tags and {} binary tags",
+            final_text_tags,
+            final_binary_tags
+        );
+
+        Ok(std::mem::take(&mut self.output_buffer))
+    }
+
+    /// Pack interleaved samples in chunks and flush them to the output
+    fn pack_samples(&mut self, interleaved: &mut [i32], channels: usize) -> R<()> {
         const CHUNK_SIZE: usize = 4096;
+        let samples_per_channel = interleaved.len() / channels;
         let mut sample_pos = 0;
 
         while sample_pos < samples_per_channel {
@@ -386,7 +884,7 @@ impl WavpackEncoder {
             let pack_result = unsafe {
                 WavpackPackSamples(
                     self.context,
-                    interleaved_samples[start_idx..end_idx].as_mut_ptr(),
+                    interleaved[start_idx..end_idx].as_mut_ptr(),
                     samples_to_pack as uint32_t,
                 )
             };
@@ -404,55 +902,108 @@ impl WavpackEncoder {
             return Err(anyhow!("Failed to flush WavPack samples"));
         }
 
-        // CRITICAL: Write metadata tags AFTER all audio data has been encoded
-        // This ensures the WavPack header comes first, then audio data, then metadata
-        if metadata.is_some() {
-            dprintln!("WavPack encode: Writing metadata tags to output stream...");
-            let write_result = unsafe { WavpackWriteTag(self.context) };
-            if write_result == 0 {
-                dprintln!("WavPack encode: WARNING - WavpackWriteTag() failed");
-            } else {
-                dprintln!("WavPack encode: WavpackWriteTag() successful");
-            }
+        Ok(())
+    }
+
+    /// Pack raw samples together with the original file's header and trailer so
+    /// the source container can be restored byte-for-byte on unpack
+    pub fn encode_wrapped(
+        &mut self,
+        interleaved: &mut [i32],
+        extension: &str,
+        file_format: c_uchar,
+        header: &[u8],
+        trailer: &[u8],
+    ) -> R<Vec<u8>> {
+        if self.context.is_null() {
+            return Err(anyhow!("Encoder not initialized"));
         }
 
-        dprintln!(
-            "WavPack encode: After flush, output buffer has {} bytes",
-            self.output_buffer.len()
-        );
+        let extension =
+            CString::new(extension).map_err(|e| anyhow!("Failed to create CString: {}", e))?;
+        unsafe {
+            WavpackSetFileInformation(self.context, extension.as_ptr() as *mut c_char, file_format);
+        }
 
-        // Verify metadata is still in the context after encoding
-        let final_text_tags = unsafe { WavpackGetNumTagItems(self.context) };
-        let final_binary_tags = unsafe { WavpackGetNumBinaryTagItems(self.context) };
-        dprintln!(
-            "WavPack encode: Final verification - context has {} text tags and {} binary tags",
-            final_text_tags,
-            final_binary_tags
-        );
+        let channels = self.config.num_channels as usize;
+        let total_samples = (interleaved.len() / channels) as int64_t;
+        let result = unsafe {
+            WavpackSetConfiguration64(self.context, &mut self.config, total_samples, ptr::null())
+        };
+        if result == 0 {
+            return Err(anyhow!("Failed to set WavPack configuration"));
+        }
+
+        // The header wrapper must be added before packing starts
+        let result = unsafe {
+            WavpackAddWrapper(
+                self.context,
+                header.as_ptr() as *mut c_void,
+                header.len() as uint32_t,
+            )
+        };
+        if result == 0 {
+            return Err(anyhow!("Failed to store WavPack header wrapper"));
+        }
+
+        if unsafe { WavpackPackInit(self.context) } == 0 {
+            return Err(anyhow!("Failed to initialize WavPack packing"));
+        }
+
+        self.pack_samples(interleaved, channels)?;
+
+        if !trailer.is_empty() {
+            let result = unsafe {
+                WavpackAddWrapper(
+                    self.context,
+                    trailer.as_ptr() as *mut c_void,
+                    trailer.len() as uint32_t,
+                )
+            };
+            if result == 0 || unsafe { WavpackFlushSamples(self.context) } == 0 {
+                return Err(anyhow!("Failed to store WavPack trailer wrapper"));
+            }
+        }
 
         Ok(std::mem::take(&mut self.output_buffer))
     }
 
+    /// MD5 of the samples laid out as in a WAV data chunk (little-endian,
+    /// unsigned 8-bit), which is what WavPack checks on unpack
+    fn audio_md5(&self, interleaved: &[i32]) -> [u8; 16] {
+        use md5::{Digest, Md5};
+
+        let bytes_per_sample = self.config.bytes_per_sample as usize;
+        let mut hasher = Md5::new();
+        for &sample in interleaved {
+            if bytes_per_sample == 1 {
+                hasher.update([(sample + 128) as u8]);
+            } else {
+                hasher.update(&sample.to_le_bytes()[..bytes_per_sample]);
+            }
+        }
+        let mut md5 = [0u8; 16];
+        md5.copy_from_slice(&hasher.finalize());
+        md5
+    }
+
     /// Convert f32 samples to i32 and interleave by channels
     fn interleave_and_convert_samples(&self, buffer: &AudioBuffer, output: &mut [i32]) -> R<()> {
         let samples_per_channel = buffer.data[0].len();
         let channels = buffer.channels as usize;
-        let bits_per_sample = self.config.bits_per_sample;
+        // Scale to the container size, matching how the decoder unpacks samples
+        let bits_per_sample = self.config.bytes_per_sample * 8;
         let is_float = (self.config.flags & CONFIG_EXTRA_MODE) != 0;
 
         for i in 0..samples_per_channel {
             for ch in 0..channels {
                 let sample_f32 = buffer.data[ch][i];
                 let sample_i32 = match (bits_per_sample, is_float) {
-                    (8, false) => {
-                        let unsigned_val = ((sample_f32 + 1.0) * 127.5) as u8;
-                        (unsigned_val as i8) as i32
+                    (8 | 16 | 24 | 32, false) => {
+                        super::quantize_sample(sample_f32, bits_per_sample as u16)
                     }
-                    (16, false) => (sample_f32 * 32768.0) as i32,
-                    (24, false) => (sample_f32 * 8388608.0) as i32,
-                    (32, false) => (sample_f32 * 2147483648.0) as i32,
                     (32, true) => sample_f32.to_bits() as i32,
-                    _ => (sample_f32 * 32768.0) as i32, // Default to 16-bit
+                    _ => super::quantize_sample(sample_f32, 16), // Default to 16-bit
                 };
 
                 let output_idx = i * channels + ch;
@@ -473,10 +1024,6 @@ impl Drop for WavpackEncoder {
                 WavpackCloseFile(self.context);
             }
         }
-
-        if let Some(ref temp_file) = self.temp_file {
-            let _ = std::fs::remove_file(temp_file);
-        }
     }
 }
 
@@ -496,12 +1043,12 @@ impl Codec for WvCodec {
     }
 
     fn validate_file_format(&self, data: &[u8]) -> R<()> {
-        // WavPack files start with "wvpk" signature
+        // WavPack files start with "wvpk"; self-extracting ones reach it past their stub
         if data.len() < 4 {
             return Err(anyhow!("File too small to be a valid WavPack file"));
         }
 
-        if &data[0..4] != b"wvpk" {
+        if !data.starts_with(b"wvpk") && find_sfx_payload(data).is_none() {
             return Err(anyhow!("Invalid WavPack file: Missing 'wvpk' signature"));
         }
 
@@ -572,14 +1119,38 @@ impl Codec for WvCodec {
         Ok(FileInfo {
             path: file_path.to_string(),
             size: file_size,
-            sample_rate: sample_rate as u16,
+            sample_rate,
             channels,
             bit_depth: bit_depth as u16,
             duration,
+            duration_secs: duration_seconds,
             description,
         })
     }
 
+    fn is_lossless(&self, file_path: &str) -> R<bool> {
+        let file = std::fs::File::open(file_path)?;
+        let mapped_file = unsafe { MmapOptions::new().map(&file)? };
+        self.validate_file_format(&mapped_file)?;
+
+        // A hybrid file decodes losslessly when its correction file is beside it
+        let correction = read_correction(file_path)?;
+        let decoder = match &correction {
+            Some(correction) => WavpackDecoder::with_correction(&mapped_file, correction)?,
+            None => WavpackDecoder::new(&mapped_file)?,
+        };
+        let mode = unsafe { WavpackGetMode(decoder.context) };
+        Ok((mode & MODE_LOSSLESS) != 0)
+    }
+
+    fn tag_name_for(&self, logical: &str) -> String {
+        self.map_to_wavpack_key(&self.normalize_wavpack_key(logical))
+    }
+
+    fn logical_for(&self, tag: &str) -> String {
+        self.normalize_wavpack_key(tag)
+    }
+
     fn decode(&self, input: &[u8]) -> R<AudioBuffer> {
         self.validate_file_format(input)?;
 
@@ -588,6 +1159,50 @@ impl Codec for WvCodec {
         decoder.decode()
     }
 
+    /// Reads the `.wvc` beside a hybrid file too, so the pair decodes losslessly
+    fn decode_file(&self, file_path: &str) -> R<AudioBuffer> {
+        let file = std::fs::File::open(file_path)?;
+        let mapped_file = unsafe { MmapOptions::new().map(&file)? };
+        match read_correction(file_path)? {
+            Some(correction) => self.decode_with_correction(&mapped_file, &correction),
+            None => self.decode(&mapped_file),
+        }
+    }
+
+    fn decode_stream<'a>(&self, input: &'a [u8]) -> R<(StreamHeader, FrameStream<'a>)> {
+        self.validate_file_format(input)?;
+
+        let mut decoder = WavpackDecoder::new(input)?;
+        let header = decoder.stream_header();
+        if header.channels == 0 || header.sample_rate == 0 {
+            return Err(anyhow!(
+                "Invalid WavPack file: zero channels or sample rate"
+            ));
+        }
+        let blocks =
+            std::iter::from_fn(move || decoder.read_frames(STREAM_BLOCK_FRAMES).transpose());
+        Ok((header, Box::new(blocks)))
+    }
+
+    fn decode_range(&self, input: &[u8], start: u64, end: u64) -> R<AudioBuffer> {
+        self.validate_file_format(input)?;
+
+        let mut decoder = WavpackDecoder::new(input)?;
+        let header = decoder.stream_header();
+        // Seeking to or past the end fails, and the range is empty there anyway
+        let len = if header.total_frames.is_some_and(|total| start >= total) {
+            0
+        } else {
+            if start > 0 {
+                decoder.seek(start)?;
+            }
+            end.saturating_sub(start)
+        };
+        let blocks =
+            std::iter::from_fn(move || decoder.read_frames(STREAM_BLOCK_FRAMES).transpose());
+        crate::collect_range(header, Box::new(blocks), 0, len)
+    }
+
     fn encode(&self, buffer: &Option<AudioBuffer>) -> R<Vec<u8>> {
         self.encode_with_metadata(buffer, &None)
     }
@@ -690,18 +1305,19 @@ impl Codec for WvCodec {
                             binary_data.len()
                         );
 
-                        match item_name.as_ref() {
-                            "Cover Art (Front)" | "APIC" => {
+                        match cover_art_type(&item_name) {
+                            Some(picture_type) => {
                                 // Image data
                                 let mime_type = detect_image_mime_type(&binary_data);
                                 let image_chunk = ImageChunk::new(
                                     mime_type,
                                     "Cover Art".to_string(),
                                     binary_data,
-                                );
+                                )
+                                .with_picture_type(picture_type);
                                 metadata.add_image(image_chunk);
                             }
-                            _ => {
+                            None => {
                                 // For other binary tags, try to parse as text if possible
                                 if let Ok(text_data) = String::from_utf8(binary_data)
                                     && !text_data.trim().is_empty()
@@ -715,6 +1331,11 @@ impl Codec for WvCodec {
             }
         }
 
+        // Legacy files may carry only an ID3v1 trailer; it fills in what the native tags lack
+        if input.len() >= ID3V1_TAG_SIZE {
+            metadata.parse_id3v1(&input[input.len() - ID3V1_TAG_SIZE..])?;
+        }
+
         // Extract format information for metadata completion
         let channels = unsafe { WavpackGetNumChannels(decoder.context) };
         let sample_rate = unsafe { WavpackGetSampleRate(decoder.context) };
@@ -750,60 +1371,167 @@ impl Codec for WvCodec {
     }
 
     fn embed_metadata_to_file(&self, file_path: &str, metadata: &Metadata) -> R<()> {
-        // For WavPack, we need to decode, add metadata, and re-encode
-        let file = std::fs::File::open(file_path)?;
-        let mapped_file = unsafe { MmapOptions::new().map(&file)? };
+        self.embed_metadata_with_options(file_path, metadata, &EncodeOptions::default())
+    }
 
-        // First, decode the WavPack file to get the audio data
-        let audio_buffer = self.decode(&mapped_file)?;
+    fn embed_metadata(&self, input: &[u8], metadata: &Metadata) -> R<Vec<u8>> {
+        let (encoded, _) =
+            self.reencode_with_metadata(input, None, metadata, &EncodeOptions::default())?;
+        Ok(encoded)
+    }
+}
 
-        // Create a new encoder with the same parameters
-        let sample_rate = audio_buffer.sample_rate;
-        let channels = audio_buffer.channels;
-        let bits_per_sample = match audio_buffer.format {
-            SampleFormat::U8 => 8,
-            SampleFormat::I16 => 16,
-            SampleFormat::I24 => 24,
-            SampleFormat::I32 => 32,
-            SampleFormat::F32 => 32,
+impl WvCodec {
+    /// Compress a WAV file losslessly, storing its header and trailing chunks
+    /// so `restore_original` can reproduce it byte-for-byte
+    pub fn wrap_wav(&self, input: &[u8]) -> R<Vec<u8>> {
+        if input.len() < 12 || &input[0..4] != b"RIFF" || &input[8..12] != b"WAVE" {
+            return Err(anyhow!("Not a RIFF/WAVE file"));
+        }
+
+        let mut fmt: Option<&[u8]> = None;
+        let mut pos = 12;
+        let (data_start, data_end) = loop {
+            if pos + 8 > input.len() {
+                return Err(anyhow!("WAV file has no data chunk"));
+            }
+            let chunk_id = &input[pos..pos + 4];
+            let chunk_size = u32::from_le_bytes(input[pos + 4..pos + 8].try_into()?) as usize;
+            let body = pos + 8;
+            if chunk_id == b"data" {
+                break (body, (body + chunk_size).min(input.len()));
+            }
+            if body + chunk_size > input.len() {
+                return Err(anyhow!("WAV chunk extends past end of file"));
+            }
+            if chunk_id == b"fmt " {
+                fmt = Some(&input[body..body + chunk_size]);
+            }
+            pos = body + chunk_size + (chunk_size & 1);
         };
-        let is_float = audio_buffer.format == SampleFormat::F32;
-        let lossless = true;
-        let total_samples = audio_buffer.data[0].len() as u64;
 
-        let mut encoder =
-            WavpackEncoder::new(sample_rate, channels, bits_per_sample, is_float, lossless)?;
+        let fmt = fmt
+            .filter(|fmt| fmt.len() >= 16)
+            .ok_or_else(|| anyhow!("WAV file has no valid fmt chunk"))?;
+        let format_tag = u16::from_le_bytes([fmt[0], fmt[1]]);
+        let channels = u16::from_le_bytes([fmt[2], fmt[3]]);
+        let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+        let block_align = u16::from_le_bytes([fmt[12], fmt[13]]) as usize;
+        let bits_per_sample = u16::from_le_bytes([fmt[14], fmt[15]]) as u32;
+        let is_float = format_tag == 3 || (format_tag == 0xFFFE && fmt.len() >= 26 && fmt[24] == 3);
+
+        if channels == 0 || block_align % channels as usize != 0 {
+            return Err(anyhow!("Invalid WAV block alignment"));
+        }
+        let bytes_per_sample = block_align / channels as usize;
+        if !(1..=4).contains(&bytes_per_sample) {
+            return Err(anyhow!(
+                "Unsupported WAV sample size: {} bytes",
+                bytes_per_sample
+            ));
+        }
 
-        encoder.init()?;
+        let data = &input[data_start..data_end];
+        let mut samples: Vec<i32> = data
+            .chunks_exact(bytes_per_sample)
+            .map(|bytes| match bytes_per_sample {
+                1 => bytes[0] as i32 - 128,
+                2 => i16::from_le_bytes([bytes[0], bytes[1]]) as i32,
+                3 => i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8,
+                _ => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            })
+            .collect();
+        samples.truncate(samples.len() - samples.len() % channels as usize);
 
-        // Add metadata to the encoder context before encoding
-        self.add_metadata_to_encoder(&mut encoder, metadata)?;
+        let mut encoder = WavpackEncoder::new(
+            sample_rate,
+            channels,
+            bits_per_sample,
+            is_float,
+            true,
+            WavpackQuality::default(),
+        )?;
+        encoder.config.bytes_per_sample = bytes_per_sample as c_int;
+        encoder.init()?;
 
-        // Verify metadata was added to context
-        let text_tags = unsafe { WavpackGetNumTagItems(encoder.context) };
-        let binary_tags = unsafe { WavpackGetNumBinaryTagItems(encoder.context) };
-        dprintln!(
-            "WavPack embed_metadata_to_file: After adding metadata - context has {} text tags and {} binary tags",
-            text_tags,
-            binary_tags
-        );
+        // Anything after the whole frames (partial frames, pad byte, trailing chunks) is trailer
+        let trailer_start = data_start + samples.len() * bytes_per_sample;
+        encoder.encode_wrapped(
+            &mut samples,
+            "wav",
+            WP_FORMAT_WAV,
+            &input[..data_start],
+            &input[trailer_start..],
+        )
+    }
 
-        // Encode with the metadata
-        let result = encoder.encode(&audio_buffer, total_samples, &Some(metadata))?;
+    /// Unpack a `.wv` back into the file it was compressed from, returning the
+    /// restored bytes and the original file extension
+    pub fn restore_original(&self, input: &[u8]) -> R<(Vec<u8>, String)> {
+        self.validate_file_format(input)?;
 
-        // Write the result back to the file
-        std::fs::write(file_path, result)?;
-        Ok(())
+        let mut decoder = WavpackDecoder::with_flags(input, OPEN_WRAPPER)?;
+        let extension = decoder.file_extension();
+        let original = decoder.unpack_original()?;
+        Ok((original, extension))
     }
-}
 
-impl WvCodec {
     /// Encode with optional metadata - avoids double encoding for WavPack
     pub fn encode_with_metadata(
         &self,
         buffer: &Option<AudioBuffer>,
         metadata: &Option<&Metadata>,
     ) -> R<Vec<u8>> {
+        self.encode_with_options(buffer, metadata, &EncodeOptions::default())
+    }
+
+    /// `encode_with_metadata` honouring the WavPack settings in `options`
+    /// (e.g. `store_md5`)
+    pub fn encode_with_options(
+        &self,
+        buffer: &Option<AudioBuffer>,
+        metadata: &Option<&Metadata>,
+        options: &EncodeOptions,
+    ) -> R<Vec<u8>> {
+        Ok(self.encode_with_correction(buffer, metadata, options)?.0)
+    }
+
+    /// Encode in hybrid mode at `bitrate_kbps`. The `.wv` stream is lossy; with
+    /// `create_correction_file` the `.wvc` stream is returned as well, and
+    /// decoding the two together restores the original samples.
+    pub fn encode_hybrid(
+        &self,
+        buffer: &Option<AudioBuffer>,
+        bitrate_kbps: f32,
+        create_correction_file: bool,
+    ) -> R<(Vec<u8>, Option<Vec<u8>>)> {
+        let options = EncodeOptions {
+            wavpack_hybrid: Some(WavpackHybrid {
+                bitrate_kbps,
+                create_correction_file,
+            }),
+            ..Default::default()
+        };
+        self.encode_with_correction(buffer, &None, &options)
+    }
+
+    /// Decode a hybrid stream together with its `.wvc` correction stream,
+    /// restoring the lossless audio
+    pub fn decode_with_correction(&self, input: &[u8], correction: &[u8]) -> R<AudioBuffer> {
+        self.validate_file_format(input)?;
+
+        let mut decoder = WavpackDecoder::with_correction(input, correction)?;
+        decoder.decode()
+    }
+
+    /// `encode_with_options`, also returning the `.wvc` correction stream when
+    /// `options.wavpack_hybrid` asks for one
+    pub fn encode_with_correction(
+        &self,
+        buffer: &Option<AudioBuffer>,
+        metadata: &Option<&Metadata>,
+        options: &EncodeOptions,
+    ) -> R<(Vec<u8>, Option<Vec<u8>>)> {
         let Some(buffer) = buffer else {
             return Err(anyhow!("Cannot encode None AudioBuffer"));
         };
@@ -831,18 +1559,32 @@ impl WvCodec {
         let total_samples = buffer.data[0].len() as u64;
 
         // Create and initialize encoder
-        let mut encoder =
-            WavpackEncoder::new(sample_rate, channels, bits_per_sample, is_float, lossless)?;
+        let mut encoder = WavpackEncoder::new(
+            sample_rate,
+            channels,
+            bits_per_sample,
+            is_float,
+            lossless,
+            options.wavpack_quality,
+        )?;
+        if let Some(threads) = options.wavpack_threads {
+            encoder.set_threads(threads);
+        }
+        encoder.set_store_md5(options.store_md5);
+        if let Some(hybrid) = options.wavpack_hybrid {
+            encoder.set_hybrid(hybrid.bitrate_kbps, hybrid.create_correction_file);
+        }
 
         encoder.init()?;
 
         // Add metadata to encoder context if provided
         if let Some(metadata) = metadata {
-            self.add_metadata_to_encoder(&mut encoder, metadata)?;
+            self.add_metadata_to_encoder(&mut encoder, metadata, options.wavpack_tags)?;
         }
 
         // Encode the audio buffer with metadata
-        encoder.encode(buffer, total_samples, metadata)
+        let encoded = encoder.encode(buffer, total_samples, metadata)?;
+        Ok((encoded, encoder.take_correction()))
     }
 
     fn parse_metadata(&self, input: &[u8]) -> R<Metadata> {
@@ -949,17 +1691,18 @@ impl WvCodec {
                     data_buffer.truncate(data_len as usize);
 
                     // Check if this looks like image data
-                    if item_name.to_lowercase().contains("cover")
-                        || item_name.to_lowercase().contains("art")
-                        || item_name.to_lowercase().contains("picture")
-                        || item_name.to_lowercase().contains("apic")
-                    {
+                    let picture_type = cover_art_type(&item_name).or_else(|| {
+                        let name = item_name.to_lowercase();
+                        (name.contains("cover")
+                            || name.contains("art")
+                            || name.contains("picture")
+                            || name.contains("apic"))
+                        .then_some(PictureType::Other)
+                    });
+                    if let Some(picture_type) = picture_type {
                         let mime_type = detect_image_mime_type(&data_buffer);
-                        let image = ImageChunk {
-                            mime_type,
-                            description: item_name,
-                            data: data_buffer,
-                        };
+                        let image = ImageChunk::new(mime_type, item_name, data_buffer)
+                            .with_picture_type(picture_type);
                         metadata.add_image(image);
                     }
                 }
@@ -1003,51 +1746,120 @@ impl WvCodec {
         Ok(metadata)
     }
 
-    fn embed_metadata_to_file(&self, file_path: &str, metadata: &Metadata) -> R<()> {
-        // For WavPack, we need to decode, add metadata, and re-encode
+    /// `embed_metadata_to_file` honouring the WavPack settings in `options`
+    /// (e.g. `wavpack_tags`). A hybrid file's `.wvc` is rewritten with it.
+    pub fn embed_metadata_with_options(
+        &self,
+        file_path: &str,
+        metadata: &Metadata,
+        options: &EncodeOptions,
+    ) -> R<()> {
         let file = std::fs::File::open(file_path)?;
         let mapped_file = unsafe { MmapOptions::new().map(&file)? };
+        let correction = read_correction(file_path)?;
+        let (encoded, correction) =
+            self.reencode_with_metadata(&mapped_file, correction.as_deref(), metadata, options)?;
+        drop(mapped_file);
+
+        // Both files go through temp files beside them, so a failed write
+        // leaves the pair as it was
+        let path = std::path::Path::new(file_path);
+        let temp = path.with_extension(format!("ffcodex-{:08x}.wv", rand::random::<u32>()));
+        let temp_correction = temp.with_extension("wvc");
+        let result = (|| -> R<()> {
+            std::fs::write(&temp, encoded)?;
+            if let Some(correction) = &correction {
+                std::fs::write(&temp_correction, correction)?;
+            }
+            std::fs::rename(&temp, path)?;
+            if correction.is_some() {
+                std::fs::rename(&temp_correction, correction_path(file_path))?;
+            }
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temp);
+            let _ = std::fs::remove_file(&temp_correction);
+        }
+        result
+    }
 
-        // First, decode the WavPack file to get the audio data
-        let audio_buffer = self.decode(&mapped_file)?;
-
-        // Create a new encoder with the same parameters
+    /// For WavPack, embedding means decoding, adding metadata, and re-encoding.
+    ///
+    /// A hybrid file stays hybrid at about its current bitrate and gets a new
+    /// correction stream, which needs the old one in `correction`: re-encoding
+    /// the lossy audio alone would lose quality again and orphan the `.wvc`.
+    fn reencode_with_metadata(
+        &self,
+        input: &[u8],
+        correction: Option<&[u8]>,
+        metadata: &Metadata,
+        options: &EncodeOptions,
+    ) -> R<(Vec<u8>, Option<Vec<u8>>)> {
+        self.validate_file_format(input)?;
+        let mut source = match correction {
+            Some(correction) => WavpackDecoder::with_correction(input, correction)?,
+            None => WavpackDecoder::new(input)?,
+        };
+        let mode = unsafe { WavpackGetMode(source.context) };
+        let hybrid = (mode & MODE_HYBRID) != 0;
+        if hybrid && (mode & MODE_WVC) == 0 {
+            return Err(anyhow!(
+                "Re-embedding metadata into hybrid WavPack needs its .wvc correction file"
+            ));
+        }
+        // The main stream's bitrate alone, as the hybrid setting counts it
+        let bitrate_kbps = unsafe { WavpackGetAverageBitrate(source.context, 0) } / 1000.0;
+
+        // Re-encode with the stream's original sample layout rather than one derived
+        // from the decoded buffer, so a tag edit never changes the bit depth
+        let bits_per_sample = source.bits_per_sample();
+        let bytes_per_sample = source.bytes_per_sample();
+        let is_float = source.is_float();
+        let channel_mask = unsafe { WavpackGetChannelMask(source.context) };
+        let (layout_tag, reorder) = source.channel_layout();
+        let audio_buffer = source.decode()?;
+        drop(source);
         let sample_rate = audio_buffer.sample_rate;
         let channels = audio_buffer.channels;
-        let bits_per_sample = match audio_buffer.format {
-            SampleFormat::U8 => 8,
-            SampleFormat::I16 => 16,
-            SampleFormat::I24 => 24,
-            SampleFormat::I32 => 32,
-            SampleFormat::F32 => 32,
-        };
-        let is_float = audio_buffer.format == SampleFormat::F32;
         let lossless = true;
         let total_samples = audio_buffer.data[0].len() as u64;
 
-        let mut encoder =
-            WavpackEncoder::new(sample_rate, channels, bits_per_sample, is_float, lossless)?;
+        let mut encoder = WavpackEncoder::new(
+            sample_rate,
+            channels,
+            bits_per_sample,
+            is_float,
+            lossless,
+            options.wavpack_quality,
+        )?;
+        if let Some(threads) = options.wavpack_threads {
+            encoder.set_threads(threads);
+        }
+        encoder.config.bytes_per_sample = bytes_per_sample as c_int;
+        encoder.config.channel_mask = channel_mask;
+        encoder.set_channel_layout(layout_tag, reorder);
+        if hybrid {
+            encoder.set_hybrid(bitrate_kbps as f32, true);
+        }
 
         encoder.init()?;
 
         // Add metadata to the encoder context before encoding
-        self.add_metadata_to_encoder(&mut encoder, metadata)?;
+        self.add_metadata_to_encoder(&mut encoder, metadata, options.wavpack_tags)?;
 
         // Verify metadata was added to context
         let text_tags = unsafe { WavpackGetNumTagItems(encoder.context) };
         let binary_tags = unsafe { WavpackGetNumBinaryTagItems(encoder.context) };
         dprintln!(
-            "WavPack embed_metadata_to_file: After adding metadata - context has {} text tags and {} binary tags",
+            "WavPack embed: After adding metadata - context has {} text tags and {} binary tags",
             text_tags,
             binary_tags
         );
 
         // Encode with the metadata
-        let result = encoder.encode(&audio_buffer, total_samples, &Some(metadata))?;
-
-        // Write the result back to the file
-        std::fs::write(file_path, result)?;
-        Ok(())
+        let encoded = encoder.encode(&audio_buffer, total_samples, &Some(metadata))?;
+        Ok((encoded, encoder.take_correction()))
     }
 }
 
@@ -1212,7 +2024,7 @@ impl WvCodec {
     fn extract_wavpack_technical_metadata(
         &self,
         metadata: &mut Metadata,
-        decoder: &WavpackDecoder,
+        decoder: &WavpackDecoder<'_>,
     ) -> R<()> {
         // Get file format information
         let mode = unsafe { WavpackGetMode(decoder.context) };
@@ -1234,6 +2046,19 @@ impl WvCodec {
         if (mode & MODE_FAST) != 0 {
             metadata.set_field("WAVPACK_FAST_MODE", "true")?;
         }
+        if decoder.is_self_extracting() {
+            metadata.set_field("WAVPACK_SELF_EXTRACTING", "true")?;
+        }
+
+        // Original sample layout, used to keep the bit depth on re-encode
+        metadata.set_field(
+            "WAVPACK_BITS_PER_SAMPLE",
+            &decoder.bits_per_sample().to_string(),
+        )?;
+        metadata.set_field(
+            "WAVPACK_BYTES_PER_SAMPLE",
+            &decoder.bytes_per_sample().to_string(),
+        )?;
 
         // Get version information
         let version = unsafe { WavpackGetVersion(decoder.context) };
@@ -1278,6 +2103,15 @@ impl WvCodec {
         let channel_mask = unsafe { WavpackGetChannelMask(decoder.context) };
         if channel_mask != 0 {
             metadata.set_field("WAVPACK_CHANNEL_MASK", &format!("{:#x}", channel_mask))?;
+            metadata.channel_mask = channel_mask as u32;
+        }
+        let (layout_tag, reorder) = decoder.channel_layout();
+        if layout_tag != 0 {
+            metadata.set_field("WAVPACK_CHANNEL_LAYOUT", &format!("{:#x}", layout_tag))?;
+        }
+        if let Some(reorder) = reorder {
+            let order: Vec<String> = reorder.iter().map(|ch| ch.to_string()).collect();
+            metadata.set_field("WAVPACK_CHANNEL_REORDER", &order.join(","))?;
         }
 
         // Check for lossy blocks
@@ -1559,7 +2393,12 @@ impl WvCodec {
     }
 
     /// Add metadata to encoder before encoding
-    fn add_metadata_to_encoder(&self, encoder: &mut WavpackEncoder, metadata: &Metadata) -> R<()> {
+    fn add_metadata_to_encoder(
+        &self,
+        encoder: &mut WavpackEncoder,
+        metadata: &Metadata,
+        tags: WavpackTags,
+    ) -> R<()> {
         if encoder.context.is_null() {
             return Err(anyhow!("Encoder not initialized"));
         }
@@ -1639,8 +2478,9 @@ impl WvCodec {
             }
         }
 
-        // Restore MD5 checksum if available
-        if let Some(md5_hex) = metadata.get_field("WAVPACK_MD5")
+        // Restore MD5 checksum if available, unless the encoder computes a fresh one
+        if (encoder.config.flags & CONFIG_MD5_CHECKSUM) == 0
+            && let Some(md5_hex) = metadata.get_field("WAVPACK_MD5")
             && md5_hex.len() == 32
         {
             // 16 bytes * 2 chars per byte
@@ -1675,8 +2515,8 @@ impl WvCodec {
 
         // Add text fields from the hashmap
         for (key, value) in metadata.get_all_fields().iter() {
-            // Skip internal WavPack metadata fields
-            if key.starts_with("WAVPACK_") {
+            // Skip technical stream fields, and all of them when only iXML is wanted
+            if tags == WavpackTags::IxmlOnly || super::is_technical_field(key) {
                 continue;
             }
 
@@ -1723,7 +2563,7 @@ impl WvCodec {
         }
 
         // Create and add iXML from all metadata fields
-        if !metadata.get_all_fields().is_empty() {
+        if tags != WavpackTags::NativeOnly && metadata.len() > 0 {
             let ixml_content = self.create_ixml(metadata)?;
             let c_key = CString::new("iXML").map_err(|_| anyhow!("Invalid metadata key"))?;
             let c_value = CString::new(ixml_content.as_str())
@@ -1745,15 +2585,31 @@ impl WvCodec {
             }
         }
 
+        // Raw chunks become binary tags named after the chunk id
+        for (id, data) in metadata.get_raw_chunks() {
+            let item_name = String::from_utf8_lossy(id).trim_end().to_string();
+            let c_item =
+                CString::new(item_name.as_str()).map_err(|_| anyhow!("Invalid item name"))?;
+            let result = unsafe {
+                WavpackAppendBinaryTagItem(
+                    encoder.context,
+                    c_item.as_ptr(),
+                    data.as_ptr() as *const c_char,
+                    data.len() as c_int,
+                )
+            };
+            if result != 1 {
+                dprintln!(
+                    "Warning: Failed to add raw chunk '{}' - result: {}",
+                    item_name,
+                    result
+                );
+            }
+        }
+
         // Add image data as binary tags
         for image in metadata.get_images() {
-            let item_name = if image.description().to_lowercase().contains("cover")
-                || image.description().to_lowercase().contains("art")
-            {
-                "Cover Art"
-            } else {
-                "Picture"
-            };
+            let item_name = cover_art_item(image.picture_type());
 
             let c_item = CString::new(item_name).map_err(|_| anyhow!("Invalid item name"))?;
 
@@ -1831,3 +2687,528 @@ impl WvCodec {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codecs::WavCodec;
+
+    /// Repeatable white noise in [-0.5, 0.5) from a linear congruential generator
+    fn noise(seed: u32) -> impl FnMut() -> f32 {
+        let mut seed = seed;
+        move || {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) as f32 / (1 << 24) as f32 - 0.5
+        }
+    }
+
+    #[test]
+    fn test_encode_rounds_and_clamps_integer_samples() {
+        crate::codecs::tests::assert_encoder_quantizes(
+            &WvCodec,
+            &[
+                SampleFormat::U8,
+                SampleFormat::I16,
+                SampleFormat::I24,
+                SampleFormat::I32,
+            ],
+        );
+    }
+
+    #[test]
+    fn test_float_roundtrip_amplitude() {
+        let left: Vec<f32> = (0..2048).map(|i| (i as f32 * 0.01).sin() * 0.9).collect();
+        let right: Vec<f32> = left.iter().map(|s| -s * 0.5).collect();
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 2,
+            format: SampleFormat::F32,
+            data: vec![left, right],
+        };
+
+        let encoded = WvCodec.encode(&Some(buffer.clone())).unwrap();
+        let decoded = WvCodec.decode(&encoded).unwrap();
+
+        assert_eq!(decoded.format, SampleFormat::F32);
+        assert_eq!(decoded.channels, 2);
+        for (src, dst) in buffer.data.iter().zip(decoded.data.iter()) {
+            assert_eq!(src.len(), dst.len());
+            for (a, b) in src.iter().zip(dst.iter()) {
+                assert!((a - b).abs() < 1e-6, "expected {a}, got {b}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_self_extracting_stub_is_flagged_and_skipped() {
+        let buffer = AudioBuffer {
+            sample_rate: 44100,
+            channels: 1,
+            format: SampleFormat::I16,
+            data: vec![(0..2000).map(|i| (i as f32 * 0.02).sin() * 0.7).collect()],
+        };
+        let encoded = WvCodec.encode(&Some(buffer)).unwrap();
+        let mut sfx = b"MZ".to_vec();
+        sfx.resize(4096, 0x90);
+        sfx.extend_from_slice(&encoded);
+
+        let metadata = WvCodec.parse_metadata(&sfx).unwrap();
+        assert_eq!(
+            metadata.get_field("WAVPACK_SELF_EXTRACTING").as_deref(),
+            Some("true")
+        );
+        assert!(
+            WvCodec
+                .parse_metadata(&encoded)
+                .unwrap()
+                .get_field("WAVPACK_SELF_EXTRACTING")
+                .is_none()
+        );
+
+        let plain = WvCodec.decode(&encoded).unwrap();
+        assert!(WvCodec.validate_file_format(&sfx).is_ok());
+        assert_eq!(WvCodec.decode(&sfx).unwrap().data, plain.data);
+        assert!(WvCodec.decode_stream(&sfx).is_ok());
+
+        // An executable with no WavPack stream behind it is still rejected
+        assert!(WvCodec.validate_file_format(&sfx[..4096]).is_err());
+    }
+
+    #[test]
+    fn test_year_maps_to_date_and_back() {
+        assert_eq!(WvCodec.tag_name_for("Year"), "Date");
+        assert_eq!(WvCodec.logical_for("Date"), "Year");
+        assert_eq!(WvCodec.logical_for(&WvCodec.tag_name_for("Year")), "Year");
+        assert_eq!(WvCodec.tag_name_for("Description"), "Description");
+    }
+
+    #[test]
+    fn test_reordered_layout_decodes_in_standard_order() {
+        // Four distinct DC levels so every channel is identifiable after decode
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 4,
+            format: SampleFormat::I16,
+            data: (0..4).map(|ch| vec![0.1 * (ch + 1) as f32; 1000]).collect(),
+        };
+        let reorder = vec![2u8, 0, 3, 1];
+        // kCAFChannelLayoutTag_Quadraphonic (108 << 16) with four channels
+        let layout_tag = (108 << 16) | 4;
+
+        let mut encoder =
+            WavpackEncoder::new(48000, 4, 16, false, true, WavpackQuality::High).unwrap();
+        encoder.set_channel_layout(layout_tag, Some(reorder.clone()));
+        encoder.init().unwrap();
+        let encoded = encoder.encode(&buffer, 1000, &None).unwrap();
+        drop(encoder);
+
+        let decoder = WavpackDecoder::new(&encoded).unwrap();
+        assert_eq!(decoder.channel_layout(), (layout_tag, Some(reorder)));
+        drop(decoder);
+
+        let decoded = WvCodec.decode(&encoded).unwrap();
+        for (src, dst) in buffer.data.iter().zip(decoded.data.iter()) {
+            assert!(
+                (src[500] - dst[500]).abs() < 1e-3,
+                "expected {}, got {}",
+                src[500],
+                dst[500]
+            );
+        }
+        let metadata = WvCodec.parse_metadata(&encoded).unwrap();
+        assert_eq!(
+            metadata.get_field("WAVPACK_CHANNEL_REORDER").as_deref(),
+            Some("2,0,3,1")
+        );
+    }
+
+    #[test]
+    fn test_restore_original_wav_is_byte_identical() {
+        let buffer = AudioBuffer {
+            sample_rate: 44100,
+            channels: 2,
+            format: SampleFormat::I24,
+            data: vec![
+                (0..3001).map(|i| (i as f32 * 0.013).sin() * 0.8).collect(),
+                (0..3001).map(|i| (i as f32 * 0.007).cos() * -0.6).collect(),
+            ],
+        };
+        let mut wav = WavCodec.encode(&Some(buffer)).unwrap();
+        // A trailing chunk after the data must survive too
+        wav.extend_from_slice(b"junk\x03\x00\x00\x00abc\x00");
+        let riff_size = wav.len() as u32 - 8;
+        wav[4..8].copy_from_slice(&riff_size.to_le_bytes());
+
+        let packed = WvCodec.wrap_wav(&wav).unwrap();
+        let (restored, extension) = WvCodec.restore_original(&packed).unwrap();
+
+        assert_eq!(extension, "wav");
+        assert_eq!(restored, wav);
+    }
+
+    #[test]
+    fn test_metadata_embed_keeps_24_bit() {
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 2,
+            format: SampleFormat::I24,
+            data: vec![
+                (0..4000).map(|i| (i as f32 * 0.01).sin() * 0.5).collect(),
+                (0..4000).map(|i| (i as f32 * 0.02).sin() * 0.5).collect(),
+            ],
+        };
+        let path = std::env::temp_dir().join(format!("ffcodex_24bit_{}.wv", rand::random::<u32>()));
+        let path_str = path.to_str().unwrap();
+        WvCodec.encode_file(&Some(buffer), path_str).unwrap();
+        let before = WvCodec.decode(&std::fs::read(&path).unwrap()).unwrap();
+
+        let mut metadata = Metadata::new();
+        metadata
+            .set_field("Description", "Distant thunder")
+            .unwrap();
+        WvCodec.embed_metadata_to_file(path_str, &metadata).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let decoder = WavpackDecoder::new(&bytes).unwrap();
+        assert_eq!(decoder.bits_per_sample(), 24);
+        assert_eq!(decoder.bytes_per_sample(), 3);
+        drop(decoder);
+
+        let decoded = WvCodec.decode(&bytes).unwrap();
+        assert_eq!(decoded.format, SampleFormat::I24);
+        assert_eq!(decoded.data, before.data);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_front_and_back_covers_stay_distinct() {
+        let png = |fill: u8| [b"\x89PNG\r\n\x1a\n".as_slice(), &[fill; 32]].concat();
+        let mut metadata = Metadata::new();
+        metadata.add_image(
+            ImageChunk::new("image/png".into(), "Front".into(), png(1))
+                .with_picture_type(PictureType::FrontCover),
+        );
+        metadata.add_image(
+            ImageChunk::new("image/png".into(), "Back".into(), png(2))
+                .with_picture_type(PictureType::BackCover),
+        );
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 1,
+            format: SampleFormat::I16,
+            data: vec![vec![0.1; 1000]],
+        };
+        let encoded = WvCodec
+            .encode_with_metadata(&Some(buffer), &Some(&metadata))
+            .unwrap();
+
+        let codec: &dyn Codec = &WvCodec;
+        for parsed in [
+            WvCodec.parse_metadata(&encoded),
+            codec.parse_metadata(&encoded),
+        ] {
+            let mut images: Vec<_> = parsed
+                .unwrap()
+                .get_images()
+                .iter()
+                .map(|image| (image.picture_type(), image.data().to_vec()))
+                .collect();
+            images.sort_by_key(|(picture_type, _)| picture_type.code());
+            assert_eq!(
+                images,
+                vec![
+                    (PictureType::FrontCover, png(1)),
+                    (PictureType::BackCover, png(2))
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_store_md5_matches_source_audio() {
+        use md5::{Digest, Md5};
+
+        let samples: Vec<i16> = (0..6000)
+            .map(|i| ((i * 37) % 20000 - 10000) as i16)
+            .collect();
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 2,
+            format: SampleFormat::I16,
+            data: vec![
+                samples.iter().map(|&s| s as f32 / 32768.0).collect(),
+                samples.iter().map(|&s| -(s as f32) / 32768.0).collect(),
+            ],
+        };
+        let mut expected = Md5::new();
+        for &sample in &samples {
+            expected.update(sample.to_le_bytes());
+            expected.update((-sample).to_le_bytes());
+        }
+        let expected: String = expected
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        let options = EncodeOptions {
+            store_md5: true,
+            ..Default::default()
+        };
+        let encoded = WvCodec
+            .encode_with_options(&Some(buffer.clone()), &None, &options)
+            .unwrap();
+        let metadata = WvCodec.parse_metadata(&encoded).unwrap();
+        assert_eq!(metadata.get_field("WAVPACK_MD5"), Some(expected));
+        assert_eq!(WvCodec.decode(&encoded).unwrap().data, buffer.data);
+
+        let plain = WvCodec.encode(&Some(buffer)).unwrap();
+        let metadata = WvCodec.parse_metadata(&plain).unwrap();
+        assert_eq!(metadata.get_field("WAVPACK_MD5"), None);
+    }
+
+    #[test]
+    fn test_wavpack_tags_can_skip_ixml() {
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 1,
+            format: SampleFormat::I16,
+            data: vec![(0..480).map(|i| i as f32 / 1024.0).collect()],
+        };
+        let mut metadata = Metadata::new();
+        metadata.set_field("TAG_Title", "Door Slam").unwrap();
+        metadata.set_field("Description", "Heavy oak door").unwrap();
+
+        let tag_items = |tags: WavpackTags| {
+            let options = EncodeOptions {
+                wavpack_tags: tags,
+                ..Default::default()
+            };
+            let encoded = WvCodec
+                .encode_with_options(&Some(buffer.clone()), &Some(&metadata), &options)
+                .unwrap();
+            let decoder = WavpackDecoder::new(&encoded).unwrap();
+            let count = unsafe { WavpackGetNumTagItems(decoder.context) };
+            (0..count)
+                .map(|i| {
+                    let mut name = vec![0u8; 256];
+                    let len = unsafe {
+                        WavpackGetTagItemIndexed(
+                            decoder.context,
+                            i,
+                            name.as_mut_ptr() as *mut c_char,
+                            name.len() as c_int,
+                        )
+                    };
+                    String::from_utf8_lossy(&name[..len.max(0) as usize]).to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let both = tag_items(WavpackTags::NativeAndIxml);
+        assert!(both.iter().any(|name| name == "iXML"));
+        assert!(both.iter().any(|name| name == "Title"));
+
+        let native = tag_items(WavpackTags::NativeOnly);
+        assert!(!native.iter().any(|name| name == "iXML"));
+        assert!(native.iter().any(|name| name == "Title"));
+
+        assert_eq!(tag_items(WavpackTags::IxmlOnly), vec!["iXML".to_string()]);
+    }
+
+    #[test]
+    fn test_hybrid_encode_with_correction() {
+        // Noise, so the lossless encode can't shrink below the hybrid bitrate
+        let mut noise = noise(1);
+        let buffer = Some(AudioBuffer {
+            sample_rate: 48000,
+            channels: 2,
+            format: SampleFormat::I16,
+            data: (0..2)
+                .map(|_| (0..48000).map(|_| noise()).collect())
+                .collect(),
+        });
+
+        let lossless = WvCodec.encode(&buffer).unwrap();
+        let (lossy, correction) = WvCodec.encode_hybrid(&buffer, 128.0, true).unwrap();
+        assert!(lossy.len() < lossless.len() / 4);
+        let correction = correction.unwrap();
+        assert!(correction.starts_with(b"wvpk"));
+
+        let decoder = WavpackDecoder::new(&lossy).unwrap();
+        let mode = unsafe { WavpackGetMode(decoder.context) };
+        assert_ne!(mode & MODE_HYBRID, 0);
+        assert_eq!(mode & MODE_LOSSLESS, 0);
+        assert_eq!(WvCodec.decode(&lossy).unwrap().data[0].len(), 48000);
+
+        let (_, correction) = WvCodec.encode_hybrid(&buffer, 128.0, false).unwrap();
+        assert!(correction.is_none());
+    }
+
+    #[test]
+    fn test_hybrid_pair_restores_bit_exact_through_a_tag_edit() {
+        let mut noise = noise(3);
+        let buffer = Some(AudioBuffer {
+            sample_rate: 48000,
+            channels: 2,
+            format: SampleFormat::I24,
+            data: (0..2)
+                .map(|_| (0..48000).map(|_| noise()).collect())
+                .collect(),
+        });
+        let original = WvCodec.decode(&WvCodec.encode(&buffer).unwrap()).unwrap();
+
+        let (lossy, correction) = WvCodec.encode_hybrid(&buffer, 256.0, true).unwrap();
+        let correction = correction.unwrap();
+        let restored = WvCodec.decode_with_correction(&lossy, &correction).unwrap();
+        assert_eq!(restored.data, original.data);
+        assert_ne!(WvCodec.decode(&lossy).unwrap().data, original.data);
+
+        let path = std::env::temp_dir()
+            .join(format!("ffcodex_hybrid_{}.wv", rand::random::<u32>()))
+            .to_string_lossy()
+            .to_string();
+        std::fs::write(&path, &lossy).unwrap();
+        std::fs::write(correction_path(&path), &correction).unwrap();
+        let mut metadata = Metadata::new();
+        metadata.set_field("TAG_Title", "Surf").unwrap();
+        let embedded = WvCodec.embed_metadata_to_file(&path, &metadata);
+        let (tagged, tagged_correction) = (
+            std::fs::read(&path).unwrap(),
+            std::fs::read(correction_path(&path)).unwrap(),
+        );
+        let lossless = WvCodec.is_lossless(&path);
+        let decoded = WvCodec.decode_file(&path);
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(correction_path(&path));
+        embedded.unwrap();
+
+        assert!(lossless.unwrap());
+        assert_eq!(decoded.unwrap().data, original.data);
+        let mode = unsafe { WavpackGetMode(WavpackDecoder::new(&tagged).unwrap().context) };
+        assert_ne!(mode & MODE_HYBRID, 0);
+        let parsed = WvCodec.parse_metadata(&tagged).unwrap();
+        assert_eq!(parsed.get_field("TAG_Title").as_deref(), Some("Surf"));
+        let restored = WvCodec
+            .decode_with_correction(&tagged, &tagged_correction)
+            .unwrap();
+        assert_eq!(restored.data, original.data);
+
+        // Without the correction stream the lossy audio would be re-encoded
+        assert!(WvCodec.embed_metadata(&lossy, &metadata).is_err());
+    }
+
+    #[test]
+    fn test_id3v1_trailer_fills_missing_tags() {
+        let buffer = AudioBuffer {
+            sample_rate: 44100,
+            channels: 1,
+            format: SampleFormat::I16,
+            data: vec![(0..4410).map(|i| (i as f32 * 0.02).sin() * 0.5).collect()],
+        };
+        let mut metadata = Metadata::new();
+        metadata.set_field("TAG_Title", "Native Title").unwrap();
+        let mut encoded = WvCodec
+            .encode_with_metadata(&Some(buffer), &Some(&metadata))
+            .unwrap();
+
+        let mut trailer = [0u8; ID3V1_TAG_SIZE];
+        trailer[..3].copy_from_slice(b"TAG");
+        trailer[3..12].copy_from_slice(b"Old Title");
+        trailer[33..46].copy_from_slice(b"Legacy Artist");
+        trailer[97..108].copy_from_slice(b"Tape 12    ");
+        trailer[126] = 4; // v1.1 track number
+        trailer[127] = 8; // Jazz
+        encoded.extend_from_slice(&trailer);
+
+        // The native title wins; the trailer supplies the rest
+        let parsed = WvCodec.parse_metadata(&encoded).unwrap();
+        for (key, value) in [
+            ("TAG_Title", "Native Title"),
+            ("TAG_Artist", "Legacy Artist"),
+            ("TAG_Comment", "Tape 12"),
+            ("TAG_Genre", "Jazz"),
+        ] {
+            assert_eq!(parsed.get_field(key).as_deref(), Some(value), "{key}");
+        }
+        assert_eq!(WvCodec.decode(&encoded).unwrap().data[0].len(), 4410);
+    }
+
+    #[test]
+    fn test_threaded_encode_is_bit_identical() {
+        let buffer = Some(AudioBuffer {
+            sample_rate: 96000,
+            channels: 6,
+            format: SampleFormat::I24,
+            data: (0..6)
+                .map(|ch| {
+                    (0..96000)
+                        .map(|i| (i as f32 * 0.013 * (ch + 1) as f32).sin() * 0.4)
+                        .collect()
+                })
+                .collect(),
+        });
+        let encode = |threads| {
+            let options = EncodeOptions {
+                wavpack_threads: threads,
+                ..Default::default()
+            };
+            WvCodec
+                .encode_with_options(&buffer, &None, &options)
+                .unwrap()
+        };
+
+        let single = encode(None);
+        assert_eq!(encode(Some(1)), single);
+        assert_eq!(encode(Some(4)), single);
+        assert_eq!(encode(Some(0)), single);
+    }
+
+    #[test]
+    fn test_quality_levels_trade_size_and_stay_lossless() {
+        // Decaying partials over a little noise, so the modes differ in what they find
+        let mut noise = noise(7);
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 2,
+            format: SampleFormat::I16,
+            data: (0..2)
+                .map(|ch| {
+                    (0..48000)
+                        .map(|i| {
+                            let t = i as f32 / 48000.0;
+                            let tone = (t * (440.0 + ch as f32 * 3.0) * std::f32::consts::TAU)
+                                .sin()
+                                + 0.3 * (t * 1870.0 * std::f32::consts::TAU).sin();
+                            let dither = noise() * 0.01;
+                            ((tone * 0.4 * (-t * 2.0).exp() + dither) * 32768.0).round() / 32768.0
+                        })
+                        .collect()
+                })
+                .collect(),
+        };
+
+        let encoded: Vec<Vec<u8>> = [
+            WavpackQuality::Fast,
+            WavpackQuality::High,
+            WavpackQuality::VeryHigh,
+        ]
+        .into_iter()
+        .map(|quality| {
+            let options = EncodeOptions {
+                wavpack_quality: quality,
+                ..Default::default()
+            };
+            WvCodec
+                .encode_with_options(&Some(buffer.clone()), &None, &options)
+                .unwrap()
+        })
+        .collect();
+
+        assert!(encoded[0].len() > encoded[2].len());
+        for bytes in &encoded {
+            assert_eq!(WvCodec.decode(bytes).unwrap().data, buffer.data);
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use crate::codecs::{ImageChunk, PictureType};
+use crate::prelude::*;
+
+/// Text frames we read and write, with the field each one maps to when parsed
+const TEXT_FRAMES: [(&[u8; 4], &str); 8] = [
+    (b"TIT2", "Title"),
+    (b"TPE1", "Artist"),
+    (b"TALB", "Album"),
+    (b"TDRC", "Year"),
+    (b"TCON", "Genre"),
+    (b"TRCK", "Track"),
+    (b"TPE2", "AlbumArtist"),
+    (b"TCOM", "Composer"),
+];
+
+const ENCODING_LATIN1: u8 = 0;
+const ENCODING_UTF16: u8 = 1;
+const ENCODING_UTF16BE: u8 = 2;
+const ENCODING_UTF8: u8 = 3;
+
+const HEADER_SIZE: usize = 10;
+const FLAG_EXTENDED_HEADER: u8 = 0x40;
+
+/// Serialize an ID3v2.4 tag (header and frames) for an `ID3 `/`id3 ` chunk.
+///
+/// TIT2, TPE1 and COMM come from the title, artist and description aliases so
+/// iXML or Vorbis-sourced values land in the tag too; the other text frames
+/// use the `TAG_*` fields written by [`parse_tag`]. Returns `None` when there
+/// is nothing to write.
+pub fn create_tag(metadata: &Metadata) -> R<Option<Vec<u8>>> {
+    let mut frames = Vec::new();
+    for (id, key) in TEXT_FRAMES {
+        let value = match key {
+            "Title" => metadata.get_field("TrackTitle"),
+            "Artist" => metadata.get_field("Artist"),
+            _ => metadata.get_field(&format!("TAG_{key}")),
+        };
+        if let Some(value) = value.filter(|v| !v.is_empty()) {
+            let mut body = vec![ENCODING_UTF8];
+            body.extend_from_slice(value.as_bytes());
+            write_frame(&mut frames, id, &body)?;
+        }
+    }
+
+    if let Some(comment) = metadata.get_field("Description").filter(|v| !v.is_empty()) {
+        let mut body = vec![ENCODING_UTF8];
+        body.extend_from_slice(b"eng");
+        body.push(0); // empty short description
+        body.extend_from_slice(comment.as_bytes());
+        write_frame(&mut frames, b"COMM", &body)?;
+    }
+
+    for image in metadata.get_images() {
+        let mut body = vec![ENCODING_UTF8];
+        body.extend_from_slice(image.mime_type().as_bytes());
+        body.push(0);
+        body.push(image.picture_type().code());
+        body.extend_from_slice(image.description().as_bytes());
+        body.push(0);
+        body.extend_from_slice(image.data());
+        write_frame(&mut frames, b"APIC", &body)?;
+    }
+
+    if frames.is_empty() {
+        return Ok(None);
+    }
+
+    let mut tag = Vec::with_capacity(HEADER_SIZE + frames.len());
+    tag.extend_from_slice(b"ID3");
+    tag.extend_from_slice(&[4, 0, 0]); // v2.4.0, no flags
+    tag.extend_from_slice(&to_syncsafe(frames.len())?);
+    tag.extend_from_slice(&frames);
+    Ok(Some(tag))
+}
+
+/// Parse a complete ID3v2 tag, header included. Text frames become `TAG_*`
+/// fields and APIC frames become images. Tags older than v2.3 are skipped.
+pub fn parse_tag(data: &[u8], metadata: &mut Metadata) -> R<()> {
+    if data.len() < HEADER_SIZE || &data[0..3] != b"ID3" {
+        return Ok(());
+    }
+    let major = data[3];
+    let size = from_syncsafe(&data[6..10]);
+    metadata.set_field("ID3Version", &format!("2.{}.{}", major, data[4]))?;
+
+    if major < 3 || size == 0 || data.len() < HEADER_SIZE + size {
+        return Ok(());
+    }
+    let mut frames = &data[HEADER_SIZE..HEADER_SIZE + size];
+
+    if data[5] & FLAG_EXTENDED_HEADER != 0 && frames.len() >= 4 {
+        // v2.4 counts the size field itself, v2.3 doesn't
+        let ext_size = if major >= 4 {
+            from_syncsafe(&frames[0..4])
+        } else {
+            u32::from_be_bytes([frames[0], frames[1], frames[2], frames[3]]) as usize + 4
+        };
+        frames = frames.get(ext_size..).unwrap_or_default();
+    }
+
+    parse_frames(frames, major, metadata)
+}
+
+fn parse_frames(data: &[u8], major: u8, metadata: &mut Metadata) -> R<()> {
+    let mut offset = 0;
+    while offset + HEADER_SIZE <= data.len() {
+        let id = &data[offset..offset + 4];
+        if id[0] == 0 {
+            break; // padding
+        }
+        let size_bytes = &data[offset + 4..offset + 8];
+        // v2.4 frame sizes are syncsafe; v2.3 ones are plain big-endian
+        let size = if major >= 4 {
+            from_syncsafe(size_bytes)
+        } else {
+            u32::from_be_bytes([size_bytes[0], size_bytes[1], size_bytes[2], size_bytes[3]])
+                as usize
+        };
+        let start = offset + HEADER_SIZE;
+        if size == 0 || start + size > data.len() {
+            break;
+        }
+        let body = &data[start..start + size];
+
+        match id {
+            b"COMM" => {
+                if let Some(comment) = parse_comment(body) {
+                    metadata.set_field("TAG_Comment", &comment)?;
+                }
+            }
+            b"APIC" => {
+                if let Some(image) = parse_picture(body) {
+                    metadata.add_image(image);
+                }
+            }
+            _ => {
+                // v2.3 stores the year in TYER
+                let key = if id == b"TYER" {
+                    Some("Year")
+                } else {
+                    TEXT_FRAMES
+                        .iter()
+                        .find(|(frame, _)| frame.as_slice() == id)
+                        .map(|(_, key)| *key)
+                };
+                if let Some(key) = key
+                    && let Some((&encoding, text)) = body.split_first()
+                {
+                    let text = decode_text(encoding, text);
+                    if !text.is_empty() {
+                        metadata.set_field(&format!("TAG_{key}"), &text)?;
+                    }
+                }
+            }
+        }
+
+        offset = start + size;
+    }
+    Ok(())
+}
+
+// COMM body: encoding, 3-byte language, terminated short description, text
+fn parse_comment(body: &[u8]) -> Option<String> {
+    let (&encoding, rest) = body.split_first()?;
+    let (_, text) = split_terminated(encoding, rest.get(3..)?);
+    let text = decode_text(encoding, text);
+    if text.is_empty() { None } else { Some(text) }
+}
+
+// APIC body: encoding, Latin-1 MIME type, picture type, terminated description, data
+fn parse_picture(body: &[u8]) -> Option<ImageChunk> {
+    let (&encoding, rest) = body.split_first()?;
+    let (mime, rest) = split_terminated(ENCODING_LATIN1, rest);
+    let (&picture_type, rest) = rest.split_first()?;
+    let (description, data) = split_terminated(encoding, rest);
+    Some(
+        ImageChunk::new(
+            decode_text(ENCODING_LATIN1, mime),
+            decode_text(encoding, description),
+            data.to_vec(),
+        )
+        .with_picture_type(PictureType::from_code(picture_type)),
+    )
+}
+
+/// Split at the first terminator for `encoding` (one zero byte, or an aligned
+/// zero pair for UTF-16), dropping the terminator itself
+fn split_terminated(encoding: u8, data: &[u8]) -> (&[u8], &[u8]) {
+    let end = match encoding {
+        ENCODING_UTF16 | ENCODING_UTF16BE => data
+            .chunks_exact(2)
+            .position(|pair| pair == [0, 0])
+            .map(|i| (i * 2, i * 2 + 2)),
+        _ => data.iter().position(|&b| b == 0).map(|i| (i, i + 1)),
+    };
+    match end {
+        Some((end, next)) => (&data[..end], &data[next..]),
+        None => (data, &data[data.len()..]),
+    }
+}
+
+fn decode_text(encoding: u8, data: &[u8]) -> String {
+    let text = match encoding {
+        ENCODING_LATIN1 => data.iter().map(|&b| b as char).collect(),
+        ENCODING_UTF16 | ENCODING_UTF16BE => {
+            // A BOM picks the byte order; without one, encoding 1 is ambiguous
+            // and big-endian is the spec's fallback
+            let (little_endian, data) = match data {
+                [0xFF, 0xFE, rest @ ..] => (true, rest),
+                [0xFE, 0xFF, rest @ ..] => (false, rest),
+                _ => (false, data),
+            };
+            let units: Vec<u16> = data
+                .chunks_exact(2)
+                .map(|pair| {
+                    if little_endian {
+                        u16::from_le_bytes([pair[0], pair[1]])
+                    } else {
+                        u16::from_be_bytes([pair[0], pair[1]])
+                    }
+                })
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(data).to_string(),
+    };
+    text.trim_end_matches('\0').trim().to_string()
+}
+
+fn write_frame(out: &mut Vec<u8>, id: &[u8; 4], body: &[u8]) -> R<()> {
+    out.extend_from_slice(id);
+    out.extend_from_slice(&to_syncsafe(body.len())?);
+    out.extend_from_slice(&[0, 0]); // flags
+    out.extend_from_slice(body);
+    Ok(())
+}
+
+/// 28-bit size spread over four bytes with the top bit of each clear
+fn to_syncsafe(size: usize) -> R<[u8; 4]> {
+    if size >= 1 << 28 {
+        return Err(anyhow!("ID3 frame of {} bytes is too large", size));
+    }
+    Ok([
+        ((size >> 21) & 0x7F) as u8,
+        ((size >> 14) & 0x7F) as u8,
+        ((size >> 7) & 0x7F) as u8,
+        (size & 0x7F) as u8,
+    ])
+}
+
+fn from_syncsafe(bytes: &[u8]) -> usize {
+    bytes[..4]
+        .iter()
+        .fold(0, |size, &b| (size << 7) | (b & 0x7F) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_roundtrip() {
+        let mut metadata = Metadata::new();
+        metadata.set_field("TrackTitle", "Thunder Roll").unwrap();
+        metadata.set_field("Artist", "Tim Nielsen").unwrap();
+        metadata
+            .set_field("Description", "Distant rumble, rain on tin — café roof")
+            .unwrap();
+
+        let tag = create_tag(&metadata).unwrap().unwrap();
+        assert_eq!(&tag[0..5], b"ID3\x04\x00");
+
+        let mut parsed = Metadata::new();
+        parse_tag(&tag, &mut parsed).unwrap();
+        for key in ["TrackTitle", "Artist", "Description"] {
+            assert_eq!(parsed.get_field(key), metadata.get_field(key), "{key}");
+        }
+        assert_eq!(parsed.get_field("ID3Version").as_deref(), Some("2.4.0"));
+    }
+
+    #[test]
+    fn test_utf16_comment_and_picture() {
+        let mut metadata = Metadata::new();
+        metadata.add_image(
+            ImageChunk::new("image/png".into(), "cover".into(), vec![1, 2, 3, 0, 4])
+                .with_picture_type(PictureType::FrontCover),
+        );
+        let tag = create_tag(&metadata).unwrap().unwrap();
+
+        // v2.3 frame with a little-endian UTF-16 comment and short description
+        let mut comm = vec![ENCODING_UTF16];
+        comm.extend_from_slice(b"eng");
+        comm.extend_from_slice(&[0xFF, 0xFE, b'x', 0, 0, 0, 0xFF, 0xFE]);
+        comm.extend("Hi".encode_utf16().flat_map(u16::to_le_bytes));
+        let mut v23 = b"ID3\x03\x00\x00".to_vec();
+        v23.extend_from_slice(&to_syncsafe(HEADER_SIZE + comm.len()).unwrap());
+        v23.extend_from_slice(b"COMM");
+        v23.extend_from_slice(&(comm.len() as u32).to_be_bytes());
+        v23.extend_from_slice(&[0, 0]);
+        v23.extend_from_slice(&comm);
+
+        let mut parsed = Metadata::new();
+        parse_tag(&tag, &mut parsed).unwrap();
+        parse_tag(&v23, &mut parsed).unwrap();
+        assert_eq!(parsed.get_images(), metadata.get_images());
+        assert_eq!(parsed.get_field("Description").as_deref(), Some("Hi"));
+    }
+
+    #[test]
+    fn test_wav_and_aiff_embeds_carry_the_tag() {
+        let buffer = AudioBuffer {
+            sample_rate: 48000,
+            channels: 1,
+            format: SampleFormat::I16,
+            data: vec![vec![0.1; 480]],
+        };
+        let mut metadata = Metadata::new();
+        metadata.set_field("TAG_Album", "Storm Library").unwrap();
+        metadata.add_image(
+            ImageChunk::new("image/png".into(), "cover".into(), vec![1, 2, 3])
+                .with_picture_type(PictureType::FrontCover),
+        );
+
+        for codec in [&WavCodec as &dyn Codec, &AifCodec] {
+            let encoded = codec.encode(&Some(buffer.clone())).unwrap();
+            let tagged = codec.embed_metadata(&encoded, &metadata).unwrap();
+            let parsed = codec.parse_metadata(&tagged).unwrap();
+            assert_eq!(
+                parsed.get_field("TAG_Album").as_deref(),
+                Some("Storm Library"),
+                "{}",
+                codec.as_str()
+            );
+            assert_eq!(parsed.get_images(), metadata.get_images());
+
+            // Embedding again replaces the tag instead of adding a second one
+            let retagged = codec.embed_metadata(&tagged, &parsed).unwrap();
+            let reparsed = codec.parse_metadata(&retagged).unwrap();
+            assert_eq!(reparsed.get_images(), metadata.get_images());
+        }
+    }
+}
@@ -1,10 +1,28 @@
 use crate::prelude::*;
 
+/// U+FEFF, which `from_utf8_lossy` yields for a UTF-8 byte order mark (EF BB BF)
+const BYTE_ORDER_MARK: char = '\u{feff}';
+
+/// Add one iXML chunk payload to `pending`, returning the document once it is whole.
+///
+/// Some writers split a large document across several iXML chunks. A payload that
+/// opens `<BWFXML>` without closing it stays in `pending` until a later chunk closes
+/// it. `pending` belongs to the chunk loop reading one file; whatever is left in it
+/// when the loop ends is still worth parsing.
+pub(crate) fn join_ixml_chunk(pending: &mut String, payload: &str) -> Option<String> {
+    pending.push_str(payload.trim_start_matches(BYTE_ORDER_MARK));
+    if pending.contains("<BWFXML") && !pending.contains("</BWFXML>") {
+        return None;
+    }
+    Some(std::mem::take(pending))
+}
+
 pub enum MetadataBlock {
     BEXT,
     USER,
     ASWG,
     STEINBERG,
+    LOUDNESS,
 }
 
 impl MetadataBlock {
@@ -14,11 +32,16 @@ impl MetadataBlock {
             MetadataBlock::USER => "USER",
             MetadataBlock::ASWG => "ASWG",
             MetadataBlock::STEINBERG => "STEINBERG",
+            MetadataBlock::LOUDNESS => "LOUDNESS",
         }
     }
 }
 impl Metadata {
+    /// Parse one iXML payload into fields, merging with what is already set.
     pub fn parse_ixml(&mut self, ixml: &str) -> R<()> {
+        // A BOM is not whitespace to `trim`, so it would hide the first tag
+        let ixml = ixml.trim_start_matches(BYTE_ORDER_MARK);
+
         let mut block: Option<MetadataBlock> = None;
         let mut key: Option<String> = None;
         let mut val: Option<String> = None;
@@ -29,7 +52,7 @@ impl Metadata {
                 continue; // Skip empty lines and comments
             }
             match line {
-                "</BEXT>" | "</USER>" | "</ASWG>" | "</STEINBERG>" => block = None,
+                "</BEXT>" | "</USER>" | "</ASWG>" | "</STEINBERG>" | "</LOUDNESS>" => block = None,
                 "<BEXT>" => {
                     block = Some(MetadataBlock::BEXT);
                     continue;
@@ -46,6 +69,10 @@ impl Metadata {
                     block = Some(MetadataBlock::STEINBERG);
                     continue;
                 }
+                "<LOUDNESS>" => {
+                    block = Some(MetadataBlock::LOUDNESS);
+                    continue;
+                }
                 _ => {}
             }
             let Some(block) = &block else {
@@ -53,27 +80,24 @@ impl Metadata {
             };
 
             match block {
-                MetadataBlock::STEINBERG => {
-                    if line.starts_with("<NAME>") {
-                        key = Some(line.replace("<NAME>", "").replace("</NAME>", ""));
-                    } else if line.starts_with("<VALUE>") {
-                        val = Some(line.replace("<VALUE>", "").replace("</VALUE>", ""));
-                    }
-                }
+                MetadataBlock::STEINBERG => match parse_element(line) {
+                    Some(("NAME", name)) => key = Some(name),
+                    Some(("VALUE", value)) => val = Some(value),
+                    _ => {}
+                },
                 _ => {
-                    let r: Vec<&str> = line.split(['<', '>']).collect();
-                    if r.len() >= 3 {
-                        key = Some(r[1].trim().to_string());
-                        val = Some(r[2].trim().to_string());
+                    if let Some((name, value)) = parse_element(line) {
+                        key = Some(name.to_string());
+                        val = Some(value);
                     }
                 }
             }
 
-            if let (Some(k), Some(v)) = (key.take(), val.take()) {
-                self.set_field(
-                    &format!("{}_{}", block.as_str(), k),
-                    &v,
-                )?;
+            // A Steinberg NAME waits here for the VALUE on a later line
+            if let (Some(k), Some(v)) = (&key, &val) {
+                self.set_field(&format!("{}_{}", block.as_str(), k), v)?;
+                key = None;
+                val = None;
             }
         }
 
@@ -94,6 +118,32 @@ impl Metadata {
 
         Ok(())
     }
+
+    /// Record measured loudness as `LOUDNESS_*` fields, written back out as the
+    /// iXML `<LOUDNESS>` block. Values that don't exist for silence are left unset.
+    pub fn set_loudness(&mut self, stats: &LoudnessStats) -> R<()> {
+        for (key, value) in [
+            ("LOUDNESS_LOUDNESS_VALUE", stats.integrated_lufs),
+            ("LOUDNESS_LOUDNESS_RANGE", stats.loudness_range_lu),
+            ("LOUDNESS_MAX_TRUE_PEAK_LEVEL", stats.true_peak_dbfs),
+        ] {
+            if value.is_finite() {
+                self.set_field(key, &format!("{:.2}", value))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split a one-line `<NAME>text</NAME>` element into its name and unescaped
+/// text. Container tags (`<TRACK_LIST>`) and empty elements give `None`.
+fn parse_element(line: &str) -> Option<(&str, String)> {
+    let (name, rest) = line.strip_prefix('<')?.split_once('>')?;
+    let (text, close) = rest.split_once("</")?;
+    if close.strip_suffix('>')?.trim() != name {
+        return None;
+    }
+    Some((name.trim(), xml_unescape(text.trim())))
 }
 
 pub fn create_ixml_from_metadata(metadata: &Metadata) -> R<String> {
@@ -101,8 +151,11 @@ pub fn create_ixml_from_metadata(metadata: &Metadata) -> R<String> {
     let mut steinberg = String::from("<STEINBERG>\n <ATTR_LIST>\n");
     let mut user = String::from("<USER>\n");
     let mut aswg = String::from("<ASWG>\n");
+    let mut loudness = String::new();
 
     for (k, v) in metadata.get_all_fields() {
+        // Values copied from BOM-prefixed text must not carry the mark into the output
+        let v = v.trim_start_matches(BYTE_ORDER_MARK);
         if let Some(key) = k.strip_prefix("BEXT_") {
             bext.push_str(&format!("  <{}>{}</{}>\n", key, xml_escape(v), key));
         } else if let Some(key) = k.strip_prefix("STEINBERG_") {
@@ -115,6 +168,8 @@ pub fn create_ixml_from_metadata(metadata: &Metadata) -> R<String> {
             user.push_str(&format!("  <{}>{}</{}>\n", key, xml_escape(v), key));
         } else if let Some(key) = k.strip_prefix("ASWG_") {
             aswg.push_str(&format!("  <{}>{}</{}>\n", key, xml_escape(v), key));
+        } else if let Some(key) = k.strip_prefix("LOUDNESS_") {
+            loudness.push_str(&format!("  <{}>{}</{}>\n", key, xml_escape(v), key));
         }
     }
 
@@ -131,26 +186,145 @@ pub fn create_ixml_from_metadata(metadata: &Metadata) -> R<String> {
     xml.push_str(&aswg);
     xml.push_str(&steinberg);
     xml.push_str(&user);
+    // Only files that have been measured carry a loudness block
+    if !loudness.is_empty() {
+        xml.push_str("<LOUDNESS>\n");
+        xml.push_str(&loudness);
+        xml.push_str("</LOUDNESS>\n");
+    }
 
     Ok(xml)
 }
 
+/// Escape text for an element body. Values never hold line breaks
+/// (`Metadata::set_field` strips them), so every element stays on one line.
 pub fn xml_escape(text: &str) -> String {
-    // Check if the text is already XML-escaped to avoid double-encoding
-    if text.contains("&amp;")
-        || text.contains("&lt;")
-        || text.contains("&gt;")
-        || text.contains("&quot;")
-        || text.contains("&apos;")
-    {
-        // Text appears to already be XML-escaped, return as-is
-        text.to_string()
-    } else {
-        // Apply XML escaping
-        text.replace('&', "&amp;")
-            .replace('<', "&lt;")
-            .replace('>', "&gt;")
-            .replace('"', "&quot;")
-            .replace('\'', "&apos;")
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Resolve the predefined entities and numeric character references; anything
+/// else that starts with `&` is kept as written
+pub fn xml_unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..].split_once(';').map(|(entity, _)| entity);
+        let resolved = match entity {
+            Some("amp") => Some('&'),
+            Some("lt") => Some('<'),
+            Some("gt") => Some('>'),
+            Some("quot") => Some('"'),
+            Some("apos") => Some('\''),
+            Some(entity) => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+            None => None,
+        };
+        match (resolved, entity) {
+            (Some(c), Some(entity)) => {
+                unescaped.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bom_prefixed_ixml_keeps_first_field() {
+        let payload = b"\xEF\xBB\xBF<BEXT>\n<BWF_DESCRIPTION>Rain</BWF_DESCRIPTION>\n</BEXT>\n\
+            <USER>\n<TAKE>3</TAKE>\n</USER>\n";
+        let mut metadata = Metadata::new();
+        metadata
+            .parse_ixml(&String::from_utf8_lossy(payload))
+            .unwrap();
+        assert_eq!(
+            metadata.get_field("BEXT_BWF_DESCRIPTION").as_deref(),
+            Some("Rain")
+        );
+        assert_eq!(metadata.get_field("USER_TAKE").as_deref(), Some("3"));
+
+        metadata.set_field("USER_SCENE", "\u{feff}Harbor").unwrap();
+        let xml = WavCodec.create_ixml(&metadata).unwrap();
+        assert!(!xml.contains(BYTE_ORDER_MARK));
+        assert!(xml.contains("<SCENE>Harbor</SCENE>"));
+    }
+
+    #[test]
+    fn test_special_characters_roundtrip() {
+        let description = "R&D <loud> \"take\" 'two'";
+        let mut metadata = Metadata::new();
+        metadata
+            .set_field("BEXT_BWF_DESCRIPTION", description)
+            .unwrap();
+        metadata.set_field("USER_SCENE", "Tom & Jerry").unwrap();
+        metadata
+            .set_field("STEINBERG_MediaLibrary", "A < B")
+            .unwrap();
+
+        let xml = WavCodec.create_ixml(&metadata).unwrap();
+        assert!(xml.contains("<BWFXML>\n") && xml.ends_with("</BWFXML>\n"));
+        assert!(xml.contains("<SCENE>Tom &amp; Jerry</SCENE>"));
+        assert!(!xml.contains("R&D"));
+
+        let mut parsed = Metadata::new();
+        parsed.parse_ixml(&xml).unwrap();
+        for (key, value) in [
+            ("BEXT_BWF_DESCRIPTION", description),
+            ("USER_SCENE", "Tom & Jerry"),
+            ("STEINBERG_MediaLibrary", "A < B"),
+        ] {
+            assert_eq!(parsed.get_field(key).as_deref(), Some(value), "{key}");
+        }
+
+        // Re-exporting parsed values must not escape them twice
+        let xml = WavCodec.create_ixml(&parsed).unwrap();
+        assert!(!xml.contains("&amp;amp;"));
+        assert_eq!(xml_unescape("&#x41;&#66; &copy; & x"), "AB &copy; & x");
+    }
+
+    #[test]
+    fn test_join_ixml_chunk_holds_only_unterminated_documents() {
+        let mut pending = String::new();
+        assert_eq!(
+            join_ixml_chunk(&mut pending, "<BWFXML>\n<USER>\n<SCE"),
+            None
+        );
+        let whole =
+            join_ixml_chunk(&mut pending, "NE>Harbor</SCENE>\n</USER>\n</BWFXML>\n").unwrap();
+        assert!(whole.contains("<SCENE>Harbor</SCENE>"));
+        assert!(pending.is_empty());
+
+        let single = "<BWFXML>\n<USER>\n<TAKE>3</TAKE>\n</USER>\n</BWFXML>\n";
+        assert_eq!(
+            join_ixml_chunk(&mut pending, single).as_deref(),
+            Some(single)
+        );
     }
 }
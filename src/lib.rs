@@ -1,16 +1,20 @@
 // pub mod decode;
+pub mod aswg;
 pub mod codecs;
 pub mod ixml;
 use std::path::PathBuf;
 
 use codecs::*;
-mod prelude;
+pub mod prelude;
 use crate::prelude::*;
 pub mod bindings;
 mod chromaprint;
+pub mod id3;
+pub mod loudness;
 pub mod playback;
 pub mod resample;
 pub mod soundminer;
+pub mod ucs;
 
 // Standard bit depths
 // const BIT_DEPTH_8: u16 = 8;
@@ -38,7 +42,7 @@ pub mod soundminer;
 
 pub fn debug_println(args: std::fmt::Arguments) {
     if cfg!(debug_assertions) {
-        println!("{}", args);
+        eprintln!("{}", args);
     }
 }
 
@@ -103,7 +107,7 @@ pub fn strip_soundminer_metadata(file_path: &str) -> R<()> {
 
     let cleaned_data = match extension.to_lowercase().as_str() {
         "flac" => strip_smed_from_flac(&original_data)?,
-        "aif" | "aiff" => strip_smed_from_aiff(&original_data)?,
+        "aif" | "aiff" | "aifc" => strip_smed_from_aiff(&original_data)?,
         "wav" => strip_smed_from_wav(&original_data)?,
         "wv" => strip_smed_from_wavpack(&original_data)?,
         _ => return Err(anyhow::anyhow!("Unsupported file format: {}", extension)),
@@ -122,6 +126,84 @@ pub fn strip_soundminer_metadata(file_path: &str) -> R<()> {
     Ok(())
 }
 
+/// Maximum length difference (in frames) tolerated by `null_test` after alignment
+const NULL_TEST_LENGTH_TOLERANCE: usize = 16;
+
+/// Subtract `path_b` from `path_a` and return the residual peak in dBFS.
+/// Identical audio nulls to `f32::NEG_INFINITY`. B is resampled to A's rate if needed.
+pub fn null_test(path_a: &str, path_b: &str) -> R<f32> {
+    let a = Codex::new(path_a)?.decode()?;
+    let b = Codex::new(path_b)?.decode()?;
+    let mut a = a
+        .buffer
+        .ok_or_else(|| anyhow::anyhow!("No audio decoded from {}", path_a))?;
+    let mut b = b
+        .buffer
+        .ok_or_else(|| anyhow::anyhow!("No audio decoded from {}", path_b))?;
+
+    if a.data.len() != b.data.len() {
+        return Err(anyhow::anyhow!(
+            "Channel count mismatch: {} vs {}",
+            a.data.len(),
+            b.data.len()
+        ));
+    }
+    b.resample(a.sample_rate);
+
+    let len_a = a.data.first().map_or(0, |c| c.len());
+    let len_b = b.data.first().map_or(0, |c| c.len());
+    if len_a.abs_diff(len_b) > NULL_TEST_LENGTH_TOLERANCE {
+        return Err(anyhow::anyhow!(
+            "Length mismatch: {} vs {} frames",
+            len_a,
+            len_b
+        ));
+    }
+
+    let frames = len_a.min(len_b);
+    for channel in a.data.iter_mut().chain(b.data.iter_mut()) {
+        channel.truncate(frames);
+    }
+
+    b.invert_polarity(None);
+    a.mix(&b)?;
+
+    let peak = a
+        .data
+        .iter()
+        .flatten()
+        .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+
+    Ok(if peak == 0.0 {
+        f32::NEG_INFINITY
+    } else {
+        20.0 * peak.log10()
+    })
+}
+
+/// Set every field in `fields` on each file in `paths` and embed it in place.
+/// Files are processed in parallel, and each gets its own result so one
+/// failure doesn't abort the rest of the batch.
+pub fn apply_metadata_batch(
+    paths: &[&str],
+    fields: &std::collections::HashMap<String, String>,
+) -> Vec<(String, R<()>)> {
+    paths
+        .par_iter()
+        .map(|&path| {
+            let result = (|| -> R<()> {
+                let mut codex = Codex::new(path)?.extract_metadata()?;
+                for (key, value) in fields {
+                    codex.set_metadata_field(key, value)?;
+                }
+                codex.embed_metadata()?;
+                Ok(())
+            })();
+            (path.to_string(), result)
+        })
+        .collect()
+}
+
 pub fn get_basic_metadata(path: &str) -> R<FileInfo> {
     let codex = Codex::new(path)?.extract_metadata()?;
     codex.get_file_info()
@@ -131,19 +213,116 @@ pub fn get_basic_metadata(path: &str) -> R<FileInfo> {
 pub struct FileInfo {
     pub path: String,
     pub size: usize,
-    pub sample_rate: u16,
+    pub sample_rate: u32,
     pub channels: u16,
     pub bit_depth: u16,
     pub duration: String,
+    pub duration_secs: f64,
     pub description: String,
 }
 
+/// Result of a deep health check from `Codec::validate_audio`
+#[derive(Debug, Default, Clone)]
+pub struct ValidationReport {
+    pub issues: Vec<String>,
+    pub decoded: bool,
+}
+
+impl ValidationReport {
+    pub fn add_issue(&mut self, issue: impl Into<String>) {
+        self.issues.push(issue.into());
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// One difference reported by `Codex::metadata_diff`; `None` means absent on that side
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub key: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// Frames per channel in each block yielded by `Codec::decode_stream`
+pub const STREAM_BLOCK_FRAMES: usize = 8192;
+
+/// Layout of a `Codec::decode_stream`, known before any frames are decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamHeader {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub format: SampleFormat,
+    /// Frames per channel, when the container declares it
+    pub total_frames: Option<u64>,
+}
+
+/// De-interleaved blocks of at most `STREAM_BLOCK_FRAMES` frames
+pub type FrameStream<'a> = Box<dyn Iterator<Item = R<Vec<Vec<f32>>>> + 'a>;
+
+/// Split a fully decoded buffer into stream blocks
+fn buffer_stream<'a>(buffer: AudioBuffer) -> (StreamHeader, FrameStream<'a>) {
+    let frames = buffer.data.first().map_or(0, |channel| channel.len());
+    let header = StreamHeader {
+        sample_rate: buffer.sample_rate,
+        channels: buffer.channels,
+        format: buffer.format,
+        total_frames: Some(frames as u64),
+    };
+    let blocks = (0..frames).step_by(STREAM_BLOCK_FRAMES).map(move |start| {
+        let end = (start + STREAM_BLOCK_FRAMES).min(frames);
+        Ok(buffer
+            .data
+            .iter()
+            .map(|channel| channel[start..end].to_vec())
+            .collect())
+    });
+    (header, Box::new(blocks))
+}
+
+/// Gather frames `[start, end)` of a stream into one buffer, reading no
+/// further than `end`
+fn collect_range(
+    header: StreamHeader,
+    blocks: FrameStream<'_>,
+    start: u64,
+    end: u64,
+) -> R<AudioBuffer> {
+    let mut data = vec![Vec::new(); header.channels as usize];
+    let mut position = 0u64;
+    for block in blocks {
+        if position >= end {
+            break;
+        }
+        let block = block?;
+        let len = block.first().map_or(0, |channel| channel.len()) as u64;
+        let from = start.saturating_sub(position).min(len) as usize;
+        let to = (end - position).min(len) as usize;
+        for (channel, samples) in data.iter_mut().zip(&block) {
+            channel.extend_from_slice(&samples[from..to.max(from)]);
+        }
+        position += len;
+    }
+    Ok(AudioBuffer {
+        sample_rate: header.sample_rate,
+        channels: header.channels,
+        format: header.format,
+        data,
+    })
+}
+
 #[derive(Default)]
 pub struct Codex {
     pub path: PathBuf,
     pub buffer: Option<AudioBuffer>,
     pub metadata: Option<Metadata>,
     pub codec: Option<Box<dyn Codec>>,
+    stats: Option<AudioStats>,
+    /// Set by `open_readonly`; later decodes read from this mapping
+    mapped: Option<memmap2::Mmap>,
+    info: Option<FileInfo>,
 }
 
 impl Codex {
@@ -156,19 +335,97 @@ impl Codex {
             ));
         }
 
+        // Surface unsupported extensions here rather than as a vague failure on decode
+        let codec = get_codec(input_file)
+            .map_err(|e| anyhow::anyhow!("Cannot open {}: {}", path.display(), e))?;
+
         // let mut codex = Self::default();
         Ok(Self {
             path,
-            codec: get_codec(input_file).ok(),
+            codec: Some(codec),
             metadata: None,
             buffer: None,
+            stats: None,
+            mapped: None,
+            info: None,
         })
     }
 
-    fn open(input_file: &str) -> R<Self> {
+    /// Decode the audio and extract the metadata in one step. The processing
+    /// methods return `&mut Self`, so a conversion reads as one chain:
+    ///
+    /// ```
+    /// use ffcodex_lib::prelude::*;
+    ///
+    /// let buffer = AudioBuffer {
+    ///     sample_rate: 44100,
+    ///     channels: 2,
+    ///     format: SampleFormat::I16,
+    ///     data: vec![(0..44100).map(|i| (i as f32 * 0.05).sin() * 0.25).collect(); 2],
+    /// };
+    /// let id = rand::random::<u32>();
+    /// let src = std::env::temp_dir().join(format!("ffcodex_chain_{id}_src.wav"));
+    /// let dst = std::env::temp_dir().join(format!("ffcodex_chain_{id}_dst.wav"));
+    /// let (src, dst) = (src.to_str().unwrap(), dst.to_str().unwrap());
+    /// WavCodec.encode_file(&Some(buffer), src)?;
+    ///
+    /// Codex::open(src)?
+    ///     .resample(48000)?
+    ///     .change_bit_depth(24, DitherMode::Triangular)?
+    ///     .normalize_peak(-1.0)?
+    ///     .set_category("doors", "wood", "DOORWood")?
+    ///     .export(dst)?;
+    ///
+    /// let converted = Codex::open(dst)?;
+    /// let buffer = converted.buffer.as_ref().unwrap();
+    /// assert_eq!(buffer.sample_rate, 48000);
+    /// assert_eq!(buffer.format, SampleFormat::I24);
+    /// assert!((buffer.stats().peak_dbfs() + 1.0).abs() < 0.01);
+    /// assert_eq!(converted.get_metadata_field("CATEGORY").as_deref(), Some("DOORS"));
+    /// # std::fs::remove_file(src)?;
+    /// # std::fs::remove_file(dst)?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn open(input_file: &str) -> R<Self> {
         Self::new(input_file)?.decode()?.extract_metadata()
     }
 
+    /// Map the file for viewing without decoding anything. Metadata and file info
+    /// are parsed on first use (`load_metadata`, `file_info`); audio only on `decode`.
+    pub fn open_readonly(input_file: &str) -> R<Self> {
+        let mut codex = Self::new(input_file)?;
+        let codec = codex
+            .codec
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No codec available for file: {}", input_file))?;
+        let file = std::fs::File::open(&codex.path)?;
+        let mapped_file = unsafe { MmapOptions::new().map(&file)? };
+        codec.validate_file_format(&mapped_file)?;
+        codex.mapped = Some(mapped_file);
+        Ok(codex)
+    }
+
+    /// Decode the audio and parse the metadata of an in-memory file, choosing
+    /// the codec from `extension` (e.g. "wv") as a file's extension would.
+    /// Nothing is read from or written to disk; `path` is left empty.
+    pub fn from_bytes(data: Vec<u8>, extension: &str) -> R<Self> {
+        let codec = get_codec(&format!("input.{}", extension))?;
+        codec.validate_file_format(&data)?;
+        let buffer = codec.decode(&data)?;
+        let metadata = codec.parse_metadata(&data)?;
+        let mut codex = Self {
+            path: PathBuf::new(),
+            codec: Some(codec),
+            metadata: Some(metadata),
+            buffer: Some(buffer),
+            stats: None,
+            mapped: None,
+            info: None,
+        };
+        codex.stamp_pcm_md5()?;
+        Ok(codex)
+    }
+
     pub fn decode(mut self) -> R<Self> {
         let codec = self.codec.as_ref().ok_or_else(|| {
             anyhow::anyhow!(
@@ -176,9 +433,17 @@ impl Codex {
                 self.path.display()
             )
         })?;
-        let file = std::fs::File::open(&self.path)?;
-        let mapped_file = unsafe { MmapOptions::new().map(&file)? };
-        self.buffer = Some(codec.decode(&mapped_file)?);
+        let buffer = match &self.mapped {
+            Some(mapped_file) => codec.decode(mapped_file)?,
+            // By path, so a hybrid WavPack file is decoded with the .wvc beside it
+            None => codec.decode_file(self.path.to_str().ok_or_else(|| {
+                anyhow::anyhow!("Path contains invalid UTF-8: {}", self.path.display())
+            })?)?,
+        };
+        self.buffer = Some(buffer);
+        self.stats = None;
+        self.sync_channels();
+        self.stamp_pcm_md5()?;
         Ok(self)
     }
 
@@ -194,33 +459,40 @@ impl Codex {
             .to_str()
             .ok_or_else(|| anyhow::anyhow!("Path contains invalid UTF-8: {}", self.path.display()))?;
         self.metadata = Some(codec.extract_metadata_from_file(path_str)?);
+        self.sync_channels();
+        self.stamp_pcm_md5()?;
         Ok(self)
     }
 
-    pub fn embed_metadata(&self) -> R<()> {
-        let metadata = match &self.metadata {
-            Some(metadata) => metadata,
-            None => return Err(anyhow::anyhow!("No metadata available to embed")),
-        };
-
-        let Some(codec) = &self.codec else {
-            return Err(anyhow::anyhow!(
-                "No codec available for embedding metadata in file: {}",
-                self.path.display()
-            ));
-        };
+    /// Write the loaded metadata back into the source file in place. Returns a
+    /// warning for each field the format could only store truncated.
+    pub fn embed_metadata(&self) -> R<Vec<String>> {
+        let (metadata, codec, path_str) = self.embed_target()?;
+        codec.embed_metadata_to_file(path_str, metadata)?;
+        Ok(codec.embed_warnings(metadata))
+    }
 
-        let path_str = self
-            .path
-            .to_str()
-            .ok_or_else(|| anyhow::anyhow!("Path contains invalid UTF-8: {}", self.path.display()))?;
-        codec.embed_metadata_to_file(path_str, metadata)
+    #[deprecated(note = "use embed_metadata_as")]
+    pub fn embed_metadata_to_different_file(&self, file_path: &str) -> R<Vec<String>> {
+        self.embed_metadata_as(file_path)
     }
-    pub fn embed_metadata_to_different_file(&self, file_path: &str) -> R<()> {
+
+    /// Write the metadata to `file_path`, leaving the source file untouched.
+    ///
+    /// With a decoded buffer the audio is encoded in the output's format. Without
+    /// one, an existing `file_path` is tagged as-is, or the source file is copied
+    /// there first when both share a format. Returns truncation warnings as
+    /// `embed_metadata` does.
+    pub fn embed_metadata_as(&self, file_path: &str) -> R<Vec<String>> {
         let metadata = match &self.metadata {
             Some(metadata) => metadata,
             None => return Err(anyhow::anyhow!("No metadata available to embed")),
         };
+        if file_path.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No output path given for embedding metadata"
+            ));
+        }
 
         // Get codec based on OUTPUT file extension, not input file
         let output_codec = get_codec(file_path)?;
@@ -239,18 +511,168 @@ impl Codex {
             // Then embed metadata to the newly created file
             output_codec.embed_metadata_to_file(file_path, &updated_metadata)?;
         } else {
-            // If no audio buffer, just embed metadata (this might fail for some formats)
+            if !std::path::Path::new(file_path).exists() {
+                let same_format = self
+                    .codec
+                    .as_ref()
+                    .is_some_and(|codec| codec.as_str() == output_codec.as_str());
+                if !same_format || !self.path.exists() {
+                    return Err(anyhow::anyhow!(
+                        "No audio to write to {}: decode() the source first to change format",
+                        file_path
+                    ));
+                }
+                std::fs::copy(&self.path, file_path)?;
+            }
             output_codec.embed_metadata_to_file(file_path, metadata)?;
         }
 
-        Ok(())
+        Ok(output_codec.embed_warnings(metadata))
+    }
+
+    /// Embed the loaded metadata into the source file without keeping a backup.
+    ///
+    /// The source is copied to a temp file beside it and tagged there. The temp
+    /// file replaces the original only after it re-parses with every field that
+    /// differs from what is on disk now. On any failure the original is left
+    /// untouched.
+    pub fn embed_metadata_verified(&self) -> R<()> {
+        let (metadata, codec, path_str) = self.embed_target()?;
+        let before = codec.extract_metadata_from_file(path_str)?;
+        let changed: Vec<(&String, &String)> = metadata
+            .get_all_fields()
+            .iter()
+            // Technical fields such as PCM_MD5 describe the audio and are never written
+            .filter(|(key, _)| !codecs::is_technical_field(key))
+            .filter(|(key, value)| before.get_field(key).as_deref() != Some(value.as_str()))
+            .collect();
+
+        self.embed_metadata_checked(&|written: &Metadata| {
+            for (key, value) in &changed {
+                if written.get_field(key).as_deref().map(str::trim) != Some(value.trim()) {
+                    return Err(anyhow::anyhow!(
+                        "Verification failed: field '{}' did not read back as written",
+                        key
+                    ));
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Preview what embedding the loaded metadata into `file_path` would change,
+    /// without writing anything. Fields are compared by stored key (aliases are
+    /// listed separately) and sorted by key; images are reported under `IMAGE`.
+    /// Technical stream fields such as `WAVPACK_MODE` are not user-editable and
+    /// are left out.
+    pub fn metadata_diff(&self, file_path: &str) -> R<Vec<FieldChange>> {
+        let metadata = self
+            .metadata
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No metadata available to compare"))?;
+        let current = get_codec(file_path)?.extract_metadata_from_file(file_path)?;
+
+        let old_fields = current.get_all_fields();
+        let new_fields = metadata.get_all_fields();
+        let mut keys: Vec<&String> = old_fields
+            .keys()
+            .chain(new_fields.keys())
+            .filter(|key| !codecs::is_technical_field(key))
+            .collect();
+        keys.sort();
+        keys.dedup();
+        let mut changes: Vec<FieldChange> = keys
+            .into_iter()
+            .filter(|key| old_fields.get(*key) != new_fields.get(*key))
+            .map(|key| FieldChange {
+                key: key.clone(),
+                old: old_fields.get(key).cloned(),
+                new: new_fields.get(key).cloned(),
+            })
+            .collect();
+
+        let describe = |image: &ImageChunk| {
+            format!(
+                "{:?} {} ({} bytes)",
+                image.picture_type(),
+                image.mime_type(),
+                image.data().len()
+            )
+        };
+        let removed = current
+            .get_images()
+            .iter()
+            .filter(|image| !metadata.get_images().contains(image))
+            .map(|image| (Some(describe(image)), None));
+        let added = metadata
+            .get_images()
+            .iter()
+            .filter(|image| !current.get_images().contains(image))
+            .map(|image| (None, Some(describe(image))));
+        changes.extend(removed.chain(added).map(|(old, new)| FieldChange {
+            key: "IMAGE".to_string(),
+            old,
+            new,
+        }));
+        Ok(changes)
+    }
+
+    fn embed_metadata_checked(&self, verify: &dyn Fn(&Metadata) -> R<()>) -> R<()> {
+        let (metadata, codec, path_str) = self.embed_target()?;
+        let extension = self
+            .path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("");
+        // Same directory as the source so the final rename stays on one filesystem
+        let temp = self.path.with_extension(format!(
+            "ffcodex-{:08x}.{}",
+            rand::random::<u32>(),
+            extension
+        ));
+        let temp_str = temp
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("Path contains invalid UTF-8: {}", temp.display()))?;
+
+        let result = (|| -> R<()> {
+            std::fs::copy(path_str, &temp)?;
+            codec.embed_metadata_to_file(temp_str, metadata)?;
+            verify(&codec.extract_metadata_from_file(temp_str)?)?;
+            std::fs::rename(&temp, path_str)?;
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temp);
+        }
+        result
+    }
+
+    fn embed_target(&self) -> R<(&Metadata, &dyn Codec, &str)> {
+        let Some(metadata) = &self.metadata else {
+            return Err(anyhow::anyhow!("No metadata available to embed"));
+        };
+        if self.path.as_os_str().is_empty() {
+            return Err(anyhow::anyhow!(
+                "Codex has no source file to embed into, use embed_metadata_as"
+            ));
+        }
+        let Some(codec) = &self.codec else {
+            return Err(anyhow::anyhow!(
+                "No codec available for embedding metadata in file: {}",
+                self.path.display()
+            ));
+        };
+        let path_str = self.path.to_str().ok_or_else(|| {
+            anyhow::anyhow!("Path contains invalid UTF-8: {}", self.path.display())
+        })?;
+        Ok((metadata, codec.as_ref(), path_str))
     }
 
-    pub fn set_metadata_field(&mut self, key: &str, value: &str) -> R<()> {
+    pub fn set_metadata_field(&mut self, key: &str, value: &str) -> R<&mut Self> {
         match &mut self.metadata {
             Some(metadata) => {
                 metadata.set_field(key, value)?;
-                Ok(())
+                Ok(self)
             }
             None => Err(anyhow::anyhow!(
                 "No metadata available to set field: {}",
@@ -259,6 +681,101 @@ impl Codex {
         }
     }
 
+    /// Remove a field (and its aliases and derived fields) from the loaded metadata,
+    /// returning its previous value
+    pub fn remove_metadata_field(&mut self, key: &str) -> Option<String> {
+        self.metadata.as_mut()?.remove_field(key)
+    }
+
+    /// Remove every content field and image from the loaded metadata
+    pub fn clear_metadata(&mut self) -> R<()> {
+        match &mut self.metadata {
+            Some(metadata) => {
+                metadata.clear_fields();
+                Ok(())
+            }
+            None => Err(anyhow::anyhow!("No metadata available to clear")),
+        }
+    }
+
+    /// Write the loaded metadata to `path` as JSON; see `Metadata::to_json`
+    pub fn export_metadata_json(&self, path: &str) -> R<()> {
+        let Some(metadata) = &self.metadata else {
+            return Err(anyhow::anyhow!("No metadata available to export"));
+        };
+        std::fs::write(path, metadata.to_json()?)?;
+        Ok(())
+    }
+
+    /// Replace the loaded metadata with the contents of a JSON file written by
+    /// `export_metadata_json`, e.g. after editing it, ready to embed or export
+    pub fn apply_metadata_json(&mut self, path: &str) -> R<&mut Self> {
+        self.metadata = Some(Metadata::from_json(&std::fs::read_to_string(path)?)?);
+        Ok(self)
+    }
+
+    /// `open` the audio file with its metadata taken from a JSON file instead
+    /// of the file's own tags
+    pub fn open_with_metadata_json(input_file: &str, json_file: &str) -> R<Self> {
+        let mut codex = Self::new(input_file)?.decode()?;
+        codex.apply_metadata_json(json_file)?;
+        codex.sync_channels();
+        Ok(codex)
+    }
+
+    /// Write the loaded metadata as a standalone iXML file, the same document
+    /// `embed_metadata` would put in the audio file. `path` defaults to the
+    /// audio file with an `.xml` extension; returns the path written.
+    pub fn write_ixml_sidecar(&self, path: Option<&str>) -> R<PathBuf> {
+        let Some(metadata) = &self.metadata else {
+            return Err(anyhow::anyhow!("No metadata available to export"));
+        };
+        let path = self.sidecar_path(path)?;
+        // A Codex built from a bare buffer has no codec; WAV is the iXML home format
+        let codec = self.codec.as_deref().unwrap_or(&WavCodec);
+        std::fs::write(&path, codec.create_ixml(metadata)?)?;
+        Ok(path)
+    }
+
+    /// Parse an iXML sidecar into the loaded metadata, exactly as an embedded
+    /// iXML chunk would be. `path` defaults as in `write_ixml_sidecar`.
+    pub fn read_ixml_sidecar(&mut self, path: Option<&str>) -> R<&mut Self> {
+        let path = self.sidecar_path(path)?;
+        let ixml = std::fs::read(&path)?;
+        self.metadata
+            .get_or_insert_with(Metadata::new)
+            .parse_ixml(&String::from_utf8_lossy(&ixml))?;
+        Ok(self)
+    }
+
+    fn sidecar_path(&self, path: Option<&str>) -> R<PathBuf> {
+        match path {
+            Some(path) => Ok(PathBuf::from(path)),
+            None if self.path.as_os_str().is_empty() => Err(anyhow::anyhow!(
+                "Codex has no source file to name the sidecar after, pass a path"
+            )),
+            None => Ok(self.path.with_extension("xml")),
+        }
+    }
+
+    /// Overlay the metadata of another file, such as a sidecar or a house
+    /// template, onto the loaded metadata; see `Metadata::merge`
+    pub fn merge_metadata_from(&mut self, path: &str, strategy: MergeStrategy) -> R<&mut Self> {
+        let overlay = get_codec(path)?.extract_metadata_from_file(path)?;
+        self.metadata
+            .get_or_insert_with(Metadata::new)
+            .merge(&overlay, strategy)?;
+        Ok(self)
+    }
+
+    /// Write an arbitrary chunk verbatim on the next export or embed, placed among the
+    /// metadata chunks (a binary tag named after `id` in WavPack)
+    pub fn add_raw_chunk(&mut self, id: [u8; 4], data: Vec<u8>) -> R<()> {
+        self.metadata
+            .get_or_insert_with(Metadata::new)
+            .add_raw_chunk(id, data)
+    }
+
     pub fn get_metadata_field(&self, key: &str) -> Option<String> {
         match &self.metadata {
             Some(metadata) => metadata.get_field(key),
@@ -273,19 +790,22 @@ impl Codex {
             .unwrap_or("unknown")
     }
 
-    fn resample(&mut self, new_rate: u32) -> R<()> {
+    pub fn resample(&mut self, new_rate: u32) -> R<&mut Self> {
         if let Some(buffer) = &mut self.buffer {
             buffer.resample(new_rate);
-            Ok(())
+            self.stats = None;
+            Ok(self)
         } else {
             Err(anyhow::anyhow!("No audio buffer available for resampling"))
         }
     }
 
-    fn change_bit_depth(&mut self, new_bit_depth: u16) -> R<()> {
+    /// Requantize the decoded audio; see `AudioBuffer::change_bit_depth`
+    pub fn change_bit_depth(&mut self, new_bit_depth: u16, dither: DitherMode) -> R<&mut Self> {
         if let Some(buffer) = &mut self.buffer {
-            buffer.change_bit_depth(new_bit_depth);
-            Ok(())
+            buffer.change_bit_depth(new_bit_depth, dither);
+            self.stats = None;
+            Ok(self)
         } else {
             Err(anyhow::anyhow!(
                 "No audio buffer available for changing bit depth"
@@ -293,48 +813,95 @@ impl Codex {
         }
     }
 
+    /// Convert the decoded audio to `format` (dithering on bit depth reduction)
+    /// and update the loaded metadata to match, ready for export
+    pub fn set_format(&mut self, format: SampleFormat, dither: DitherMode) -> R<&mut Self> {
+        let Some(buffer) = &mut self.buffer else {
+            return Err(anyhow::anyhow!(
+                "No audio buffer available for format change"
+            ));
+        };
+        buffer.set_format(format, dither);
+        self.stats = None;
+
+        if let (Some(metadata), Some(buffer)) = (&self.metadata, &self.buffer) {
+            self.metadata = Some(self.update_metadata_from_buffer(metadata, buffer));
+        }
+        Ok(self)
+    }
+
     pub fn export(&self, output_file: &str) -> R<()> {
-        let temp_file = std::env::temp_dir().join("temp_audio_file");
+        self.export_with_options(output_file, &EncodeOptions::default())
+    }
+
+    pub fn export_with_options(&self, output_file: &str, options: &EncodeOptions) -> R<()> {
+        let synthesized = self.synthesized_metadata(options)?;
+        let source_metadata = synthesized.as_ref().or(self.metadata.as_ref());
+
+        // Apply buffer-level export options (e.g. force_mono) without touching self.buffer
+        let prepared = self
+            .buffer
+            .as_ref()
+            .and_then(|buffer| options.prepare_buffer(buffer));
+        let buffer = if prepared.is_some() {
+            &prepared
+        } else {
+            &self.buffer
+        };
+
+        let temp_file =
+            std::env::temp_dir().join(format!("ffcodex_export_{}", rand::random::<u32>()));
         let temp_path = temp_file.to_str().unwrap_or("");
+        let temp_correction = temp_file.with_extension("wvc");
 
         match get_codec(output_file) {
             Ok(codec) => {
-                // Check if this is a WavPack file and we have metadata - optimize for single encoding
+                // Check if this is a WavPack file - encode audio, metadata and options in one pass
                 let extension = std::path::Path::new(output_file)
                     .extension()
                     .and_then(|ext| ext.to_str())
                     .unwrap_or("")
                     .to_lowercase();
 
-                if extension == "wv" && self.metadata.is_some() {
+                if extension == "wv" {
                     // WavPack optimization: encode with metadata in one pass
                     let final_metadata =
-                        if let (Some(buffer), Some(metadata)) = (&self.buffer, &self.metadata) {
+                        if let (Some(buffer), Some(metadata)) = (buffer, source_metadata) {
                             Some(self.update_metadata_from_buffer(metadata, buffer))
                         } else {
-                            self.metadata.clone()
+                            source_metadata.cloned()
                         };
 
-                    // Cast to WvCodec to access encode_with_metadata
+                    // Cast to WvCodec to access encode_with_options
                     if let Some(wv_codec) = codec.as_any().downcast_ref::<crate::codecs::WvCodec>()
                     {
-                        let encoded_data = wv_codec
-                            .encode_with_metadata(&self.buffer, &final_metadata.as_ref())?;
+                        let (encoded_data, correction) = wv_codec.encode_with_correction(
+                            buffer,
+                            &final_metadata.as_ref(),
+                            options,
+                        )?;
                         std::fs::write(temp_path, encoded_data)?;
+                        // The hybrid correction file goes beside the .wv once both are written
+                        if let Some(correction) = correction {
+                            std::fs::write(&temp_correction, correction)?;
+                        }
                     } else {
                         // Fallback to standard approach
-                        codec.encode_file(&self.buffer, temp_path)?;
+                        codec.encode_file(buffer, temp_path)?;
                         if let Some(metadata) = &final_metadata {
                             codec.embed_metadata_to_file(temp_path, metadata)?;
                         }
                     }
                 } else {
                     // Standard approach for other formats
-                    codec.encode_file(&self.buffer, temp_path)?;
+                    match Self::encode_for_export(codec.as_ref(), &extension, buffer, options)? {
+                        Some(encoded) => std::fs::write(temp_path, encoded)?,
+                        None => codec.encode_file(buffer, temp_path)?,
+                    }
 
                     // Embed metadata if available, updating it with current buffer info
-                    if let Some(metadata) = &self.metadata {
-                        if let Some(buffer) = &self.buffer {
+                    if let Some(metadata) = source_metadata {
+                        if let Some(buffer) = buffer {
                             let updated_metadata =
                                 self.update_metadata_from_buffer(metadata, buffer);
                             codec.embed_metadata_to_file(temp_path, &updated_metadata)?;
@@ -342,41 +909,752 @@ impl Codex {
                             codec.embed_metadata_to_file(temp_path, metadata)?;
                         }
                     }
-                }
+                }
+            }
+            Err(error) => return Err(error),
+        }
+
+        move_into_place(&temp_file, std::path::Path::new(output_file))?;
+        if temp_correction.exists() {
+            let correction_file = std::path::Path::new(output_file).with_extension("wvc");
+            move_into_place(&temp_correction, &correction_file)?;
+        }
+        Ok(())
+    }
+
+    /// `export_verified_with_options` with the default options: `output_file`
+    /// is only replaced once the written audio is checked against the buffer.
+    pub fn export_verified(&self, output_file: &str) -> R<()> {
+        self.export_verified_with_options(output_file, &EncodeOptions::default())
+    }
+
+    /// Export to a temp file beside `output_file`, decode it again and check it
+    /// against the buffer being exported before it replaces `output_file`.
+    ///
+    /// Every file must match the sample rate and channel count. Lossless files
+    /// must also match the sample count and the MD5 of the buffer's PCM at the
+    /// written bit depth; lossy ones (Opus, WavPack hybrid) can't. On any
+    /// failure the temp file is removed and `output_file` is left as it was.
+    pub fn export_verified_with_options(
+        &self,
+        output_file: &str,
+        options: &EncodeOptions,
+    ) -> R<()> {
+        let prepared = self
+            .buffer
+            .as_ref()
+            .and_then(|buffer| options.prepare_buffer(buffer));
+        let buffer = if prepared.is_some() {
+            &prepared
+        } else {
+            &self.buffer
+        };
+        let Some(expected) = buffer else {
+            return Err(anyhow::anyhow!("No audio buffer available to export"));
+        };
+        let codec = get_codec(output_file)?;
+
+        let output = std::path::Path::new(output_file);
+        let extension = output
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("");
+        // Same directory as the output so the final rename stays on one filesystem
+        let temp = output.with_extension(format!(
+            "ffcodex-{:08x}.{}",
+            rand::random::<u32>(),
+            extension
+        ));
+        let temp_str = temp
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("Path contains invalid UTF-8: {}", temp.display()))?;
+        // A hybrid WavPack export writes its correction file beside the temp file
+        let temp_correction = temp.with_extension("wvc");
+
+        let result = (|| -> R<()> {
+            self.export_with_options(temp_str, options)?;
+            let written = codec.decode_file(temp_str)?;
+            let lossless = codec.is_lossless(temp_str)?;
+            let frames = |buffer: &AudioBuffer| buffer.data.first().map_or(0, Vec::len);
+
+            let mut checks = vec![
+                (
+                    "sample rate",
+                    expected.sample_rate as usize,
+                    written.sample_rate as usize,
+                ),
+                ("channel count", expected.data.len(), written.data.len()),
+            ];
+            if lossless {
+                checks.push(("sample count", frames(expected), frames(&written)));
+            }
+            for (what, wanted, found) in checks {
+                if wanted != found {
+                    return Err(anyhow::anyhow!(
+                        "Verification failed: {} of the written file is {}, expected {}",
+                        what,
+                        found,
+                        wanted
+                    ));
+                }
+            }
+
+            if lossless {
+                // The source as the written format stores it, not a re-encode
+                // that would repeat any encoder fault
+                let expected = AudioBuffer {
+                    format: written.format,
+                    ..expected.clone()
+                };
+                if written.pcm_md5() != expected.pcm_md5() {
+                    return Err(anyhow::anyhow!(
+                        "Verification failed: PCM checksum of the written file does not match"
+                    ));
+                }
+            }
+
+            std::fs::rename(&temp, output_file)?;
+            if temp_correction.exists() {
+                std::fs::rename(&temp_correction, output.with_extension("wvc"))?;
+            }
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = std::fs::remove_file(&temp);
+            let _ = std::fs::remove_file(&temp_correction);
+        }
+        result
+    }
+
+    /// Optionally synthesize minimal broadcast metadata so exports always carry a bext
+    fn synthesized_metadata(&self, options: &EncodeOptions) -> R<Option<Metadata>> {
+        let synthesized = match (&self.metadata, options.synthesize_missing_metadata) {
+            (None, true) => Some(Metadata::synthesized(options.originator.as_deref())?),
+            (Some(metadata), _) if options.fill_origination_date_time => {
+                let mut stamped = metadata.clone();
+                stamped.fill_origination_date_time()?.then_some(stamped)
+            }
+            _ => None,
+        };
+        Ok(synthesized)
+    }
+
+    /// Encodings chosen by `options` or the extension rather than the codec's
+    /// plain `encode`; `None` when the plain encode applies.
+    fn encode_for_export(
+        codec: &dyn Codec,
+        extension: &str,
+        buffer: &Option<AudioBuffer>,
+        options: &EncodeOptions,
+    ) -> R<Option<Vec<u8>>> {
+        let flac = codec.as_any().downcast_ref::<FlacCodec>();
+        Ok(match (flac, &options.flac) {
+            (Some(flac), flac_options) if options.store_md5 => {
+                Some(flac.encode_with_md5(buffer, &flac_options.unwrap_or_default())?)
+            }
+            (Some(flac), Some(flac_options)) => {
+                Some(flac.encode_with_options(buffer, flac_options)?)
+            }
+            // Integer audio is AIFF by default; a .aifc export asks for the AIFC form
+            _ if extension.eq_ignore_ascii_case("aifc") => Some(AifCodec.encode_aifc(buffer)?),
+            _ => None,
+        })
+    }
+
+    /// Export in the format for `extension` (e.g. "wav") to any writer, such as
+    /// stdout in a pipeline. Untagged audio is encoded straight into the writer
+    /// with `encode_to_writer`; tagged audio is encoded and embedded in memory
+    /// first. Hybrid WavPack needs a .wvc beside the file, so it can't be streamed.
+    pub fn export_to_writer(
+        &self,
+        writer: &mut dyn Write,
+        extension: &str,
+        options: &EncodeOptions,
+    ) -> R<()> {
+        let codec = get_codec(&format!("output.{}", extension))?;
+        let synthesized = self.synthesized_metadata(options)?;
+        let prepared = self
+            .buffer
+            .as_ref()
+            .and_then(|buffer| options.prepare_buffer(buffer));
+        let buffer = if prepared.is_some() {
+            &prepared
+        } else {
+            &self.buffer
+        };
+        let metadata = match (synthesized.as_ref().or(self.metadata.as_ref()), buffer) {
+            (Some(metadata), Some(buffer)) => {
+                Some(self.update_metadata_from_buffer(metadata, buffer))
+            }
+            (metadata, _) => metadata.cloned(),
+        };
+
+        if let Some(wv_codec) = codec.as_any().downcast_ref::<WvCodec>() {
+            let (encoded, correction) =
+                wv_codec.encode_with_correction(buffer, &metadata.as_ref(), options)?;
+            if correction.is_some() {
+                return Err(anyhow!("Hybrid WavPack can't be streamed without its .wvc"));
+            }
+            writer.write_all(&encoded)?;
+            return Ok(());
+        }
+
+        let special = Self::encode_for_export(codec.as_ref(), extension, buffer, options)?;
+        let Some(metadata) = metadata else {
+            return match special {
+                Some(encoded) => Ok(writer.write_all(&encoded)?),
+                None => codec.encode_to_writer(buffer, writer),
+            };
+        };
+        let encoded = match special {
+            Some(encoded) => encoded,
+            None => {
+                let mut encoded = Vec::new();
+                codec.encode_to_writer(buffer, &mut encoded)?;
+                encoded
+            }
+        };
+        writer.write_all(&codec.embed_metadata(&encoded, &metadata)?)?;
+        Ok(())
+    }
+
+    /// Encode in the format for `extension` (e.g. "flac") with the loaded
+    /// metadata embedded, entirely in memory. Unlike `export_to_writer`, no
+    /// export options apply.
+    pub fn to_bytes(&self, extension: &str) -> R<Vec<u8>> {
+        let codec = get_codec(&format!("output.{}", extension))?;
+        let metadata = match (&self.metadata, &self.buffer) {
+            (Some(metadata), Some(buffer)) => {
+                Some(self.update_metadata_from_buffer(metadata, buffer))
+            }
+            (metadata, _) => metadata.clone(),
+        };
+
+        // WavPack takes its tags while encoding rather than as a second pass
+        if let Some(wv_codec) = codec.as_any().downcast_ref::<WvCodec>() {
+            return wv_codec.encode_with_metadata(&self.buffer, &metadata.as_ref());
+        }
+        let encoded = if extension.eq_ignore_ascii_case("aifc") {
+            AifCodec.encode_aifc(&self.buffer)?
+        } else {
+            codec.encode(&self.buffer)?
+        };
+        match &metadata {
+            Some(metadata) => codec.embed_metadata(&encoded, metadata),
+            None => Ok(encoded),
+        }
+    }
+
+    /// Export to `output_file` only if the result would differ from what is
+    /// already there.
+    ///
+    /// The would-be output is rendered to a temp file and compared with the
+    /// existing file by audio content hash and parsed metadata. Returns whether
+    /// `output_file` was written.
+    pub fn export_if_changed(&self, output_file: &str, options: &EncodeOptions) -> R<bool> {
+        if !std::path::Path::new(output_file).exists() {
+            self.export_with_options(output_file, options)?;
+            return Ok(true);
+        }
+
+        let codec = get_codec(output_file)?;
+        let extension = std::path::Path::new(output_file)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("");
+        let candidate = std::env::temp_dir().join(format!(
+            "ffcodex_candidate_{}.{}",
+            rand::random::<u32>(),
+            extension
+        ));
+        let candidate_path = candidate.to_str().unwrap_or("");
+        self.export_with_options(candidate_path, options)?;
+
+        let unchanged = (|| -> R<bool> {
+            let existing = std::fs::read(output_file)?;
+            let rendered = std::fs::read(&candidate)?;
+            if existing == rendered {
+                return Ok(true);
+            }
+            let same_audio =
+                codec.decode(&existing)?.content_hash() == codec.decode(&rendered)?.content_hash();
+            Ok(same_audio
+                && codec
+                    .parse_metadata(&existing)?
+                    .content_eq(&codec.parse_metadata(&rendered)?))
+        })()
+        .unwrap_or(false);
+
+        if unchanged {
+            let _ = std::fs::remove_file(&candidate);
+            return Ok(false);
+        }
+
+        match std::fs::rename(&candidate, output_file) {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                let result = std::fs::copy(&candidate, output_file);
+                let _ = std::fs::remove_file(&candidate);
+                result.map(|_| true).map_err(|_| e.into())
+            }
+        }
+    }
+
+    /// Unpack a WavPack archive back to the exact file it was compressed from.
+    ///
+    /// Only works when the `.wv` stores its source header and trailer; the
+    /// extension of `output_file` must match the original container.
+    pub fn restore_original_container(&self, output_file: &str) -> R<()> {
+        let Some(wv_codec) = self
+            .codec
+            .as_ref()
+            .and_then(|codec| codec.as_any().downcast_ref::<WvCodec>())
+        else {
+            return Err(anyhow::anyhow!(
+                "Only WavPack files store an original container: {}",
+                self.path.display()
+            ));
+        };
+
+        let (original, extension) = wv_codec.restore_original(&std::fs::read(&self.path)?)?;
+        let output_extension = std::path::Path::new(output_file)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("");
+        if !output_extension.eq_ignore_ascii_case(&extension) {
+            return Err(anyhow::anyhow!(
+                "Original container is .{}, cannot restore to {}",
+                extension,
+                output_file
+            ));
+        }
+
+        std::fs::write(output_file, original)?;
+        Ok(())
+    }
+
+    /// Hash of the decoded audio, see `AudioBuffer::content_hash`
+    pub fn audio_content_hash(&self) -> R<String> {
+        if let Some(buffer) = &self.buffer {
+            return Ok(buffer.content_hash());
+        }
+        let codec = self.codec.as_ref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "No codec available for decoding audio file: {}",
+                self.path.display()
+            )
+        })?;
+        Ok(codec.decode(&std::fs::read(&self.path)?)?.content_hash())
+    }
+
+    /// Make an exact working copy of the source file at `output_file`.
+    ///
+    /// Unlike `export`, nothing is re-encoded or rebuilt: the original bytes are
+    /// copied so every chunk and metadata block survives untouched. The copy is
+    /// then verified by comparing bytes, decoded audio and metadata fields.
+    pub fn clone_to(&self, output_file: &str) -> R<()> {
+        let codec = self.codec.as_ref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "No codec available for cloning audio file: {}",
+                self.path.display()
+            )
+        })?;
+
+        let output_codec = get_codec(output_file)?;
+        if output_codec.as_str() != codec.as_str() {
+            return Err(anyhow::anyhow!(
+                "clone_to cannot change format ({} -> {}), use export to transcode",
+                codec.as_str(),
+                output_codec.as_str()
+            ));
+        }
+
+        let temp_file =
+            std::env::temp_dir().join(format!("ffcodex_clone_{}", rand::random::<u32>()));
+        std::fs::copy(&self.path, &temp_file)?;
+
+        if let Err(e) = self.verify_clone(codec.as_ref(), &temp_file) {
+            let _ = std::fs::remove_file(&temp_file);
+            return Err(e);
+        }
+
+        match std::fs::rename(&temp_file, output_file) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                let result = std::fs::copy(&temp_file, output_file);
+                let _ = std::fs::remove_file(&temp_file);
+                result.map(|_| ()).map_err(|_| e.into())
+            }
+        }
+    }
+
+    fn verify_clone(&self, codec: &dyn Codec, copy: &std::path::Path) -> R<()> {
+        let source_bytes = std::fs::read(&self.path)?;
+        let copy_bytes = std::fs::read(copy)?;
+        if source_bytes != copy_bytes {
+            return Err(anyhow::anyhow!("Cloned file differs from source"));
+        }
+
+        let source_audio = match &self.buffer {
+            Some(buffer) => buffer.clone(),
+            None => codec.decode(&source_bytes)?,
+        };
+        let copy_audio = codec.decode(&copy_bytes)?;
+        if source_audio.data != copy_audio.data
+            || source_audio.sample_rate != copy_audio.sample_rate
+            || source_audio.channels != copy_audio.channels
+        {
+            return Err(anyhow::anyhow!("Cloned audio does not decode identically"));
+        }
+
+        let source_metadata = codec.parse_metadata(&source_bytes)?;
+        let copy_metadata = codec.parse_metadata(&copy_bytes)?;
+        if source_metadata.get_all_fields() != copy_metadata.get_all_fields() {
+            return Err(anyhow::anyhow!("Cloned metadata does not match source"));
+        }
+
+        Ok(())
+    }
+
+    /// Add silence before and after the audio; markers move with the audio and
+    /// the bext time reference moves back to the new first frame (not below 0)
+    pub fn pad(&mut self, head_frames: usize, tail_frames: usize) -> R<&mut Self> {
+        let Some(buffer) = &mut self.buffer else {
+            return Err(anyhow::anyhow!("No audio buffer available for padding"));
+        };
+        buffer.pad(head_frames, tail_frames);
+        self.stats = None;
+
+        if head_frames > 0
+            && let Some(metadata) = &mut self.metadata
+        {
+            if let Some(time_reference) = metadata
+                .get_field("TimeReference")
+                .and_then(|value| value.parse::<u64>().ok())
+            {
+                metadata.set_field(
+                    "TimeReference",
+                    &time_reference
+                        .saturating_sub(head_frames as u64)
+                        .to_string(),
+                )?;
+            }
+            let ids: Vec<u32> = metadata.get_markers().iter().map(|m| m.id).collect();
+            for id in ids {
+                let marker = metadata.marker_mut(id);
+                marker.position = marker.position.saturating_add(head_frames as u32);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Fade the loaded audio in and out; see `AudioBuffer::apply_fade`
+    pub fn apply_fade(
+        &mut self,
+        fade_in_frames: usize,
+        fade_out_frames: usize,
+        curve: FadeCurve,
+    ) -> R<&mut Self> {
+        let Some(buffer) = &mut self.buffer else {
+            return Err(anyhow::anyhow!("No audio buffer available for fading"));
+        };
+        buffer.apply_fade(fade_in_frames, fade_out_frames, curve);
+        self.stats = None;
+        Ok(self)
+    }
+
+    /// Keep only frames `[start, end)`. The bext time reference moves to the new
+    /// first frame; markers are shifted, and those outside the range dropped.
+    pub fn trim(&mut self, start: usize, end: usize) -> R<&mut Self> {
+        let Some(buffer) = &mut self.buffer else {
+            return Err(anyhow::anyhow!("No audio buffer available for trimming"));
+        };
+        *buffer = buffer.slice(start, end)?;
+        self.stats = None;
+        self.info = None;
+
+        if let Some(metadata) = &mut self.metadata {
+            Self::retime_metadata(metadata, start, end)?;
+        }
+        Ok(self)
+    }
+
+    /// Move TimeReference to `start` and keep only the markers inside
+    /// `[start, end)`, rebased to the new first frame
+    fn retime_metadata(metadata: &mut Metadata, start: usize, end: usize) -> R<()> {
+        if let Some(time_reference) = metadata
+            .get_field("TimeReference")
+            .and_then(|value| value.parse::<u64>().ok())
+        {
+            metadata.set_field(
+                "TimeReference",
+                &(time_reference + start as u64).to_string(),
+            )?;
+        }
+
+        let markers = metadata.markers_mut();
+        markers.retain(|m| (start..end).contains(&(m.position as usize)));
+        for marker in markers.iter_mut() {
+            let remaining = (end - marker.position as usize) as u32;
+            marker.position -= start as u32;
+            marker.length = marker.length.min(remaining);
+        }
+        Ok(())
+    }
+
+    /// Export `[start_secs, end_secs)` of the audio to `output_file`, as `trim`
+    /// followed by `export_with_options` would, without touching `self`. When
+    /// no buffer is loaded only that region of the source is decoded, seeking
+    /// where the codec supports it. The end is clamped to the audio's length.
+    pub fn export_range(
+        &self,
+        start_secs: f64,
+        end_secs: f64,
+        output_file: &str,
+        options: &EncodeOptions,
+    ) -> R<()> {
+        if !(0.0..end_secs).contains(&start_secs) {
+            return Err(anyhow::anyhow!(
+                "Invalid export range: {}s to {}s",
+                start_secs,
+                end_secs
+            ));
+        }
+
+        let (clip, metadata) = match &self.buffer {
+            Some(buffer) => {
+                let rate = buffer.sample_rate as f64;
+                let frames = buffer.data.first().map_or(0, |channel| channel.len());
+                let end = ((end_secs * rate).round() as usize).min(frames);
+                let start = ((start_secs * rate).round() as usize).min(end);
+                (buffer.slice(start, end)?, self.metadata.clone())
+            }
+            None => {
+                let codec = self.codec.as_ref().ok_or_else(|| {
+                    anyhow::anyhow!("No codec available for file: {}", self.path.display())
+                })?;
+                let source_file;
+                let source: &[u8] = match &self.mapped {
+                    Some(mapped_file) => mapped_file,
+                    None => {
+                        let file = std::fs::File::open(&self.path)?;
+                        source_file = unsafe { MmapOptions::new().map(&file)? };
+                        &source_file
+                    }
+                };
+                // The header's rate, so the audio is only decoded once, by decode_range
+                let rate = match &self.info {
+                    Some(info) => info.sample_rate,
+                    None => self.get_file_info()?.sample_rate,
+                } as f64;
+                let start = (start_secs * rate).round() as u64;
+                let end = (end_secs * rate).round() as u64;
+                let metadata = match &self.metadata {
+                    Some(metadata) => metadata.clone(),
+                    None => codec.parse_metadata(source)?,
+                };
+                (codec.decode_range(source, start, end)?, Some(metadata))
             }
-            Err(error) => return Err(error),
+        };
+        let frames = clip.data.first().map_or(0, |channel| channel.len());
+        if frames == 0 {
+            return Err(anyhow::anyhow!(
+                "Export range starts past the end of the audio: {}s",
+                start_secs
+            ));
         }
 
-        match std::fs::rename(&temp_file, output_file) {
-            Ok(_) => Ok(()),
-            Err(e) => {
-                // As a fallback, try to copy then delete
-                if let Err(_copy_err) = std::fs::copy(&temp_file, output_file) {
-                    Err(e.into()) // Return the original error
-                } else {
-                    let _ = std::fs::remove_file(&temp_file); // Try to cleanup
-                    Ok(())
-                }
+        let metadata = match metadata {
+            Some(mut metadata) => {
+                let start = (start_secs * clip.sample_rate as f64).round() as usize;
+                Self::retime_metadata(&mut metadata, start, start + frames)?;
+                Some(metadata)
             }
+            None => None,
+        };
+        Self {
+            path: self.path.clone(),
+            buffer: Some(clip),
+            metadata,
+            ..Default::default()
+        }
+        .export_with_options(output_file, options)
+    }
+
+    /// Fold surround audio down to stereo; see `AudioBuffer::downmix_to_stereo_with_mask`.
+    /// Channels are identified by the file's channel mask, read from the source
+    /// when metadata hasn't been extracted.
+    pub fn downmix_to_stereo(&mut self, coefficients: DownmixCoefficients) -> R<&mut Self> {
+        let channel_mask = match &self.metadata {
+            Some(metadata) => metadata.channel_mask,
+            None => self.source_channel_mask(),
+        };
+        let Some(buffer) = &mut self.buffer else {
+            return Err(anyhow::anyhow!("No audio buffer available for downmix"));
+        };
+        buffer.downmix_to_stereo_with_mask(coefficients, channel_mask)?;
+        self.stats = None;
+        self.sync_channels();
+
+        if let Some(metadata) = &mut self.metadata {
+            metadata.channel_mask = 0;
+            metadata.remove_field("WAVPACK_CHANNEL_MASK");
+        }
+
+        Ok(self)
+    }
+
+    /// Reduce the buffer to one channel; see `AudioBuffer::to_mono`
+    pub fn to_mono(&mut self, mode: MonoMode) -> R<&mut Self> {
+        let Some(buffer) = &mut self.buffer else {
+            return Err(anyhow::anyhow!(
+                "No audio buffer available for mono conversion"
+            ));
+        };
+        buffer.to_mono(mode)?;
+        let format = buffer.format;
+        self.stats = None;
+        self.sync_channels();
+
+        // A mono file has no speaker layout, so it is plain PCM or float
+        if let Some(metadata) = &mut self.metadata {
+            metadata.format_tag = match format {
+                SampleFormat::F32 => 3, // IEEE float
+                _ => 1,                 // PCM
+            };
+            metadata.channel_mask = 0;
+            metadata.remove_field("WAVPACK_CHANNEL_MASK");
+        }
+
+        Ok(self)
+    }
+
+    /// Speaker mask of the source file, or 0 when it has none or can't be read
+    fn source_channel_mask(&self) -> u32 {
+        let (Some(codec), Some(path)) = (&self.codec, self.path.to_str()) else {
+            return 0;
+        };
+        if path.is_empty() {
+            return 0;
         }
+        codec
+            .extract_metadata_from_file(path)
+            .map_or(0, |metadata| metadata.channel_mask)
     }
 
-    pub fn convert_dual_mono(&mut self) -> R<()> {
+    pub fn convert_dual_mono(&mut self) -> R<&mut Self> {
         let Some(buffer) = &mut self.buffer else {
             return Err(anyhow::anyhow!(
                 "No audio buffer available for dual mono conversion"
             ));
         };
         buffer.strip_multi_mono()?;
+        self.stats = None;
+        self.sync_channels();
 
-        // Update metadata to reflect the new channel count
-        if let Some(metadata) = &mut self.metadata {
+        Ok(self)
+    }
+
+    /// Make the channel data the one source of truth for the channel count:
+    /// `buffer.channels` and the metadata's count follow `buffer.data`, and a
+    /// speaker mask describing a different count is dropped. Codex operations
+    /// that change channels call this; call it after editing `buffer` directly.
+    pub fn sync_channels(&mut self) -> &mut Self {
+        let Some(buffer) = &mut self.buffer else {
+            return self;
+        };
+        buffer.sync_channels();
+        if let Some(metadata) = &mut self.metadata
+            && metadata.channels != buffer.channels
+        {
             metadata.channels = buffer.channels;
+            if metadata.channel_mask.count_ones() != buffer.channels as u32 {
+                metadata.channel_mask = 0;
+                metadata.remove_field("WAVPACK_CHANNEL_MASK");
+            }
         }
+        self
+    }
 
+    /// Record the decoded audio's `AudioBuffer::pcm_md5` as the `PCM_MD5`
+    /// field (lowercase hex), once both audio and metadata are loaded. It
+    /// describes the audio as decoded and is never written into files.
+    fn stamp_pcm_md5(&mut self) -> R<()> {
+        if let (Some(buffer), Some(metadata)) = (&self.buffer, &mut self.metadata) {
+            let md5: String = buffer
+                .pcm_md5()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            metadata.set_field("PCM_MD5", &md5)?;
+        }
         Ok(())
     }
 
+    /// Level statistics for the loaded buffer, computed once and reused.
+    ///
+    /// Codex operations that change the audio drop the cached value; call
+    /// `invalidate_stats` after editing `buffer` directly.
+    pub fn audio_stats(&mut self) -> R<AudioStats> {
+        if let Some(stats) = self.stats {
+            return Ok(stats);
+        }
+        let Some(buffer) = &self.buffer else {
+            return Err(anyhow::anyhow!("No audio buffer available"));
+        };
+        let stats = buffer.stats();
+        self.stats = Some(stats);
+        Ok(stats)
+    }
+
+    pub fn invalidate_stats(&mut self) {
+        self.stats = None;
+    }
+
+    /// Normalize to `target_lufs` without exceeding the true-peak ceiling; see
+    /// `AudioBuffer::normalize_lufs`. Returns the loudness achieved.
+    pub fn normalize_loudness(&mut self, target_lufs: f32, true_peak_ceiling_db: f32) -> R<f32> {
+        let Some(buffer) = &mut self.buffer else {
+            return Err(anyhow::anyhow!(
+                "No audio buffer available for loudness normalization"
+            ));
+        };
+        let achieved = buffer.normalize_lufs(target_lufs, true_peak_ceiling_db)?;
+        self.stats = None;
+        Ok(achieved)
+    }
+
+    /// Bring the sample peak to `target_dbfs`; see `AudioBuffer::normalize_peak`
+    pub fn normalize_peak(&mut self, target_dbfs: f32) -> R<&mut Self> {
+        let Some(buffer) = &mut self.buffer else {
+            return Err(anyhow::anyhow!(
+                "No audio buffer available for peak normalization"
+            ));
+        };
+        buffer.normalize_peak(target_dbfs)?;
+        self.stats = None;
+        Ok(self)
+    }
+
+    /// Peak, true-peak, RMS and integrated loudness of the decoded buffer
+    pub fn measure_loudness(&self) -> R<LoudnessStats> {
+        let Some(buffer) = &self.buffer else {
+            return Err(anyhow::anyhow!("No audio buffer available"));
+        };
+        Ok(buffer.analyze())
+    }
+
+    /// Measure loudness and store it in the loaded metadata, so the next embed
+    /// writes an iXML `<LOUDNESS>` block that QC tools can read without re-measuring
+    pub fn record_loudness(&mut self) -> R<LoudnessStats> {
+        let stats = self.measure_loudness()?;
+        let Some(metadata) = &mut self.metadata else {
+            return Err(anyhow::anyhow!("No metadata available to record loudness"));
+        };
+        metadata.set_loudness(&stats)?;
+        Ok(stats)
+    }
+
     // Add helper methods to expose channel information
     pub fn channels(&self) -> R<u16> {
         let Some(buffer) = &self.buffer else {
@@ -392,6 +1670,55 @@ impl Codex {
         Ok(buffer.data.len())
     }
 
+    /// `FileInfo` plus format and lossless flags as a JSON object, for scripting
+    pub fn summary_json(&self) -> R<String> {
+        let info = self.get_file_info()?;
+        let codec = self
+            .codec
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No codec available: {}", self.path.display()))?;
+
+        let summary = serde_json::json!({
+            "path": info.path,
+            "format": codec.as_str(),
+            "size": info.size,
+            "sample_rate": info.sample_rate,
+            "channels": info.channels,
+            "bit_depth": info.bit_depth,
+            "duration": info.duration,
+            "duration_secs": info.duration_secs,
+            "description": info.description,
+            "is_lossless": codec.is_lossless(&info.path)?,
+        });
+        Ok(serde_json::to_string_pretty(&summary)?)
+    }
+
+    /// File info, read from the file's header on first call and cached
+    pub fn file_info(&mut self) -> R<&FileInfo> {
+        let info = match self.info.take() {
+            Some(info) => info,
+            None => self.get_file_info()?,
+        };
+        Ok(self.info.insert(info))
+    }
+
+    /// The loaded metadata, extracted from the file on first call
+    pub fn load_metadata(&mut self) -> R<&Metadata> {
+        let metadata = match self.metadata.take() {
+            Some(metadata) => metadata,
+            None => {
+                let codec = self.codec.as_ref().ok_or_else(|| {
+                    anyhow::anyhow!("No codec available for file: {}", self.path.display())
+                })?;
+                let path_str = self.path.to_str().ok_or_else(|| {
+                    anyhow::anyhow!("Path contains invalid UTF-8: {}", self.path.display())
+                })?;
+                codec.extract_metadata_from_file(path_str)?
+            }
+        };
+        Ok(self.metadata.insert(metadata))
+    }
+
     fn get_file_info(&self) -> R<FileInfo> {
         let codec = self.codec.as_ref().ok_or_else(|| {
             anyhow::anyhow!(
@@ -408,7 +1735,7 @@ impl Codex {
     fn update_metadata_from_buffer(&self, metadata: &Metadata, buffer: &AudioBuffer) -> Metadata {
         let mut updated_metadata = metadata.clone();
         updated_metadata.sample_rate = buffer.sample_rate;
-        updated_metadata.channels = buffer.channels;
+        updated_metadata.channels = buffer.data.len() as u16;
         updated_metadata.bit_depth = match buffer.format {
             SampleFormat::U8 => 8,
             SampleFormat::I16 => 16,
@@ -427,8 +1754,42 @@ impl Codex {
 pub trait Codec: Send + Sync {
     fn as_str(&self) -> &'static str;
     fn validate_file_format(&self, data: &[u8]) -> R<()>;
+    /// Codec-specific structural checks (chunk sizes, declared format) used by `validate_audio`
+    fn validate_structure(&self, _input: &[u8], _report: &mut ValidationReport) {}
+    /// Deep check: signature, internal structure and, when the structure is sound,
+    /// a full decode of the stream. Problems are collected into the report.
+    fn validate_audio(&self, input: &[u8]) -> R<ValidationReport> {
+        let mut report = ValidationReport::default();
+        if let Err(e) = self.validate_file_format(input) {
+            report.add_issue(e.to_string());
+            return Ok(report);
+        }
+
+        self.validate_structure(input, &mut report);
+
+        if report.is_healthy() {
+            match self.decode(input) {
+                Ok(_) => report.decoded = true,
+                Err(e) => report.add_issue(format!("Decode failed: {}", e)),
+            }
+        }
+
+        Ok(report)
+    }
     fn file_extension(&self) -> &'static str;
     fn get_file_info(&self, file_path: &str) -> R<FileInfo>;
+    /// Whether the file holds a bit-exact copy of the audio (false for lossy or hybrid streams)
+    fn is_lossless(&self, _file_path: &str) -> R<bool> {
+        Ok(true)
+    }
+    /// Name this format stores a logical field under, e.g. "Year" is written as "Date" in WavPack
+    fn tag_name_for(&self, logical: &str) -> String {
+        logical.to_string()
+    }
+    /// Logical field name for a tag read from this format; the inverse of `tag_name_for`
+    fn logical_for(&self, tag: &str) -> String {
+        tag.to_string()
+    }
     fn as_any(&self) -> &dyn std::any::Any;
     fn encode(&self, buffer: &Option<AudioBuffer>) -> R<Vec<u8>>;
     fn encode_file(&self, buffer: &Option<AudioBuffer>, file_path: &str) -> R<()> {
@@ -436,7 +1797,25 @@ pub trait Codec: Send + Sync {
         std::fs::write(file_path, encoded_data)?;
         Ok(())
     }
+    /// Encode the audio alone (no metadata) to any writer, e.g. stdout
+    fn encode_to_writer(&self, buffer: &Option<AudioBuffer>, writer: &mut dyn Write) -> R<()> {
+        writer.write_all(&self.encode(buffer)?)?;
+        Ok(())
+    }
     fn decode(&self, input: &[u8]) -> R<AudioBuffer>;
+    /// Decode block by block instead of into one `AudioBuffer`, so long files
+    /// can be processed without holding every sample in memory. Codecs
+    /// without a streaming decoder fall back to a full decode.
+    fn decode_stream<'a>(&self, input: &'a [u8]) -> R<(StreamHeader, FrameStream<'a>)> {
+        Ok(buffer_stream(self.decode(input)?))
+    }
+    /// Decode only frames `[start, end)`, clamped to the stream. Codecs that can
+    /// seek override this; the default streams up to `end` and drops the blocks
+    /// before `start`, so memory stays bounded by the range.
+    fn decode_range(&self, input: &[u8], start: u64, end: u64) -> R<AudioBuffer> {
+        let (header, blocks) = self.decode_stream(input)?;
+        collect_range(header, blocks, start, end)
+    }
     fn decode_file(&self, file_path: &str) -> R<AudioBuffer> {
         use memmap2::Mmap;
         use std::fs::File;
@@ -473,16 +1852,14 @@ pub trait Codec: Send + Sync {
         }
     }
 
+    /// The iXML document for `metadata`. The root is `BWFXML` whatever the
+    /// container, as the iXML spec requires and readers look for.
     fn create_ixml(&self, metadata: &Metadata) -> R<String> {
         let mut xml = String::new();
         xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
-        xml.push('<');
-        xml.push_str(self.as_str());
-        xml.push_str("XML>\n");
+        xml.push_str("<BWFXML>\n");
         xml.push_str(&ixml::create_ixml_from_metadata(metadata)?);
-        xml.push_str("</");
-        xml.push_str(self.as_str());
-        xml.push_str("XML>\n");
+        xml.push_str("</BWFXML>\n");
         Ok(xml)
     }
     // fn create_ixml(&self, metadata: &Metadata) -> R<String> {
@@ -501,10 +1878,41 @@ pub trait Codec: Send + Sync {
     fn parse_metadata(&self, input: &[u8]) -> R<Metadata>;
 
     fn embed_metadata_to_file(&self, file_path: &str, metadata: &Metadata) -> R<()>;
+
+    /// `input` with `metadata` embedded, built in memory without touching the
+    /// filesystem. Formats that can only tag files on disk keep the default error.
+    fn embed_metadata(&self, _input: &[u8], _metadata: &Metadata) -> R<Vec<u8>> {
+        Err(anyhow!(
+            "In-memory metadata embedding is not supported for .{} files",
+            self.file_extension()
+        ))
+    }
+
+    /// Fields this format can only store partially, e.g. bext text cut to its
+    /// fixed size. Embedding still succeeds; this says what was lost.
+    fn embed_warnings(&self, _metadata: &Metadata) -> Vec<String> {
+        Vec::new()
+    }
 }
 
 // Helper functions for stripping Soundminer metadata from different formats
 
+/// Rename `from` over `to`, copying instead when they're on different filesystems
+fn move_into_place(from: &std::path::Path, to: &std::path::Path) -> R<()> {
+    match std::fs::rename(from, to) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            // As a fallback, try to copy then delete
+            if let Err(_copy_err) = std::fs::copy(from, to) {
+                Err(e.into()) // Return the original error
+            } else {
+                let _ = std::fs::remove_file(from); // Try to cleanup
+                Ok(())
+            }
+        }
+    }
+}
+
 fn strip_smed_from_flac(data: &[u8]) -> R<Vec<u8>> {
     if data.len() < 4 || &data[0..4] != b"fLaC" {
         return Err(anyhow::anyhow!("Not a valid FLAC file"));
@@ -574,7 +1982,7 @@ fn strip_smed_from_flac(data: &[u8]) -> R<Vec<u8>> {
 }
 
 fn strip_smed_from_aiff(data: &[u8]) -> R<Vec<u8>> {
-    if data.len() < 12 || &data[0..4] != b"FORM" || &data[8..12] != b"AIFF" {
+    if data.len() < 12 || &data[0..4] != b"FORM" || !matches!(&data[8..12], b"AIFF" | b"AIFC") {
         return Err(anyhow::anyhow!("Not a valid AIFF file"));
     }
 
@@ -690,3 +2098,1051 @@ fn strip_smed_from_wavpack(data: &[u8]) -> R<Vec<u8>> {
     println!("WavPack SMED removal not yet implemented - use WavPack tools to remove SMED tags");
     Ok(data.to_vec())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("ffcodex_test_{}_{}", rand::random::<u32>(), name))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn test_buffer(channels: u16, frames: usize) -> AudioBuffer {
+        AudioBuffer {
+            sample_rate: 48000,
+            channels,
+            format: SampleFormat::I24,
+            data: (0..channels)
+                .map(|ch| {
+                    (0..frames)
+                        .map(|i| ((i as f32 * 0.02) + ch as f32).sin() * 0.5)
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_open_readonly_defers_decoding() {
+        let path = temp_path("readonly.wav");
+        WavCodec
+            .encode_file(&Some(test_buffer(2, 4800)), &path)
+            .unwrap();
+
+        let mut codex = Codex::open_readonly(&path).unwrap();
+        assert_eq!(codex.file_info().unwrap().channels, 2);
+        codex.load_metadata().unwrap();
+        assert!(codex.buffer.is_none());
+
+        let codex = codex.decode().unwrap();
+        assert_eq!(codex.buffer.as_ref().unwrap().data[0].len(), 4800);
+        std::fs::remove_file(&path).unwrap();
+        assert!(Codex::open_readonly(&path).is_err());
+    }
+
+    #[test]
+    fn test_decode_stream_matches_full_decode() {
+        let codecs: [&dyn Codec; 4] = [&WavCodec, &AifCodec, &FlacCodec, &WvCodec];
+        let frames = 2 * STREAM_BLOCK_FRAMES + 100;
+        for codec in codecs {
+            let encoded = codec.encode(&Some(test_buffer(2, frames))).unwrap();
+            let full = codec.decode(&encoded).unwrap();
+
+            let (header, blocks) = codec.decode_stream(&encoded).unwrap();
+            assert_eq!(header.channels, 2, "{}", codec.as_str());
+            assert_eq!(header.format, full.format, "{}", codec.as_str());
+            assert_eq!(
+                header.total_frames,
+                Some(frames as u64),
+                "{}",
+                codec.as_str()
+            );
+
+            let mut streamed = vec![Vec::new(); 2];
+            for block in blocks {
+                let block = block.unwrap();
+                assert!(block[0].len() <= STREAM_BLOCK_FRAMES);
+                for (channel, samples) in streamed.iter_mut().zip(block) {
+                    channel.extend(samples);
+                }
+            }
+            assert_eq!(streamed, full.data, "{}", codec.as_str());
+        }
+    }
+
+    #[test]
+    fn test_set_format_dithers_down_to_16_bit() {
+        let path = temp_path("set_format.wav");
+        let mut buffer = test_buffer(1, 4800);
+        buffer.data[0][..2400].fill(0.0); // digital silence shows the dither noise
+        WavCodec.encode_file(&Some(buffer), &path).unwrap();
+
+        let mut codex = Codex::new(&path)
+            .unwrap()
+            .decode()
+            .unwrap()
+            .extract_metadata()
+            .unwrap();
+        let mut undithered = codex.buffer.clone().unwrap();
+        codex
+            .set_format(SampleFormat::I16, DitherMode::Triangular)
+            .unwrap();
+        undithered.set_format(SampleFormat::I16, DitherMode::None);
+
+        let converted = codex.buffer.as_ref().unwrap();
+        assert_eq!(converted.format, SampleFormat::I16);
+        assert_eq!(codex.metadata.as_ref().unwrap().bit_depth, 16);
+        let lsb = 1.0 / 32767.0;
+        assert!(converted.data[0].iter().all(|s| {
+            let steps = s / lsb;
+            (steps - steps.round()).abs() < 1e-3
+        }));
+        assert!(converted.data[0][..2400].iter().any(|s| *s != 0.0));
+        assert!(undithered.data[0][..2400].iter().all(|s| *s == 0.0));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_raw_chunk_is_exported_verbatim() {
+        let source = temp_path("raw_source.wav");
+        let output = temp_path("raw_output.wav");
+        WavCodec
+            .encode_file(&Some(test_buffer(2, 4800)), &source)
+            .unwrap();
+
+        let payload = b"engine-state\x00\x01\x02".to_vec(); // odd length needs a pad byte
+        let mut codex = Codex::new(&source).unwrap().decode().unwrap();
+        codex.add_raw_chunk(*b"xEng", payload.clone()).unwrap();
+        assert!(codex.add_raw_chunk(*b"data", vec![0; 4]).is_err());
+        codex.export(&output).unwrap();
+
+        let bytes = std::fs::read(&output).unwrap();
+        let mut expected = b"xEng".to_vec();
+        expected.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        expected.extend_from_slice(&payload);
+        let at = bytes
+            .windows(expected.len())
+            .position(|w| w == expected.as_slice())
+            .expect("raw chunk missing from export");
+        let data_at = bytes.windows(4).position(|w| w == b"data").unwrap();
+        assert!(at < data_at);
+        assert_eq!(bytes[at + expected.len()], 0);
+
+        let _ = std::fs::remove_file(&source);
+        let _ = std::fs::remove_file(&output);
+    }
+
+    #[test]
+    fn test_clone_to_is_identical() {
+        let source = temp_path("source.wav");
+        let clone = temp_path("clone.wav");
+
+        let codec = WavCodec;
+        codec.encode_file(&Some(test_buffer(2, 4800)), &source).unwrap();
+        let mut metadata = Metadata::new();
+        metadata.set_field("Description", "Door slam").unwrap();
+        metadata.set_field("Category", "DOORS").unwrap();
+        codec.embed_metadata_to_file(&source, &metadata).unwrap();
+
+        let original = Codex::open(&source).unwrap();
+        original.clone_to(&clone).unwrap();
+        let copy = Codex::open(&clone).unwrap();
+
+        assert_eq!(
+            original.buffer.as_ref().unwrap().data,
+            copy.buffer.as_ref().unwrap().data
+        );
+        assert_eq!(
+            original.metadata.as_ref().unwrap().get_all_fields(),
+            copy.metadata.as_ref().unwrap().get_all_fields()
+        );
+
+        let _ = std::fs::remove_file(&source);
+        let _ = std::fs::remove_file(&clone);
+    }
+
+    #[test]
+    fn test_export_synthesizes_missing_metadata() {
+        let output = temp_path("synth.wav");
+        let codex = Codex {
+            buffer: Some(test_buffer(1, 4800)),
+            ..Default::default()
+        };
+        let options = EncodeOptions {
+            synthesize_missing_metadata: true,
+            originator: Some("FFCodex".to_string()),
+            ..Default::default()
+        };
+        // The export may straddle UTC midnight, so either side's date is right
+        let date_before = codecs::utc_date_time_now().0;
+        codex.export_with_options(&output, &options).unwrap();
+        let date_after = codecs::utc_date_time_now().0;
+
+        let data = std::fs::read(&output).unwrap();
+        let bext = data
+            .windows(4)
+            .position(|w| w == b"bext")
+            .expect("bext chunk missing");
+        let date = String::from_utf8_lossy(&data[bext + 8 + 320..bext + 8 + 330]).to_string();
+        assert!(date == date_before || date == date_after, "{}", date);
+
+        let _ = std::fs::remove_file(&output);
+    }
+
+    #[test]
+    fn test_export_fills_missing_origination_date_time() {
+        let output = temp_path("stamped.wav");
+        let mut metadata = Metadata::new();
+        metadata.set_field("Description", "Harbor bell").unwrap();
+        let codex = Codex {
+            buffer: Some(test_buffer(1, 4800)),
+            metadata: Some(metadata),
+            ..Default::default()
+        };
+        let options = EncodeOptions {
+            fill_origination_date_time: true,
+            ..Default::default()
+        };
+        // The export may straddle UTC midnight, so either side's date is right
+        let date_before = codecs::utc_date_time_now().0;
+        codex.export_with_options(&output, &options).unwrap();
+        let date_after = codecs::utc_date_time_now().0;
+
+        let exported = Codex::new(&output).unwrap().extract_metadata().unwrap();
+        let exported = exported.metadata.unwrap();
+        let date = exported.get_field("OriginationDate").unwrap();
+        let time = exported.get_field("OriginationTime").unwrap();
+        assert!(date == date_before || date == date_after, "{}", date);
+        let digits = |s: &str, separator: char, positions: &[usize]| {
+            s.char_indices().all(|(i, c)| {
+                if positions.contains(&i) {
+                    c == separator
+                } else {
+                    c.is_ascii_digit()
+                }
+            })
+        };
+        assert!(date.len() == 10 && digits(&date, '-', &[4, 7]), "{}", date);
+        assert!(time.len() == 8 && digits(&time, ':', &[2, 5]), "{}", time);
+        assert_eq!(
+            exported.get_field("Description").as_deref(),
+            Some("Harbor bell")
+        );
+
+        let _ = std::fs::remove_file(&output);
+    }
+
+    #[test]
+    fn test_null_test() {
+        let path_a = temp_path("null_a.wav");
+        let path_b = temp_path("null_b.wav");
+        let path_c = temp_path("null_c.wav");
+
+        let buffer = test_buffer(2, 4800);
+        let mut quieter = buffer.clone();
+        quieter
+            .data
+            .iter_mut()
+            .flatten()
+            .for_each(|sample| *sample *= 0.99);
+
+        WavCodec.encode_file(&Some(buffer.clone()), &path_a).unwrap();
+        WavCodec.encode_file(&Some(buffer), &path_b).unwrap();
+        WavCodec.encode_file(&Some(quieter), &path_c).unwrap();
+
+        assert_eq!(null_test(&path_a, &path_b).unwrap(), f32::NEG_INFINITY);
+        let residual = null_test(&path_a, &path_c).unwrap();
+        assert!(residual.is_finite() && residual < -30.0);
+
+        for path in [path_a, path_b, path_c] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn test_export_force_mono() {
+        let output = temp_path("mono.wav");
+        let stereo = test_buffer(2, 4800);
+        let codex = Codex {
+            buffer: Some(stereo.clone()),
+            metadata: Some(Metadata::new()),
+            ..Default::default()
+        };
+        let options = EncodeOptions {
+            force_mono: true,
+            ..Default::default()
+        };
+        codex.export_with_options(&output, &options).unwrap();
+
+        let mono = Codex::open(&output).unwrap();
+        let buffer = mono.buffer.unwrap();
+        assert_eq!(buffer.channels, 1);
+        assert_eq!(mono.metadata.unwrap().channels, 1);
+        for (i, sample) in buffer.data[0].iter().enumerate() {
+            let expected = (stereo.data[0][i] + stereo.data[1][i]) / 2.0;
+            assert!((sample - expected).abs() < 1e-5);
+        }
+
+        let _ = std::fs::remove_file(&output);
+    }
+
+    #[test]
+    fn test_export_if_changed_skips_identical_content() {
+        let output = temp_path("idempotent.wav");
+        let mut metadata = Metadata::new();
+        metadata
+            .set_field("Description", "Rain on tin roof")
+            .unwrap();
+        let mut codex = Codex {
+            buffer: Some(test_buffer(2, 4800)),
+            metadata: Some(metadata),
+            ..Default::default()
+        };
+        let options = EncodeOptions::default();
+
+        assert!(codex.export_if_changed(&output, &options).unwrap());
+        let written = std::fs::metadata(&output).unwrap().modified().unwrap();
+
+        assert!(!codex.export_if_changed(&output, &options).unwrap());
+        let after = std::fs::metadata(&output).unwrap().modified().unwrap();
+        assert_eq!(written, after);
+
+        codex
+            .set_metadata_field("Description", "Rain on car roof")
+            .unwrap();
+        assert!(codex.export_if_changed(&output, &options).unwrap());
+
+        let _ = std::fs::remove_file(&output);
+    }
+
+    #[test]
+    fn test_summary_json_fields() {
+        let path = temp_path("summary.wav");
+        WavCodec
+            .encode_file(&Some(test_buffer(2, 96000)), &path)
+            .unwrap();
+
+        let json = Codex::new(&path).unwrap().summary_json().unwrap();
+        let summary: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(summary["sample_rate"], 48000);
+        assert_eq!(summary["channels"], 2);
+        assert_eq!(summary["duration_secs"], 2.0);
+        assert_eq!(summary["is_lossless"], true);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_export_to_writer_yields_decodable_wav() {
+        let path = temp_path("stream_source.flac");
+        FlacCodec
+            .encode_file(&Some(test_buffer(2, 4800)), &path)
+            .unwrap();
+
+        let mut codex = Codex::new(&path).unwrap().extract_metadata().unwrap();
+        codex
+            .set_metadata_field("Description", "Piped out")
+            .unwrap();
+        let codex = codex.decode().unwrap();
+        let mut piped: Vec<u8> = Vec::new();
+        codex
+            .export_to_writer(&mut piped, "wav", &EncodeOptions::default())
+            .unwrap();
+
+        let decoded = WavCodec.decode(&piped).unwrap();
+        assert_eq!(decoded.channels, 2);
+        assert_eq!(decoded.data[0].len(), 4800);
+        assert_eq!(
+            WavCodec
+                .parse_metadata(&piped)
+                .unwrap()
+                .get_field("Description")
+                .as_deref(),
+            Some("Piped out")
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_export_to_writer_streams_untagged_audio_as_encoded() {
+        let buffer = Some(test_buffer(2, 4800));
+        let codex = Codex {
+            buffer: buffer.clone(),
+            ..Default::default()
+        };
+        let mut piped: Vec<u8> = Vec::new();
+        codex
+            .export_to_writer(&mut piped, "flac", &EncodeOptions::default())
+            .unwrap();
+        assert_eq!(piped, FlacCodec.encode(&buffer).unwrap());
+    }
+
+    #[test]
+    fn test_embed_metadata_in_place() {
+        let path = temp_path("in_place.wav");
+        WavCodec
+            .encode_file(&Some(test_buffer(2, 4800)), &path)
+            .unwrap();
+
+        let mut codex = Codex::new(&path).unwrap().extract_metadata().unwrap();
+        codex
+            .set_metadata_field("Description", "Gravel footsteps")
+            .unwrap();
+        codex.embed_metadata().unwrap();
+
+        let written = WavCodec
+            .parse_metadata(&std::fs::read(&path).unwrap())
+            .unwrap();
+        assert_eq!(
+            written.get_field("Description").as_deref(),
+            Some("Gravel footsteps")
+        );
+        assert!(Codex::default().embed_metadata().is_err());
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_embed_escapes_ixml_punctuation() {
+        let description = r#"Metal & "friction" <loud>"#;
+        for extension in ["wav", "aif"] {
+            let path = temp_path(&format!("punctuation.{extension}"));
+            let codec = get_codec(&path).unwrap();
+            codec
+                .encode_file(&Some(test_buffer(1, 4800)), &path)
+                .unwrap();
+
+            let mut codex = Codex::new(&path).unwrap().extract_metadata().unwrap();
+            codex
+                .set_metadata_field("Description", description)
+                .unwrap();
+            codex.embed_metadata().unwrap();
+
+            let bytes = std::fs::read(&path).unwrap();
+            let escaped =
+                "<DESCRIPTION>Metal &amp; &quot;friction&quot; &lt;loud&gt;</DESCRIPTION>";
+            assert!(
+                String::from_utf8_lossy(&bytes).contains(escaped),
+                "{extension}"
+            );
+            let extracted = Codex::new(&path).unwrap().extract_metadata().unwrap();
+            assert_eq!(
+                extracted.get_metadata_field("USER_DESCRIPTION").as_deref(),
+                Some(description),
+                "{extension}"
+            );
+
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+
+    #[test]
+    fn test_embed_warns_on_truncated_bext_description() {
+        let path = temp_path("truncated.wav");
+        WavCodec
+            .encode_file(&Some(test_buffer(1, 4800)), &path)
+            .unwrap();
+
+        let mut codex = Codex::new(&path).unwrap().extract_metadata().unwrap();
+        codex.set_metadata_field("Description", "Rain").unwrap();
+        assert!(codex.embed_metadata().unwrap().is_empty());
+
+        let long = "Heavy rain on a corrugated roof, ".repeat(10);
+        codex.set_metadata_field("Description", &long).unwrap();
+        let warnings = codex.embed_metadata().unwrap();
+        assert_eq!(
+            warnings,
+            vec![format!(
+                "bext Description truncated to 255 of {} bytes",
+                long.len()
+            )]
+        );
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_recorded_loudness_roundtrips_through_ixml() {
+        let path = temp_path("loudness.wav");
+        WavCodec
+            .encode_file(&Some(test_buffer(2, 48000)), &path)
+            .unwrap();
+
+        let mut codex = Codex::new(&path)
+            .unwrap()
+            .decode()
+            .unwrap()
+            .extract_metadata()
+            .unwrap();
+        let stats = codex.record_loudness().unwrap();
+        codex.embed_metadata().unwrap();
+
+        let reread = Codex::new(&path).unwrap().extract_metadata().unwrap();
+        for (key, value) in [
+            ("LOUDNESS_LOUDNESS_VALUE", stats.integrated_lufs),
+            ("LOUDNESS_LOUDNESS_RANGE", stats.loudness_range_lu),
+            ("LOUDNESS_MAX_TRUE_PEAK_LEVEL", stats.true_peak_dbfs),
+        ] {
+            let parsed: f32 = reread.get_metadata_field(key).unwrap().parse().unwrap();
+            assert!((parsed - value).abs() < 0.01, "{key}: {parsed} vs {value}");
+        }
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_metadata_survives_conversion_between_formats() {
+        const FIELDS: [(&str, &str); 4] = [
+            ("Description", "Heavy oak door, slow creak then slam"),
+            ("Designer", "Tim Nielsen"),
+            ("Category", "DOORS"),
+            ("Year", "2024"),
+        ];
+        const FORMATS: [&str; 4] = ["wav", "aif", "flac", "wv"];
+
+        for source_ext in FORMATS {
+            let source = temp_path(&format!("matrix_source.{source_ext}"));
+            get_codec(&source)
+                .unwrap()
+                .encode_file(&Some(test_buffer(2, 4800)), &source)
+                .unwrap();
+            let mut tagged = Codex::new(&source).unwrap().extract_metadata().unwrap();
+            for (key, value) in FIELDS {
+                tagged.set_metadata_field(key, value).unwrap();
+            }
+            tagged.embed_metadata().unwrap();
+
+            let codex = Codex::new(&source)
+                .unwrap()
+                .decode()
+                .unwrap()
+                .extract_metadata()
+                .unwrap();
+            for target_ext in FORMATS {
+                let target = temp_path(&format!("matrix_target.{target_ext}"));
+                codex.export(&target).unwrap();
+                let converted = Codex::new(&target).unwrap().extract_metadata().unwrap();
+                for (key, value) in FIELDS {
+                    assert_eq!(
+                        converted.get_metadata_field(key).as_deref(),
+                        Some(value),
+                        "{key}: {source_ext} -> {target_ext}"
+                    );
+                }
+                let _ = std::fs::remove_file(&target);
+            }
+            let _ = std::fs::remove_file(&source);
+        }
+    }
+
+    #[test]
+    fn test_bytes_roundtrip_in_memory() {
+        const FIELDS: [(&str, &str); 3] = [
+            ("Description", "Footsteps on wet gravel"),
+            ("Designer", "Tim Nielsen"),
+            ("Category", "FOOTSTEPS"),
+        ];
+        let wav = WavCodec.encode(&Some(test_buffer(2, 4800))).unwrap();
+        let mut codex = Codex::from_bytes(wav, "wav").unwrap();
+        for (key, value) in FIELDS {
+            codex.set_metadata_field(key, value).unwrap();
+        }
+
+        for extension in ["wav", "aif", "flac", "wv"] {
+            let bytes = codex.to_bytes(extension).unwrap();
+            let reopened = Codex::from_bytes(bytes, extension).unwrap();
+            assert_eq!(
+                reopened.buffer.as_ref().unwrap().data,
+                codex.buffer.as_ref().unwrap().data,
+                "{extension}"
+            );
+            for (key, value) in FIELDS {
+                assert_eq!(
+                    reopened.get_metadata_field(key).as_deref(),
+                    Some(value),
+                    "{key}: {extension}"
+                );
+            }
+        }
+        assert!(Codex::from_bytes(b"not audio".to_vec(), "wav").is_err());
+    }
+
+    #[test]
+    fn test_verified_embed_failure_leaves_original() {
+        let path = temp_path("verified.wav");
+        WavCodec
+            .encode_file(&Some(test_buffer(2, 4800)), &path)
+            .unwrap();
+        let original = std::fs::read(&path).unwrap();
+
+        let mut codex = Codex::new(&path).unwrap().extract_metadata().unwrap();
+        codex
+            .set_metadata_field("Description", "Creaking door")
+            .unwrap();
+        let forced =
+            codex.embed_metadata_checked(&|_: &Metadata| Err(anyhow::anyhow!("forced failure")));
+        assert!(forced.is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), original);
+
+        codex.embed_metadata_verified().unwrap();
+        let written = WavCodec
+            .parse_metadata(&std::fs::read(&path).unwrap())
+            .unwrap();
+        assert_eq!(
+            written.get_field("Description").as_deref(),
+            Some("Creaking door")
+        );
+        let stem = std::path::Path::new(&path).file_stem().unwrap();
+        let temp_prefix = format!("{}.ffcodex-", stem.to_string_lossy());
+        let leftovers = std::fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(&temp_prefix)
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_decoded_codex_embeds_without_pcm_md5() {
+        // Decoding stamps PCM_MD5, which no format stores
+        let path = temp_path("verified_decoded.wav");
+        WavCodec
+            .encode_file(&Some(test_buffer(2, 4800)), &path)
+            .unwrap();
+        let mut codex = Codex::open(&path).unwrap();
+        assert!(codex.get_metadata_field("PCM_MD5").is_some());
+        codex
+            .set_metadata_field("Description", "Creaking door")
+            .unwrap();
+        let verified = codex.embed_metadata_verified();
+        let written = WavCodec.parse_metadata(&std::fs::read(&path).unwrap());
+        let _ = std::fs::remove_file(&path);
+
+        verified.unwrap();
+        assert_eq!(
+            written.unwrap().get_field("Description").as_deref(),
+            Some("Creaking door")
+        );
+
+        // An unchanged FLAC is left byte-for-byte as it was
+        let path = temp_path("unchanged.flac");
+        FlacCodec
+            .encode_file(&Some(test_buffer(2, 4800)), &path)
+            .unwrap();
+        let original = std::fs::read(&path).unwrap();
+        let embedded = Codex::open(&path).unwrap().embed_metadata();
+        let after = std::fs::read(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        embedded.unwrap();
+        assert_eq!(after, original);
+    }
+
+    #[test]
+    fn test_embed_metadata_as_different_path() {
+        let source = temp_path("embed_source.wav");
+        let target = temp_path("embed_target.wav");
+        WavCodec
+            .encode_file(&Some(test_buffer(2, 4800)), &source)
+            .unwrap();
+        let original = std::fs::read(&source).unwrap();
+
+        let mut codex = Codex::new(&source).unwrap().extract_metadata().unwrap();
+        codex
+            .set_metadata_field("Description", "Wind in pines")
+            .unwrap();
+        codex.embed_metadata_as(&target).unwrap();
+
+        assert_eq!(std::fs::read(&source).unwrap(), original);
+        let target_bytes = std::fs::read(&target).unwrap();
+        let written = WavCodec.parse_metadata(&target_bytes).unwrap();
+        assert_eq!(
+            written.get_field("Description").as_deref(),
+            Some("Wind in pines")
+        );
+        assert_eq!(
+            WavCodec.decode(&target_bytes).unwrap().data,
+            WavCodec.decode(&original).unwrap().data
+        );
+
+        // Changing format needs decoded audio
+        assert!(
+            codex
+                .embed_metadata_as(&temp_path("embed_target.flac"))
+                .is_err()
+        );
+
+        let _ = std::fs::remove_file(&source);
+        let _ = std::fs::remove_file(&target);
+    }
+
+    #[test]
+    fn test_trim_moves_time_reference_and_markers() {
+        let mut metadata = Metadata::new();
+        metadata.set_field("TimeReference", "1000").unwrap();
+        for (id, position) in [(1, 50), (2, 150), (3, 500)] {
+            metadata.add_marker(CueMarker {
+                id,
+                position,
+                length: 300,
+                ..Default::default()
+            });
+        }
+        let mut codex = Codex {
+            buffer: Some(test_buffer(2, 1000)),
+            metadata: Some(metadata),
+            ..Default::default()
+        };
+
+        codex.trim(100, 400).unwrap();
+        assert_eq!(codex.buffer.as_ref().unwrap().data[0].len(), 300);
+        let metadata = codex.metadata.as_ref().unwrap();
+        assert_eq!(metadata.get_field("TimeReference").as_deref(), Some("1100"));
+        assert_eq!(metadata.get_markers().len(), 1);
+        assert_eq!(metadata.get_markers()[0].position, 50);
+        assert_eq!(metadata.get_markers()[0].length, 250);
+        assert!(codex.trim(0, 301).is_err());
+    }
+
+    #[test]
+    fn test_pad_moves_time_reference_and_markers() {
+        let mut metadata = Metadata::new();
+        metadata.set_field("TimeReference", "1000").unwrap();
+        metadata.add_marker(CueMarker {
+            id: 1,
+            position: 50,
+            ..Default::default()
+        });
+        let mut codex = Codex {
+            buffer: Some(test_buffer(2, 1000)),
+            metadata: Some(metadata),
+            ..Default::default()
+        };
+
+        codex.pad(100, 10).unwrap();
+        assert_eq!(codex.buffer.as_ref().unwrap().data[0].len(), 1110);
+        let metadata = codex.metadata.as_ref().unwrap();
+        assert_eq!(metadata.get_field("TimeReference").as_deref(), Some("900"));
+        assert_eq!(metadata.get_markers()[0].position, 150);
+
+        // The time reference can't start before zero
+        codex.pad(2000, 0).unwrap();
+        let metadata = codex.metadata.as_ref().unwrap();
+        assert_eq!(metadata.get_field("TimeReference").as_deref(), Some("0"));
+        assert_eq!(metadata.get_markers()[0].position, 2150);
+    }
+
+    #[test]
+    fn test_sync_channels_follows_channel_data() {
+        // The header said stereo but the data is mono
+        let mut buffer = test_buffer(1, 4800);
+        buffer.channels = 2;
+        let mut metadata = Metadata::new();
+        metadata.channels = 2;
+        metadata.channel_mask = 0x3;
+        let mut codex = Codex {
+            buffer: Some(buffer),
+            metadata: Some(metadata),
+            ..Default::default()
+        };
+
+        // Every format exports the data's channel count
+        for extension in ["wav", "aif", "flac", "wv"] {
+            let dst = temp_path(&format!("sync.{extension}"));
+            codex.export(&dst).unwrap();
+            let exported = Codex::open(&dst).unwrap();
+            let metadata = exported.metadata.as_ref().unwrap();
+            assert_eq!(exported.buffer.as_ref().unwrap().channels, 1, "{extension}");
+            assert_eq!(metadata.channels, 1, "{extension}");
+            std::fs::remove_file(&dst).unwrap();
+        }
+
+        codex.sync_channels();
+        assert_eq!(codex.buffer.as_ref().unwrap().channels, 1);
+        let metadata = codex.metadata.as_ref().unwrap();
+        assert_eq!(metadata.channels, 1);
+        assert_eq!(metadata.channel_mask, 0);
+    }
+
+    #[test]
+    fn test_ixml_sidecar_matches_embedded() {
+        let src = temp_path("sidecar.wav");
+        let mut metadata = Metadata::new();
+        metadata.set_field("USER_SCENE", "Harbor & Dock").unwrap();
+        metadata.set_field("USER_TAKE", "3").unwrap();
+        metadata.set_field("ASWG_project", "Storm").unwrap();
+        let codex = Codex {
+            buffer: Some(test_buffer(1, 4800)),
+            metadata: Some(metadata),
+            ..Default::default()
+        };
+        codex.export(&src).unwrap();
+
+        let embedded = Codex::open(&src).unwrap();
+        let sidecar = embedded.write_ixml_sidecar(None).unwrap();
+        assert_eq!(sidecar, std::path::Path::new(&src).with_extension("xml"));
+
+        let mut codex = Codex::new(&src).unwrap();
+        codex.read_ixml_sidecar(None).unwrap();
+        let ixml_fields = |codex: &Codex| {
+            let mut fields: Vec<_> = codex
+                .metadata
+                .as_ref()
+                .unwrap()
+                .get_all_fields()
+                .iter()
+                .filter(|(key, _)| key.starts_with("USER_") || key.starts_with("ASWG_"))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            fields.sort();
+            fields
+        };
+        assert_eq!(ixml_fields(&codex), ixml_fields(&embedded));
+        assert_eq!(codex.get_metadata_field("USER_TAKE").as_deref(), Some("3"));
+
+        assert!(Codex::default().read_ixml_sidecar(None).is_err());
+        std::fs::remove_file(&src).unwrap();
+        std::fs::remove_file(&sidecar).unwrap();
+    }
+
+    #[test]
+    fn test_export_verified_replaces_only_checked_files() {
+        let codex = Codex {
+            buffer: Some(test_buffer(2, 48000)),
+            ..Default::default()
+        };
+        for extension in ["wav", "aif", "flac", "wv", "opus"] {
+            let dst = temp_path(&format!("verified.{extension}"));
+            codex.export_verified(&dst).unwrap();
+            let written = get_codec(&dst).unwrap().decode_file(&dst).unwrap();
+            assert_eq!(written.data.len(), 2, "{extension}");
+            if extension != "opus" {
+                assert_eq!(written.data[1].len(), 48000, "{extension}");
+            }
+            std::fs::remove_file(&dst).unwrap();
+        }
+
+        // Nothing to export: an existing file stays as it was
+        let dst = temp_path("verified_keep.wav");
+        std::fs::write(&dst, b"previous").unwrap();
+        assert!(Codex::default().export_verified(&dst).is_err());
+        assert_eq!(std::fs::read(&dst).unwrap(), b"previous");
+        std::fs::remove_file(&dst).unwrap();
+    }
+
+    #[test]
+    fn test_export_verified_failure_keeps_existing_file() {
+        // Opus always writes 48 kHz, so a 44.1 kHz source fails the rate check
+        let codex = Codex {
+            buffer: Some(AudioBuffer {
+                sample_rate: 44100,
+                ..test_buffer(2, 44100)
+            }),
+            ..Default::default()
+        };
+        let dst = temp_path("verified_fail.opus");
+        std::fs::write(&dst, b"previous").unwrap();
+
+        let error = codex.export_verified(&dst).unwrap_err().to_string();
+        assert!(
+            error.starts_with("Verification failed: sample rate"),
+            "{error}"
+        );
+        assert_eq!(std::fs::read(&dst).unwrap(), b"previous");
+
+        let dst_path = std::path::Path::new(&dst);
+        let stem = dst_path.file_stem().unwrap().to_string_lossy().to_string();
+        let leftovers = std::fs::read_dir(dst_path.parent().unwrap())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name.starts_with(&format!("{stem}.ffcodex-"))
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+        std::fs::remove_file(&dst).unwrap();
+    }
+
+    #[test]
+    fn test_pcm_md5_matches_across_lossless_formats() {
+        let mut metadata = Metadata::new();
+        metadata.set_field("TrackTitle", "Harbor bell").unwrap();
+        let codex = Codex {
+            buffer: Some(test_buffer(2, 9600)),
+            metadata: Some(metadata),
+            ..Default::default()
+        };
+        let expected: String = codex
+            .buffer
+            .as_ref()
+            .unwrap()
+            .pcm_md5()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let options = EncodeOptions {
+            store_md5: true,
+            ..Default::default()
+        };
+
+        for extension in ["wav", "flac", "wv"] {
+            let dst = temp_path(&format!("pcm_md5.{extension}"));
+            codex.export_with_options(&dst, &options).unwrap();
+            let opened = Codex::open(&dst).unwrap();
+            assert_eq!(
+                opened.get_metadata_field("PCM_MD5").as_deref(),
+                Some(expected.as_str()),
+                "{extension}"
+            );
+            if extension == "flac" {
+                let md5 = FlacCodec.peek_streaminfo(&dst).unwrap().md5;
+                assert_eq!(md5, codex.buffer.as_ref().unwrap().pcm_md5());
+            }
+            std::fs::remove_file(&dst).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_export_range_matches_source_region() {
+        for extension in ["wav", "aif", "flac", "wv"] {
+            let src = temp_path(&format!("range_src.{extension}"));
+            let dst = temp_path(&format!("range_dst.{extension}"));
+            let codec = get_codec(&src).unwrap();
+            codec
+                .encode_file(&Some(test_buffer(2, 48000 * 3)), &src)
+                .unwrap();
+            let source = codec.decode_file(&src).unwrap();
+
+            Codex::new(&src)
+                .unwrap()
+                .export_range(1.0, 1.5, &dst, &EncodeOptions::default())
+                .unwrap();
+            let clip = codec.decode_file(&dst).unwrap();
+            assert_eq!(clip.sample_rate, 48000, "{extension}");
+            for (clip, source) in clip.data.iter().zip(&source.data) {
+                assert_eq!(clip.as_slice(), &source[48000..72000], "{extension}");
+            }
+
+            // Past the end is clamped, and a fully decoded buffer gives the same clip
+            let codex = Codex::open(&src).unwrap();
+            codex
+                .export_range(2.5, 10.0, &dst, &EncodeOptions::default())
+                .unwrap();
+            let tail = codec.decode_file(&dst).unwrap();
+            assert_eq!(tail.data[0][..], source.data[0][120000..], "{extension}");
+            let options = EncodeOptions::default();
+            assert!(codex.export_range(4.0, 5.0, &dst, &options).is_err());
+
+            std::fs::remove_file(&src).unwrap();
+            std::fs::remove_file(&dst).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_apply_metadata_batch_tags_mixed_formats() {
+        let paths: Vec<String> = ["batch.wav", "batch.flac", "batch.wv"]
+            .iter()
+            .map(|name| temp_path(name))
+            .collect();
+        for path in &paths {
+            get_codec(path)
+                .unwrap()
+                .encode_file(&Some(test_buffer(2, 4800)), path)
+                .unwrap();
+        }
+        let missing = temp_path("missing.wav");
+        let mut batch: Vec<&str> = paths.iter().map(String::as_str).collect();
+        batch.push(&missing);
+
+        let fields = std::collections::HashMap::from([(
+            "USER_LIBRARY".to_string(),
+            "Harbor Ambiences".to_string(),
+        )]);
+        let results = apply_metadata_batch(&batch, &fields);
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[3].0, missing);
+        assert!(results[3].1.is_err());
+
+        for (path, result) in &results[..3] {
+            assert!(result.is_ok(), "{}: {:?}", path, result);
+            let codex = Codex::new(path).unwrap().extract_metadata().unwrap();
+            assert_eq!(
+                codex.get_metadata_field("USER_LIBRARY").as_deref(),
+                Some("Harbor Ambiences"),
+                "{}",
+                path
+            );
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_metadata_diff_previews_without_writing() {
+        let path = temp_path("diff.wv");
+        let mut tagged = Metadata::new();
+        tagged.set_field("USER_SHOW", "Harbor").unwrap();
+        let encoded = WvCodec
+            .encode_with_metadata(&Some(test_buffer(2, 4800)), &Some(&tagged))
+            .unwrap();
+        std::fs::write(&path, &encoded).unwrap();
+
+        let mut codex = Codex::new(&path).unwrap();
+        let mut metadata = Metadata::new();
+        metadata.set_field("USER_SHOW", "Harbor").unwrap();
+        metadata.set_field("USER_FXNAME", "Foghorn").unwrap();
+        metadata.add_image(ImageChunk::new(
+            "image/png".to_string(),
+            "Front".to_string(),
+            b"\x89PNG\r\n\x1a\n".to_vec(),
+        ));
+        codex.metadata = Some(metadata);
+
+        let changes = codex.metadata_diff(&path).unwrap();
+        assert!(
+            changes
+                .iter()
+                .all(|change| !change.key.starts_with("WAVPACK_"))
+        );
+        assert!(changes.contains(&FieldChange {
+            key: "USER_FXNAME".to_string(),
+            old: None,
+            new: Some("Foghorn".to_string()),
+        }));
+        let images: Vec<&FieldChange> = changes.iter().filter(|c| c.key == "IMAGE").collect();
+        assert_eq!(images.len(), 1);
+        assert!(images[0].old.is_none());
+        assert_eq!(std::fs::read(&path).unwrap(), encoded);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_new_reports_unsupported_extension() {
+        let path = temp_path("notes.xyz");
+        std::fs::write(&path, b"not audio").unwrap();
+        let error = Codex::new(&path).err().unwrap().to_string();
+        assert!(error.contains("xyz"), "{}", error);
+        assert!(error.contains("No codec found"), "{}", error);
+        std::fs::remove_file(&path).unwrap();
+
+        let error = Codex::new("/nonexistent/take1.wav")
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(error.contains("does not exist"), "{}", error);
+    }
+}
@@ -0,0 +1,350 @@
+//! Loudness measurement: sample peak, RMS, 4x oversampled true peak,
+//! ITU-R BS.1770 integrated loudness (K-weighting with absolute and relative gating)
+//! and EBU Tech 3342 loudness range.
+
+use crate::prelude::*;
+use crate::resample::{convolve, generate_kernel};
+
+/// Taps of the interpolation kernel used for true-peak oversampling
+const TRUE_PEAK_KERNEL_SIZE: usize = 32;
+/// Gating block length and hop (400 ms blocks with 75% overlap)
+const BLOCK_SECONDS: f64 = 0.4;
+const BLOCK_HOP_SECONDS: f64 = 0.1;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Loudness range uses 3 s short-term blocks, a -20 LU relative gate and the
+/// spread between the 10th and 95th percentiles
+const SHORT_TERM_SECONDS: f64 = 3.0;
+const RANGE_RELATIVE_GATE_LU: f64 = -20.0;
+const RANGE_LOW_PERCENTILE: f64 = 0.10;
+const RANGE_HIGH_PERCENTILE: f64 = 0.95;
+/// Offset in the BS.1770 loudness formula
+const LOUDNESS_OFFSET: f64 = -0.691;
+/// Weight of the surround channels of a 5.1 layout (L R C LFE Ls Rs)
+const SURROUND_WEIGHT: f64 = 1.41;
+
+/// Levels of a single channel, in dBFS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelLoudness {
+    pub sample_peak_dbfs: f32,
+    pub true_peak_dbfs: f32,
+    pub rms_dbfs: f32,
+}
+
+/// Result of `AudioBuffer::analyze`. Silence is reported as `f32::NEG_INFINITY`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoudnessStats {
+    pub channels: Vec<ChannelLoudness>,
+    pub sample_peak_dbfs: f32,
+    pub true_peak_dbfs: f32,
+    pub rms_dbfs: f32,
+    /// Gated programme loudness; `NEG_INFINITY` when no block passes the gates
+    pub integrated_lufs: f32,
+    /// Loudness range in LU; zero for material shorter than one 3 s block or silent
+    pub loudness_range_lu: f32,
+}
+
+fn to_dbfs(linear: f64) -> f32 {
+    if linear > 0.0 {
+        (20.0 * linear.log10()) as f32
+    } else {
+        f32::NEG_INFINITY
+    }
+}
+
+pub fn analyze(buffer: &AudioBuffer) -> LoudnessStats {
+    let true_peak_kernels: Vec<Vec<f32>> = [0.25, 0.5, 0.75]
+        .iter()
+        .map(|&pos| generate_kernel(pos, TRUE_PEAK_KERNEL_SIZE, 1.0))
+        .collect();
+
+    let mut sum_squares = 0.0f64;
+    let mut sample_count = 0usize;
+    let mut sample_peak = 0.0f32;
+    let mut true_peak = 0.0f32;
+    let channels = buffer
+        .data
+        .iter()
+        .map(|channel| {
+            let peak = channel.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+            let channel_true_peak = oversampled_peak(channel, &true_peak_kernels).max(peak);
+            let squares: f64 = channel.iter().map(|&s| s as f64 * s as f64).sum();
+            let rms = if channel.is_empty() {
+                0.0
+            } else {
+                (squares / channel.len() as f64).sqrt()
+            };
+
+            sum_squares += squares;
+            sample_count += channel.len();
+            sample_peak = sample_peak.max(peak);
+            true_peak = true_peak.max(channel_true_peak);
+            ChannelLoudness {
+                sample_peak_dbfs: to_dbfs(peak as f64),
+                true_peak_dbfs: to_dbfs(channel_true_peak as f64),
+                rms_dbfs: to_dbfs(rms),
+            }
+        })
+        .collect();
+
+    let rms = if sample_count > 0 {
+        (sum_squares / sample_count as f64).sqrt()
+    } else {
+        0.0
+    };
+
+    LoudnessStats {
+        channels,
+        sample_peak_dbfs: to_dbfs(sample_peak as f64),
+        true_peak_dbfs: to_dbfs(true_peak as f64),
+        rms_dbfs: to_dbfs(rms),
+        integrated_lufs: integrated_loudness(buffer),
+        loudness_range_lu: loudness_range(buffer),
+    }
+}
+
+/// Largest magnitude between samples, interpolated at quarter-sample positions
+fn oversampled_peak(channel: &[f32], kernels: &[Vec<f32>]) -> f32 {
+    let half = TRUE_PEAK_KERNEL_SIZE as isize / 2;
+    let mut peak = 0.0f32;
+    for n in 0..channel.len() {
+        for kernel in kernels {
+            peak = peak.max(convolve(channel, kernel, n as isize - half).abs());
+        }
+    }
+    peak
+}
+
+/// Second-order IIR section in direct form I
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    fn filter(&self, input: &[f64]) -> Vec<f64> {
+        let (mut x1, mut x2, mut y1, mut y2) = (0.0, 0.0, 0.0, 0.0);
+        input
+            .iter()
+            .map(|&x| {
+                let y = self.b[0] * x + self.b[1] * x1 + self.b[2] * x2
+                    - self.a[0] * y1
+                    - self.a[1] * y2;
+                (x2, x1, y2, y1) = (x1, x, y1, y);
+                y
+            })
+            .collect()
+    }
+}
+
+/// The two K-weighting stages (head shelf and RLB high-pass) for any sample rate
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let fs = sample_rate as f64;
+
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (std::f64::consts::PI * f0 / fs).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+    };
+
+    [shelf, high_pass]
+}
+
+fn channel_weights(channels: usize) -> Vec<f64> {
+    if channels == 6 {
+        vec![1.0, 1.0, 1.0, 0.0, SURROUND_WEIGHT, SURROUND_WEIGHT]
+    } else {
+        vec![1.0; channels]
+    }
+}
+
+fn block_loudness(power: f64) -> f64 {
+    LOUDNESS_OFFSET + 10.0 * power.log10()
+}
+
+/// K-weighted mean square of each block, summed over channels. Empty when the
+/// buffer is shorter than one block.
+fn block_powers(buffer: &AudioBuffer, block_seconds: f64) -> Vec<f64> {
+    let frames = buffer.data.first().map_or(0, Vec::len);
+    let block_len = (block_seconds * buffer.sample_rate as f64).round() as usize;
+    let hop = (BLOCK_HOP_SECONDS * buffer.sample_rate as f64).round() as usize;
+    if block_len == 0 || hop == 0 || frames < block_len {
+        return Vec::new();
+    }
+
+    let filters = k_weighting(buffer.sample_rate);
+    let mut block_powers = vec![0.0f64; (frames - block_len) / hop + 1];
+    for (channel, weight) in buffer.data.iter().zip(channel_weights(buffer.data.len())) {
+        if weight == 0.0 {
+            continue;
+        }
+        let samples: Vec<f64> = channel.iter().map(|&s| s as f64).collect();
+        let filtered = filters[1].filter(&filters[0].filter(&samples));
+        for (j, power) in block_powers.iter_mut().enumerate() {
+            let block = &filtered[j * hop..j * hop + block_len];
+            *power += weight * block.iter().map(|s| s * s).sum::<f64>() / block_len as f64;
+        }
+    }
+    block_powers
+}
+
+fn integrated_loudness(buffer: &AudioBuffer) -> f32 {
+    let block_powers = block_powers(buffer, BLOCK_SECONDS);
+
+    let gated_mean = |threshold: f64| {
+        let passing: Vec<f64> = block_powers
+            .iter()
+            .copied()
+            .filter(|&power| power > 0.0 && block_loudness(power) > threshold)
+            .collect();
+        (!passing.is_empty()).then(|| passing.iter().sum::<f64>() / passing.len() as f64)
+    };
+
+    let Some(absolute) = gated_mean(ABSOLUTE_GATE_LUFS) else {
+        return f32::NEG_INFINITY;
+    };
+    let relative_gate = block_loudness(absolute) + RELATIVE_GATE_LU;
+    match gated_mean(relative_gate.max(ABSOLUTE_GATE_LUFS)) {
+        Some(power) => block_loudness(power) as f32,
+        None => f32::NEG_INFINITY,
+    }
+}
+
+fn loudness_range(buffer: &AudioBuffer) -> f32 {
+    let powers: Vec<f64> = block_powers(buffer, SHORT_TERM_SECONDS)
+        .into_iter()
+        .filter(|&power| power > 0.0 && block_loudness(power) > ABSOLUTE_GATE_LUFS)
+        .collect();
+    if powers.is_empty() {
+        return 0.0;
+    }
+
+    let relative_gate =
+        block_loudness(powers.iter().sum::<f64>() / powers.len() as f64) + RANGE_RELATIVE_GATE_LU;
+    let mut loudness: Vec<f64> = powers
+        .into_iter()
+        .map(block_loudness)
+        .filter(|&l| l > relative_gate)
+        .collect();
+    loudness.sort_by(f64::total_cmp);
+
+    let percentile = |p: f64| loudness[((loudness.len() - 1) as f64 * p).round() as usize];
+    (percentile(RANGE_HIGH_PERCENTILE) - percentile(RANGE_LOW_PERCENTILE)) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(channels: u16, amplitude: f32, frequency: f64, phase: f64) -> AudioBuffer {
+        let sample_rate = 48000;
+        let samples: Vec<f32> = (0..sample_rate * 5)
+            .map(|i| {
+                let t = i as f64 / sample_rate as f64;
+                let angle = 2.0 * std::f64::consts::PI * frequency * t + phase;
+                amplitude * angle.sin() as f32
+            })
+            .collect();
+        AudioBuffer {
+            sample_rate,
+            channels,
+            format: SampleFormat::F32,
+            data: vec![samples; channels as usize],
+        }
+    }
+
+    #[test]
+    fn test_stereo_sine_at_minus_23_dbfs_reads_minus_23_lufs() {
+        // EBU Tech 3341 case 1: 1 kHz stereo sine at -23 dBFS
+        let stats = sine(2, 10f32.powf(-23.0 / 20.0), 1000.0, 0.0).analyze();
+        assert!((stats.integrated_lufs + 23.0).abs() < 0.1, "{:?}", stats);
+        assert!((stats.sample_peak_dbfs + 23.0).abs() < 0.01);
+        assert!((stats.rms_dbfs + 26.01).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_loudness_range_spans_level_change() {
+        // Steady tone has no range; 10 s at one level then 10 s 10 dB lower
+        // spreads the short-term loudness over about 10 LU
+        let steady = sine(1, 0.1, 1000.0, 0.0);
+        assert!(steady.analyze().loudness_range_lu.abs() < 0.1);
+
+        let mut stepped = sine(1, 0.1, 1000.0, 0.0);
+        let quiet: Vec<f32> = stepped.data[0]
+            .iter()
+            .map(|s| s * 10f32.powf(-0.5))
+            .collect();
+        stepped.data[0].extend(stepped.data[0].clone());
+        stepped.data[0].extend(quiet.iter().chain(&quiet));
+        let range = stepped.analyze().loudness_range_lu;
+        assert!((range - 10.0).abs() < 0.5, "{range}");
+    }
+
+    #[test]
+    fn test_true_peak_catches_inter_sample_peaks() {
+        // A quarter-rate sine offset by 45 degrees never has a sample at its crest
+        let stats = sine(1, 1.0, 12000.0, std::f64::consts::FRAC_PI_4).analyze();
+        assert!((stats.sample_peak_dbfs + 3.01).abs() < 0.01);
+        assert!(stats.true_peak_dbfs > -0.5, "{:?}", stats.true_peak_dbfs);
+    }
+
+    #[test]
+    fn test_silence_and_empty_buffers_are_negative_infinity() {
+        for stats in [
+            sine(1, 0.0, 1000.0, 0.0).analyze(),
+            AudioBuffer::default().analyze(),
+        ] {
+            assert_eq!(stats.integrated_lufs, f32::NEG_INFINITY);
+            assert_eq!(stats.true_peak_dbfs, f32::NEG_INFINITY);
+            assert_eq!(stats.rms_dbfs, f32::NEG_INFINITY);
+            assert!(
+                stats
+                    .channels
+                    .iter()
+                    .all(|c| c.sample_peak_dbfs == f32::NEG_INFINITY)
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_hits_target_or_peak_ceiling() {
+        let mut quiet = sine(2, 0.01, 1000.0, 0.0);
+        let achieved = quiet.normalize_lufs(-23.0, -1.0).unwrap();
+        assert!((achieved + 23.0).abs() < 0.01);
+        assert!((quiet.analyze().integrated_lufs + 23.0).abs() < 0.1);
+
+        // A stereo sine reaches 0 LUFS only at full scale, so the ceiling wins
+        let mut loud = sine(2, 0.5, 1000.0, 0.0);
+        let achieved = loud.normalize_lufs(0.0, -1.0).unwrap();
+        let stats = loud.analyze();
+        assert!(achieved < -0.5);
+        assert!((stats.true_peak_dbfs + 1.0).abs() < 0.01);
+        assert!((stats.integrated_lufs - achieved).abs() < 0.1);
+
+        let mut again = sine(2, 0.5, 1000.0, 0.0);
+        again.normalize_lufs(0.0, -1.0).unwrap();
+        assert_eq!(again.data, loud.data);
+
+        assert!(
+            sine(1, 0.0, 1000.0, 0.0)
+                .normalize_lufs(-23.0, -1.0)
+                .is_err()
+        );
+    }
+}
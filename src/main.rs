@@ -2,6 +2,11 @@ pub use anyhow::{Result as R, anyhow};
 use ffcodex_lib::*;
 
 fn main() -> R<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("convert") {
+        return convert(&args[2..]);
+    }
+
     // Use the directly exported get_version function
     let version = bindings::chromaprint_bindings::get_version();
     // let start_time = std::time::Instant::now();
@@ -70,7 +75,7 @@ fn main() -> R<()> {
 
     // c.convert_dual_mono()?;
     println!("Embedding metadata to output file...");
-    c.embed_metadata_to_different_file(output_file)?;
+    c.embed_metadata_as(output_file)?;
     // println!("First embedding complete!");
     // // clean_multi_mono(input_file)?;
 
@@ -105,7 +110,7 @@ fn main() -> R<()> {
     //     c2.get_metadata_field("USER_CATEGORYFULL")
     // );
     // println!("Re-embedding metadata to output file...");
-    // c2.embed_metadata(output_file)?;
+    // c2.embed_metadata()?;
     // println!("Second embedding complete!");
 
     let elapsed_time = start_time.elapsed();
@@ -113,3 +118,23 @@ fn main() -> R<()> {
 
     Ok(())
 }
+
+/// `convert <input> <output> [format]`. An output of `-` streams to stdout in
+/// `format` (default wav), so nothing else may be printed on that path.
+fn convert(args: &[String]) -> R<()> {
+    let [input, output, rest @ ..] = args else {
+        return Err(anyhow!(
+            "usage: ffcodex convert <input> <output|-> [format]"
+        ));
+    };
+    let codex = Codex::new(input)?.extract_metadata()?.decode()?;
+    if output == "-" {
+        let format = rest.first().map_or("wav", String::as_str);
+        let mut stdout = std::io::stdout().lock();
+        codex.export_to_writer(&mut stdout, format, &codecs::EncodeOptions::default())?;
+        std::io::Write::flush(&mut stdout)?;
+    } else {
+        codex.export(output)?;
+    }
+    Ok(())
+}
@@ -1,12 +1,48 @@
-pub use crate::dprintln; // Make the macro available
-pub use crate::*;
-pub use anyhow::{Result as R, anyhow};
-pub use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
+//! One-stop imports for library users.
+//!
+//! ```
+//! use ffcodex_lib::prelude::*;
+//!
+//! let buffer = AudioBuffer {
+//!     sample_rate: 48000,
+//!     channels: 1,
+//!     format: SampleFormat::I16,
+//!     data: vec![vec![0.25; 480]],
+//! };
+//! let path = std::env::temp_dir().join("ffcodex_prelude_doctest.wav");
+//! let path = path.to_str().unwrap();
+//! WavCodec.encode_file(&Some(buffer), path)?;
+//!
+//! let codex = Codex::new(path)?.decode()?;
+//! assert_eq!(codex.channels()?, 1);
+//! # std::fs::remove_file(path)?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub use crate::aswg::AswgMetadata;
+pub use crate::codecs::{
+    AifCodec, AudioBuffer, AudioStats, CafCodec, ClipMode, CueMarker, DitherMode,
+    DownmixCoefficients, DownmixMatrix, EncodeOptions, FadeCurve, FlacCodec, FlacEncodeOptions,
+    MergeStrategy, Metadata, MonoMode, OpusCodec, SampleFormat, W64Codec, WavCodec, WavpackHybrid,
+    WavpackQuality, WavpackTags, WvCodec, get_codec,
+};
+pub use crate::loudness::{ChannelLoudness, LoudnessStats};
+pub use crate::resample::ResampleQuality;
+pub use crate::{
+    Codec, Codex, FieldChange, FileInfo, FrameStream, StreamHeader, ValidationReport,
+    apply_metadata_batch, get_basic_metadata, get_fingerprint, null_test,
+};
+
+// Crate-internal conveniences shared by every module
+pub(crate) use crate::dprintln; // Make the macro available
+pub(crate) use crate::*;
+pub(crate) use anyhow::{Result as R, anyhow};
+pub(crate) use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
 // pub use std::collections::HashMap;
 
-pub use memmap2::MmapOptions;
-pub use rayon::prelude::*;
-pub use std::io::{Cursor, Read, Seek, SeekFrom, Write};
+pub(crate) use memmap2::MmapOptions;
+pub(crate) use rayon::prelude::*;
+pub(crate) use std::io::{Cursor, Read, Seek, SeekFrom, Write};
 // use std::path::PathBuf;
 
 // pub use std::path::Path;
@@ -1,3 +1,5 @@
+use crate::prelude::*;
+
 pub fn get_metadata_keys(key: &str) -> &'static [&'static str] {
     // Strip all possible prefixes in sequence
     let normalized_key = key
@@ -30,8 +32,12 @@ pub fn get_metadata_keys(key: &str) -> &'static [&'static str] {
         ],
         "categoryfull" => &["USER_CATEGORYFULL", "TAG_CategoryFull"],
         "usercategory" => &["USER_USERCATEGORY", "ASWG_userCategory", "TAG_UserCategory"],
-        "vendorcategory" => &["USER_VENDORCATEGORY", "TAG_VendorCategory"],
-        "fxname" => &["USER_FXNAME", "TAG_FXName"],
+        "vendorcategory" => &[
+            "USER_VENDORCATEGORY",
+            "ASWG_vendorCategory",
+            "TAG_VendorCategory",
+        ],
+        "fxname" => &["USER_FXNAME", "ASWG_fxName", "TAG_FXName"],
         "tracktitle" | "songtitle" => &[
             "USER_TRACKTITLE",
             "ASWG_songTitle",
@@ -80,18 +86,27 @@ pub fn get_metadata_keys(key: &str) -> &'static [&'static str] {
             "ASWG_micType",
             "STEINBERG_MediaRecordingMethod",
         ],
-        "micperspective" | "mcperspective" => &["USER_MICPERSPECTIVE", "TAG_MicPerspective"],
+        "micperspective" | "mcperspective" | "micdistance" => &[
+            "USER_MICPERSPECTIVE",
+            "ASWG_micDistance",
+            "TAG_MicPerspective",
+        ],
 
-        "location" => &[
+        "location" | "recordingloc" => &[
             "USER_LOCATION",
+            "ASWG_recordingLoc",
             "TAG_Location",
             "STEINBERG_MediaRecordingLocation",
         ],
 
-        "usercomments" | "userdata" | "data" | "note" => &["USER_USERCOMMENTS", "TAG_UserComments"],
+        "usercomments" | "userdata" | "data" | "note" => {
+            &["USER_USERCOMMENTS", "ASWG_userData", "TAG_UserComments"]
+        }
 
         "releasedate" => &["USER_RELEASEDATE", "ASWG_releaseDate", "TAG_RETAIL_DATE"],
 
+        "year" => &["USER_YEAR", "TAG_Year"],
+
         "rating" => &["USER_RATING", "TAG_Rating", "STEINBERG_MediaTrackNumber"],
 
         "embedder" => &["USER_EMBEDDER", "TAG_Embedder", "BEXT_BWF_CODING_HISTORY"],
@@ -99,6 +114,62 @@ pub fn get_metadata_keys(key: &str) -> &'static [&'static str] {
     }
 }
 
+/// UCS category triple plus the composite `CATEGORY-SUBCATEGORY` name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Category {
+    pub category: String,
+    pub subcategory: String,
+    pub cat_id: String,
+    pub category_full: String,
+}
+
+impl Category {
+    /// Set CATEGORY, SUBCATEGORY, CATID and CATEGORYFULL in `metadata`
+    pub(crate) fn write_to(&self, metadata: &mut Metadata) -> R<()> {
+        metadata.set_field("CATEGORY", &self.category)?;
+        metadata.set_field("SUBCATEGORY", &self.subcategory)?;
+        metadata.set_field("CATID", &self.cat_id)?;
+        metadata.set_field("CATEGORYFULL", &self.category_full)?;
+        Ok(())
+    }
+}
+
+impl Codex {
+    /// Set category, subcategory and CatID together and keep CATEGORYFULL in sync
+    pub fn set_category(
+        &mut self,
+        category: &str,
+        subcategory: &str,
+        cat_id: &str,
+    ) -> R<&mut Self> {
+        let category = category.trim().to_uppercase();
+        let subcategory = subcategory.trim().to_uppercase();
+        Category {
+            category_full: format!("{}-{}", category, subcategory),
+            category,
+            subcategory,
+            cat_id: cat_id.trim().to_string(),
+        }
+        .write_to(self.metadata.get_or_insert_with(Metadata::new))?;
+        Ok(self)
+    }
+
+    pub fn get_category(&self) -> Option<Category> {
+        let category = self.get_metadata_field("CATEGORY")?;
+        let subcategory = self.get_metadata_field("SUBCATEGORY").unwrap_or_default();
+        let category_full = self
+            .get_metadata_field("CATEGORYFULL")
+            .unwrap_or_else(|| format!("{}-{}", category, subcategory));
+
+        Some(Category {
+            cat_id: self.get_metadata_field("CATID").unwrap_or_default(),
+            category,
+            subcategory,
+            category_full,
+        })
+    }
+}
+
 // pub fn set_soundminer_metadata(key: &str, value: &str, map: &mut HashMap<String, String>) {
 //     match key
 //         .strip_prefix("USER_")
@@ -251,3 +322,27 @@ pub fn get_metadata_keys(key: &str) -> &'static [&'static str] {
 //         _ => { &[}
 //    ]}
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_category_populates_all_fields() {
+        let mut codex = Codex::default();
+        codex.set_category("metal", "friction", "METLFric").unwrap();
+
+        let metadata = codex.metadata.as_ref().unwrap();
+        assert_eq!(metadata.get_field("USER_CATEGORY").unwrap(), "METAL");
+        assert_eq!(metadata.get_field("ASWG_subCategory").unwrap(), "FRICTION");
+        assert_eq!(metadata.get_field("TAG_CatID").unwrap(), "METLFric");
+        assert_eq!(
+            metadata.get_field("USER_CATEGORYFULL").unwrap(),
+            "METAL-FRICTION"
+        );
+
+        let category = codex.get_category().unwrap();
+        assert_eq!(category.category_full, "METAL-FRICTION");
+        assert_eq!(category.cat_id, "METLFric");
+    }
+}
@@ -0,0 +1,762 @@
+//! UCS (Universal Category System) categories: the CatID list, and helpers to
+//! fill the category fields from a CatID and to check them before delivery.
+
+use crate::prelude::*;
+use crate::soundminer::Category;
+
+/// How many known CatIDs an unknown one suggests
+const MAX_SUGGESTIONS: usize = 5;
+
+/// The UCS list as (CatID, CATEGORY, SUBCATEGORY).
+///
+/// This list was not generated from the official UCS spreadsheet, so it has no
+/// UCS version and lacks later categories such as ARCHIVED. When it is
+/// regenerated, name the spreadsheet's version here and update the count in
+/// `test_category_list_size_and_unique_cat_ids`.
+const UCS_CATEGORIES: &[(&str, &str, &str)] = &[
+    ("AIRBrst", "AIR", "BURST"),
+    ("AIRHiss", "AIR", "HISS"),
+    ("AIRMisc", "AIR", "MISC"),
+    ("AIRSuck", "AIR", "SUCTION"),
+    ("AEROGlide", "AIRCRAFT", "GLIDER"),
+    ("AEROHeli", "AIRCRAFT", "HELICOPTER"),
+    ("AEROInt", "AIRCRAFT", "INTERIOR"),
+    ("AEROJet", "AIRCRAFT", "JET"),
+    ("AEROMech", "AIRCRAFT", "MECHANISM"),
+    ("AEROMil", "AIRCRAFT", "MILITARY"),
+    ("AEROMisc", "AIRCRAFT", "MISC"),
+    ("AEROProp", "AIRCRAFT", "PROP"),
+    ("AERORadio", "AIRCRAFT", "RADIO CONTROLLED"),
+    ("ALRMBell", "ALARMS", "BELL"),
+    ("ALRMBuzr", "ALARMS", "BUZZER"),
+    ("ALRMClock", "ALARMS", "CLOCK"),
+    ("ALRMElec", "ALARMS", "ELECTRONIC"),
+    ("ALRMMisc", "ALARMS", "MISC"),
+    ("ALRMSiren", "ALARMS", "SIREN"),
+    ("AMBAir", "AMBIENCE", "AIR"),
+    ("AMBAmus", "AMBIENCE", "AMUSEMENT"),
+    ("AMBBirdsong", "AMBIENCE", "BIRDSONG"),
+    ("AMBCele", "AMBIENCE", "CELEBRATION"),
+    ("AMBCnst", "AMBIENCE", "CONSTRUCTION"),
+    ("AMBDsgn", "AMBIENCE", "DESIGNED"),
+    ("AMBDsrt", "AMBIENCE", "DESERT"),
+    ("AMBDungeon", "AMBIENCE", "DUNGEON"),
+    ("AMBEmrg", "AMBIENCE", "EMERGENCY"),
+    ("AMBFarm", "AMBIENCE", "FARM"),
+    ("AMBForst", "AMBIENCE", "FOREST"),
+    ("AMBGras", "AMBIENCE", "GRASSLAND"),
+    ("AMBHist", "AMBIENCE", "HISTORICAL"),
+    ("AMBHosp", "AMBIENCE", "HOSPITAL"),
+    ("AMBInd", "AMBIENCE", "INDUSTRIAL"),
+    ("AMBMrkt", "AMBIENCE", "MARKET"),
+    ("AMBMisc", "AMBIENCE", "MISC"),
+    ("AMBMtn", "AMBIENCE", "MOUNTAIN"),
+    ("AMBNaut", "AMBIENCE", "NAUTICAL"),
+    ("AMBOffc", "AMBIENCE", "OFFICE"),
+    ("AMBPark", "AMBIENCE", "PARK"),
+    ("AMBPrisn", "AMBIENCE", "PRISON"),
+    ("AMBPubl", "AMBIENCE", "PUBLIC PLACE"),
+    ("AMBRlgn", "AMBIENCE", "RELIGIOUS"),
+    ("AMBRest", "AMBIENCE", "RESTAURANT & BAR"),
+    ("AMBRoomTn", "AMBIENCE", "ROOM TONE"),
+    ("AMBRurl", "AMBIENCE", "RURAL"),
+    ("AMBSchl", "AMBIENCE", "SCHOOL"),
+    ("AMBScifi", "AMBIENCE", "SCIFI"),
+    ("AMBSeas", "AMBIENCE", "SEASIDE"),
+    ("AMBSprt", "AMBIENCE", "SPORTS"),
+    ("AMBSubn", "AMBIENCE", "SUBURBAN"),
+    ("AMBSwmp", "AMBIENCE", "SWAMP"),
+    ("AMBTown", "AMBIENCE", "TOWN"),
+    ("AMBTraf", "AMBIENCE", "TRAFFIC"),
+    ("AMBTran", "AMBIENCE", "TRANSPORTATION"),
+    ("AMBTropc", "AMBIENCE", "TROPICAL"),
+    ("AMBUndr", "AMBIENCE", "UNDERGROUND"),
+    ("AMBUndwtr", "AMBIENCE", "UNDERWATER"),
+    ("AMBUrbn", "AMBIENCE", "URBAN"),
+    ("AMBWar", "AMBIENCE", "WARFARE"),
+    ("ANMLAmph", "ANIMALS", "AMPHIBIAN"),
+    ("ANMLAqua", "ANIMALS", "AQUATIC"),
+    ("ANMLBat", "ANIMALS", "BAT"),
+    ("ANMLCat", "ANIMALS", "CAT - DOMESTIC"),
+    ("ANMLCatWild", "ANIMALS", "CAT - WILD"),
+    ("ANMLDog", "ANIMALS", "DOG"),
+    ("ANMLFarm", "ANIMALS", "FARM"),
+    ("ANMLHorse", "ANIMALS", "HORSE"),
+    ("ANMLInsct", "ANIMALS", "INSECT"),
+    ("ANMLMisc", "ANIMALS", "MISC"),
+    ("ANMLMvmt", "ANIMALS", "MOVEMENT"),
+    ("ANMLPrimt", "ANIMALS", "PRIMATE"),
+    ("ANMLRdnt", "ANIMALS", "RODENT"),
+    ("ANMLRept", "ANIMALS", "REPTILE"),
+    ("ANMLWild", "ANIMALS", "WILD"),
+    ("BEEPAppl", "BEEPS", "APPLIANCE"),
+    ("BEEPLofi", "BEEPS", "LOFI"),
+    ("BEEPMisc", "BEEPS", "MISC"),
+    ("BEEPTimer", "BEEPS", "TIMER"),
+    ("BEEPVeh", "BEEPS", "VEHICLE"),
+    ("BELLAnml", "BELLS", "ANIMAL"),
+    ("BELLDoor", "BELLS", "DOOR"),
+    ("BELLGong", "BELLS", "GONG"),
+    ("BELLHand", "BELLS", "HANDBELL"),
+    ("BELLMisc", "BELLS", "MISC"),
+    ("BIRDCrow", "BIRDS", "CROW"),
+    ("BIRDFowl", "BIRDS", "FOWL"),
+    ("BIRDMisc", "BIRDS", "MISC"),
+    ("BIRDPrey", "BIRDS", "PREY"),
+    ("BIRDSong", "BIRDS", "SONGBIRD"),
+    ("BIRDTrop", "BIRDS", "TROPICAL"),
+    ("BIRDWade", "BIRDS", "WADING"),
+    ("BOATAir", "BOATS", "AIRBOAT"),
+    ("BOATDoor", "BOATS", "DOOR"),
+    ("BOATFerry", "BOATS", "FERRY"),
+    ("BOATFish", "BOATS", "FISHING"),
+    ("BOATHorn", "BOATS", "HORN"),
+    ("BOATInt", "BOATS", "INTERIOR"),
+    ("BOATMech", "BOATS", "MECHANISM"),
+    ("BOATMil", "BOATS", "MILITARY"),
+    ("BOATMisc", "BOATS", "MISC"),
+    ("BOATMotr", "BOATS", "MOTORBOAT"),
+    ("BOATRow", "BOATS", "ROWBOAT"),
+    ("BOATSail", "BOATS", "SAILBOAT"),
+    ("BOATShip", "BOATS", "SHIP"),
+    ("BOATSteam", "BOATS", "STEAM"),
+    ("BOATSub", "BOATS", "SUBMARINE"),
+    ("BLLTBy", "BULLETS", "BY"),
+    ("BLLTImpt", "BULLETS", "IMPACT"),
+    ("BLLTMisc", "BULLETS", "MISC"),
+    ("BLLTRico", "BULLETS", "RICOCHET"),
+    ("BLLTShell", "BULLETS", "SHELL"),
+    ("TOONAnml", "CARTOON", "ANIMAL"),
+    ("TOONBoing", "CARTOON", "BOING"),
+    ("TOONClang", "CARTOON", "CLANG"),
+    ("TOONCreak", "CARTOON", "CREAK"),
+    ("TOONHorn", "CARTOON", "HORN"),
+    ("TOONImpt", "CARTOON", "IMPACT"),
+    ("TOONMisc", "CARTOON", "MISC"),
+    ("TOONPluck", "CARTOON", "PLUCK"),
+    ("TOONPop", "CARTOON", "POP"),
+    ("TOONSplt", "CARTOON", "SPLAT"),
+    ("TOONSqk", "CARTOON", "SQUEAK"),
+    ("TOONStrch", "CARTOON", "STRETCH"),
+    ("TOONSwsh", "CARTOON", "SWISH"),
+    ("TOONVox", "CARTOON", "VOCAL"),
+    ("TOONWhstl", "CARTOON", "WHISTLE"),
+    ("CERMBrk", "CERAMICS", "BREAK"),
+    ("CERMImpt", "CERAMICS", "IMPACT"),
+    ("CERMMisc", "CERAMICS", "MISC"),
+    ("CERMMvmt", "CERAMICS", "MOVEMENT"),
+    ("CERMTonl", "CERAMICS", "TONAL"),
+    ("CHAINBrk", "CHAINS", "BREAK"),
+    ("CHAINImpt", "CHAINS", "IMPACT"),
+    ("CHAINMisc", "CHAINS", "MISC"),
+    ("CHAINMvmt", "CHAINS", "MOVEMENT"),
+    ("CHEMAcid", "CHEMICALS", "ACID"),
+    ("CHEMBurn", "CHEMICALS", "BURN"),
+    ("CHEMMisc", "CHEMICALS", "MISC"),
+    ("CHEMReact", "CHEMICALS", "REACTION"),
+    ("CLOCKChim", "CLOCKS", "CHIME"),
+    ("CLOCKMech", "CLOCKS", "MECHANICS"),
+    ("CLOCKMisc", "CLOCKS", "MISC"),
+    ("CLOCKTick", "CLOCKS", "TICK"),
+    ("CLOTHFlp", "CLOTH", "FLAP"),
+    ("CLOTHImpt", "CLOTH", "IMPACT"),
+    ("CLOTHMisc", "CLOTH", "MISC"),
+    ("CLOTHMvmt", "CLOTH", "MOVEMENT"),
+    ("CLOTHRip", "CLOTH", "RIP"),
+    ("COMCell", "COMMUNICATIONS", "CELLPHONE"),
+    ("COMMisc", "COMMUNICATIONS", "MISC"),
+    ("COMRadio", "COMMUNICATIONS", "RADIO"),
+    ("COMStatic", "COMMUNICATIONS", "STATIC"),
+    ("COMTelm", "COMMUNICATIONS", "TELEMETRY"),
+    ("COMTelph", "COMMUNICATIONS", "TELEPHONE"),
+    ("COMPKey", "COMPUTERS", "KEYBOARD & MOUSE"),
+    ("COMPMisc", "COMPUTERS", "MISC"),
+    ("CREAAqua", "CREATURES", "AQUATIC"),
+    ("CREABeast", "CREATURES", "BEAST"),
+    ("CREABlob", "CREATURES", "BLOB"),
+    ("CREADrgn", "CREATURES", "DRAGON"),
+    ("CREAEthr", "CREATURES", "ETHEREAL"),
+    ("CREAHmn", "CREATURES", "HUMANOID"),
+    ("CREAInsc", "CREATURES", "INSECTOID"),
+    ("CREAMisc", "CREATURES", "MISC"),
+    ("CREAMnstr", "CREATURES", "MONSTER"),
+    ("CREAMvmt", "CREATURES", "MOVEMENT"),
+    ("CREASmall", "CREATURES", "SMALL"),
+    ("CRWDAngr", "CROWDS", "ANGRY"),
+    ("CRWDApls", "CROWDS", "APPLAUSE"),
+    ("CRWDBatl", "CROWDS", "BATTLE"),
+    ("CRWDCele", "CROWDS", "CELEBRATION"),
+    ("CRWDChant", "CROWDS", "CHANT"),
+    ("CRWDCheer", "CROWDS", "CHEERING"),
+    ("CRWDChld", "CROWDS", "CHILDREN"),
+    ("CRWDConv", "CROWDS", "CONVERSATION"),
+    ("CRWDLaugh", "CROWDS", "LAUGHTER"),
+    ("CRWDMisc", "CROWDS", "MISC"),
+    ("CRWDPanic", "CROWDS", "PANIC"),
+    ("CRWDReac", "CROWDS", "REACTION"),
+    ("CRWDSing", "CROWDS", "SINGING"),
+    ("CRWDSprt", "CROWDS", "SPORTS"),
+    ("CRWDWalla", "CROWDS", "WALLA"),
+    ("DSGNBram", "DESIGNED", "BRAAM"),
+    ("DSGNDist", "DESIGNED", "DISTORTION"),
+    ("DSGNDron", "DESIGNED", "DRONE"),
+    ("DSGNErie", "DESIGNED", "EERIE"),
+    ("DSGNEthr", "DESIGNED", "ETHEREAL"),
+    ("DSGNImpt", "DESIGNED", "IMPACT"),
+    ("DSGNMisc", "DESIGNED", "MISC"),
+    ("DSGNRise", "DESIGNED", "RISER"),
+    ("DSGNRmbl", "DESIGNED", "RUMBLE"),
+    ("DSGNSrce", "DESIGNED", "SOURCE"),
+    ("DSGNStngr", "DESIGNED", "STINGER"),
+    ("DSGNSynth", "DESIGNED", "SYNTHETIC"),
+    ("DSGNTonl", "DESIGNED", "TONAL"),
+    ("DSGNVocl", "DESIGNED", "VOCAL"),
+    ("DSGNWhsh", "DESIGNED", "WHOOSH"),
+    ("DESTCrsh", "DESTRUCTION", "CRASH & DEBRIS"),
+    ("DESTMisc", "DESTRUCTION", "MISC"),
+    ("DIRTCrsh", "DIRT & SAND", "CRASH & DEBRIS"),
+    ("DIRTDust", "DIRT & SAND", "DUST"),
+    ("DIRTImpt", "DIRT & SAND", "IMPACT"),
+    ("DIRTMisc", "DIRT & SAND", "MISC"),
+    ("DIRTMvmt", "DIRT & SAND", "MOVEMENT"),
+    ("DOORAntq", "DOORS", "ANTIQUE"),
+    ("DOORAppl", "DOORS", "APPLIANCE"),
+    ("DOORCab", "DOORS", "CABINET"),
+    ("DOORCreak", "DOORS", "CREAK"),
+    ("DOORDungeon", "DOORS", "DUNGEON"),
+    ("DOORElec", "DOORS", "ELECTRIC"),
+    ("DOORGate", "DOORS", "GATE"),
+    ("DOORGlas", "DOORS", "GLASS"),
+    ("DOORHdwr", "DOORS", "HARDWARE"),
+    ("DOORHydr", "DOORS", "HYDRAULIC & PNEUMATIC"),
+    ("DOORKnck", "DOORS", "KNOCK"),
+    ("DOORMetl", "DOORS", "METAL"),
+    ("DOORMisc", "DOORS", "MISC"),
+    ("DOORPlas", "DOORS", "PLASTIC"),
+    ("DOORPrison", "DOORS", "PRISON"),
+    ("DOORRevl", "DOORS", "REVOLVING"),
+    ("DOORScfi", "DOORS", "SCIFI"),
+    ("DOORSlid", "DOORS", "SLIDING"),
+    ("DOORSqk", "DOORS", "SQUEAK"),
+    ("DOORStone", "DOORS", "STONE"),
+    ("DOORWood", "DOORS", "WOOD"),
+    ("DRWRMetl", "DRAWERS", "METAL"),
+    ("DRWRMisc", "DRAWERS", "MISC"),
+    ("DRWRPlas", "DRAWERS", "PLASTIC"),
+    ("DRWRWood", "DRAWERS", "WOOD"),
+    ("ELECArc", "ELECTRICITY", "ARC"),
+    ("ELECBuzz", "ELECTRICITY", "BUZZ & HUM"),
+    ("ELECMisc", "ELECTRICITY", "MISC"),
+    ("ELECSprk", "ELECTRICITY", "SPARK"),
+    ("ELECZap", "ELECTRICITY", "ZAP"),
+    ("EXPLDsgn", "EXPLOSIONS", "DESIGNED"),
+    ("EXPLMisc", "EXPLOSIONS", "MISC"),
+    ("EXPLReal", "EXPLOSIONS", "REAL"),
+    ("FARTDsgn", "FARTS", "DESIGNED"),
+    ("FARTMisc", "FARTS", "MISC"),
+    ("FARTReal", "FARTS", "REAL"),
+    ("FGHTBf", "FIGHT", "BODYFALL"),
+    ("FGHTClth", "FIGHT", "CLOTH"),
+    ("FGHTGrab", "FIGHT", "GRAB"),
+    ("FGHTImpt", "FIGHT", "IMPACT"),
+    ("FGHTMisc", "FIGHT", "MISC"),
+    ("FIREBrst", "FIRE", "BURST"),
+    ("FIREBurn", "FIRE", "BURNING"),
+    ("FIRECrkl", "FIRE", "CRACKLE"),
+    ("FIREGas", "FIRE", "GAS"),
+    ("FIREIgn", "FIRE", "IGNITE"),
+    ("FIREMisc", "FIRE", "MISC"),
+    ("FIRESizz", "FIRE", "SIZZLE"),
+    ("FIRETrch", "FIRE", "TORCH"),
+    ("FIREWhsh", "FIRE", "WHOOSH"),
+    ("FIREWCrkl", "FIREWORKS", "CRACKLE"),
+    ("FIREWExpl", "FIREWORKS", "EXPLOSION"),
+    ("FIREWLnch", "FIREWORKS", "LAUNCH"),
+    ("FIREWMisc", "FIREWORKS", "MISC"),
+    ("FOLYClth", "FOLEY", "CLOTH"),
+    ("FOLYFeet", "FOLEY", "FEET"),
+    ("FOLYHand", "FOLEY", "HANDS"),
+    ("FOLYMisc", "FOLEY", "MISC"),
+    ("FOLYProp", "FOLEY", "PROP"),
+    ("FOODCook", "FOOD & DRINK", "COOKING"),
+    ("FOODDrnk", "FOOD & DRINK", "DRINKING"),
+    ("FOODEat", "FOOD & DRINK", "EATING"),
+    ("FOODGware", "FOOD & DRINK", "GLASSWARE"),
+    ("FOODIngr", "FOOD & DRINK", "INGREDIENTS"),
+    ("FOODKware", "FOOD & DRINK", "KITCHENWARE"),
+    ("FOODMisc", "FOOD & DRINK", "MISC"),
+    ("FOODTware", "FOOD & DRINK", "TABLEWARE"),
+    ("FEETAnml", "FOOTSTEPS", "ANIMAL"),
+    ("FEETCrea", "FOOTSTEPS", "CREATURE"),
+    ("FEETHors", "FOOTSTEPS", "HORSE"),
+    ("FEETHuman", "FOOTSTEPS", "HUMAN"),
+    ("FEETMisc", "FOOTSTEPS", "MISC"),
+    ("GAMEArcd", "GAMES", "ARCADE"),
+    ("GAMEBoard", "GAMES", "BOARD"),
+    ("GAMECas", "GAMES", "CASINO"),
+    ("GAMEMisc", "GAMES", "MISC"),
+    ("GAMEVideo", "GAMES", "VIDEO"),
+    ("GEOFumr", "GEOTHERMAL", "FUMAROLE"),
+    ("GEOGeyr", "GEOTHERMAL", "GEYSER"),
+    ("GEOLava", "GEOTHERMAL", "LAVA"),
+    ("GEOMisc", "GEOTHERMAL", "MISC"),
+    ("GEOMud", "GEOTHERMAL", "MUD POT"),
+    ("GLASBrk", "GLASS", "BREAK"),
+    ("GLASCrsh", "GLASS", "CRASH & DEBRIS"),
+    ("GLASFric", "GLASS", "FRICTION"),
+    ("GLASImpt", "GLASS", "IMPACT"),
+    ("GLASMisc", "GLASS", "MISC"),
+    ("GLASMvmt", "GLASS", "MOVEMENT"),
+    ("GLASTonl", "GLASS", "TONAL"),
+    ("GOREBlood", "GORE", "BLOOD"),
+    ("GOREBone", "GORE", "BONE"),
+    ("GOREBurn", "GORE", "BURN"),
+    ("GOREFlsh", "GORE", "FLESH"),
+    ("GOREMisc", "GORE", "MISC"),
+    ("GOREOoze", "GORE", "OOZE"),
+    ("GORESplt", "GORE", "SPLAT"),
+    ("GOREStab", "GORE", "STAB"),
+    ("GUNAntq", "GUNS", "ANTIQUE"),
+    ("GUNArtl", "GUNS", "ARTILLERY"),
+    ("GUNAuto", "GUNS", "AUTOMATIC"),
+    ("GUNCano", "GUNS", "CANNON"),
+    ("GUNMech", "GUNS", "MECHANISM"),
+    ("GUNMisc", "GUNS", "MISC"),
+    ("GUNPis", "GUNS", "PISTOL"),
+    ("GUNRif", "GUNS", "RIFLE"),
+    ("GUNShotg", "GUNS", "SHOTGUN"),
+    ("GUNSupr", "GUNS", "SUPPRESSED"),
+    ("HORNAir", "HORNS", "AIR POWERED"),
+    ("HORNCele", "HORNS", "CELEBRATION"),
+    ("HORNMisc", "HORNS", "MISC"),
+    ("HORNTrad", "HORNS", "TRADITIONAL"),
+    ("HMNBrth", "HUMAN", "BREATH"),
+    ("HMNBurp", "HUMAN", "BURP"),
+    ("HMNCough", "HUMAN", "COUGH"),
+    ("HMNHeart", "HUMAN", "HEARTBEAT"),
+    ("HMNMisc", "HUMAN", "MISC"),
+    ("HMNSneez", "HUMAN", "SNEEZE"),
+    ("ICEBrk", "ICE", "BREAK"),
+    ("ICECrsh", "ICE", "CRASH & DEBRIS"),
+    ("ICEFric", "ICE", "FRICTION"),
+    ("ICEImpt", "ICE", "IMPACT"),
+    ("ICEMisc", "ICE", "MISC"),
+    ("ICEMvmt", "ICE", "MOVEMENT"),
+    ("ICETonl", "ICE", "TONAL"),
+    ("LASRBeam", "LASERS", "BEAM"),
+    ("LASRGun", "LASERS", "GUN"),
+    ("LASRImpt", "LASERS", "IMPACT"),
+    ("LASRMisc", "LASERS", "MISC"),
+    ("LETHCreak", "LEATHER", "CREAK"),
+    ("LETHImpt", "LEATHER", "IMPACT"),
+    ("LETHMisc", "LEATHER", "MISC"),
+    ("LETHMvmt", "LEATHER", "MOVEMENT"),
+    ("LIQBubl", "LIQUID & MUD", "BUBBLES"),
+    ("LIQDrip", "LIQUID & MUD", "DRIP"),
+    ("LIQImpt", "LIQUID & MUD", "IMPACT"),
+    ("LIQMisc", "LIQUID & MUD", "MISC"),
+    ("LIQMud", "LIQUID & MUD", "MUD"),
+    ("LIQMvmt", "LIQUID & MUD", "MOVEMENT"),
+    ("LIQSuct", "LIQUID & MUD", "SUCTION"),
+    ("MACHAmus", "MACHINES", "AMUSEMENT"),
+    ("MACHAnlg", "MACHINES", "ANALOG"),
+    ("MACHAppl", "MACHINES", "APPLIANCE"),
+    ("MACHConst", "MACHINES", "CONSTRUCTION"),
+    ("MACHElev", "MACHINES", "ELEVATOR"),
+    ("MACHEscl", "MACHINES", "ESCALATOR"),
+    ("MACHFan", "MACHINES", "FAN"),
+    ("MACHGrdn", "MACHINES", "GARDEN"),
+    ("MACHHvac", "MACHINES", "HVAC"),
+    ("MACHInd", "MACHINES", "INDUSTRIAL"),
+    ("MACHMed", "MACHINES", "MEDICAL"),
+    ("MACHMisc", "MACHINES", "MISC"),
+    ("MACHOffc", "MACHINES", "OFFICE"),
+    ("MACHPump", "MACHINES", "PUMP"),
+    ("MACHRobt", "MACHINES", "ROBOTIC"),
+    ("MAGAngl", "MAGIC", "ANGELIC"),
+    ("MAGElem", "MAGIC", "ELEMENTAL"),
+    ("MAGEvil", "MAGIC", "EVIL"),
+    ("MAGMisc", "MAGIC", "MISC"),
+    ("MAGPoof", "MAGIC", "POOF"),
+    ("MAGShim", "MAGIC", "SHIMMER"),
+    ("MAGSpel", "MAGIC", "SPELL"),
+    ("MECHClik", "MECHANICAL", "CLICK"),
+    ("MECHGear", "MECHANICAL", "GEARS"),
+    ("MECHHydr", "MECHANICAL", "HYDRAULIC & PNEUMATIC"),
+    ("MECHLtch", "MECHANICAL", "LATCH"),
+    ("MECHLvr", "MECHANICAL", "LEVER"),
+    ("MECHMisc", "MECHANICAL", "MISC"),
+    ("MECHPuly", "MECHANICAL", "PULLEY"),
+    ("MECHRtch", "MECHANICAL", "RATCHET"),
+    ("MECHRelay", "MECHANICAL", "RELAY"),
+    ("MECHRoll", "MECHANICAL", "ROLLING"),
+    ("MECHSwtch", "MECHANICAL", "SWITCH"),
+    ("METLBend", "METAL", "BEND"),
+    ("METLBrk", "METAL", "BREAK"),
+    ("METLCrsh", "METAL", "CRASH & DEBRIS"),
+    ("METLFric", "METAL", "FRICTION"),
+    ("METLImpt", "METAL", "IMPACT"),
+    ("METLMisc", "METAL", "MISC"),
+    ("METLMvmt", "METAL", "MOVEMENT"),
+    ("METLTonl", "METAL", "TONAL"),
+    ("MOTRAntq", "MOTORS", "ANTIQUE"),
+    ("MOTRCmbst", "MOTORS", "COMBUSTION"),
+    ("MOTRElec", "MOTORS", "ELECTRIC"),
+    ("MOTRMisc", "MOTORS", "MISC"),
+    ("MOTRTurbn", "MOTORS", "TURBINE"),
+    ("MOVEActv", "MOVEMENT", "ACTIVITY"),
+    ("MOVEAnml", "MOVEMENT", "ANIMAL"),
+    ("MOVEMisc", "MOVEMENT", "MISC"),
+    ("MUSCBras", "MUSICAL", "BRASS"),
+    ("MUSCChim", "MUSICAL", "CHIMES"),
+    ("MUSCElec", "MUSICAL", "ELECTRONIC"),
+    ("MUSCKey", "MUSICAL", "KEYED"),
+    ("MUSCLoop", "MUSICAL", "LOOP"),
+    ("MUSCMisc", "MUSICAL", "MISC"),
+    ("MUSCPerc", "MUSICAL", "PERCUSSION"),
+    ("MUSCPlkd", "MUSICAL", "PLUCKED"),
+    ("MUSCShkn", "MUSICAL", "SHAKEN"),
+    ("MUSCStr", "MUSICAL", "STRINGED"),
+    ("MUSCToy", "MUSICAL", "TOY"),
+    ("NATDAval", "NATURAL DISASTER", "AVALANCHE"),
+    ("NATDEqk", "NATURAL DISASTER", "EARTHQUAKE"),
+    ("NATDMisc", "NATURAL DISASTER", "MISC"),
+    ("OBJBag", "OBJECTS", "BAG"),
+    ("OBJBook", "OBJECTS", "BOOK"),
+    ("OBJCont", "OBJECTS", "CONTAINER"),
+    ("OBJFurn", "OBJECTS", "FURNITURE"),
+    ("OBJHsehld", "OBJECTS", "HOUSEHOLD"),
+    ("OBJJewl", "OBJECTS", "JEWELRY"),
+    ("OBJKey", "OBJECTS", "KEYS"),
+    ("OBJLug", "OBJECTS", "LUGGAGE"),
+    ("OBJMisc", "OBJECTS", "MISC"),
+    ("OBJOffc", "OBJECTS", "OFFICE"),
+    ("OBJPack", "OBJECTS", "PACKAGING"),
+    ("OBJTape", "OBJECTS", "TAPE"),
+    ("OBJUmbr", "OBJECTS", "UMBRELLA"),
+    ("OBJZipr", "OBJECTS", "ZIPPER"),
+    ("PAPRCrum", "PAPER", "CRUMPLE"),
+    ("PAPRFltr", "PAPER", "FLUTTER"),
+    ("PAPRFric", "PAPER", "FRICTION"),
+    ("PAPRHndl", "PAPER", "HANDLE"),
+    ("PAPRImpt", "PAPER", "IMPACT"),
+    ("PAPRMisc", "PAPER", "MISC"),
+    ("PAPRRip", "PAPER", "RIP"),
+    ("PLASBrk", "PLASTIC", "BREAK"),
+    ("PLASCrsh", "PLASTIC", "CRASH & DEBRIS"),
+    ("PLASFric", "PLASTIC", "FRICTION"),
+    ("PLASImpt", "PLASTIC", "IMPACT"),
+    ("PLASMisc", "PLASTIC", "MISC"),
+    ("PLASMvmt", "PLASTIC", "MOVEMENT"),
+    ("RAINClos", "RAIN", "CLOSE"),
+    ("RAINGen", "RAIN", "GENERAL"),
+    ("RAINInt", "RAIN", "INTERIOR"),
+    ("RAINMisc", "RAIN", "MISC"),
+    ("RAINSurf", "RAIN", "SURFACE"),
+    ("RAINVeh", "RAIN", "VEHICLE"),
+    ("ROBTMisc", "ROBOTS", "MISC"),
+    ("ROBTMvmt", "ROBOTS", "MOVEMENT"),
+    ("ROBTTrns", "ROBOTS", "TRANSFORM"),
+    ("ROBTVox", "ROBOTS", "VOCAL"),
+    ("ROCKBrk", "ROCKS", "BREAK"),
+    ("ROCKCrsh", "ROCKS", "CRASH & DEBRIS"),
+    ("ROCKFric", "ROCKS", "FRICTION"),
+    ("ROCKImpt", "ROCKS", "IMPACT"),
+    ("ROCKMisc", "ROCKS", "MISC"),
+    ("ROCKMvmt", "ROCKS", "MOVEMENT"),
+    ("ROPECreak", "ROPE", "CREAK"),
+    ("ROPEImpt", "ROPE", "IMPACT"),
+    ("ROPEMisc", "ROPE", "MISC"),
+    ("ROPEMvmt", "ROPE", "MOVEMENT"),
+    ("RUBRCreak", "RUBBER", "CREAK"),
+    ("RUBRImpt", "RUBBER", "IMPACT"),
+    ("RUBRMisc", "RUBBER", "MISC"),
+    ("RUBRMvmt", "RUBBER", "MOVEMENT"),
+    ("SCIAlrm", "SCIFI", "ALARM"),
+    ("SCICmpt", "SCIFI", "COMPUTER"),
+    ("SCIEnrg", "SCIFI", "ENERGY"),
+    ("SCIImpt", "SCIFI", "IMPACT"),
+    ("SCIMach", "SCIFI", "MACHINE"),
+    ("SCIMech", "SCIFI", "MECHANISM"),
+    ("SCIMisc", "SCIFI", "MISC"),
+    ("SCIRetro", "SCIFI", "RETRO"),
+    ("SCIShip", "SCIFI", "SPACESHIP"),
+    ("SCIWeap", "SCIFI", "WEAPON"),
+    ("SNOWCrsh", "SNOW", "CRASH & DEBRIS"),
+    ("SNOWImpt", "SNOW", "IMPACT"),
+    ("SNOWMisc", "SNOW", "MISC"),
+    ("SNOWMvmt", "SNOW", "MOVEMENT"),
+    ("SWSHMisc", "SWOOSHES", "MISC"),
+    ("SWSHSwsh", "SWOOSHES", "SWISH"),
+    ("SWSHWhsh", "SWOOSHES", "WHOOSH"),
+    ("TOOLGrdn", "TOOLS", "GARDEN"),
+    ("TOOLHand", "TOOLS", "HAND"),
+    ("TOOLMisc", "TOOLS", "MISC"),
+    ("TOOLPneu", "TOOLS", "PNEUMATIC"),
+    ("TOOLPwr", "TOOLS", "POWER"),
+    ("TOYElec", "TOYS", "ELECTRONIC"),
+    ("TOYMech", "TOYS", "MECHANICAL"),
+    ("TOYMisc", "TOYS", "MISC"),
+    ("TRNDiesel", "TRAINS", "DIESEL"),
+    ("TRNElec", "TRAINS", "ELECTRIC"),
+    ("TRNHorn", "TRAINS", "HORN"),
+    ("TRNInt", "TRAINS", "INTERIOR"),
+    ("TRNMech", "TRAINS", "MECHANISM"),
+    ("TRNMisc", "TRAINS", "MISC"),
+    ("TRNSteam", "TRAINS", "STEAM"),
+    ("TRNSubw", "TRAINS", "SUBWAY"),
+    ("TRNTram", "TRAINS", "TRAM"),
+    ("UIAlert", "USER INTERFACE", "ALERT"),
+    ("UIBeep", "USER INTERFACE", "BEEP"),
+    ("UIClick", "USER INTERFACE", "CLICK"),
+    ("UIData", "USER INTERFACE", "DATA"),
+    ("UIGlitch", "USER INTERFACE", "GLITCH"),
+    ("UIMisc", "USER INTERFACE", "MISC"),
+    ("UIMvmt", "USER INTERFACE", "MOVEMENT"),
+    ("VEHAlrm", "VEHICLES", "ALARM"),
+    ("VEHAntq", "VEHICLES", "ANTIQUE"),
+    ("VEHAtv", "VEHICLES", "ATV"),
+    ("VEHBike", "VEHICLES", "BICYCLE"),
+    ("VEHBrake", "VEHICLES", "BRAKE"),
+    ("VEHBus", "VEHICLES", "BUS"),
+    ("VEHCar", "VEHICLES", "CAR"),
+    ("VEHConst", "VEHICLES", "CONSTRUCTION"),
+    ("VEHDoor", "VEHICLES", "DOOR"),
+    ("VEHEmrg", "VEHICLES", "EMERGENCY"),
+    ("VEHFarm", "VEHICLES", "FARM"),
+    ("VEHHorn", "VEHICLES", "HORN"),
+    ("VEHInt", "VEHICLES", "INTERIOR"),
+    ("VEHMech", "VEHICLES", "MECHANISM"),
+    ("VEHMil", "VEHICLES", "MILITARY"),
+    ("VEHMisc", "VEHICLES", "MISC"),
+    ("VEHMoto", "VEHICLES", "MOTORCYCLE"),
+    ("VEHRace", "VEHICLES", "RACING"),
+    ("VEHTire", "VEHICLES", "TIRE"),
+    ("VEHTruck", "VEHICLES", "TRUCK"),
+    ("VEHUtil", "VEHICLES", "UTILITY"),
+    ("VEHWagn", "VEHICLES", "WAGON"),
+    ("VOXAlien", "VOICES", "ALIEN"),
+    ("VOXBaby", "VOICES", "BABY"),
+    ("VOXChld", "VOICES", "CHILD"),
+    ("VOXCry", "VOICES", "CRYING"),
+    ("VOXEfrt", "VOICES", "EFFORTS"),
+    ("VOXFem", "VOICES", "FEMALE"),
+    ("VOXFutz", "VOICES", "FUTZED"),
+    ("VOXHist", "VOICES", "HISTORICAL"),
+    ("VOXLaff", "VOICES", "LAUGH"),
+    ("VOXMale", "VOICES", "MALE"),
+    ("VOXMisc", "VOICES", "MISC"),
+    ("VOXReac", "VOICES", "REACTION"),
+    ("VOXScrm", "VOICES", "SCREAM"),
+    ("VOXSing", "VOICES", "SINGING"),
+    ("VOXWhsp", "VOICES", "WHISPER"),
+    ("WATRBubl", "WATER", "BUBBLES"),
+    ("WATRDran", "WATER", "DRAIN"),
+    ("WATRDrip", "WATER", "DRIP"),
+    ("WATRFall", "WATER", "WATERFALL"),
+    ("WATRFizz", "WATER", "FIZZ"),
+    ("WATRFlow", "WATER", "FLOW"),
+    ("WATRFoun", "WATER", "FOUNTAIN"),
+    ("WATRImpt", "WATER", "IMPACT"),
+    ("WATRLap", "WATER", "LAPPING"),
+    ("WATRMisc", "WATER", "MISC"),
+    ("WATRMvmt", "WATER", "MOVEMENT"),
+    ("WATRPlmb", "WATER", "PLUMBING"),
+    ("WATRPour", "WATER", "POUR"),
+    ("WATRRapd", "WATER", "RAPIDS"),
+    ("WATRSplsh", "WATER", "SPLASH"),
+    ("WATRSpray", "WATER", "SPRAY"),
+    ("WATRStm", "WATER", "STEAM"),
+    ("WATRSurf", "WATER", "SURF"),
+    ("WATRTurb", "WATER", "TURBULENT"),
+    ("WATRUndwtr", "WATER", "UNDERWATER"),
+    ("WATRWave", "WATER", "WAVES"),
+    ("WEAPArmr", "WEAPONS", "ARMOR"),
+    ("WEAPArrw", "WEAPONS", "ARROW"),
+    ("WEAPBlnt", "WEAPONS", "BLUNT"),
+    ("WEAPBow", "WEAPONS", "BOW"),
+    ("WEAPKnif", "WEAPONS", "KNIFE"),
+    ("WEAPMisc", "WEAPONS", "MISC"),
+    ("WEAPPole", "WEAPONS", "POLEARM"),
+    ("WEAPSiege", "WEAPONS", "SIEGE"),
+    ("WEAPSwrd", "WEAPONS", "SWORD"),
+    ("WEAPWhip", "WEAPONS", "WHIP"),
+    ("WTHRHail", "WEATHER", "HAIL"),
+    ("WTHRMisc", "WEATHER", "MISC"),
+    ("WTHRStrm", "WEATHER", "STORM"),
+    ("WTHRThun", "WEATHER", "THUNDER"),
+    ("WHSTHuman", "WHISTLES", "HUMAN"),
+    ("WHSTMech", "WHISTLES", "MECHANICAL"),
+    ("WHSTMisc", "WHISTLES", "MISC"),
+    ("WINDDsgn", "WIND", "DESIGNED"),
+    ("WINDGust", "WIND", "GUST"),
+    ("WINDInt", "WIND", "INTERIOR"),
+    ("WINDMisc", "WIND", "MISC"),
+    ("WINDTonl", "WIND", "TONAL"),
+    ("WINDTurb", "WIND", "TURBULENT"),
+    ("WINDVege", "WIND", "VEGETATION"),
+    ("WINDWHdwr", "WINDOWS", "HARDWARE"),
+    ("WINDWKnck", "WINDOWS", "KNOCK"),
+    ("WINDWMetl", "WINDOWS", "METAL"),
+    ("WINDWMisc", "WINDOWS", "MISC"),
+    ("WINDWPlas", "WINDOWS", "PLASTIC"),
+    ("WINDWWood", "WINDOWS", "WOOD"),
+    ("WINGBird", "WINGS", "BIRD"),
+    ("WINGCrea", "WINGS", "CREATURE"),
+    ("WINGInsct", "WINGS", "INSECT"),
+    ("WINGMisc", "WINGS", "MISC"),
+    ("WOODBrk", "WOOD", "BREAK"),
+    ("WOODCrsh", "WOOD", "CRASH & DEBRIS"),
+    ("WOODFric", "WOOD", "FRICTION"),
+    ("WOODHndl", "WOOD", "HANDLE"),
+    ("WOODImpt", "WOOD", "IMPACT"),
+    ("WOODMisc", "WOOD", "MISC"),
+    ("WOODMvmt", "WOOD", "MOVEMENT"),
+    ("WOODTonl", "WOOD", "TONAL"),
+];
+
+/// The UCS entry for `cat_id`, matched case-insensitively
+pub fn lookup(cat_id: &str) -> Option<Category> {
+    let cat_id = cat_id.trim();
+    UCS_CATEGORIES
+        .iter()
+        .find(|(id, _, _)| id.eq_ignore_ascii_case(cat_id))
+        .map(|&(id, category, subcategory)| Category {
+            cat_id: id.to_string(),
+            category: category.to_string(),
+            subcategory: subcategory.to_string(),
+            category_full: format!("{}-{}", category, subcategory),
+        })
+}
+
+/// The known CatIDs closest to `cat_id`, nearest first
+pub fn suggestions(cat_id: &str) -> Vec<&'static str> {
+    let wanted = cat_id.trim().to_lowercase();
+    let mut scored: Vec<(usize, &'static str)> = UCS_CATEGORIES
+        .iter()
+        .map(|&(id, _, _)| (edit_distance(&wanted, &id.to_lowercase()), id))
+        .collect();
+    scored.sort();
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, id)| id)
+        .collect()
+}
+
+fn unknown_cat_id(cat_id: &str) -> anyhow::Error {
+    anyhow!(
+        "'{}' is not a UCS CatID, did you mean {}?",
+        cat_id.trim(),
+        suggestions(cat_id).join(", ")
+    )
+}
+
+/// Levenshtein distance over chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+impl Metadata {
+    /// Set CATEGORY, SUBCATEGORY, CATID and CATEGORYFULL (with their aliases)
+    /// from a UCS CatID such as `METLFric`
+    pub fn apply_ucs(&mut self, cat_id: &str) -> R<()> {
+        lookup(cat_id)
+            .ok_or_else(|| unknown_cat_id(cat_id))?
+            .write_to(self)
+    }
+
+    /// Check that the CatID is in the UCS list and the category fields match it
+    pub fn validate_ucs(&self) -> R<()> {
+        let cat_id = self
+            .get_field("CATID")
+            .ok_or_else(|| anyhow!("No UCS CatID set"))?;
+        let ucs = lookup(&cat_id).ok_or_else(|| unknown_cat_id(&cat_id))?;
+
+        let mismatches: Vec<String> = [
+            ("CATEGORY", &ucs.category),
+            ("SUBCATEGORY", &ucs.subcategory),
+            ("CATEGORYFULL", &ucs.category_full),
+        ]
+        .into_iter()
+        .filter_map(|(key, expected)| {
+            let actual = self.get_field(key).unwrap_or_default();
+            (actual != *expected)
+                .then(|| format!("{} is '{}' but {} is '{}'", key, actual, cat_id, expected))
+        })
+        .collect();
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("UCS mismatch: {}", mismatches.join("; ")))
+        }
+    }
+}
+
+impl Codex {
+    /// Fill the category fields from a UCS CatID; see `Metadata::apply_ucs`
+    pub fn apply_ucs(&mut self, cat_id: &str) -> R<&mut Self> {
+        self.metadata
+            .get_or_insert_with(Metadata::new)
+            .apply_ucs(cat_id)?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_list_size_and_unique_cat_ids() {
+        assert_eq!(UCS_CATEGORIES.len(), 599);
+        let mut cat_ids: Vec<String> = UCS_CATEGORIES
+            .iter()
+            .map(|(id, _, _)| id.to_lowercase())
+            .collect();
+        cat_ids.sort();
+        cat_ids.dedup();
+        assert_eq!(cat_ids.len(), UCS_CATEGORIES.len());
+    }
+
+    #[test]
+    fn test_apply_ucs_fills_category_fields() {
+        let mut metadata = Metadata::new();
+        metadata.apply_ucs(" metlfric ").unwrap();
+        assert_eq!(
+            metadata.get_field("USER_CATEGORY").as_deref(),
+            Some("METAL")
+        );
+        assert_eq!(
+            metadata.get_field("ASWG_catId").as_deref(),
+            Some("METLFric")
+        );
+        assert_eq!(
+            metadata.get_field("USER_CATEGORYFULL").as_deref(),
+            Some("METAL-FRICTION")
+        );
+        metadata.validate_ucs().unwrap();
+
+        metadata.set_field("SUBCATEGORY", "IMPACT").unwrap();
+        let error = metadata.validate_ucs().unwrap_err().to_string();
+        assert!(error.contains("SUBCATEGORY is 'IMPACT' but METLFric is 'FRICTION'"));
+    }
+
+    #[test]
+    fn test_unknown_cat_id_suggests_nearest() {
+        let mut metadata = Metadata::new();
+        let error = metadata.apply_ucs("METLFrik").unwrap_err().to_string();
+        assert!(error.starts_with("'METLFrik' is not a UCS CatID, did you mean METLFric"));
+        assert_eq!(metadata.get_field("CATEGORY"), None);
+    }
+}